@@ -0,0 +1,427 @@
+//! The built-in info sheet's data: what Finder's Get Info window shows
+//! about an app, read straight from the bundle (plus Spotlight's dates on
+//! macOS). Finder's window needs AppleScript, and with it an Automation
+//! consent that, once refused, leaves Get Info silently doing nothing.
+//! On Linux the same sheet is filled from the desktop entry (an
+//! AppImage's embedded one), there being no Get Info to fall back on.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::appimage::{is_appimage, AppImage};
+use crate::bundle_audit::audit_bundle;
+use crate::bundle_info::{wrapped_ios_bundle, BundleInfo};
+use crate::bundle_size::bundle_size;
+use crate::codesign::read_code_signature;
+use crate::config::{AppDetails, Distribution, DocumentType};
+use crate::desktop_entry::{find_program, DesktopEntry};
+use crate::desktop_launch::{parse_exec, ExecFields};
+use crate::macho::{host_needs_rosetta_for_intel, read_architecture, Architecture};
+use crate::quarantine::read_quarantine;
+
+/// ms since the epoch (None before it, or when the platform has no value)
+fn epoch_ms(time: std::io::Result<SystemTime>) -> Option<u64> {
+    let since = time.ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some(since.as_millis() as u64)
+}
+
+/// Days from 1970-01-01 to a proleptic Gregorian date
+#[cfg(target_os = "macos")]
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// A date as mdls prints it ("2024-05-01 09:30:00 +0000"), in ms since
+/// the epoch
+#[cfg(target_os = "macos")]
+fn parse_mdls_date(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let (date, time, zone) = (parts.next()?, parts.next()?, parts.next()?);
+    let numbers = |text: &str, separator: char| -> Option<Vec<i64>> {
+        text.split(separator).map(|n| n.parse().ok()).collect()
+    };
+    let (date, time) = (numbers(date, '-')?, numbers(time, ':')?);
+    let (&[year, month, day], &[hour, minute, second]) = (&date[..], &time[..]) else {
+        return None;
+    };
+    let sign = if zone.starts_with('-') { -1 } else { 1 };
+    let zone: i64 = zone.get(1..)?.parse().ok()?;
+    let offset = sign * ((zone / 100) * 3600 + (zone % 100) * 60);
+    let seconds =
+        days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second - offset;
+    u64::try_from(seconds).ok().map(|s| s * 1000)
+}
+
+/// Spotlight's date-added and last-used dates for the bundle: the dates
+/// Finder shows. None for either when the volume isn't indexed.
+#[cfg(target_os = "macos")]
+fn spotlight_dates(path: &Path) -> (Option<u64>, Option<u64>) {
+    let Ok(output) = std::process::Command::new("mdls")
+        .args(["-name", "kMDItemDateAdded", "-name", "kMDItemLastUsedDate"])
+        .arg(path)
+        .output()
+    else {
+        return (None, None);
+    };
+    let (mut added, mut last_used) = (None, None);
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "kMDItemDateAdded" => added = parse_mdls_date(value),
+            "kMDItemLastUsedDate" => last_used = parse_mdls_date(value),
+            _ => {}
+        }
+    }
+    (added, last_used)
+}
+
+#[cfg(not(target_os = "macos"))]
+fn spotlight_dates(_path: &Path) -> (Option<u64>, Option<u64>) {
+    (None, None)
+}
+
+/// Mac App Store apps carry a receipt; iPhone/iPad apps only ever come
+/// from the store, which leaves its metadata next to the wrapped bundle
+fn distribution(path: &Path) -> Distribution {
+    // /Applications/Safari.app and the like are symlinks into the system
+    let resolved = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if resolved.starts_with("/System") {
+        return Distribution::System;
+    }
+    let store_receipt = path.join("Contents/_MASReceipt/receipt").is_file()
+        || (wrapped_ios_bundle(path).is_some()
+            && (path.join("Wrapper/iTunesMetadata.plist").is_file()
+                || path.join("Wrapper/BundleMetadata.plist").is_file()));
+    if store_receipt {
+        Distribution::AppStore
+    } else {
+        Distribution::Direct
+    }
+}
+
+/// The program a desktop entry runs: TryExec, else Exec's first word
+fn entry_executable(entry: &DesktopEntry, path: &Path) -> Option<PathBuf> {
+    let program = match entry.string("TryExec").filter(|t| !t.is_empty()) {
+        Some(try_exec) => try_exec,
+        None => {
+            let fields = ExecFields {
+                files: &[],
+                icon: None,
+                name: None,
+                desktop_file: path,
+            };
+            parse_exec(&entry.string("Exec")?, &fields)
+                .ok()?
+                .into_iter()
+                .next()?
+        }
+    };
+    find_program(&program)
+}
+
+/// Details of the (validated) desktop entry or AppImage at `path`: its
+/// name and id, the MIME types it opens, and the dates of its file (and
+/// of the program it runs, for the last launch). Entries installed under
+/// /usr came with the system's packages. The id is the served one: a
+/// desktop-file id is the file's path below its applications directory,
+/// which the file name alone doesn't give.
+fn read_entry_details(path: &Path, served_id: Option<String>) -> AppDetails {
+    let stem = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let (id, entry, executable) = if is_appimage(path) {
+        let embedded = AppImage::open(path).and_then(|appimage| appimage.desktop_entry());
+        let (id, entry) = embedded.unzip();
+        let id = served_id.or(id).unwrap_or_else(|| stem.clone());
+        (Some(id), entry, Some(path.to_path_buf()))
+    } else {
+        let entry = DesktopEntry::read(path);
+        let executable = entry
+            .as_ref()
+            .and_then(|entry| entry_executable(entry, path));
+        (served_id, entry, executable)
+    };
+    let metadata = fs::metadata(path).ok();
+    let mime_types = entry
+        .as_ref()
+        .map(|entry| entry.list("MimeType"))
+        .unwrap_or_default();
+
+    AppDetails {
+        name: entry.as_ref().and_then(DesktopEntry::name).unwrap_or(stem),
+        path: path.to_string_lossy().to_string(),
+        bundle_id: id,
+        version: entry
+            .as_ref()
+            .and_then(|entry| entry.string("X-AppImage-Version")),
+        build: None,
+        architecture: None,
+        needs_rosetta: false,
+        signature: None,
+        quarantine: read_quarantine(path),
+        size_on_disk: bundle_size(path),
+        installed_at: metadata.as_ref().and_then(|m| epoch_ms(m.created())),
+        modified_at: metadata.as_ref().and_then(|m| epoch_ms(m.modified())),
+        last_opened_at: executable
+            .and_then(|executable| fs::metadata(executable).ok())
+            .and_then(|m| epoch_ms(m.accessed())),
+        distribution: if path.starts_with("/usr") {
+            Distribution::System
+        } else {
+            Distribution::Direct
+        },
+        document_types: mime_types
+            .into_iter()
+            .map(|mime_type| DocumentType {
+                name: None,
+                role: None,
+                content_types: vec![mime_type],
+                extensions: Vec::new(),
+            })
+            .collect(),
+        problems: audit_bundle(path),
+    }
+}
+
+/// Read the details of the (validated) bundle at `path`. The size comes
+/// from the size cache; an uncached bundle is walked, which for the
+/// largest apps takes a moment. `served_id` is the identifier the
+/// discovery index holds for it, used when the bundle declares none.
+pub(crate) fn read_app_details(path: &Path, served_id: Option<String>) -> AppDetails {
+    if cfg!(target_os = "linux") {
+        return read_entry_details(path, served_id);
+    }
+    read_bundle_details(path, served_id)
+}
+
+/// Details of the .app bundle at `path`, from its Info.plist, executable
+/// and Spotlight's dates
+fn read_bundle_details(path: &Path, served_id: Option<String>) -> AppDetails {
+    let info = BundleInfo::read(path);
+    let string = |key| {
+        info.as_ref()
+            .and_then(|info| info.string(key))
+            .map(str::to_string)
+    };
+    let executable = info.as_ref().and_then(BundleInfo::executable_path);
+    let architecture = executable.as_deref().and_then(read_architecture);
+    let metadata = fs::metadata(path).ok();
+    let (date_added, last_used) = spotlight_dates(path);
+
+    AppDetails {
+        name: path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        path: path.to_string_lossy().to_string(),
+        bundle_id: string("CFBundleIdentifier").or(served_id),
+        version: string("CFBundleShortVersionString"),
+        build: string("CFBundleVersion"),
+        architecture,
+        needs_rosetta: architecture == Some(Architecture::X86_64) && host_needs_rosetta_for_intel(),
+        signature: executable.as_deref().and_then(read_code_signature),
+        quarantine: read_quarantine(path),
+        size_on_disk: bundle_size(path),
+        installed_at: date_added.or_else(|| metadata.as_ref().and_then(|m| epoch_ms(m.created()))),
+        modified_at: metadata.as_ref().and_then(|m| epoch_ms(m.modified())),
+        last_opened_at: last_used.or_else(|| {
+            let executable = fs::metadata(executable?).ok()?;
+            epoch_ms(executable.accessed())
+        }),
+        distribution: distribution(path),
+        document_types: info
+            .as_ref()
+            .map(BundleInfo::document_types)
+            .unwrap_or_default(),
+        problems: audit_bundle(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle_audit::ProblemKind;
+    use crate::bundle_info::tests::bundle;
+    use crate::codesign::tests::executable;
+    use crate::codesign::SignatureStatus;
+    use std::os::unix::fs::PermissionsExt;
+
+    const DOCUMENT_TYPES: &str = r#"
+<key>CFBundleIdentifier</key><string>com.example.Test</string>
+<key>CFBundleShortVersionString</key><string>2.1</string>
+<key>CFBundleVersion</key><string>2104</string>
+<key>CFBundleDocumentTypes</key><array>
+  <dict>
+    <key>CFBundleTypeName</key><string>Plain Text</string>
+    <key>CFBundleTypeRole</key><string>Editor</string>
+    <key>LSItemContentTypes</key><array><string>public.plain-text</string></array>
+    <key>CFBundleTypeExtensions</key><array><string>txt</string><string>text</string></array>
+  </dict>
+  <dict><key>CFBundleTypeRole</key><string>Viewer</string></dict>
+</array>"#;
+
+    /// A whole bundle: Info.plist of `keys`, a runnable arm64 executable
+    /// and a resources folder
+    fn whole_bundle(dir: &Path, keys: &str) -> PathBuf {
+        let bundle = bundle(dir, keys, false);
+        let executable_path = bundle.join("Contents/MacOS/Test");
+        fs::write(&executable_path, executable(None)).unwrap();
+        fs::set_permissions(&executable_path, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::create_dir_all(bundle.join("Contents/Resources")).unwrap();
+        bundle
+    }
+
+    #[test]
+    fn bundle_details_come_from_the_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = whole_bundle(dir.path(), DOCUMENT_TYPES);
+        let details = read_bundle_details(&path, Some("com.example.Served".into()));
+
+        assert_eq!(details.name, "Test");
+        assert_eq!(details.path, path.to_string_lossy());
+        // The bundle's own identifier wins over the served one
+        assert_eq!(details.bundle_id.as_deref(), Some("com.example.Test"));
+        assert_eq!(details.version.as_deref(), Some("2.1"));
+        assert_eq!(details.build.as_deref(), Some("2104"));
+        assert_eq!(details.architecture, Some(Architecture::Arm64));
+        assert!(!details.needs_rosetta);
+        assert_eq!(
+            details.signature.map(|signature| signature.status),
+            Some(SignatureStatus::Unsigned)
+        );
+        assert_eq!(details.quarantine, None);
+        assert!(details.size_on_disk.is_some_and(|bytes| bytes > 0));
+        assert!(details.modified_at.is_some());
+        assert_eq!(details.distribution, Distribution::Direct);
+        // An arm64 executable is all that could be wrong, off Apple Silicon
+        let kinds: Vec<ProblemKind> = details.problems.iter().map(|p| p.kind).collect();
+        if cfg!(target_arch = "aarch64") {
+            assert!(kinds.is_empty());
+        } else {
+            assert_eq!(kinds, [ProblemKind::UnsupportedArchitecture]);
+        }
+
+        assert_eq!(details.document_types.len(), 2);
+        let text = &details.document_types[0];
+        assert_eq!(text.name.as_deref(), Some("Plain Text"));
+        assert_eq!(text.role.as_deref(), Some("Editor"));
+        assert_eq!(text.content_types, ["public.plain-text"]);
+        assert_eq!(text.extensions, ["txt", "text"]);
+        let viewer = &details.document_types[1];
+        assert_eq!(
+            (viewer.name.as_deref(), viewer.role.as_deref()),
+            (None, Some("Viewer"))
+        );
+        assert!(viewer.content_types.is_empty() && viewer.extensions.is_empty());
+    }
+
+    #[test]
+    fn bundle_without_info_plist_keeps_the_served_identifier() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Gone.app");
+        fs::create_dir_all(path.join("Contents")).unwrap();
+        let details = read_bundle_details(&path, Some("com.example.Gone".into()));
+
+        assert_eq!(details.name, "Gone");
+        assert_eq!(details.bundle_id.as_deref(), Some("com.example.Gone"));
+        assert_eq!((details.version, details.build), (None, None));
+        assert_eq!(details.architecture, None);
+        assert_eq!(details.signature, None);
+        assert!(details.document_types.is_empty());
+        assert!(!details.problems.is_empty());
+    }
+
+    #[test]
+    fn store_receipts_mark_app_store_apps() {
+        let dir = tempfile::tempdir().unwrap();
+        let mac = whole_bundle(dir.path(), DOCUMENT_TYPES);
+        fs::create_dir_all(mac.join("Contents/_MASReceipt")).unwrap();
+        fs::write(mac.join("Contents/_MASReceipt/receipt"), b"receipt").unwrap();
+        assert_eq!(distribution(&mac), Distribution::AppStore);
+
+        let ios = dir.path().join("Threes.app");
+        fs::create_dir_all(ios.join("Wrapper/Threes.app")).unwrap();
+        assert_eq!(distribution(&ios), Distribution::Direct);
+        fs::write(ios.join("Wrapper/iTunesMetadata.plist"), b"").unwrap();
+        assert_eq!(distribution(&ios), Distribution::AppStore);
+
+        // Metadata without a wrapped app isn't an iOS app's
+        let odd = dir.path().join("Odd.app");
+        fs::create_dir_all(odd.join("Wrapper")).unwrap();
+        fs::write(odd.join("Wrapper/BundleMetadata.plist"), b"").unwrap();
+        assert_eq!(distribution(&odd), Distribution::Direct);
+    }
+
+    #[test]
+    fn entry_details_come_from_the_desktop_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("notes");
+        fs::write(&program, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+        let path = dir.path().join("org.example.Notes.desktop");
+        fs::write(
+            &path,
+            format!(
+                "[Desktop Entry]\nType=Application\nName=Notes\nExec={} %F\n\
+                 MimeType=text/plain;text/markdown;\n",
+                program.display()
+            ),
+        )
+        .unwrap();
+
+        let details = read_entry_details(&path, Some("kde4-org.example.Notes".into()));
+        assert_eq!(details.name, "Notes");
+        assert_eq!(details.bundle_id.as_deref(), Some("kde4-org.example.Notes"));
+        assert_eq!(details.version, None);
+        assert_eq!(details.architecture, None);
+        assert_eq!(details.signature, None);
+        assert_eq!(details.distribution, Distribution::Direct);
+        // The program's last access stands in for the last launch
+        assert!(details.last_opened_at.is_some());
+        assert!(details.problems.is_empty());
+        let mime_types: Vec<&[String]> = details
+            .document_types
+            .iter()
+            .map(|document_type| document_type.content_types.as_slice())
+            .collect();
+        assert_eq!(mime_types, [["text/plain"], ["text/markdown"]]);
+    }
+
+    #[test]
+    fn unreadable_entry_falls_back_to_the_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.desktop");
+        fs::write(&path, "not a key file").unwrap();
+        let details = read_entry_details(&path, None);
+        assert_eq!(details.name, "broken");
+        assert_eq!(details.bundle_id, None);
+        assert_eq!(details.last_opened_at, None);
+        assert!(details.document_types.is_empty());
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn parses_mdls_dates() {
+        assert_eq!(
+            parse_mdls_date(" 2024-05-01 09:30:00 +0000"),
+            Some(1_714_555_800_000)
+        );
+        assert_eq!(
+            parse_mdls_date("2024-05-01 11:30:00 +0200"),
+            Some(1_714_555_800_000)
+        );
+        assert_eq!(parse_mdls_date("(null)"), None);
+    }
+}
@@ -1,25 +1,100 @@
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-pub(crate) fn get_applications_dirs() -> Vec<PathBuf> {
-    let mut dirs = vec![
-        PathBuf::from("/Applications"),
-        PathBuf::from("/System/Applications"),
-    ];
-    if let Some(home) = dirs::home_dir() {
-        dirs.push(home.join("Applications"));
+use crate::app_sources::{
+    appimage_dirs, bundle_source, desktop_entry_dirs, homebrew_appdirs, provider_folders,
+    source_enabled, volume_applications_dirs, DiscoveryRoots,
+};
+use crate::bookmark::{looks_like_alias_file, read_alias_target};
+use crate::config::{AppSettings, AppSource, BrokenAlias, BrokenAliasReason};
+use crate::xdg_discovery::discover_desktop_entries;
+
+/// A discovered .app bundle and the provider that found it
+pub(crate) struct DiscoveredApp {
+    pub path: PathBuf,
+    pub source: AppSource,
+    /// External volume name, for apps under /Volumes/<name>/Applications
+    pub volume: Option<String>,
+    /// The alias or symlink it was found through
+    pub via: Option<PathBuf>,
+    /// Desktop-file id, minus ".desktop", for a Linux desktop entry
+    pub desktop_id: Option<String>,
+}
+
+/// Directories listed in full, with the source their apps get and their
+/// external volume: the applications directories, plus enabled Homebrew
+/// appdirs and mounted volumes' Applications directories. On Linux: the
+/// enabled sources' desktop entry directories and AppImage directories.
+fn listed_dirs(
+    roots: &DiscoveryRoots,
+    settings: &AppSettings,
+) -> Vec<(PathBuf, AppSource, Option<String>)> {
+    if cfg!(target_os = "linux") {
+        let mut listed: Vec<(PathBuf, AppSource, Option<String>)> = desktop_entry_dirs(roots)
+            .into_iter()
+            .filter(|(source, _)| source_enabled(*source, settings))
+            .map(|(source, dir)| (dir, source, None))
+            .collect();
+        if settings.show_appimages {
+            listed.extend(
+                appimage_dirs(roots)
+                    .into_iter()
+                    .map(|dir| (dir, AppSource::AppImage, None)),
+            );
+        }
+        return listed;
+    }
+    bundle_listed_dirs(roots, settings)
+}
+
+/// The directories of .app bundles listed in full
+fn bundle_listed_dirs(
+    roots: &DiscoveryRoots,
+    settings: &AppSettings,
+) -> Vec<(PathBuf, AppSource, Option<String>)> {
+    let mut listed: Vec<(PathBuf, AppSource, Option<String>)> = roots
+        .bundle_applications_dirs()
+        .into_iter()
+        .map(|dir| (dir, AppSource::Applications, None))
+        .collect();
+    if settings.show_homebrew_apps {
+        listed.extend(
+            homebrew_appdirs(roots)
+                .into_iter()
+                .map(|dir| (dir, AppSource::Homebrew, None)),
+        );
+    }
+    if settings.show_external_volumes {
+        listed.extend(
+            volume_applications_dirs(roots)
+                .into_iter()
+                .map(|(name, dir)| (dir, AppSource::ExternalVolume, Some(name))),
+        );
     }
-    dirs
+    listed
+}
+
+/// Directories apps may be discovered in, top level and one subfolder
+/// deep. Volume roots are included only while mounted, so apps on an
+/// unplugged volume can't be acted on.
+pub(crate) fn get_applications_dirs(settings: &AppSettings) -> Vec<PathBuf> {
+    listed_dirs(&DiscoveryRoots::system(), settings)
+        .into_iter()
+        .map(|(dir, _, _)| dir)
+        .collect()
 }
 
-fn sort_paths_by_name(paths: &mut [PathBuf]) {
-    paths.sort_by(|a, b| {
-        a.file_stem()
+fn sort_apps_by_name(apps: &mut [DiscoveredApp]) {
+    apps.sort_by(|a, b| {
+        a.path
+            .file_stem()
             .unwrap_or_default()
             .to_string_lossy()
             .to_lowercase()
             .cmp(
-                &b.file_stem()
+                &b.path
+                    .file_stem()
                     .unwrap_or_default()
                     .to_string_lossy()
                     .to_lowercase(),
@@ -29,7 +104,7 @@ fn sort_paths_by_name(paths: &mut [PathBuf]) {
 
 /// Bundle identifier of an .app bundle, read via NSBundle.
 #[cfg(target_os = "macos")]
-fn bundle_identifier(path: &std::path::Path) -> Option<String> {
+fn bundle_identifier(path: &Path) -> Option<String> {
     use objc2_foundation::{NSBundle, NSString};
     // Pool required: called off the main thread (no ambient autorelease pool)
     objc2::rc::autoreleasepool(|_| {
@@ -40,29 +115,6 @@ fn bundle_identifier(path: &std::path::Path) -> Option<String> {
     })
 }
 
-/// App Store category identifier (LSApplicationCategoryType) of an .app,
-/// e.g. "public.app-category.developer-tools". Used for folder name
-/// suggestions, matching original Launchpad behavior.
-#[cfg(target_os = "macos")]
-pub(crate) fn app_category(path: &str) -> Option<String> {
-    use objc2_foundation::{NSBundle, NSString};
-    // Pool required: rayon worker threads have no autorelease pool, so
-    // autoreleased NSBundle/NSString would otherwise pin until thread exit
-    objc2::rc::autoreleasepool(|_| {
-        let ns_path = NSString::from_str(path);
-        let bundle = NSBundle::bundleWithPath(&ns_path)?;
-        let key = NSString::from_str("LSApplicationCategoryType");
-        let value = bundle.objectForInfoDictionaryKey(&key)?;
-        let category = value.downcast::<NSString>().ok()?;
-        Some(category.to_string())
-    })
-}
-
-#[cfg(not(target_os = "macos"))]
-pub(crate) fn app_category(_path: &str) -> Option<String> {
-    None
-}
-
 /// Path of the .app bundle this process runs from (None in dev-server mode).
 fn own_bundle_path() -> Option<PathBuf> {
     let exe = std::env::current_exe().ok()?;
@@ -79,11 +131,7 @@ fn own_bundle_path() -> Option<PathBuf> {
 /// This runs per discovered app on the startup path (window still hidden),
 /// so the expensive Info.plist identifier read is gated behind two cheap
 /// checks: the running bundle's exact path, then a bundle-name match.
-fn is_own_bundle(
-    path: &std::path::Path,
-    own_bundle_id: &str,
-    own_app_path: Option<&std::path::Path>,
-) -> bool {
+fn is_own_bundle(path: &Path, own_bundle_id: &str, own_app_path: Option<&Path>) -> bool {
     #[cfg(target_os = "macos")]
     {
         if own_app_path.is_some_and(|own| own == path) {
@@ -109,60 +157,260 @@ fn is_own_bundle(
     }
 }
 
-fn get_apps_in_dir(
-    dir: &PathBuf,
-    own_bundle_id: &str,
-    own_app_path: Option<&std::path::Path>,
-) -> Vec<PathBuf> {
-    let mut apps = Vec::new();
-    if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.extension().is_some_and(|ext| ext == "app")
-                && !is_own_bundle(&path, own_bundle_id, own_app_path)
-            {
-                apps.push(path);
+/// What an entry of an applications directory is
+enum Entry {
+    /// An .app bundle, or an alias or symlink resolving to one (its own
+    /// path then in `via`)
+    App {
+        path: PathBuf,
+        via: Option<PathBuf>,
+    },
+    /// An alias or symlink that should lead to an app but doesn't
+    Broken(BrokenAlias),
+    /// A directory that may hold apps (or a symlink to one)
+    Dir(PathBuf),
+    Other,
+}
+
+/// An app bundle at `target`, reached through the link at `via`
+fn link_entry(target: PathBuf, via: &Path) -> Entry {
+    if target.extension().is_some_and(|ext| ext == "app") && target.is_dir() {
+        Entry::App {
+            path: target,
+            via: Some(via.to_path_buf()),
+        }
+    } else if target.is_dir() {
+        Entry::Dir(via.to_path_buf())
+    } else {
+        Entry::Other
+    }
+}
+
+fn broken(path: &Path, target: Option<PathBuf>, reason: BrokenAliasReason) -> Entry {
+    Entry::Broken(BrokenAlias {
+        path: path.to_string_lossy().to_string(),
+        target: target.map(|t| t.to_string_lossy().to_string()),
+        reason,
+    })
+}
+
+/// Classify a directory entry. .app entries are taken as they are (system
+/// apps in /Applications are symlinks, and stay listed by their link
+/// path); other symlinks and Finder alias files are followed to an app.
+fn classify_entry(path: PathBuf) -> Entry {
+    if path.extension().is_some_and(|ext| ext == "app") {
+        return Entry::App { path, via: None };
+    }
+    let Ok(metadata) = fs::symlink_metadata(&path) else {
+        return Entry::Other;
+    };
+
+    if metadata.is_symlink() {
+        return match fs::canonicalize(&path) {
+            Ok(target) => link_entry(target, &path),
+            // Dangling: only worth reporting when it meant an app
+            Err(_) => match fs::read_link(&path) {
+                Ok(target) if target.extension().is_some_and(|ext| ext == "app") => {
+                    broken(&path, Some(target), BrokenAliasReason::TargetMissing)
+                }
+                _ => Entry::Other,
+            },
+        };
+    }
+    if metadata.is_dir() {
+        return Entry::Dir(path);
+    }
+    if metadata.is_file() && looks_like_alias_file(&path) {
+        return match read_alias_target(&path) {
+            // Aliases to folders aren't followed: the folder's path would
+            // be the alias file, which can't be listed
+            Some(target) if target.exists() => match link_entry(target, &path) {
+                Entry::Dir(_) => Entry::Other,
+                entry => entry,
+            },
+            Some(target) => broken(&path, Some(target), BrokenAliasReason::TargetMissing),
+            None => broken(&path, None, BrokenAliasReason::Unreadable),
+        };
+    }
+    Entry::Other
+}
+
+/// Everything one discovery walk found
+pub(crate) struct Discovery {
+    pub apps: Vec<DiscoveredApp>,
+    /// Subdirectories (and provider folders) holding 2+ apps
+    pub folders: Vec<(PathBuf, Vec<DiscoveredApp>)>,
+    pub broken_aliases: Vec<BrokenAlias>,
+}
+
+/// The walk's shared state: what it runs for and what it found so far
+struct Walk<'a> {
+    settings: &'a AppSettings,
+    own_bundle_id: &'a str,
+    own_app_path: Option<&'a Path>,
+    broken_aliases: Vec<BrokenAlias>,
+}
+
+impl Walk<'_> {
+    /// The app an entry leads to, if it is one this walk lists
+    fn app(
+        &mut self,
+        entry: Entry,
+        listing: AppSource,
+        volume: Option<&String>,
+    ) -> Result<Option<DiscoveredApp>, PathBuf> {
+        match entry {
+            Entry::App { path, via } => {
+                if is_own_bundle(&path, self.own_bundle_id, self.own_app_path) {
+                    return Ok(None);
+                }
+                let source = bundle_source(&path, listing);
+                Ok(
+                    source_enabled(source, self.settings).then(|| DiscoveredApp {
+                        path,
+                        source,
+                        volume: volume.cloned(),
+                        via,
+                        desktop_id: None,
+                    }),
+                )
             }
+            Entry::Broken(alias) => {
+                self.broken_aliases.push(alias);
+                Ok(None)
+            }
+            Entry::Dir(path) => Err(path),
+            Entry::Other => Ok(None),
+        }
+    }
+
+    fn apps_in_dir(
+        &mut self,
+        dir: &Path,
+        listing: AppSource,
+        volume: Option<&String>,
+    ) -> Vec<DiscoveredApp> {
+        let mut apps = Vec::new();
+        if let Ok(entries) = fs::read_dir(dir) {
+            for entry in entries.flatten() {
+                let entry = classify_entry(entry.path());
+                if let Ok(Some(app)) = self.app(entry, listing, volume) {
+                    apps.push(app);
+                }
+            }
+        }
+        sort_apps_by_name(&mut apps);
+        apps
+    }
+}
+
+fn add_subdir(
+    apps: &mut Vec<DiscoveredApp>,
+    folders: &mut Vec<(PathBuf, Vec<DiscoveredApp>)>,
+    path: PathBuf,
+    sub_apps: Vec<DiscoveredApp>,
+) {
+    if sub_apps.len() >= 2 {
+        // Only create folder if 2+ apps
+        folders.push((path, sub_apps));
+    } else {
+        // Single app goes to main list
+        apps.extend(sub_apps);
+    }
+}
+
+/// An alias to an app that is listed anyway (directly or through another
+/// alias) would make a second tile with the same path: keep the first
+fn drop_redundant_aliases(discovery: &mut Discovery) {
+    let mut seen: HashSet<PathBuf> = discovery
+        .apps
+        .iter()
+        .chain(discovery.folders.iter().flat_map(|(_, apps)| apps.iter()))
+        .filter(|app| app.via.is_none())
+        .map(|app| app.path.clone())
+        .collect();
+    let mut keep = |app: &DiscoveredApp| app.via.is_none() || seen.insert(app.path.clone());
+
+    discovery.apps.retain(&mut keep);
+    let mut dissolved = Vec::new();
+    for (_, apps) in &mut discovery.folders {
+        apps.retain(&mut keep);
+        if apps.len() < 2 {
+            dissolved.append(apps);
         }
     }
-    sort_paths_by_name(&mut apps);
-    apps
+    discovery.folders.retain(|(_, apps)| !apps.is_empty());
+    discovery.apps.extend(dissolved);
 }
 
+/// Walk the applications directories (plus enabled providers' locations)
+/// under `roots`. Apps in a subdirectory, or in a provider's folder,
+/// become a physical folder when there are 2+ of them. Aliases and
+/// symlinks are followed to their app; broken ones are reported instead.
+/// On Linux the desktop entries of the XDG applications directories are
+/// discovered instead (see xdg_discovery).
 pub(crate) fn discover_apps_and_folders(
     own_bundle_id: &str,
-) -> (Vec<PathBuf>, Vec<(PathBuf, Vec<PathBuf>)>) {
+    roots: &DiscoveryRoots,
+    settings: &AppSettings,
+) -> Discovery {
+    if cfg!(target_os = "linux") {
+        return discover_desktop_entries(roots, settings);
+    }
+    discover_bundles(own_bundle_id, roots, settings)
+}
+
+/// discover_apps_and_folders' walk of .app bundles
+fn discover_bundles(
+    own_bundle_id: &str,
+    roots: &DiscoveryRoots,
+    settings: &AppSettings,
+) -> Discovery {
     let mut apps = Vec::new();
-    let mut folders: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
+    let mut folders: Vec<(PathBuf, Vec<DiscoveredApp>)> = Vec::new();
     let own_app = own_bundle_path();
-    let own_app_path = own_app.as_deref();
+    let mut walk = Walk {
+        settings,
+        own_bundle_id,
+        own_app_path: own_app.as_deref(),
+        broken_aliases: Vec::new(),
+    };
 
-    for dir in get_applications_dirs() {
-        if let Ok(entries) = fs::read_dir(&dir) {
+    let provider_dirs = provider_folders(roots);
+    for (dir, listing, volume) in &bundle_listed_dirs(roots, settings) {
+        if let Ok(entries) = fs::read_dir(dir) {
             for entry in entries.flatten() {
-                let path = entry.path();
-                if path.extension().is_some_and(|ext| ext == "app") {
-                    if !is_own_bundle(&path, own_bundle_id, own_app_path) {
-                        apps.push(path);
-                    }
-                } else if path.is_dir() {
-                    // Check for apps in subdirectory (1 level deep)
-                    let sub_apps = get_apps_in_dir(&path, own_bundle_id, own_app_path);
-                    if sub_apps.len() >= 2 {
-                        // Only create folder if 2+ apps
-                        folders.push((path, sub_apps));
-                    } else if sub_apps.len() == 1 {
-                        // Single app goes to main list
-                        apps.extend(sub_apps);
+                let entry = classify_entry(entry.path());
+                match walk.app(entry, *listing, volume.as_ref()) {
+                    Ok(app) => apps.extend(app),
+                    Err(path) if !provider_dirs.iter().any(|(_, dir)| *dir == path) => {
+                        // Check for apps in subdirectory (1 level deep)
+                        let sub_apps = walk.apps_in_dir(&path, *listing, volume.as_ref());
+                        add_subdir(&mut apps, &mut folders, path, sub_apps);
                     }
+                    Err(_) => {}
                 }
             }
         }
     }
 
-    sort_paths_by_name(&mut apps);
+    for (source, dir) in provider_dirs {
+        if source_enabled(source, settings) {
+            let sub_apps = walk.apps_in_dir(&dir, source, None);
+            add_subdir(&mut apps, &mut folders, dir, sub_apps);
+        }
+    }
+
+    let mut discovery = Discovery {
+        apps,
+        folders,
+        broken_aliases: walk.broken_aliases,
+    };
+    drop_redundant_aliases(&mut discovery);
+
+    sort_apps_by_name(&mut discovery.apps);
 
-    folders.sort_by(|a, b| {
+    discovery.folders.sort_by(|a, b| {
         a.0.file_name()
             .unwrap_or_default()
             .to_string_lossy()
@@ -175,5 +423,134 @@ pub(crate) fn discover_apps_and_folders(
             )
     });
 
-    (apps, folders)
+    discovery
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    fn mkdir(path: &Path) -> PathBuf {
+        fs::create_dir_all(path).unwrap();
+        path.to_path_buf()
+    }
+
+    fn names(apps: &[DiscoveredApp]) -> Vec<String> {
+        apps.iter()
+            .map(|app| app.path.file_name().unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    /// A synthetic file system root: /Applications with plain, symlinked,
+    /// nested and grouped bundles, and /Volumes with a mounted volume, a
+    /// left-behind mount point and the boot volume's symlink
+    fn discover(show_external_volumes: bool) -> (tempfile::TempDir, PathBuf, Discovery) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let applications = mkdir(&root.join("Applications"));
+        let elsewhere = mkdir(&root.join("elsewhere"));
+
+        mkdir(&applications.join("Plain.app"));
+        mkdir(&applications.join("Outer.app/Contents/Helpers/Inner.app"));
+        symlink(
+            mkdir(&elsewhere.join("Linked.app")),
+            applications.join("Linked.app"),
+        )
+        .unwrap();
+        symlink(
+            mkdir(&elsewhere.join("Target.app")),
+            applications.join("Shortcut"),
+        )
+        .unwrap();
+        symlink(root.join("missing/Gone.app"), applications.join("Dangling")).unwrap();
+        mkdir(&applications.join("Utilities/A.app"));
+        mkdir(&applications.join("Utilities/B.app"));
+        mkdir(&applications.join("Lone/C.app"));
+        mkdir(&applications.join("Deep/Sub/D.app"));
+
+        mkdir(&root.join("Volumes/External/Applications/E.app"));
+        mkdir(&root.join("Volumes/Unplugged"));
+        mkdir(&root.join("Volumes/.timemachine/Applications/T.app"));
+        symlink(&root, root.join("Volumes/Macintosh HD")).unwrap();
+
+        let roots = DiscoveryRoots {
+            root: root.clone(),
+            home: None,
+        };
+        let settings = AppSettings {
+            show_external_volumes,
+            ..AppSettings::default()
+        };
+        (
+            dir,
+            root,
+            discover_bundles("com.example.launcher", &roots, &settings),
+        )
+    }
+
+    #[test]
+    fn symlinked_bundles_are_followed() {
+        let (_dir, root, discovery) = discover(false);
+        let linked = discovery
+            .apps
+            .iter()
+            .find(|app| app.path.ends_with("Linked.app"))
+            .unwrap();
+        // Listed by its link path, like the system apps in /Applications
+        assert_eq!(linked.path, root.join("Applications/Linked.app"));
+        assert_eq!(linked.via, None);
+
+        let target = discovery
+            .apps
+            .iter()
+            .find(|app| app.path.ends_with("Target.app"))
+            .unwrap();
+        assert_eq!(target.path, root.join("elsewhere/Target.app"));
+        assert_eq!(target.via, Some(root.join("Applications/Shortcut")));
+
+        assert_eq!(discovery.broken_aliases.len(), 1);
+        let dangling = &discovery.broken_aliases[0];
+        assert!(dangling.path.ends_with("Applications/Dangling"));
+        assert_eq!(dangling.reason, BrokenAliasReason::TargetMissing);
+    }
+
+    #[test]
+    fn nested_bundles_are_listed_one_level_deep() {
+        let (_dir, root, discovery) = discover(false);
+        assert_eq!(
+            names(&discovery.apps),
+            [
+                "C.app",
+                "Linked.app",
+                "Outer.app",
+                "Plain.app",
+                "Target.app"
+            ]
+        );
+        assert_eq!(discovery.folders.len(), 1);
+        let (folder, apps) = &discovery.folders[0];
+        assert_eq!(*folder, root.join("Applications/Utilities"));
+        assert_eq!(names(apps), ["A.app", "B.app"]);
+    }
+
+    #[test]
+    fn only_mounted_volumes_are_listed() {
+        let (_dir, _, discovery) = discover(true);
+        let on_volumes: Vec<&DiscoveredApp> = discovery
+            .apps
+            .iter()
+            .filter(|app| app.source == AppSource::ExternalVolume)
+            .collect();
+        assert_eq!(on_volumes.len(), 1);
+        assert!(on_volumes[0]
+            .path
+            .ends_with("Volumes/External/Applications/E.app"));
+        assert_eq!(on_volumes[0].volume.as_deref(), Some("External"));
+        // The boot volume's symlink doesn't list /Applications twice
+        assert_eq!(discovery.apps.len(), 6);
+
+        let (_dir, _, discovery) = discover(false);
+        assert!(discovery.apps.iter().all(|app| app.volume.is_none()));
+    }
 }
@@ -48,20 +48,44 @@ pub(crate) fn show_folder_menu(
 }
 
 /// Show the view-options menu (the "…" button) at the current cursor
-/// position, check-marking the active layout. The chosen layout is emitted
-/// as an "options-menu-action" event. Sync command: main thread, blocks
-/// until dismissed, like show_app_menu.
+/// position, check-marking the active layout, the view toggles named in
+/// `enabled`, the active duplicate and placement policies and how long
+/// missing apps keep their places (`ghost_grace_period_secs`). One-shot
+/// actions follow, those named in `actions` (the frontend offers only what
+/// applies, like grouping when there are tagged apps). The choice is emitted as an
+/// "options-menu-action" event. Sync command: main thread, blocks until
+/// dismissed, like show_app_menu.
 #[tauri::command]
 pub(crate) fn show_options_menu(
     window: tauri::WebviewWindow,
     layout: String,
+    enabled: Vec<String>,
+    duplicate_policy: String,
+    placement_policy: String,
+    ghost_grace_period_secs: u64,
+    actions: Vec<String>,
 ) -> Result<(), AppError> {
     #[cfg(target_os = "macos")]
-    return macos::show_options(&window, &layout);
+    return macos::show_options(
+        &window,
+        &layout,
+        &enabled,
+        &[&duplicate_policy, &placement_policy],
+        ghost_grace_period_secs,
+        &actions,
+    );
 
     #[cfg(not(target_os = "macos"))]
     {
-        let _ = (window, layout);
+        let _ = (
+            window,
+            layout,
+            enabled,
+            duplicate_policy,
+            placement_policy,
+            ghost_grace_period_secs,
+            actions,
+        );
         Err(AppError::Validation(
             "Options menu is only available on macOS".into(),
         ))
@@ -71,6 +95,7 @@ pub(crate) fn show_options_menu(
 #[cfg(target_os = "macos")]
 mod macos {
     use std::cell::RefCell;
+    use std::path::Path;
 
     use objc2::rc::Retained;
     use objc2::{define_class, msg_send, sel, DefinedClass, MainThreadMarker, MainThreadOnly};
@@ -79,6 +104,8 @@ mod macos {
     use serde::Serialize;
     use tauri::{Emitter, Manager};
 
+    use crate::bundle_audit::{audit_bundle, Severity};
+    use crate::quarantine::read_quarantine;
     use crate::AppError;
 
     /// One selectable menu entry
@@ -88,6 +115,10 @@ mod macos {
         symbol: Option<&'a str>,
         /// Check-marked (NSControlStateValueOn)
         checked: bool,
+        /// Preceded by a separator line, starting a new section
+        separator_before: bool,
+        /// Dimmed header shown above the item (after its separator)
+        header_before: Option<&'a str>,
     }
 
     struct HandlerIvars {
@@ -139,9 +170,8 @@ mod macos {
         items: &[ItemSpec],
         on_select: Box<dyn Fn(usize)>,
     ) -> Result<(), AppError> {
-        let mtm = MainThreadMarker::new().ok_or_else(|| {
-            AppError::Validation("Menus must be shown on the main thread".into())
-        })?;
+        let mtm = MainThreadMarker::new()
+            .ok_or_else(|| AppError::Validation("Menus must be shown on the main thread".into()))?;
 
         let handler = MenuHandler::new(mtm, on_select);
 
@@ -157,6 +187,15 @@ mod macos {
         }
 
         for (index, spec) in items.iter().enumerate() {
+            if spec.separator_before {
+                menu.addItem(&NSMenuItem::separatorItem(mtm));
+            }
+            if let Some(title) = spec.header_before {
+                let item = NSMenuItem::new(mtm);
+                item.setTitle(&NSString::from_str(title));
+                item.setEnabled(false);
+                menu.addItem(&item);
+            }
             let item = NSMenuItem::new(mtm);
             item.setTitle(&NSString::from_str(spec.title));
             item.setTag(index as isize);
@@ -203,14 +242,36 @@ mod macos {
         path: String,
     }
 
+    /// Offered for downloaded apps whose origin URL is known
+    const SHOW_ORIGIN: (&str, &str, &str) = ("Show Origin", "globe", "show-origin");
+
+    /// Offered for apps with a problem worth a badge (Warning or worse);
+    /// the frontend shows it in the info sheet
+    const SHOW_PROBLEM: (&str, &str, &str) =
+        ("Show Problem", "exclamationmark.triangle", "show-problem");
+
     pub(super) fn show_app(window: &tauri::WebviewWindow, path: String) -> Result<(), AppError> {
         let app = window.app_handle().clone();
-        let items: Vec<ItemSpec> = APP_ACTIONS
+        let has_origin = read_quarantine(Path::new(&path)).is_some_and(|q| q.origin.is_some());
+        let has_problem = audit_bundle(Path::new(&path))
             .iter()
-            .map(|(title, symbol, _)| ItemSpec {
+            .any(|problem| problem.severity >= Severity::Warning);
+        let actions: Vec<(&str, &str, &str)> = APP_ACTIONS
+            .iter()
+            .copied()
+            .chain(has_origin.then_some(SHOW_ORIGIN))
+            .chain(has_problem.then_some(SHOW_PROBLEM))
+            .collect();
+        let items: Vec<ItemSpec> = actions
+            .iter()
+            .enumerate()
+            .map(|(index, (title, symbol, _))| ItemSpec {
                 title,
                 symbol: Some(symbol),
                 checked: false,
+                // The extras after the standard actions form their own section
+                separator_before: index == APP_ACTIONS.len(),
+                header_before: None,
             })
             .collect();
 
@@ -218,7 +279,7 @@ mod macos {
             None,
             &items,
             Box::new(move |index| {
-                let Some((_, _, action)) = APP_ACTIONS.get(index) else {
+                let Some((_, _, action)) = actions.get(index) else {
                     return;
                 };
                 let _ = app.emit(
@@ -259,6 +320,8 @@ mod macos {
                 title,
                 symbol: Some(symbol),
                 checked: false,
+                separator_before: false,
+                header_before: None,
             })
             .collect();
 
@@ -284,33 +347,166 @@ mod macos {
     /// default (Paged) leads
     const LAYOUTS: [(&str, &str); 2] = [("Paged", "paged"), ("Scrollable", "scroll")];
 
+    /// Menu title, setting id — on/off view settings in their own section
+    /// below the layouts; ids match the AppSettings field names (camelCase)
+    /// and ViewToggle's serde names
+    const TOGGLES: [(&str, &str); 6] = [
+        ("Show Apps That Can't Open", "showUnlaunchable"),
+        ("Show Web Apps", "showWebApps"),
+        ("Show Setapp Apps", "showSetappApps"),
+        ("Show Homebrew Apps", "showHomebrewApps"),
+        ("Show iPhone & iPad Apps", "showIosApps"),
+        ("Show Apps on External Volumes", "showExternalVolumes"),
+    ];
+
+    /// Menu title, policy id — ids match DuplicatePolicy's serde names; a
+    /// section of its own below the toggles, the default (PreferSystem) first
+    const DUPLICATE_POLICIES: [(&str, &str); 4] = [
+        ("Prefer Copy in /Applications", "preferSystem"),
+        ("Prefer Newest Version", "preferNewest"),
+        ("Prefer Copy in ~/Applications", "preferUser"),
+        ("Show All Copies", "showAll"),
+    ];
+
+    /// Menu title, policy id — ids match PlacementPolicy's serde names; the
+    /// section after the duplicate policies, the default (EndOfLastPage) first
+    const PLACEMENT_POLICIES: [(&str, &str); 4] = [
+        ("At the End", "endOfLastPage"),
+        ("In the First Free Spot", "firstFreeSlot"),
+        ("On a New Page", "newPage"),
+        ("In Their Category Folder", "categoryFolder"),
+    ];
+
+    /// Menu title, seconds — how long a missing app keeps its place (see
+    /// Ghost), the section after the placement policies, shortest first
+    const GHOST_GRACE_PERIODS: [(&str, u64); 3] = [
+        ("For an Hour", 60 * 60),
+        ("For a Day", 24 * 60 * 60),
+        ("For a Week", 7 * 24 * 60 * 60),
+    ];
+
+    /// Menu title, action id — one-shot actions rather than settings, in a
+    /// last section; shown only when the frontend offers them
+    const OPTION_ACTIONS: [(&str, &str); 2] = [
+        ("Group by Finder Tags", "groupByTags"),
+        ("Mark All as Seen", "markAllSeen"),
+    ];
+
     #[derive(Clone, Serialize)]
-    struct LayoutPayload {
-        layout: &'static str,
+    #[serde(tag = "kind", rename_all = "camelCase")]
+    enum OptionsPayload {
+        Layout { layout: &'static str },
+        Toggle { setting: &'static str },
+        DuplicatePolicy { policy: &'static str },
+        PlacementPolicy { policy: &'static str },
+        GhostGracePeriod { secs: u64 },
+        Action { action: &'static str },
     }
 
     pub(super) fn show_options(
         window: &tauri::WebviewWindow,
         active_layout: &str,
+        enabled: &[String],
+        [active_duplicates, active_placement]: &[&str; 2],
+        active_grace_secs: u64,
+        offered: &[String],
     ) -> Result<(), AppError> {
         let app = window.app_handle().clone();
-        let items: Vec<ItemSpec> = LAYOUTS
+        let layouts = LAYOUTS.iter().map(|(title, layout)| ItemSpec {
+            title,
+            symbol: None,
+            checked: *layout == active_layout,
+            separator_before: false,
+            header_before: None,
+        });
+        let toggles = TOGGLES
             .iter()
-            .map(|(title, layout)| ItemSpec {
+            .enumerate()
+            .map(|(index, (title, setting))| ItemSpec {
                 title,
                 symbol: None,
-                checked: *layout == active_layout,
-            })
+                checked: enabled.iter().any(|e| e == setting),
+                separator_before: index == 0,
+                header_before: None,
+            });
+        let policies = DUPLICATE_POLICIES
+            .iter()
+            .enumerate()
+            .map(|(index, (title, policy))| ItemSpec {
+                title,
+                symbol: None,
+                checked: policy == active_duplicates,
+                separator_before: index == 0,
+                header_before: (index == 0).then_some("Duplicate apps"),
+            });
+        let placements = PLACEMENT_POLICIES
+            .iter()
+            .enumerate()
+            .map(|(index, (title, policy))| ItemSpec {
+                title,
+                symbol: None,
+                checked: policy == active_placement,
+                separator_before: index == 0,
+                header_before: (index == 0).then_some("Place new apps"),
+            });
+        let grace_periods = GHOST_GRACE_PERIODS
+            .iter()
+            .enumerate()
+            .map(|(index, (title, secs))| ItemSpec {
+                title,
+                symbol: None,
+                checked: *secs == active_grace_secs,
+                separator_before: index == 0,
+                header_before: (index == 0).then_some("Keep missing apps' places"),
+            });
+        let actions: Vec<(&'static str, &'static str)> = OPTION_ACTIONS
+            .into_iter()
+            .filter(|(_, action)| offered.iter().any(|o| o == action))
+            .collect();
+        let action_ids: Vec<&'static str> = actions.iter().map(|(_, action)| *action).collect();
+        let action_items = actions
+            .iter()
+            .enumerate()
+            .map(|(index, (title, _))| ItemSpec {
+                title,
+                symbol: None,
+                checked: false,
+                separator_before: index == 0,
+                header_before: None,
+            });
+        let items: Vec<ItemSpec> = layouts
+            .chain(toggles)
+            .chain(policies)
+            .chain(placements)
+            .chain(grace_periods)
+            .chain(action_items)
             .collect();
 
         popup(
             Some("View apps as"),
             &items,
             Box::new(move |index| {
-                let Some((_, layout)) = LAYOUTS.get(index) else {
+                let toggles_at = LAYOUTS.len();
+                let policies_at = toggles_at + TOGGLES.len();
+                let placements_at = policies_at + DUPLICATE_POLICIES.len();
+                let grace_periods_at = placements_at + PLACEMENT_POLICIES.len();
+                let actions_at = grace_periods_at + GHOST_GRACE_PERIODS.len();
+                let payload = if let Some((_, layout)) = LAYOUTS.get(index) {
+                    OptionsPayload::Layout { layout }
+                } else if let Some((_, setting)) = TOGGLES.get(index - toggles_at) {
+                    OptionsPayload::Toggle { setting }
+                } else if let Some((_, policy)) = DUPLICATE_POLICIES.get(index - policies_at) {
+                    OptionsPayload::DuplicatePolicy { policy }
+                } else if let Some((_, policy)) = PLACEMENT_POLICIES.get(index - placements_at) {
+                    OptionsPayload::PlacementPolicy { policy }
+                } else if let Some(&(_, secs)) = GHOST_GRACE_PERIODS.get(index - grace_periods_at) {
+                    OptionsPayload::GhostGracePeriod { secs }
+                } else if let Some(&action) = action_ids.get(index - actions_at) {
+                    OptionsPayload::Action { action }
+                } else {
                     return;
                 };
-                let _ = app.emit("options-menu-action", LayoutPayload { layout });
+                let _ = app.emit("options-menu-action", payload);
             }),
         )
     }
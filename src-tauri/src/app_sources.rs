@@ -0,0 +1,490 @@
+//! Discovery providers: where apps live besides the applications
+//! directories' own listings, each tagged with its AppSource and switched
+//! on or off in the settings.
+//!
+//! Every location is resolved against DiscoveryRoots rather than the real
+//! file system root and home folder, so each provider can be pointed at a
+//! synthetic directory tree.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::bundle_info::wrapped_ios_bundle;
+use crate::config::{AppSettings, AppSource};
+
+/// Where absolute locations are resolved: "/" and the user's home folder
+/// for real scans
+pub(crate) struct DiscoveryRoots {
+    pub root: PathBuf,
+    pub home: Option<PathBuf>,
+}
+
+impl DiscoveryRoots {
+    pub(crate) fn system() -> Self {
+        Self {
+            root: PathBuf::from("/"),
+            home: dirs::home_dir(),
+        }
+    }
+
+    fn at(&self, absolute: &str) -> PathBuf {
+        self.root.join(absolute.trim_start_matches('/'))
+    }
+
+    fn in_home(&self, relative: &str) -> Option<PathBuf> {
+        self.home.as_ref().map(|home| home.join(relative))
+    }
+
+    /// The directories .app bundles are installed in, listed in full:
+    /// system roots first
+    pub(crate) fn bundle_applications_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = vec![self.at("/Applications"), self.at("/System/Applications")];
+        dirs.extend(self.in_home("Applications"));
+        dirs
+    }
+
+    /// The XDG data directories, most important first: $XDG_DATA_HOME
+    /// (default ~/.local/share), then each of $XDG_DATA_DIRS (default
+    /// /usr/local/share:/usr/share). Relative entries are invalid per the
+    /// spec and skipped.
+    pub(crate) fn xdg_data_dirs(&self) -> Vec<PathBuf> {
+        let data_home = std::env::var("XDG_DATA_HOME")
+            .ok()
+            .filter(|dir| dir.starts_with('/'))
+            .map(|dir| self.at(&dir))
+            .or_else(|| self.in_home(".local/share"));
+        let data_dirs = std::env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| XDG_DATA_DIRS_DEFAULT.to_string());
+
+        let mut dirs: Vec<PathBuf> = Vec::new();
+        let all = data_home.into_iter().chain(
+            data_dirs
+                .split(':')
+                .filter(|dir| dir.starts_with('/'))
+                .map(|dir| self.at(dir)),
+        );
+        for dir in all {
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        dirs
+    }
+
+    /// The XDG data directories' applications subdirectories, in the
+    /// order desktop-file ids shadow each other
+    pub(crate) fn xdg_applications_dirs(&self) -> Vec<PathBuf> {
+        self.xdg_data_dirs()
+            .into_iter()
+            .map(|dir| dir.join("applications"))
+            .collect()
+    }
+
+    /// Icon theme base directories, in lookup order: ~/.icons, then each
+    /// XDG data directory's icons, then Flatpak's exported icons when the
+    /// session doesn't list its exports among the data directories
+    pub(crate) fn icon_theme_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = self.in_home(".icons").into_iter().collect();
+        for dir in self
+            .xdg_data_dirs()
+            .into_iter()
+            .chain(flatpak_exports(self))
+        {
+            let icons = dir.join("icons");
+            if !dirs.contains(&icons) {
+                dirs.push(icons);
+            }
+        }
+        dirs
+    }
+
+    /// Where unthemed icons are looked up last
+    pub(crate) fn pixmaps_dir(&self) -> PathBuf {
+        self.at("/usr/share/pixmaps")
+    }
+}
+
+/// $XDG_DATA_DIRS when unset or empty
+const XDG_DATA_DIRS_DEFAULT: &str = "/usr/local/share:/usr/share";
+
+/// Mounted external volumes with an Applications directory, by name. The
+/// boot volume's entry in /Volumes is a symlink to / and is skipped, as
+/// are hidden mounts (Time Machine's and the like).
+pub(crate) fn volume_applications_dirs(roots: &DiscoveryRoots) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(roots.at("/Volumes")) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let applications = entry.path().join("Applications");
+            (!name.starts_with('.') && applications.is_dir()).then_some((name, applications))
+        })
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+/// Whether /Volumes/<name> is mounted (as a real directory, not the boot
+/// volume's symlink)
+pub(crate) fn volume_mounted(roots: &DiscoveryRoots, name: &str) -> bool {
+    fs::symlink_metadata(roots.at("/Volumes").join(name)).is_ok_and(|m| m.is_dir())
+}
+
+/// Flatpak's exported share directories: per-user installs, then
+/// system-wide ones
+const FLATPAK_USER_EXPORTS: &str = ".local/share/flatpak/exports/share";
+const FLATPAK_SYSTEM_EXPORTS: &str = "/var/lib/flatpak/exports/share";
+
+/// Where snapd exports installed snaps' desktop entries
+const SNAP_APPLICATIONS: &str = "/var/lib/snapd/desktop/applications";
+
+/// Directories loose AppImages are kept in, in the home folder
+const APPIMAGE_DIRS: [&str; 1] = ["Applications"];
+
+/// Flatpak's exported share directories (holding applications and icons)
+pub(crate) fn flatpak_exports(roots: &DiscoveryRoots) -> Vec<PathBuf> {
+    roots
+        .in_home(FLATPAK_USER_EXPORTS)
+        .into_iter()
+        .chain([roots.at(FLATPAK_SYSTEM_EXPORTS)])
+        .collect()
+}
+
+/// Directories desktop entries are discovered in, in the order their ids
+/// shadow each other, with the source their entries get: the XDG
+/// applications directories (the session usually lists Flatpak's and
+/// Snap's exports among them), then those exports if it doesn't
+pub(crate) fn desktop_entry_dirs(roots: &DiscoveryRoots) -> Vec<(AppSource, PathBuf)> {
+    let flatpak: Vec<PathBuf> = flatpak_exports(roots)
+        .into_iter()
+        .map(|dir| dir.join("applications"))
+        .collect();
+    let snap = roots.at(SNAP_APPLICATIONS);
+    let source_of = |dir: &PathBuf| {
+        if flatpak.contains(dir) {
+            AppSource::Flatpak
+        } else if *dir == snap {
+            AppSource::Snap
+        } else {
+            AppSource::Applications
+        }
+    };
+
+    let mut dirs: Vec<(AppSource, PathBuf)> = roots
+        .xdg_applications_dirs()
+        .into_iter()
+        .map(|dir| (source_of(&dir), dir))
+        .collect();
+    for dir in flatpak.iter().chain([&snap]) {
+        if !dirs.iter().any(|(_, listed)| listed == dir) {
+            dirs.push((source_of(dir), dir.clone()));
+        }
+    }
+    dirs
+}
+
+/// Directories whose AppImages are listed
+pub(crate) fn appimage_dirs(roots: &DiscoveryRoots) -> Vec<PathBuf> {
+    APPIMAGE_DIRS
+        .iter()
+        .filter_map(|dir| roots.in_home(dir))
+        .collect()
+}
+
+/// Chromium browsers' web app (PWA) shims, one directory per browser
+const WEB_APP_DIRS: [&str; 4] = [
+    "Applications/Chrome Apps.localized",
+    "Applications/Edge Apps.localized",
+    "Applications/Brave Browser Apps.localized",
+    "Applications/Chromium Apps.localized",
+];
+
+/// Homebrew prefixes holding a Caskroom: Apple Silicon, then Intel
+const CASKROOMS: [&str; 2] = ["/opt/homebrew/Caskroom", "/usr/local/Caskroom"];
+
+/// Directories whose apps form one physical folder each, owned by a
+/// provider: listed only while it is enabled, never as an ordinary
+/// subfolder of an applications directory
+pub(crate) fn provider_folders(roots: &DiscoveryRoots) -> Vec<(AppSource, PathBuf)> {
+    let mut dirs: Vec<(AppSource, PathBuf)> = WEB_APP_DIRS
+        .iter()
+        .filter_map(|dir| roots.in_home(dir))
+        .map(|dir| (AppSource::WebApp, dir))
+        .collect();
+    dirs.push((AppSource::Setapp, roots.at("/Applications/Setapp")));
+    dirs
+}
+
+/// Custom --appdir directories of installed Homebrew casks, read from each
+/// cask's install-time config (Caskroom/<token>/.metadata/config.json,
+/// whose "explicit" options hold a non-default appdir). The applications
+/// directories themselves are left out: they are listed anyway.
+pub(crate) fn homebrew_appdirs(roots: &DiscoveryRoots) -> Vec<PathBuf> {
+    let standard = roots.bundle_applications_dirs();
+    let mut appdirs: Vec<PathBuf> = Vec::new();
+    for caskroom in CASKROOMS.iter().map(|dir| roots.at(dir)) {
+        let Ok(casks) = fs::read_dir(&caskroom) else {
+            continue;
+        };
+        for cask in casks.flatten() {
+            let Some(appdir) = cask_appdir(&cask.path(), roots) else {
+                continue;
+            };
+            if !standard.contains(&appdir) && !appdirs.contains(&appdir) {
+                appdirs.push(appdir);
+            }
+        }
+    }
+    appdirs
+}
+
+/// A cask's custom appdir, if it is a directory that can be listed. The
+/// file system root and the home folder are refused: every app under an
+/// appdir can be acted on, so either would open up the whole disk.
+fn cask_appdir(cask: &Path, roots: &DiscoveryRoots) -> Option<PathBuf> {
+    let contents = fs::read_to_string(cask.join(".metadata").join("config.json")).ok()?;
+    let config: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    let appdir = config.get("explicit")?.get("appdir")?.as_str()?;
+    // Homebrew stores the option as given, so ~ may be unexpanded
+    let appdir = match appdir.strip_prefix("~/") {
+        Some(relative) => roots.in_home(relative)?,
+        None if appdir.starts_with('/') => roots.at(appdir),
+        None => return None,
+    };
+    let resolved = fs::canonicalize(&appdir).ok()?;
+    let too_broad = [Some(&roots.root), roots.home.as_ref()]
+        .into_iter()
+        .flatten()
+        .any(|dir| fs::canonicalize(dir).is_ok_and(|dir| dir == resolved));
+    (resolved.is_dir() && !too_broad).then_some(appdir)
+}
+
+/// Source of a bundle found in an applications directory (or a Homebrew
+/// appdir): iOS apps are recognized by their Wrapper/ layout
+pub(crate) fn bundle_source(bundle: &Path, listing: AppSource) -> AppSource {
+    if wrapped_ios_bundle(bundle).is_some() {
+        AppSource::IosApp
+    } else {
+        listing
+    }
+}
+
+/// Whether the settings let apps from `source` be discovered
+pub(crate) fn source_enabled(source: AppSource, settings: &AppSettings) -> bool {
+    match source {
+        AppSource::Applications => true,
+        AppSource::WebApp => settings.show_web_apps,
+        AppSource::Setapp => settings.show_setapp_apps,
+        AppSource::Homebrew => settings.show_homebrew_apps,
+        AppSource::IosApp => settings.show_ios_apps,
+        AppSource::ExternalVolume => settings.show_external_volumes,
+        AppSource::Flatpak => settings.show_flatpak_apps,
+        AppSource::Snap => settings.show_snap_apps,
+        AppSource::AppImage => settings.show_appimages,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn volumes_without_applications_or_hidden_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = DiscoveryRoots {
+            root: dir.path().to_path_buf(),
+            home: None,
+        };
+        assert!(volume_applications_dirs(&roots).is_empty());
+
+        let volumes = dir.path().join("Volumes");
+        fs::create_dir_all(volumes.join("Backup/Applications")).unwrap();
+        fs::create_dir_all(volumes.join("Archive/Applications")).unwrap();
+        fs::create_dir_all(volumes.join("Unplugged")).unwrap();
+        fs::create_dir_all(volumes.join(".timemachine/Applications")).unwrap();
+        symlink(dir.path(), volumes.join("Macintosh HD")).unwrap();
+        fs::create_dir_all(dir.path().join("Applications")).unwrap();
+
+        assert_eq!(
+            volume_applications_dirs(&roots),
+            [
+                ("Archive".to_string(), volumes.join("Archive/Applications")),
+                ("Backup".to_string(), volumes.join("Backup/Applications")),
+            ]
+        );
+        assert!(volume_mounted(&roots, "Backup"));
+        assert!(volume_mounted(&roots, "Unplugged"));
+        assert!(!volume_mounted(&roots, "Macintosh HD"));
+        assert!(!volume_mounted(&roots, "Ejected"));
+    }
+
+    #[test]
+    fn locations_resolve_under_the_roots() {
+        let roots = DiscoveryRoots {
+            root: PathBuf::from("/fake"),
+            home: Some(PathBuf::from("/fake/home")),
+        };
+        assert_eq!(
+            roots.bundle_applications_dirs(),
+            [
+                PathBuf::from("/fake/Applications"),
+                PathBuf::from("/fake/System/Applications"),
+                PathBuf::from("/fake/home/Applications"),
+            ]
+        );
+        let no_home = DiscoveryRoots {
+            root: PathBuf::from("/fake"),
+            home: None,
+        };
+        assert_eq!(no_home.bundle_applications_dirs().len(), 2);
+        assert!(appimage_dirs(&no_home).is_empty());
+    }
+
+    /// A synthetic root with a home folder at /Users/me
+    fn synthetic_roots() -> (tempfile::TempDir, DiscoveryRoots) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        let home = root.join("Users/me");
+        fs::create_dir_all(&home).unwrap();
+        let roots = DiscoveryRoots {
+            root,
+            home: Some(home),
+        };
+        (dir, roots)
+    }
+
+    fn cask(roots: &DiscoveryRoots, caskroom: &str, token: &str, config: &str) {
+        let metadata = roots.at(caskroom).join(token).join(".metadata");
+        fs::create_dir_all(&metadata).unwrap();
+        fs::write(metadata.join("config.json"), config).unwrap();
+    }
+
+    fn appdir_config(appdir: &str) -> String {
+        format!(
+            r#"{{"default": {{"appdir": "/Applications"}}, "explicit": {{"appdir": "{appdir}"}}}}"#
+        )
+    }
+
+    #[test]
+    fn web_app_dirs_are_in_the_home_folder() {
+        let (_dir, roots) = synthetic_roots();
+        let home = roots.home.clone().unwrap();
+        let chrome = home.join("Applications/Chrome Apps.localized");
+        fs::create_dir_all(chrome.join("Docs.app")).unwrap();
+
+        let web_apps: Vec<PathBuf> = provider_folders(&roots)
+            .into_iter()
+            .filter(|(source, _)| *source == AppSource::WebApp)
+            .map(|(_, dir)| dir)
+            .collect();
+        assert_eq!(web_apps.len(), WEB_APP_DIRS.len());
+        assert_eq!(web_apps[0], chrome);
+        assert!(web_apps.iter().all(|dir| dir.starts_with(&home)));
+
+        let no_home = DiscoveryRoots {
+            root: roots.root.clone(),
+            home: None,
+        };
+        assert!(provider_folders(&no_home)
+            .iter()
+            .all(|(source, _)| *source != AppSource::WebApp));
+    }
+
+    #[test]
+    fn setapp_folder_is_under_the_root() {
+        let (_dir, roots) = synthetic_roots();
+        let setapp = roots.root.join("Applications/Setapp");
+        fs::create_dir_all(setapp.join("CleanShot X.app")).unwrap();
+        assert!(provider_folders(&roots).contains(&(AppSource::Setapp, setapp)));
+    }
+
+    #[test]
+    fn cask_appdirs_expand_the_home_folder_and_resolve_absolute_paths() {
+        let (_dir, roots) = synthetic_roots();
+        let home = roots.home.clone().unwrap();
+        fs::create_dir_all(home.join("Apps")).unwrap();
+        fs::create_dir_all(roots.at("/opt/apps")).unwrap();
+        fs::create_dir_all(roots.at("/Applications")).unwrap();
+
+        cask(&roots, CASKROOMS[0], "firefox", &appdir_config("~/Apps"));
+        cask(&roots, CASKROOMS[1], "iterm2", &appdir_config("/opt/apps"));
+        // Already listed, the same appdir twice, or no custom appdir at all
+        cask(
+            &roots,
+            CASKROOMS[0],
+            "slack",
+            &appdir_config("/Applications"),
+        );
+        cask(&roots, CASKROOMS[1], "zoom", &appdir_config("/opt/apps"));
+        cask(&roots, CASKROOMS[0], "vlc", r#"{"explicit": {}}"#);
+        cask(&roots, CASKROOMS[0], "broken", "{");
+        fs::create_dir_all(roots.at(CASKROOMS[0]).join("unconfigured")).unwrap();
+
+        let mut appdirs = homebrew_appdirs(&roots);
+        appdirs.sort();
+        assert_eq!(appdirs, [home.join("Apps"), roots.at("/opt/apps")]);
+    }
+
+    #[test]
+    fn cask_appdirs_that_are_too_broad_or_not_directories_are_refused() {
+        let (_dir, roots) = synthetic_roots();
+        fs::write(roots.at("/apps.txt"), "").unwrap();
+        for appdir in [
+            "/",
+            "~/",
+            "/Users/me",
+            "/Users/me/.",
+            "/apps.txt",
+            "/missing",
+            "Apps",
+        ] {
+            cask(&roots, CASKROOMS[0], "app", &appdir_config(appdir));
+            assert_eq!(homebrew_appdirs(&roots), Vec::<PathBuf>::new(), "{appdir}");
+        }
+
+        // A symlink to the home folder is the home folder
+        symlink(roots.home.as_ref().unwrap(), roots.at("/home-link")).unwrap();
+        cask(&roots, CASKROOMS[0], "app", &appdir_config("/home-link"));
+        assert!(homebrew_appdirs(&roots).is_empty());
+
+        let no_home = DiscoveryRoots {
+            root: roots.root.clone(),
+            home: None,
+        };
+        fs::create_dir_all(roots.home.as_ref().unwrap().join("Apps")).unwrap();
+        cask(&roots, CASKROOMS[0], "app", &appdir_config("~/Apps"));
+        assert!(homebrew_appdirs(&no_home).is_empty());
+    }
+
+    #[test]
+    fn ios_apps_are_recognized_by_their_wrapper() {
+        let (_dir, roots) = synthetic_roots();
+        let applications = roots.at("/Applications");
+        let ios = applications.join("Threes.app");
+        fs::create_dir_all(ios.join("Wrapper/Threes.app")).unwrap();
+        let mac = applications.join("Notes.app");
+        fs::create_dir_all(mac.join("Contents/MacOS")).unwrap();
+        let empty_wrapper = applications.join("Odd.app");
+        fs::create_dir_all(empty_wrapper.join("Wrapper/Resources")).unwrap();
+
+        assert_eq!(
+            bundle_source(&ios, AppSource::Applications),
+            AppSource::IosApp
+        );
+        assert_eq!(bundle_source(&ios, AppSource::Homebrew), AppSource::IosApp);
+        assert_eq!(
+            bundle_source(&mac, AppSource::Homebrew),
+            AppSource::Homebrew
+        );
+        assert_eq!(
+            bundle_source(&empty_wrapper, AppSource::Applications),
+            AppSource::Applications
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! AppImages: a single executable file per app, an ELF runtime with the
+//! app's files appended as a SquashFS image. The image's root holds the
+//! app's desktop entry and icon (and .DirIcon, the icon by convention),
+//! read here straight from the file: running the AppImage to mount its
+//! payload would start untrusted code just to list it.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::config::Launchability;
+use crate::desktop_entry::{is_executable, DesktopEntry};
+use crate::icon_theme::render_icon_data;
+use crate::squashfs::SquashFs;
+
+/// Formats an AppImage's icon may have at the image root, in lookup order
+const ICON_EXTENSIONS: [&str; 2] = ["png", "svg"];
+
+/// Whether the file at `path` is named like an AppImage (Foo.AppImage;
+/// the case varies)
+pub(crate) fn is_appimage(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("appimage"))
+}
+
+/// Where the SquashFS payload starts: right after the ELF runtime, whose
+/// section header table is the last thing in it. Only type 2 AppImages
+/// ("AI\x02" in the ELF padding) have one; type 1 is an ISO image.
+fn payload_offset(path: &Path) -> Option<u64> {
+    let mut header = [0u8; 64];
+    File::open(path).ok()?.read_exact(&mut header).ok()?;
+    if &header[..4] != b"\x7fELF" || &header[8..11] != b"AI\x02" {
+        return None;
+    }
+    let big_endian = header[5] == 2;
+    let u16_at = |at: usize| {
+        let bytes = [header[at], header[at + 1]];
+        if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    };
+    let (section_headers, entry_size, entries) = match header[4] {
+        // ELFCLASS32
+        1 => {
+            let bytes: [u8; 4] = header[0x20..0x24].try_into().ok()?;
+            let offset = if big_endian {
+                u32::from_be_bytes(bytes)
+            } else {
+                u32::from_le_bytes(bytes)
+            };
+            (offset as u64, u16_at(0x2e), u16_at(0x30))
+        }
+        // ELFCLASS64
+        2 => {
+            let bytes: [u8; 8] = header[0x28..0x30].try_into().ok()?;
+            let offset = if big_endian {
+                u64::from_be_bytes(bytes)
+            } else {
+                u64::from_le_bytes(bytes)
+            };
+            (offset, u16_at(0x3a), u16_at(0x3c))
+        }
+        _ => return None,
+    };
+    section_headers.checked_add(entry_size as u64 * entries as u64)
+}
+
+/// An AppImage's payload, opened for reading
+pub(crate) struct AppImage {
+    image: SquashFs,
+}
+
+impl AppImage {
+    pub(crate) fn open(path: &Path) -> Option<Self> {
+        let image = SquashFs::open(path, payload_offset(path)?)?;
+        Some(Self { image })
+    }
+
+    /// The desktop entry at the image root, and its desktop-file id
+    /// (minus ".desktop")
+    pub(crate) fn desktop_entry(&self) -> Option<(String, DesktopEntry)> {
+        self.image
+            .root_names()
+            .into_iter()
+            .filter_map(|name| Some(name.strip_suffix(".desktop")?.to_string()))
+            .find_map(|id| {
+                let contents = self.image.read_file(&format!("{id}.desktop"))?;
+                let entry = DesktopEntry::parse(&String::from_utf8(contents).ok()?)?;
+                Some((id, entry))
+            })
+    }
+
+    /// The icon file's contents: the one the entry's Icon names at the
+    /// image root, else .DirIcon
+    pub(crate) fn icon_data(&self, entry: Option<&DesktopEntry>) -> Option<Vec<u8>> {
+        entry
+            .and_then(|entry| entry.string("Icon"))
+            .filter(|icon| !icon.is_empty() && !icon.contains('/'))
+            .and_then(|icon| {
+                ICON_EXTENSIONS
+                    .iter()
+                    .find_map(|ext| self.image.read_file(&format!("{icon}.{ext}")))
+            })
+            .or_else(|| self.image.read_file(".DirIcon"))
+    }
+}
+
+/// Whether the AppImage can be started: it runs itself, so it needs its
+/// exec bit (a fresh download doesn't have one)
+pub(crate) fn classify_appimage_launchability(path: &Path) -> Launchability {
+    if is_executable(path) {
+        Launchability::Launchable
+    } else {
+        Launchability::MissingExecutable
+    }
+}
+
+/// PNG bytes of an AppImage's icon at `size`
+pub(crate) fn appimage_icon(path: &Path, size: u32) -> Option<Vec<u8>> {
+    let appimage = AppImage::open(path)?;
+    let entry = appimage.desktop_entry().map(|(_, entry)| entry);
+    render_icon_data(&appimage.icon_data(entry.as_ref())?, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::squashfs::tests::ImageBuilder;
+    use crate::squashfs::COMPRESSION_GZIP;
+    use std::io::Write;
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\nicon";
+
+    /// A type 2 AppImage: a 64-bit ELF header whose section header table
+    /// (two entries) ends the runtime, then the SquashFS payload
+    fn appimage(files: &[(&str, &[u8])]) -> tempfile::TempPath {
+        let mut builder = ImageBuilder::new(COMPRESSION_GZIP, 4096);
+        let entries: Vec<(&str, u64)> = files
+            .iter()
+            .map(|(name, contents)| (*name, builder.file(contents)))
+            .collect();
+        let root = builder.dir(&entries);
+
+        let mut runtime = vec![0u8; 192];
+        runtime[..4].copy_from_slice(b"\x7fELF");
+        runtime[4] = 2;
+        runtime[5] = 1;
+        runtime[8..11].copy_from_slice(b"AI\x02");
+        runtime[0x28..0x30].copy_from_slice(&64u64.to_le_bytes());
+        runtime[0x3a..0x3c].copy_from_slice(&64u16.to_le_bytes());
+        runtime[0x3c..0x3e].copy_from_slice(&2u16.to_le_bytes());
+
+        let mut file = tempfile::Builder::new()
+            .suffix(".AppImage")
+            .tempfile()
+            .unwrap();
+        file.write_all(&runtime).unwrap();
+        file.write_all(&builder.finish(root)).unwrap();
+        file.into_temp_path()
+    }
+
+    #[test]
+    fn reads_the_desktop_entry_and_icon() {
+        let path = appimage(&[
+            (
+                "org.example.Editor.desktop",
+                b"[Desktop Entry]\nName=Editor\nIcon=editor\n",
+            ),
+            ("editor.png", PNG),
+            (".DirIcon", b"not the named icon"),
+        ]);
+        assert!(is_appimage(&path));
+        let appimage = AppImage::open(&path).unwrap();
+        let (id, entry) = appimage.desktop_entry().unwrap();
+        assert_eq!(id, "org.example.Editor");
+        assert_eq!(entry.name().as_deref(), Some("Editor"));
+        assert_eq!(appimage.icon_data(Some(&entry)).as_deref(), Some(PNG));
+        assert_eq!(appimage_icon(&path, 128).as_deref(), Some(PNG));
+    }
+
+    #[test]
+    fn falls_back_to_the_dir_icon() {
+        let path = appimage(&[
+            (
+                "tool.desktop",
+                b"[Desktop Entry]\nName=Tool\nIcon=missing\n",
+            ),
+            (".DirIcon", PNG),
+        ]);
+        let appimage = AppImage::open(&path).unwrap();
+        let (_, entry) = appimage.desktop_entry().unwrap();
+        assert_eq!(appimage.icon_data(Some(&entry)).as_deref(), Some(PNG));
+        assert_eq!(appimage.icon_data(None).as_deref(), Some(PNG));
+    }
+
+    #[test]
+    fn rejects_other_executables() {
+        let path = appimage(&[("tool.desktop", b"[Desktop Entry]\nName=Tool\n")]);
+        let mut contents = std::fs::read(&path).unwrap();
+        assert_eq!(payload_offset(&path), Some(192));
+
+        // A plain ELF executable, and a type 1 (ISO) AppImage
+        for magic in [b"\0\0\0", b"AI\x01"] {
+            contents[8..11].copy_from_slice(magic);
+            std::fs::write(&path, &contents).unwrap();
+            assert_eq!(payload_offset(&path), None);
+            assert!(AppImage::open(&path).is_none());
+        }
+        std::fs::write(&path, b"#!/bin/sh\necho not an AppImage\n").unwrap();
+        assert!(AppImage::open(&path).is_none());
+    }
+}
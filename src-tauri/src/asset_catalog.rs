@@ -0,0 +1,634 @@
+//! App icons read from a compiled asset catalog (Contents/Resources/
+//! Assets.car), where apps built with a CFBundleIconName keep their icon,
+//! some without any .icns beside it.
+//!
+//! Assets.car is a BOM store: a table of blocks and named variables, some
+//! of them B-trees of key/value blocks (big-endian throughout). The
+//! catalog's own structures inside those blocks are little-endian:
+//! KEYFORMAT lists the attributes making up a rendition key, FACETKEYS
+//! maps a name ("AppIcon") to its identifier attribute, and RENDITIONS
+//! maps keys to renditions. A rendition is a CSI header (size, pixel
+//! format), then its pixels in one or more compressed chunks, or for
+//! DATA renditions the raw file. Bitmaps stored raw, zlib- or
+//! LZFSE-compressed are decoded; the newer "deepmap" and ASTC encodings
+//! aren't, which leaves those apps to the other icon sources.
+
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use resvg::tiny_skia::{Pixmap, PremultipliedColorU8};
+
+use crate::bundle_info::BundleInfo;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// The rendition key attribute linking renditions to their facet's name
+/// (kCRThemeIdentifierName)
+const IDENTIFIER_ATTRIBUTE: u16 = 17;
+
+/// Largest rendition edge decoded, in pixels
+const MAX_EDGE: u32 = 4096;
+
+/// Offset of a rendition's TLV data, after the fixed CSI header
+const CSI_HEADER_LEN: usize = 184;
+
+/// Chunk compression types (CELM) that can be undone
+const COMPRESSION_NONE: u32 = 0;
+const COMPRESSION_ZIP: u32 = 2;
+const COMPRESSION_LZFSE: u32 = 4;
+
+/// A four-character code as the catalog stores it: a little-endian u32
+/// (so 'ISTC' is the bytes "CTSI")
+const fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_be_bytes(*code)
+}
+
+fn be_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn le_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// A BOM store's block table and variables
+struct Bom<'a> {
+    data: &'a [u8],
+    /// Address and length of each block, by index
+    blocks: Vec<(usize, usize)>,
+    /// Variable names and their block indices
+    vars: Vec<(&'a [u8], u32)>,
+}
+
+impl<'a> Bom<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        if data.get(..8)? != b"BOMStore" {
+            return None;
+        }
+        let index = be_u32(data, 16)? as usize;
+        let blocks = (0..be_u32(data, index)? as usize)
+            .map(|i| {
+                let at = index + 4 + i * 8;
+                Some((be_u32(data, at)? as usize, be_u32(data, at + 4)? as usize))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let mut at = be_u32(data, 24)? as usize;
+        let count = be_u32(data, at)?;
+        at += 4;
+        let mut vars = Vec::new();
+        for _ in 0..count {
+            let block = be_u32(data, at)?;
+            let len = *data.get(at + 4)? as usize;
+            vars.push((data.get(at + 5..at + 5 + len)?, block));
+            at += 5 + len;
+        }
+        Some(Self { data, blocks, vars })
+    }
+
+    fn block(&self, index: u32) -> Option<&'a [u8]> {
+        let &(address, len) = self.blocks.get(index as usize)?;
+        self.data.get(address..address.checked_add(len)?)
+    }
+
+    fn var(&self, name: &str) -> Option<&'a [u8]> {
+        let (_, block) = self.vars.iter().find(|(var, _)| *var == name.as_bytes())?;
+        self.block(*block)
+    }
+
+    /// Every key and value of the B-tree variable `name`, in key order:
+    /// down the first branches to the leftmost leaf, then along the
+    /// leaves' forward links
+    fn tree(&self, name: &str) -> Option<Vec<(&'a [u8], &'a [u8])>> {
+        let tree = self.var(name)?;
+        if tree.get(..4)? != b"tree" {
+            return None;
+        }
+        let mut paths = self.block(be_u32(tree, 8)?)?;
+        let mut hops = 0;
+        // A paths block: isLeaf, count, forward, backward, then count
+        // (value or child, key) index pairs
+        while be_u16(paths, 0)? == 0 {
+            hops += 1;
+            if hops > self.blocks.len() {
+                return None;
+            }
+            paths = self.block(be_u32(paths, 12)?)?;
+        }
+        let mut pairs = Vec::new();
+        loop {
+            for i in 0..be_u16(paths, 2)? as usize {
+                let at = 12 + i * 8;
+                let value = self.block(be_u32(paths, at)?)?;
+                let key = self.block(be_u32(paths, at + 4)?)?;
+                pairs.push((key, value));
+            }
+            let forward = be_u32(paths, 4)?;
+            hops += 1;
+            if forward == 0 || hops > self.blocks.len() {
+                break;
+            }
+            paths = self.block(forward)?;
+        }
+        Some(pairs)
+    }
+}
+
+/// The identifier attribute of a facet's key token: a hot spot, then
+/// (attribute, value) pairs
+fn facet_identifier(token: &[u8]) -> Option<u16> {
+    (0..le_u16(token, 4)? as usize).find_map(|i| {
+        let at = 6 + i * 4;
+        if le_u16(token, at)? == IDENTIFIER_ATTRIBUTE {
+            le_u16(token, at + 2)
+        } else {
+            None
+        }
+    })
+}
+
+/// A rendition's size and encoded pixels, from its CSI header
+struct Rendition<'a> {
+    width: u32,
+    height: u32,
+    pixel_format: u32,
+    /// What follows the header and its TLV data
+    data: &'a [u8],
+}
+
+impl<'a> Rendition<'a> {
+    fn parse(value: &'a [u8]) -> Option<Self> {
+        if le_u32(value, 0)? != fourcc(b"ISTC") {
+            return None;
+        }
+        let tlv_len = le_u32(value, 168)? as usize;
+        Some(Self {
+            width: le_u32(value, 12)?,
+            height: le_u32(value, 16)?,
+            pixel_format: le_u32(value, 24)?,
+            data: value.get(CSI_HEADER_LEN.checked_add(tlv_len)?..)?,
+        })
+    }
+
+    /// The pixels of every CELM chunk, decompressed and joined until
+    /// `len` bytes are out; no chunk unpacks past that. Chunks may come
+    /// wrapped (KCBC), so each is found by its tag.
+    fn pixels(&self, len: usize) -> Option<Vec<u8>> {
+        let mut pixels = Vec::with_capacity(len);
+        let mut at = 0;
+        while pixels.len() < len {
+            at += (at..self.data.len().saturating_sub(3))
+                .position(|i| le_u32(self.data, i) == Some(fourcc(b"CELM")))?;
+            let compression = le_u32(self.data, at + 8)?;
+            let chunk_len = le_u32(self.data, at + 12)? as usize;
+            let chunk = self.data.get(at + 16..(at + 16).checked_add(chunk_len)?)?;
+            match compression {
+                COMPRESSION_NONE => pixels.extend_from_slice(chunk),
+                COMPRESSION_ZIP => {
+                    flate2::read::ZlibDecoder::new(chunk)
+                        .take((len - pixels.len()) as u64)
+                        .read_to_end(&mut pixels)
+                        .ok()?;
+                }
+                COMPRESSION_LZFSE => {
+                    let mut decoder = lzfse_rust::LzfseRingDecoder::default();
+                    decoder
+                        .reader_bytes(chunk)
+                        .take((len - pixels.len()) as u64)
+                        .read_to_end(&mut pixels)
+                        .ok()?;
+                }
+                _ => return None,
+            }
+            at += 16 + chunk_len;
+        }
+        pixels.truncate(len);
+        Some(pixels)
+    }
+
+    /// The rendition as PNG bytes: a DATA rendition's file if it is a
+    /// PNG, else its premultiplied ARGB or gray+alpha bitmap encoded
+    fn to_png(&self) -> Option<Vec<u8>> {
+        if self.pixel_format == fourcc(b"DATA") {
+            if le_u32(self.data, 0)? != fourcc(b"RAWD") {
+                return None;
+            }
+            let len = le_u32(self.data, 8)? as usize;
+            let raw = self.data.get(12..12usize.checked_add(len)?)?;
+            return raw.starts_with(PNG_SIGNATURE).then(|| raw.to_vec());
+        }
+        let bytes_per_pixel = match self.pixel_format {
+            format if format == fourcc(b"ARGB") => 4,
+            format if format == fourcc(b"GA8 ") => 2,
+            _ => return None,
+        };
+        if self.width == 0 || self.width > MAX_EDGE || self.height > MAX_EDGE {
+            return None;
+        }
+        let pixels = self.pixels((self.width * self.height) as usize * bytes_per_pixel)?;
+        let mut pixmap = Pixmap::new(self.width, self.height)?;
+        for (pixel, source) in pixmap
+            .pixels_mut()
+            .iter_mut()
+            .zip(pixels.chunks_exact(bytes_per_pixel))
+        {
+            // ARGB is stored as B, G, R, A bytes; gray+alpha as G, A
+            let color = match *source {
+                [blue, green, red, alpha] => {
+                    PremultipliedColorU8::from_rgba(red, green, blue, alpha)
+                }
+                [gray, alpha] => PremultipliedColorU8::from_rgba(gray, gray, gray, alpha),
+                _ => None,
+            };
+            *pixel = color.unwrap_or(PremultipliedColorU8::TRANSPARENT);
+        }
+        pixmap.encode_png().ok()
+    }
+}
+
+/// PNG bytes of the catalog image named `name` that best fits `size`:
+/// the smallest rendition at least that large, else the largest
+pub(crate) fn catalog_image(car: &[u8], name: &str, size: u32) -> Option<Vec<u8>> {
+    let bom = Bom::parse(car)?;
+    let key_format = bom.var("KEYFORMAT")?;
+    if le_u32(key_format, 0)? != fourcc(b"kfmt") {
+        return None;
+    }
+    let identifier_at = (0..le_u32(key_format, 8)? as usize)
+        .position(|i| le_u32(key_format, 12 + i * 4) == Some(IDENTIFIER_ATTRIBUTE as u32))?;
+    let identifier = bom
+        .tree("FACETKEYS")?
+        .into_iter()
+        .find(|(key, _)| key.strip_suffix(b"\0").unwrap_or(key) == name.as_bytes())
+        .and_then(|(_, token)| facet_identifier(token))?;
+
+    let mut renditions: Vec<Rendition> = bom
+        .tree("RENDITIONS")?
+        .into_iter()
+        .filter(|(key, _)| le_u16(key, identifier_at * 2) == Some(identifier))
+        .filter_map(|(_, value)| Rendition::parse(value))
+        .collect();
+    renditions.sort_by_key(|rendition| {
+        let edge = rendition.width.max(rendition.height);
+        if edge >= size {
+            (false, edge - size)
+        } else {
+            (true, size - edge)
+        }
+    });
+    renditions.iter().find_map(Rendition::to_png)
+}
+
+/// PNG bytes of a bundle's CFBundleIconName icon from its Assets.car at
+/// about `size`, when it has one
+pub(crate) fn bundle_catalog_icon(bundle: &Path, size: u32) -> Option<Vec<u8>> {
+    let info = BundleInfo::read(bundle)?;
+    let name = info.string("CFBundleIconName").filter(|n| !n.is_empty())?;
+    let car = fs::read(bundle.join("Contents/Resources/Assets.car")).ok()?;
+    catalog_image(&car, name, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Key attributes of the fixture catalogs: scale, then identifier
+    const SCALE_ATTRIBUTE: u32 = 12;
+
+    /// A BOM store under construction: blocks by index (0 is the null
+    /// block) and named variables
+    struct Car {
+        blocks: Vec<Vec<u8>>,
+        vars: Vec<(&'static str, u32)>,
+    }
+
+    fn be(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    fn le(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    impl Car {
+        fn new() -> Self {
+            Self {
+                blocks: vec![Vec::new()],
+                vars: Vec::new(),
+            }
+        }
+
+        fn block(&mut self, bytes: Vec<u8>) -> u32 {
+            self.blocks.push(bytes);
+            self.blocks.len() as u32 - 1
+        }
+
+        fn var(&mut self, name: &'static str, bytes: Vec<u8>) {
+            let block = self.block(bytes);
+            self.vars.push((name, block));
+        }
+
+        /// A leaf paths block of (value, key) block pairs
+        fn leaf(&mut self, pairs: &[(u32, u32)], forward: u32) -> u32 {
+            let mut leaf = vec![0, 1];
+            leaf.extend_from_slice(&(pairs.len() as u16).to_be_bytes());
+            leaf.extend(be(&[forward, 0]));
+            for &(value, key) in pairs {
+                leaf.extend(be(&[value, key]));
+            }
+            self.block(leaf)
+        }
+
+        /// A B-tree variable of `pairs`: two leaves linked forward under a
+        /// branch, as soon as there are two pairs
+        fn tree(&mut self, name: &'static str, pairs: Vec<(Vec<u8>, Vec<u8>)>) {
+            let indices: Vec<(u32, u32)> = pairs
+                .into_iter()
+                .map(|(key, value)| (self.block(value), self.block(key)))
+                .collect();
+            let (first, second) = indices.split_at(indices.len() / 2);
+            let second_leaf = self.leaf(second, 0);
+            let root = if first.is_empty() {
+                second_leaf
+            } else {
+                let first_leaf = self.leaf(first, second_leaf);
+                let mut branch = vec![0, 0, 0, 2];
+                branch.extend(be(&[0, 0, first_leaf, 0, second_leaf, 0]));
+                self.block(branch)
+            };
+            let mut tree = b"tree".to_vec();
+            tree.extend(be(&[1, root, 4096, 0]));
+            self.var(name, tree);
+        }
+
+        fn finish(self) -> Vec<u8> {
+            let mut data = b"BOMStore".to_vec();
+            data.resize(32, 0);
+            let mut addresses = Vec::new();
+            for block in &self.blocks {
+                addresses.push((data.len() as u32, block.len() as u32));
+                data.extend_from_slice(block);
+            }
+            let index = data.len() as u32;
+            data.extend(be(&[addresses.len() as u32]));
+            for (address, len) in addresses {
+                data.extend(be(&[address, len]));
+            }
+            let vars = data.len() as u32;
+            data.extend(be(&[self.vars.len() as u32]));
+            for (name, block) in &self.vars {
+                data.extend(be(&[*block]));
+                data.push(name.len() as u8);
+                data.extend_from_slice(name.as_bytes());
+            }
+            data[16..20].copy_from_slice(&index.to_be_bytes());
+            data[24..28].copy_from_slice(&vars.to_be_bytes());
+            data
+        }
+    }
+
+    /// A four-character code as the catalog stores it
+    fn code(fourcc_bytes: &[u8; 4]) -> [u8; 4] {
+        fourcc(fourcc_bytes).to_le_bytes()
+    }
+
+    /// A CSI header (with a few bytes of TLV data), then `body`
+    fn csi(width: u32, height: u32, format: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut value = code(b"ISTC").to_vec();
+        value.resize(CSI_HEADER_LEN, 0);
+        value[12..16].copy_from_slice(&width.to_le_bytes());
+        value[16..20].copy_from_slice(&height.to_le_bytes());
+        value[24..28].copy_from_slice(&code(format));
+        value[168..172].copy_from_slice(&8u32.to_le_bytes());
+        value.extend_from_slice(&[0xee; 8]);
+        value.extend_from_slice(body);
+        value
+    }
+
+    fn celm(compression: u32, data: &[u8]) -> Vec<u8> {
+        let mut chunk = code(b"CELM").to_vec();
+        chunk.extend(le(&[0, compression, data.len() as u32]));
+        chunk.extend_from_slice(data);
+        chunk
+    }
+
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn lzfse(data: &[u8]) -> Vec<u8> {
+        let mut packed = Vec::new();
+        lzfse_rust::encode_bytes(data, &mut packed).unwrap();
+        packed
+    }
+
+    /// `edge`² opaque pixels of one color, as ARGB renditions store them
+    fn argb(edge: u32, [r, g, b]: [u8; 3]) -> Vec<u8> {
+        [b, g, r, 255].repeat((edge * edge) as usize)
+    }
+
+    fn png(edge: u32) -> Vec<u8> {
+        Pixmap::new(edge, edge).unwrap().encode_png().unwrap()
+    }
+
+    /// A catalog of facets (name, identifier) and renditions (identifier,
+    /// CSI value)
+    fn catalog(facets: &[(&str, u16)], renditions: Vec<(u16, Vec<u8>)>) -> Vec<u8> {
+        let mut car = Car::new();
+        let mut key_format = code(b"kfmt").to_vec();
+        key_format.extend(le(&[0, 2, SCALE_ATTRIBUTE, IDENTIFIER_ATTRIBUTE as u32]));
+        car.var("KEYFORMAT", key_format);
+        let facet_keys = facets
+            .iter()
+            .map(|(name, identifier)| {
+                let mut token = vec![0, 0, 0, 0];
+                token.extend_from_slice(&1u16.to_le_bytes());
+                token.extend_from_slice(&(IDENTIFIER_ATTRIBUTE).to_le_bytes());
+                token.extend_from_slice(&identifier.to_le_bytes());
+                (name.as_bytes().to_vec(), token)
+            })
+            .collect();
+        car.tree("FACETKEYS", facet_keys);
+        let renditions = renditions
+            .into_iter()
+            .map(|(identifier, value)| {
+                let mut key = 1u16.to_le_bytes().to_vec();
+                key.extend_from_slice(&identifier.to_le_bytes());
+                (key, value)
+            })
+            .collect();
+        car.tree("RENDITIONS", renditions);
+        car.finish()
+    }
+
+    fn decoded(png: &[u8]) -> Pixmap {
+        Pixmap::decode_png(png).unwrap()
+    }
+
+    /// A pixel's unpremultiplied (r, g, b, a)
+    fn pixel(pixmap: &Pixmap, x: u32, y: u32) -> (u8, u8, u8, u8) {
+        let color = pixmap.pixel(x, y).unwrap().demultiply();
+        (color.red(), color.green(), color.blue(), color.alpha())
+    }
+
+    #[test]
+    fn decodes_raw_zlib_and_lzfse_renditions() {
+        let pixels = argb(32, [200, 100, 50]);
+        for chunk in [
+            celm(COMPRESSION_NONE, &pixels),
+            celm(COMPRESSION_ZIP, &zlib(&pixels)),
+            celm(COMPRESSION_LZFSE, &lzfse(&pixels)),
+        ] {
+            let car = catalog(&[("AppIcon", 7)], vec![(7, csi(32, 32, b"ARGB", &chunk))]);
+            let image = decoded(&catalog_image(&car, "AppIcon", 32).unwrap());
+            assert_eq!((image.width(), image.height()), (32, 32));
+            assert_eq!(pixel(&image, 31, 31), (200, 100, 50, 255));
+        }
+    }
+
+    #[test]
+    fn joins_wrapped_chunks() {
+        let pixels = argb(16, [0, 255, 0]);
+        let (head, tail) = pixels.split_at(pixels.len() / 2);
+        // KCBC-wrapped: the chunks come after a wrapper header
+        let mut body = b"CBCK\0\0\0\0\x10\0\0\0".to_vec();
+        body.extend(celm(COMPRESSION_NONE, head));
+        body.extend(celm(COMPRESSION_ZIP, &zlib(tail)));
+        let car = catalog(&[("AppIcon", 3)], vec![(3, csi(16, 16, b"ARGB", &body))]);
+        let image = decoded(&catalog_image(&car, "AppIcon", 16).unwrap());
+        assert_eq!(pixel(&image, 0, 0), (0, 255, 0, 255));
+        assert_eq!(pixel(&image, 15, 15), (0, 255, 0, 255));
+
+        // Chunks ending before the pixels do
+        let short = celm(COMPRESSION_NONE, head);
+        let car = catalog(&[("AppIcon", 3)], vec![(3, csi(16, 16, b"ARGB", &short))]);
+        assert_eq!(catalog_image(&car, "AppIcon", 16), None);
+    }
+
+    #[test]
+    fn decodes_gray_alpha() {
+        let pixels = [90, 255].repeat(8 * 8);
+        let chunk = celm(COMPRESSION_NONE, &pixels);
+        let car = catalog(&[("AppIcon", 1)], vec![(1, csi(8, 8, b"GA8 ", &chunk))]);
+        let image = decoded(&catalog_image(&car, "AppIcon", 8).unwrap());
+        assert_eq!(pixel(&image, 4, 4), (90, 90, 90, 255));
+    }
+
+    #[test]
+    fn serves_data_renditions_holding_a_png() {
+        let icon = png(64);
+        let mut raw = code(b"RAWD").to_vec();
+        raw.extend(le(&[0, icon.len() as u32]));
+        raw.extend_from_slice(&icon);
+        let car = catalog(&[("AppIcon", 5)], vec![(5, csi(64, 64, b"DATA", &raw))]);
+        assert_eq!(catalog_image(&car, "AppIcon", 64), Some(icon));
+
+        let mut pdf = code(b"RAWD").to_vec();
+        pdf.extend(le(&[0, 8]));
+        pdf.extend_from_slice(b"%PDF-1.7");
+        let car = catalog(&[("AppIcon", 5)], vec![(5, csi(64, 64, b"DATA", &pdf))]);
+        assert_eq!(catalog_image(&car, "AppIcon", 64), None);
+    }
+
+    #[test]
+    fn picks_the_smallest_rendition_covering_the_size() {
+        let raw = |edge| {
+            csi(
+                edge,
+                edge,
+                b"ARGB",
+                &celm(COMPRESSION_NONE, &argb(edge, [1, 2, 3])),
+            )
+        };
+        let zip = |edge| {
+            csi(
+                edge,
+                edge,
+                b"ARGB",
+                &celm(COMPRESSION_ZIP, &zlib(&argb(edge, [1, 2, 3]))),
+            )
+        };
+        let lz = |edge| {
+            csi(
+                edge,
+                edge,
+                b"ARGB",
+                &celm(COMPRESSION_LZFSE, &lzfse(&argb(edge, [1, 2, 3]))),
+            )
+        };
+        let car = catalog(
+            &[("AppIcon", 1), ("Other", 2)],
+            vec![(1, zip(128)), (2, raw(512)), (1, raw(64)), (1, lz(256))],
+        );
+        let edge = |size| decoded(&catalog_image(&car, "AppIcon", size).unwrap()).width();
+        assert_eq!(edge(16), 64);
+        assert_eq!(edge(100), 128);
+        assert_eq!(edge(128), 128);
+        assert_eq!(edge(200), 256);
+        // The other facet's larger rendition isn't the app icon's
+        assert_eq!(edge(1024), 256);
+        assert_eq!(catalog_image(&car, "Missing", 128), None);
+    }
+
+    #[test]
+    fn falls_back_past_undecodable_renditions() {
+        let deepmap = csi(256, 256, b"ARGB", &celm(11, b"deepmap2"));
+        let fine = csi(
+            128,
+            128,
+            b"ARGB",
+            &celm(COMPRESSION_NONE, &argb(128, [0; 3])),
+        );
+        let car = catalog(&[("AppIcon", 1)], vec![(1, deepmap), (1, fine)]);
+        assert_eq!(
+            decoded(&catalog_image(&car, "AppIcon", 256).unwrap()).width(),
+            128
+        );
+    }
+
+    #[test]
+    fn chunks_unpack_no_further_than_the_pixels() {
+        let len = 16 * 16 * 4;
+        let oversized = [7u8; 1 << 20];
+        for chunk in [
+            celm(COMPRESSION_ZIP, &zlib(&oversized)),
+            celm(COMPRESSION_LZFSE, &lzfse(&oversized)),
+        ] {
+            let value = csi(16, 16, b"ARGB", &chunk);
+            let rendition = Rendition::parse(&value).unwrap();
+            assert_eq!(rendition.pixels(len), Some(vec![7u8; len]));
+        }
+    }
+
+    #[test]
+    fn rejects_damaged_catalogs() {
+        assert_eq!(catalog_image(b"BOMStore", "AppIcon", 64), None);
+        assert_eq!(catalog_image(b"not a catalog", "AppIcon", 64), None);
+        let car = catalog(
+            &[("AppIcon", 1)],
+            vec![(
+                1,
+                csi(16, 16, b"ARGB", &celm(COMPRESSION_NONE, &argb(16, [0; 3]))),
+            )],
+        );
+        let mut truncated = car.clone();
+        truncated.truncate(car.len() / 2);
+        assert_eq!(catalog_image(&truncated, "AppIcon", 16), None);
+        // Too big to decode
+        let huge = csi(MAX_EDGE + 1, 1, b"ARGB", &celm(COMPRESSION_NONE, &[0; 4]));
+        let car = catalog(&[("AppIcon", 1)], vec![(1, huge)]);
+        assert_eq!(catalog_image(&car, "AppIcon", 16), None);
+    }
+}
@@ -0,0 +1,298 @@
+//! Minimal parsing of macOS bookmark data, in pure Rust: the target path a
+//! Finder alias file points at. Only the path is read; the rest of the
+//! bookmark (volume identity, file IDs, security scopes) is skipped, so a
+//! moved target is reported missing rather than searched for.
+//!
+//! Layout: a "book" header giving the data section's offset, then in that
+//! section a chain of tables of contents mapping keys to typed items.
+//! Alias files wrap the same data behind a "book\0\0\0\0mark\0\0\0\0"
+//! header of their own.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+const TOC_MAGIC: u32 = 0xffff_fffe;
+
+/// Item types (major type << 8 | subtype)
+const ITEM_STRING: u32 = 0x0101;
+const ITEM_ARRAY: u32 = 0x0601;
+
+/// kBookmarkPath: the target's path components from the root
+const KEY_PATH: u32 = 0x1004;
+
+/// Bookmarks hold a handful of tables; a longer chain is corrupt or a loop
+const MAX_TOCS: usize = 16;
+
+/// Alias files are a few KB; anything much larger isn't one
+const MAX_ALIAS_LEN: u64 = 64 * 1024;
+
+const ALIAS_FILE_MAGIC: &[u8; 16] = b"book\0\0\0\0mark\0\0\0\0";
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    let raw: [u8; 4] = bytes.get(at..at.checked_add(4)?)?.try_into().ok()?;
+    Some(u32::from_le_bytes(raw))
+}
+
+/// The data section: every item and TOC offset is relative to its start
+fn data_section(data: &[u8]) -> Option<&[u8]> {
+    if data.starts_with(ALIAS_FILE_MAGIC) {
+        // Alias file: header length at 16, the data runs to the end
+        let header_len = read_u32(data, 16)? as usize;
+        return data.get(header_len..);
+    }
+    if !data.starts_with(b"book") {
+        return None;
+    }
+    let size = read_u32(data, 4)? as usize;
+    let header_len = read_u32(data, 12)? as usize;
+    if header_len < 16 || header_len > size {
+        return None;
+    }
+    data.get(header_len..size)
+}
+
+/// A typed item at `offset`: its type and payload
+fn item(section: &[u8], offset: usize) -> Option<(u32, &[u8])> {
+    let len = read_u32(section, offset)? as usize;
+    let item_type = read_u32(section, offset.checked_add(4)?)?;
+    let start = offset.checked_add(8)?;
+    Some((item_type, section.get(start..start.checked_add(len)?)?))
+}
+
+fn string_item(section: &[u8], offset: usize) -> Option<String> {
+    match item(section, offset)? {
+        (ITEM_STRING, bytes) => String::from_utf8(bytes.to_vec()).ok(),
+        _ => None,
+    }
+}
+
+/// An array of strings: the payload lists each element's item offset
+fn string_array(section: &[u8], offset: usize) -> Option<Vec<String>> {
+    let (ITEM_ARRAY, elements) = item(section, offset)? else {
+        return None;
+    };
+    elements
+        .chunks_exact(4)
+        .map(|raw| string_item(section, read_u32(raw, 0)? as usize))
+        .collect()
+}
+
+/// Target path stored in bookmark (or alias file) data. None when the
+/// data isn't a bookmark, is damaged, or has no path.
+pub(crate) fn bookmark_target(data: &[u8]) -> Option<PathBuf> {
+    let section = data_section(data)?;
+    let mut toc_offset = read_u32(section, 0)? as usize;
+
+    for _ in 0..MAX_TOCS {
+        if toc_offset == 0 {
+            break;
+        }
+        let toc = section.get(toc_offset..)?;
+        if read_u32(toc, 4)? != TOC_MAGIC {
+            break;
+        }
+        let count = read_u32(toc, 16)? as usize;
+        for index in 0..count {
+            let entry = 20 + index * 12;
+            if read_u32(toc, entry)? == KEY_PATH {
+                let components = string_array(section, read_u32(toc, entry + 4)? as usize)?;
+                if components
+                    .iter()
+                    .any(|c| c.is_empty() || c == "." || c == ".." || c.contains('/'))
+                {
+                    return None;
+                }
+                let mut path = PathBuf::from("/");
+                path.extend(components);
+                return Some(path);
+            }
+        }
+        toc_offset = read_u32(toc, 12)? as usize;
+    }
+    None
+}
+
+/// Whether the file at `path` starts like bookmark data (cheap: reads 4
+/// bytes). Finder alias files have no extension to go by.
+pub(crate) fn looks_like_alias_file(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok()
+        && &magic == b"book"
+}
+
+/// Target of the Finder alias file at `path`
+pub(crate) fn read_alias_target(path: &Path) -> Option<PathBuf> {
+    let file = File::open(path).ok()?;
+    let mut data = Vec::new();
+    file.take(MAX_ALIAS_LEN).read_to_end(&mut data).ok()?;
+    bookmark_target(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bookmark data sections, laid out like CFURLCreateBookmarkData's:
+    /// 4-byte aligned items after the leading TOC offset, then the TOCs
+    struct Section(Vec<u8>);
+
+    impl Section {
+        fn new() -> Self {
+            Section(vec![0; 4])
+        }
+
+        fn push_item(&mut self, item_type: u32, payload: &[u8]) -> u32 {
+            let offset = self.0.len() as u32;
+            self.0
+                .extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            self.0.extend_from_slice(&item_type.to_le_bytes());
+            self.0.extend_from_slice(payload);
+            while !self.0.len().is_multiple_of(4) {
+                self.0.push(0);
+            }
+            offset
+        }
+
+        fn push_path(&mut self, components: &[&str]) -> u32 {
+            let offsets: Vec<u8> = components
+                .iter()
+                .flat_map(|c| self.push_item(ITEM_STRING, c.as_bytes()).to_le_bytes())
+                .collect();
+            self.push_item(ITEM_ARRAY, &offsets)
+        }
+
+        /// A TOC of (key, item offset) entries, linking to `next`
+        fn push_toc(&mut self, entries: &[(u32, u32)], next: u32) -> u32 {
+            let offset = self.0.len() as u32;
+            let len = 20 + entries.len() as u32 * 12;
+            for value in [len - 8, TOC_MAGIC, 1, next, entries.len() as u32] {
+                self.0.extend_from_slice(&value.to_le_bytes());
+            }
+            for &(key, item) in entries {
+                for value in [key, item, 0] {
+                    self.0.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            offset
+        }
+
+        fn set_first_toc(&mut self, offset: u32) {
+            self.0[..4].copy_from_slice(&offset.to_le_bytes());
+        }
+
+        /// Bare bookmark data (what NSURL bookmarkData returns)
+        fn bookmark(&self) -> Vec<u8> {
+            let mut data = b"book".to_vec();
+            data.extend_from_slice(&(48 + self.0.len() as u32).to_le_bytes());
+            data.extend_from_slice(&0x1004_0000u32.to_le_bytes());
+            data.extend_from_slice(&48u32.to_le_bytes());
+            data.resize(48, 0);
+            data.extend_from_slice(&self.0);
+            data
+        }
+
+        /// A Finder alias file's contents
+        fn alias_file(&self) -> Vec<u8> {
+            let mut data = ALIAS_FILE_MAGIC.to_vec();
+            data.extend_from_slice(&56u32.to_le_bytes());
+            data.resize(56, 0);
+            data.extend_from_slice(&self.0);
+            data
+        }
+    }
+
+    /// A section whose one TOC holds a volume name item and the path
+    fn single_toc(components: &[&str]) -> Section {
+        let mut section = Section::new();
+        let volume = section.push_item(ITEM_STRING, b"Macintosh HD");
+        let path = section.push_path(components);
+        let toc = section.push_toc(&[(0x2010, volume), (KEY_PATH, path)], 0);
+        section.set_first_toc(toc);
+        section
+    }
+
+    #[test]
+    fn reads_bare_bookmark_target() {
+        let section = single_toc(&["Applications", "Safari.app"]);
+        assert_eq!(
+            bookmark_target(&section.bookmark()),
+            Some(PathBuf::from("/Applications/Safari.app"))
+        );
+    }
+
+    #[test]
+    fn reads_alias_file_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let alias = dir.path().join("Numbers alias");
+        let section = single_toc(&["Users", "me", "Apps", "Numbers 14.app"]);
+        std::fs::write(&alias, section.alias_file()).unwrap();
+        assert!(looks_like_alias_file(&alias));
+        assert_eq!(
+            read_alias_target(&alias),
+            Some(PathBuf::from("/Users/me/Apps/Numbers 14.app"))
+        );
+    }
+
+    #[test]
+    fn follows_the_toc_chain() {
+        let mut section = Section::new();
+        let path = section.push_path(&["Applications", "Xcode.app"]);
+        let second = section.push_toc(&[(KEY_PATH, path)], 0);
+        let first = section.push_toc(&[(0x2010, path)], second);
+        section.set_first_toc(first);
+        assert_eq!(
+            bookmark_target(&section.bookmark()),
+            Some(PathBuf::from("/Applications/Xcode.app"))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_toc_offsets() {
+        let mut section = single_toc(&["Applications", "Safari.app"]);
+        section.set_first_toc(0xfff0);
+        assert_eq!(bookmark_target(&section.bookmark()), None);
+        section.set_first_toc(u32::MAX);
+        assert_eq!(bookmark_target(&section.bookmark()), None);
+
+        // A path entry pointing past the data
+        let mut section = Section::new();
+        let toc = section.push_toc(&[(KEY_PATH, 0x7fff_fff0)], 0);
+        section.set_first_toc(toc);
+        assert_eq!(bookmark_target(&section.bookmark()), None);
+    }
+
+    #[test]
+    fn stops_at_a_looping_toc_chain() {
+        let mut section = Section::new();
+        let path = section.push_path(&["Applications", "Safari.app"]);
+        let toc_offset = section.0.len() as u32;
+        let toc = section.push_toc(&[(0x2010, path)], toc_offset);
+        section.set_first_toc(toc);
+        assert_eq!(bookmark_target(&section.bookmark()), None);
+    }
+
+    #[test]
+    fn rejects_unsafe_path_components() {
+        for components in [
+            &["Applications", "..", "etc", "Evil.app"][..],
+            &["Applications", ".", "Safari.app"],
+            &["Applications", "", "Safari.app"],
+            &["Applications/Utilities", "Terminal.app"],
+        ] {
+            let section = single_toc(components);
+            assert_eq!(bookmark_target(&section.bookmark()), None, "{components:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_non_bookmarks() {
+        assert_eq!(bookmark_target(b""), None);
+        assert_eq!(bookmark_target(b"bplist00"), None);
+        let mut truncated = single_toc(&["Applications", "Safari.app"]).bookmark();
+        truncated.truncate(60);
+        assert_eq!(bookmark_target(&truncated), None);
+    }
+}
@@ -0,0 +1,452 @@
+//! Structural checks of discovered bundles: half-deleted apps, damaged
+//! Info.plists, executables that lost their exec bit and broken code
+//! signatures open nothing when clicked, and LaunchServices doesn't say
+//! why. Each problem found comes with how bad it is.
+//!
+//! Read-only and cheap (a stat or a header read per check), so every
+//! bundle is audited on demand rather than stored in the discovery index.
+
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::appimage::is_appimage;
+use crate::bundle_info::{bundle_layout, wrapped_ios_bundle, BundleInfo};
+use crate::codesign::read_code_signature;
+use crate::macho::{read_architecture, Architecture};
+use crate::quarantine::read_quarantine;
+
+/// How bad a problem is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    /// Cosmetic: the app opens, but looks or lists wrong
+    Info,
+    /// The app may open, but something it needs is missing
+    Warning,
+    /// The app can't open
+    Error,
+}
+
+/// What is wrong with a bundle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProblemKind {
+    MissingInfoPlist,
+    DamagedInfoPlist,
+    NoExecutableDeclared,
+    MissingExecutable,
+    EmptyExecutable,
+    NotExecutable,
+    UnreadableExecutable,
+    UnsupportedArchitecture,
+    DamagedSignature,
+    MissingResources,
+    NoBundleIdentifier,
+    MissingIcon,
+    Quarantined,
+}
+
+impl ProblemKind {
+    fn severity(self) -> Severity {
+        match self {
+            ProblemKind::MissingInfoPlist
+            | ProblemKind::DamagedInfoPlist
+            | ProblemKind::NoExecutableDeclared
+            | ProblemKind::MissingExecutable
+            | ProblemKind::EmptyExecutable
+            | ProblemKind::NotExecutable
+            | ProblemKind::UnsupportedArchitecture
+            | ProblemKind::DamagedSignature => Severity::Error,
+            ProblemKind::UnreadableExecutable
+            | ProblemKind::MissingResources
+            | ProblemKind::NoBundleIdentifier => Severity::Warning,
+            ProblemKind::MissingIcon | ProblemKind::Quarantined => Severity::Info,
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            ProblemKind::MissingInfoPlist => "The app's Info.plist is missing",
+            ProblemKind::DamagedInfoPlist => "The app's Info.plist is damaged",
+            ProblemKind::NoExecutableDeclared => "The app doesn't name its executable",
+            ProblemKind::MissingExecutable => "The app's executable is missing",
+            ProblemKind::EmptyExecutable => "The app's executable is empty",
+            ProblemKind::NotExecutable => "The app's executable isn't marked as executable",
+            ProblemKind::UnreadableExecutable => "The app's executable isn't a Mac program",
+            ProblemKind::UnsupportedArchitecture => "The app isn't built for this Mac's processor",
+            ProblemKind::DamagedSignature => "The app's code signature is damaged",
+            ProblemKind::MissingResources => "The app's resources are missing",
+            ProblemKind::NoBundleIdentifier => "The app has no bundle identifier",
+            ProblemKind::MissingIcon => "The app's icon file is missing",
+            ProblemKind::Quarantined => {
+                "The app hasn't been opened yet: macOS will ask before its first launch"
+            }
+        }
+    }
+}
+
+/// One problem found in a bundle
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppProblem {
+    pub kind: ProblemKind,
+    pub severity: Severity,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl From<ProblemKind> for AppProblem {
+    fn from(kind: ProblemKind) -> Self {
+        Self {
+            kind,
+            severity: kind.severity(),
+            message: kind.message().to_string(),
+        }
+    }
+}
+
+/// The problems of one bundle (audit_apps lists only bundles with any)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppAudit {
+    pub path: String,
+    pub problems: Vec<AppProblem>,
+}
+
+/// Whether an executable can run on this Mac, Rosetta included
+fn runs_here(architecture: Architecture) -> bool {
+    match architecture {
+        Architecture::Universal => true,
+        Architecture::Arm64 => cfg!(target_arch = "aarch64"),
+        Architecture::X86_64 => true,
+        Architecture::Other => false,
+    }
+}
+
+/// Whether the file starts with "#!": a script, which LaunchServices runs
+/// through its interpreter
+fn is_script(path: &Path) -> bool {
+    let mut magic = [0u8; 2];
+    fs::File::open(path)
+        .and_then(|mut file| file.read_exact(&mut magic))
+        .is_ok_and(|_| magic == *b"#!")
+}
+
+#[cfg(unix)]
+fn has_exec_bit(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+/// What keeps the executable at `path` from running, if anything: the
+/// first problem found, as the next check needs the previous to pass
+fn audit_executable(path: &Path) -> Option<ProblemKind> {
+    let Some(metadata) = fs::metadata(path).ok().filter(|m| m.is_file()) else {
+        return Some(ProblemKind::MissingExecutable);
+    };
+    if metadata.len() == 0 {
+        return Some(ProblemKind::EmptyExecutable);
+    }
+    #[cfg(unix)]
+    if !has_exec_bit(&metadata) {
+        return Some(ProblemKind::NotExecutable);
+    }
+    match read_architecture(path) {
+        Some(architecture) if !runs_here(architecture) => {
+            Some(ProblemKind::UnsupportedArchitecture)
+        }
+        // An unsigned executable reads as Unsigned: None is a signature
+        // that is there but can't be parsed, which macOS refuses to run
+        Some(_) => read_code_signature(path)
+            .is_none()
+            .then_some(ProblemKind::DamagedSignature),
+        None if is_script(path) => None,
+        None => Some(ProblemKind::UnreadableExecutable),
+    }
+}
+
+/// Whether the icon named by CFBundleIconFile (with or without its .icns
+/// extension) is in the bundle's resources
+fn has_icon_file(resources: &Path, name: &str) -> bool {
+    resources.join(name).is_file() || resources.join(format!("{name}.icns")).is_file()
+}
+
+/// Structural problems of the bundle at `path`, most severe first; empty
+/// when it looks whole
+pub(crate) fn audit_bundle(path: &Path) -> Vec<AppProblem> {
+    // Desktop entries and AppImages aren't bundles: their launchability
+    // says what's wrong
+    if path.extension().is_some_and(|ext| ext == "desktop") || is_appimage(path) {
+        return Vec::new();
+    }
+    let (plist_path, _) = bundle_layout(path);
+    let mut problems: Vec<ProblemKind> = Vec::new();
+    if !plist_path.is_file() {
+        problems.push(ProblemKind::MissingInfoPlist);
+    } else if let Some(info) = BundleInfo::read(path) {
+        match info.executable_path() {
+            Some(executable) => problems.extend(audit_executable(&executable)),
+            None => problems.push(ProblemKind::NoExecutableDeclared),
+        }
+        if info.string("CFBundleIdentifier").is_none_or(str::is_empty) {
+            problems.push(ProblemKind::NoBundleIdentifier);
+        }
+        // iOS bundles keep their resources at the top level
+        if wrapped_ios_bundle(path).is_none() {
+            let resources = path.join("Contents/Resources");
+            if !resources.is_dir() {
+                problems.push(ProblemKind::MissingResources);
+            } else if let Some(icon) = info.string("CFBundleIconFile").filter(|s| !s.is_empty()) {
+                // Asset-catalog icons (CFBundleIconName) live in Assets.car
+                if info.string("CFBundleIconName").is_none() && !has_icon_file(&resources, icon) {
+                    problems.push(ProblemKind::MissingIcon);
+                }
+            }
+        }
+    } else {
+        problems.push(ProblemKind::DamagedInfoPlist);
+    }
+    if read_quarantine(path).is_some_and(|quarantine| quarantine.never_opened) {
+        problems.push(ProblemKind::Quarantined);
+    }
+    let mut problems: Vec<AppProblem> = problems.into_iter().map(AppProblem::from).collect();
+    problems.sort_by_key(|problem| Reverse(problem.severity));
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle_info::tests::bundle;
+    use crate::codesign::tests::executable;
+    use std::path::PathBuf;
+
+    const IDENTIFIER: &str = "<key>CFBundleIdentifier</key><string>com.example.test</string>";
+
+    /// An Intel build of the unsigned test executable: it runs on every
+    /// host the tests do (through Rosetta on Apple silicon)
+    fn intel_executable(signature: Option<&[u8]>) -> Vec<u8> {
+        let mut file = executable(signature);
+        file[4..8].copy_from_slice(&(7i32 | 0x0100_0000).to_le_bytes());
+        file
+    }
+
+    /// Write the bundle's executable, with the exec bit when `mode` says so
+    fn write_executable(bundle: &Path, contents: &[u8], mode: u32) {
+        let path = bundle.join("Contents/MacOS/Test");
+        fs::write(&path, contents).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+    }
+
+    /// A whole bundle with `keys` added to its Info.plist
+    fn app(dir: &Path, keys: &str) -> PathBuf {
+        let bundle = bundle(dir, &format!("{IDENTIFIER}{keys}"), false);
+        write_executable(&bundle, &intel_executable(None), 0o755);
+        fs::create_dir(bundle.join("Contents/Resources")).unwrap();
+        bundle
+    }
+
+    fn kinds(path: &Path) -> Vec<ProblemKind> {
+        audit_bundle(path)
+            .into_iter()
+            .map(|problem| problem.kind)
+            .collect()
+    }
+
+    #[test]
+    fn whole_bundle_has_no_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = app(
+            dir.path(),
+            "<key>CFBundleIconFile</key><string>AppIcon</string>",
+        );
+        fs::write(bundle.join("Contents/Resources/AppIcon.icns"), b"icns").unwrap();
+        assert!(audit_bundle(&bundle).is_empty());
+
+        // A script runs through its interpreter
+        write_executable(&bundle, b"#!/bin/sh\nexec true\n", 0o755);
+        assert!(audit_bundle(&bundle).is_empty());
+    }
+
+    #[test]
+    fn desktop_entries_are_not_audited() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = dir.path().join("test.desktop");
+        fs::write(&entry, "[Desktop Entry]\n").unwrap();
+        assert!(audit_bundle(&entry).is_empty());
+    }
+
+    #[test]
+    fn missing_and_damaged_info_plist() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = app(dir.path(), "");
+        fs::write(bundle.join("Contents/Info.plist"), b"<plist><dict>").unwrap();
+        assert_eq!(kinds(&bundle), [ProblemKind::DamagedInfoPlist]);
+
+        fs::remove_file(bundle.join("Contents/Info.plist")).unwrap();
+        assert_eq!(kinds(&bundle), [ProblemKind::MissingInfoPlist]);
+        let problem = &audit_bundle(&bundle)[0];
+        assert_eq!(problem.severity, Severity::Error);
+        assert_eq!(problem.message, "The app's Info.plist is missing");
+    }
+
+    #[test]
+    fn no_executable_declared() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = app(dir.path(), "");
+        fs::write(
+            bundle.join("Contents/Info.plist"),
+            format!(r#"<plist version="1.0"><dict>{IDENTIFIER}</dict></plist>"#),
+        )
+        .unwrap();
+        assert_eq!(kinds(&bundle), [ProblemKind::NoExecutableDeclared]);
+    }
+
+    #[test]
+    fn missing_empty_and_unreadable_executables() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = app(dir.path(), "");
+        let path = bundle.join("Contents/MacOS/Test");
+
+        write_executable(&bundle, b"\x7fELF not a Mac program", 0o755);
+        assert_eq!(kinds(&bundle), [ProblemKind::UnreadableExecutable]);
+        assert_eq!(audit_bundle(&bundle)[0].severity, Severity::Warning);
+
+        write_executable(&bundle, b"", 0o755);
+        assert_eq!(kinds(&bundle), [ProblemKind::EmptyExecutable]);
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(kinds(&bundle), [ProblemKind::MissingExecutable]);
+        // A directory where the executable should be is as good as missing
+        fs::create_dir(&path).unwrap();
+        assert_eq!(kinds(&bundle), [ProblemKind::MissingExecutable]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn executable_without_exec_bit() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = app(dir.path(), "");
+        write_executable(&bundle, &intel_executable(None), 0o644);
+        assert_eq!(kinds(&bundle), [ProblemKind::NotExecutable]);
+    }
+
+    #[test]
+    fn unsupported_architecture() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = app(dir.path(), "");
+        // A PowerPC build
+        let mut powerpc = executable(None);
+        powerpc[4..8].copy_from_slice(&18i32.to_le_bytes());
+        write_executable(&bundle, &powerpc, 0o755);
+        assert_eq!(kinds(&bundle), [ProblemKind::UnsupportedArchitecture]);
+
+        // arm64 only runs on Apple silicon
+        write_executable(&bundle, &executable(None), 0o755);
+        let expected: &[ProblemKind] = if cfg!(target_arch = "aarch64") {
+            &[]
+        } else {
+            &[ProblemKind::UnsupportedArchitecture]
+        };
+        assert_eq!(kinds(&bundle), expected);
+    }
+
+    #[test]
+    fn damaged_signature() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = app(dir.path(), "");
+        // LC_CODE_SIGNATURE points at something that isn't a SuperBlob
+        write_executable(&bundle, &intel_executable(Some(&[0xff; 64])), 0o755);
+        assert_eq!(kinds(&bundle), [ProblemKind::DamagedSignature]);
+        assert_eq!(audit_bundle(&bundle)[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn missing_resources_and_icon() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = app(
+            dir.path(),
+            "<key>CFBundleIconFile</key><string>AppIcon</string>",
+        );
+        assert_eq!(kinds(&bundle), [ProblemKind::MissingIcon]);
+        assert_eq!(audit_bundle(&bundle)[0].severity, Severity::Info);
+        // Named as CFBundleIconFile spells it, without .icns
+        fs::write(bundle.join("Contents/Resources/AppIcon"), b"icns").unwrap();
+        assert!(kinds(&bundle).is_empty());
+
+        fs::remove_dir_all(bundle.join("Contents/Resources")).unwrap();
+        assert_eq!(kinds(&bundle), [ProblemKind::MissingResources]);
+    }
+
+    #[test]
+    fn no_bundle_identifier() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = bundle(dir.path(), "", false);
+        write_executable(&bundle, &intel_executable(None), 0o755);
+        fs::create_dir(bundle.join("Contents/Resources")).unwrap();
+        assert_eq!(kinds(&bundle), [ProblemKind::NoBundleIdentifier]);
+        assert_eq!(audit_bundle(&bundle)[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn asset_catalog_icons_are_not_looked_for() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = app(
+            dir.path(),
+            "<key>CFBundleIconFile</key><string>AppIcon</string>\
+             <key>CFBundleIconName</key><string>AppIcon</string>",
+        );
+        assert!(audit_bundle(&bundle).is_empty());
+    }
+
+    #[test]
+    fn problems_sort_most_severe_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = bundle(
+            dir.path(),
+            "<key>CFBundleIconFile</key><string>AppIcon</string>",
+            false,
+        );
+        fs::create_dir(bundle.join("Contents/Resources")).unwrap();
+        assert_eq!(
+            kinds(&bundle),
+            [
+                ProblemKind::MissingExecutable,
+                ProblemKind::NoBundleIdentifier,
+                ProblemKind::MissingIcon,
+            ]
+        );
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    #[test]
+    fn quarantined_until_first_opened() {
+        use crate::xattr::set_xattr;
+
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = app(dir.path(), "");
+        if !set_xattr(&bundle, "com.apple.quarantine", b"0083;65a1b2c3;Safari;") {
+            eprintln!("skipped: no user xattrs on this filesystem");
+            return;
+        }
+        assert_eq!(kinds(&bundle), [ProblemKind::Quarantined]);
+        assert_eq!(audit_bundle(&bundle)[0].severity, Severity::Info);
+
+        // Approved at its first launch
+        assert!(set_xattr(
+            &bundle,
+            "com.apple.quarantine",
+            b"00c3;65a1b2c3;Safari;"
+        ));
+        assert!(audit_bundle(&bundle).is_empty());
+    }
+}
@@ -0,0 +1,284 @@
+//! Info.plist reads for discovered bundles, in pure Rust (XML and binary
+//! plists alike). NSBundle answers the same questions on macOS, but each
+//! call off the main thread needs its own autorelease pool and it can't
+//! run anywhere else; discovery reads several keys per bundle, once.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::config::{DocumentType, Launchability};
+
+/// The iOS app inside an iPhone/iPad app installed on an Apple Silicon
+/// Mac: Foo.app/Wrapper/Foo.app, an iOS-layout bundle (Info.plist and
+/// executable at its top level, no Contents/)
+pub(crate) fn wrapped_ios_bundle(bundle: &Path) -> Option<PathBuf> {
+    fs::read_dir(bundle.join("Wrapper"))
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.extension().is_some_and(|ext| ext == "app"))
+}
+
+/// A bundle's parsed Info.plist
+pub(crate) struct BundleInfo {
+    dict: plist::Dictionary,
+    /// Where CFBundleExecutable lives: Contents/MacOS, or the wrapped iOS
+    /// bundle's top level
+    executable_dir: PathBuf,
+}
+
+/// Where a bundle's Info.plist is, and the directory its executable is in
+pub(crate) fn bundle_layout(bundle: &Path) -> (PathBuf, PathBuf) {
+    match wrapped_ios_bundle(bundle) {
+        Some(ios) => (ios.join("Info.plist"), ios),
+        None => {
+            let contents = bundle.join("Contents");
+            (contents.join("Info.plist"), contents.join("MacOS"))
+        }
+    }
+}
+
+impl BundleInfo {
+    pub(crate) fn read(bundle: &Path) -> Option<Self> {
+        let (plist_path, executable_dir) = bundle_layout(bundle);
+        let value = plist::Value::from_file(plist_path).ok()?;
+        Some(Self {
+            dict: value.into_dictionary()?,
+            executable_dir,
+        })
+    }
+
+    pub(crate) fn string(&self, key: &str) -> Option<&str> {
+        self.dict.get(key)?.as_string()
+    }
+
+    /// Boolean keys are written as <true/>, but also as "1"/"YES" strings
+    /// or integers by older tooling; LaunchServices accepts all of them.
+    pub(crate) fn flag(&self, key: &str) -> bool {
+        match self.dict.get(key) {
+            Some(plist::Value::Boolean(b)) => *b,
+            Some(plist::Value::Integer(i)) => i.as_signed().is_some_and(|i| i != 0),
+            Some(plist::Value::String(s)) => {
+                matches!(s.to_ascii_lowercase().as_str(), "1" | "yes" | "true")
+            }
+            _ => false,
+        }
+    }
+
+    /// App Store category identifier (LSApplicationCategoryType), e.g.
+    /// "public.app-category.developer-tools"
+    pub(crate) fn category(&self) -> Option<&str> {
+        self.string("LSApplicationCategoryType")
+    }
+
+    /// Declared document types (CFBundleDocumentTypes); malformed
+    /// entries are skipped
+    pub(crate) fn document_types(&self) -> Vec<DocumentType> {
+        let Some(types) = self
+            .dict
+            .get("CFBundleDocumentTypes")
+            .and_then(|v| v.as_array())
+        else {
+            return Vec::new();
+        };
+        let strings = |entry: &plist::Dictionary, key: &str| -> Vec<String> {
+            entry
+                .get(key)
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|item| item.as_string().map(str::to_string))
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        types
+            .iter()
+            .filter_map(|entry| entry.as_dictionary())
+            .map(|entry| DocumentType {
+                name: entry
+                    .get("CFBundleTypeName")
+                    .and_then(|v| v.as_string())
+                    .map(str::to_string),
+                role: entry
+                    .get("CFBundleTypeRole")
+                    .and_then(|v| v.as_string())
+                    .map(str::to_string),
+                content_types: strings(entry, "LSItemContentTypes"),
+                extensions: strings(entry, "CFBundleTypeExtensions"),
+            })
+            .collect()
+    }
+
+    /// Path of the main executable (CFBundleExecutable in executable_dir)
+    pub(crate) fn executable_path(&self) -> Option<PathBuf> {
+        let name = self.string("CFBundleExecutable")?;
+        // A name, not a path: anything else can't be what LaunchServices runs
+        if name.is_empty() || name.contains('/') {
+            return None;
+        }
+        Some(self.executable_dir.join(name))
+    }
+}
+
+/// Numeric components of a dotted version ("14.2.1" → [14, 2, 1]);
+/// parsing stops at the first non-numeric component.
+fn version_components(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect()
+}
+
+/// Whether dotted version `a` is older than `b`, missing components
+/// counting as 0 ("14" == "14.0")
+pub(crate) fn version_older_than(a: &str, b: &str) -> bool {
+    let (a, b) = (version_components(a), version_components(b));
+    for i in 0..a.len().max(b.len()) {
+        let (x, y) = (a.get(i).unwrap_or(&0), b.get(i).unwrap_or(&0));
+        if x != y {
+            return x < y;
+        }
+    }
+    false
+}
+
+/// Version of the running macOS (ProductVersion), read once. None off
+/// macOS, where LSMinimumSystemVersion has no meaning.
+fn system_version() -> Option<&'static str> {
+    static VERSION: OnceLock<Option<String>> = OnceLock::new();
+    VERSION
+        .get_or_init(|| {
+            let value =
+                plist::Value::from_file("/System/Library/CoreServices/SystemVersion.plist").ok()?;
+            Some(
+                value
+                    .as_dictionary()?
+                    .get("ProductVersion")?
+                    .as_string()?
+                    .to_string(),
+            )
+        })
+        .as_deref()
+}
+
+/// Whether the bundle can run as a regular, windowed app here — the first
+/// reason it can't wins, most fundamental first.
+pub(crate) fn classify_launchability(info: Option<&BundleInfo>) -> Launchability {
+    classify_on_system(info, system_version())
+}
+
+/// classify_launchability against the macOS version `running` (None:
+/// LSMinimumSystemVersion isn't checked)
+fn classify_on_system(info: Option<&BundleInfo>, running: Option<&str>) -> Launchability {
+    let Some(info) = info else {
+        return Launchability::MissingExecutable;
+    };
+    if !info.executable_path().is_some_and(|exe| exe.is_file()) {
+        return Launchability::MissingExecutable;
+    }
+    if let (Some(required), Some(running)) = (info.string("LSMinimumSystemVersion"), running) {
+        if version_older_than(running, required) {
+            return Launchability::RequiresNewerSystem {
+                required: required.to_string(),
+            };
+        }
+    }
+    if info.flag("LSBackgroundOnly") {
+        return Launchability::BackgroundOnly;
+    }
+    if info.flag("LSUIElement") {
+        return Launchability::Agent;
+    }
+    Launchability::Launchable
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A bundle in `dir` with an Info.plist of `keys` (raw plist XML) and,
+    /// when `executable` is set, its CFBundleExecutable file
+    pub(crate) fn bundle(dir: &Path, keys: &str, executable: bool) -> PathBuf {
+        let bundle = dir.join("Test.app");
+        fs::create_dir_all(bundle.join("Contents/MacOS")).unwrap();
+        fs::write(
+            bundle.join("Contents/Info.plist"),
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict>
+<key>CFBundleExecutable</key><string>Test</string>
+{keys}
+</dict></plist>"#
+            ),
+        )
+        .unwrap();
+        if executable {
+            fs::write(bundle.join("Contents/MacOS/Test"), b"").unwrap();
+        }
+        bundle
+    }
+
+    fn classify(keys: &str, executable: bool, running: Option<&str>) -> Launchability {
+        let dir = tempfile::tempdir().unwrap();
+        let info = BundleInfo::read(&bundle(dir.path(), keys, executable));
+        classify_on_system(info.as_ref(), running)
+    }
+
+    #[test]
+    fn compares_versions_numerically() {
+        assert!(version_older_than("10.9", "10.10"));
+        assert!(!version_older_than("10.10", "10.9"));
+        assert!(version_older_than("13.6.1", "14"));
+        assert!(!version_older_than("14", "14.0"));
+        assert!(!version_older_than("14.0", "14"));
+        assert!(version_older_than("14", "14.0.1"));
+        assert!(!version_older_than("14.2", "14.2"));
+    }
+
+    #[test]
+    fn version_parsing_stops_at_non_numeric_parts() {
+        assert_eq!(version_components(" 12.3beta.4 "), vec![12]);
+        assert_eq!(version_components("1.2.x.4"), vec![1, 2]);
+        assert!(version_components("beta").is_empty());
+        // Nothing numeric counts as 0
+        assert!(!version_older_than("beta", "0"));
+        assert!(version_older_than("beta", "1"));
+    }
+
+    #[test]
+    fn no_info_plist_or_executable_is_missing_executable() {
+        assert_eq!(
+            classify_on_system(None, None),
+            Launchability::MissingExecutable
+        );
+        assert_eq!(classify("", false, None), Launchability::MissingExecutable);
+    }
+
+    #[test]
+    fn newer_minimum_system_is_reported() {
+        let keys = "<key>LSMinimumSystemVersion</key><string>14.0</string>";
+        assert_eq!(
+            classify(keys, true, Some("13.6")),
+            Launchability::RequiresNewerSystem {
+                required: "14.0".into()
+            }
+        );
+        assert_eq!(classify(keys, true, Some("14")), Launchability::Launchable);
+        // Off macOS there is no system version to compare with
+        assert_eq!(classify(keys, true, None), Launchability::Launchable);
+    }
+
+    #[test]
+    fn background_only_wins_over_agent() {
+        let both = "<key>LSBackgroundOnly</key><true/><key>LSUIElement</key><string>1</string>";
+        assert_eq!(classify(both, true, None), Launchability::BackgroundOnly);
+        let agent = "<key>LSUIElement</key><string>YES</string>";
+        assert_eq!(classify(agent, true, None), Launchability::Agent);
+        let not_agent = "<key>LSUIElement</key><integer>0</integer>";
+        assert_eq!(classify(not_agent, true, None), Launchability::Launchable);
+    }
+}
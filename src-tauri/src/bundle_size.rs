@@ -0,0 +1,350 @@
+//! Bundle sizes on disk, computed in the background. Walking a bundle the
+//! size of Xcode takes seconds even in parallel, so sizes never hold up
+//! the app list: refresh_apps starts a scan once it has answered, and each
+//! size is emitted as an "app-sizes" event as soon as it is known.
+//!
+//! Results are cached next to the icon cache, keyed by bundle path and
+//! checked against the bundle mtime (like the discovery index), so only
+//! installed or updated apps are walked again.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use crate::discovery_index::bundle_modified;
+use crate::icon_cache::get_cache_dir;
+use crate::AppError;
+
+/// Format of the cache file; any other version is ignored and rebuilt
+const SIZE_CACHE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct CachedSize {
+    /// Bundle mtime (ms since the epoch) the size was computed at
+    modified: u64,
+    bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SizeCache {
+    version: u32,
+    sizes: HashMap<String, CachedSize>,
+}
+
+/// One bundle's size, as emitted to the frontend
+#[derive(Debug, Clone, Serialize)]
+pub struct AppSize {
+    pub path: String,
+    /// Bytes allocated on disk for the whole bundle
+    pub bytes: u64,
+}
+
+/// The cache, loaded from disk on first use
+static SIZE_CACHE: Mutex<Option<SizeCache>> = Mutex::new(None);
+
+/// Bumped by each scan: a running scan that sees a newer one stops
+static SCAN_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Cache file: ~/Library/Caches/com.helpermedia.wafflepad/bundle-sizes.json
+fn get_size_cache_path() -> Option<PathBuf> {
+    get_cache_dir().map(|p| p.join("bundle-sizes.json"))
+}
+
+fn load_size_cache() -> SizeCache {
+    get_size_cache_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str::<SizeCache>(&contents).ok())
+        .filter(|cache| cache.version == SIZE_CACHE_VERSION)
+        .unwrap_or(SizeCache {
+            version: SIZE_CACHE_VERSION,
+            sizes: HashMap::new(),
+        })
+}
+
+/// Write the cache atomically (temp file + rename, like the index)
+fn save_size_cache(cache: &SizeCache) -> Result<(), AppError> {
+    let cache_path = get_size_cache_path()
+        .ok_or_else(|| AppError::Validation("Could not determine cache directory".into()))?;
+    if let Some(dir) = cache_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string(cache)?;
+    let tmp_path = cache_path.with_extension("json.tmp");
+    fs::write(&tmp_path, json)?;
+    fs::rename(&tmp_path, &cache_path)?;
+    Ok(())
+}
+
+fn with_cache<T>(f: impl FnOnce(&mut SizeCache) -> T) -> T {
+    let mut state = SIZE_CACHE.lock().unwrap_or_else(|p| p.into_inner());
+    f(state.get_or_insert_with(load_size_cache))
+}
+
+fn allocated(metadata: &fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.blocks() * 512
+    }
+    #[cfg(not(unix))]
+    {
+        metadata.len()
+    }
+}
+
+/// Whether a file's blocks still need counting: a file with several hard
+/// links is counted at the first of them only (Xcode and the like link
+/// identical files across their frameworks)
+fn first_link(metadata: &fs::Metadata, seen: &Mutex<HashSet<(u64, u64)>>) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.nlink() <= 1
+            || seen
+                .lock()
+                .unwrap_or_else(|p| p.into_inner())
+                .insert((metadata.dev(), metadata.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (metadata, seen);
+        true
+    }
+}
+
+/// Bytes allocated under `path`, directories walked in parallel. Symlinks
+/// count as themselves, never their target.
+fn walk(path: &Path, seen: &Mutex<HashSet<(u64, u64)>>) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return if first_link(&metadata, seen) {
+            allocated(&metadata)
+        } else {
+            0
+        };
+    }
+    let children: Vec<PathBuf> = fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    allocated(&metadata)
+        + children
+            .par_iter()
+            .map(|child| walk(child, seen))
+            .sum::<u64>()
+}
+
+/// Size on disk of the bundle at `path`, from the cache when the bundle
+/// hasn't been modified since, otherwise walked (and cached)
+pub(crate) fn bundle_size(path: &Path) -> Option<u64> {
+    fs::symlink_metadata(path).ok()?;
+    let key = path.to_string_lossy().to_string();
+    let modified = bundle_modified(path);
+    let cached = with_cache(|cache| cache.sizes.get(&key).copied());
+    if let Some(cached) = cached.filter(|c| c.modified == modified) {
+        return Some(cached.bytes);
+    }
+    let bytes = walk(path, &Mutex::new(HashSet::new()));
+    with_cache(|cache| cache.sizes.insert(key, CachedSize { modified, bytes }));
+    Some(bytes)
+}
+
+/// The cached sizes of the bundles in `app_paths`; the sizes of bundles
+/// no longer listed are dropped from the cache
+fn prune_size_cache(cache: &mut SizeCache, app_paths: &[String]) -> HashMap<String, CachedSize> {
+    let listed: HashSet<&str> = app_paths.iter().map(String::as_str).collect();
+    cache.sizes.retain(|path, _| listed.contains(path.as_str()));
+    cache.sizes.clone()
+}
+
+/// Split `app_paths` into the sizes `cached` still holds good for and the
+/// bundles to walk. A cached size holds while the bundle mtime is the
+/// one it was computed at, and for bundles on unmounted volumes, which
+/// can't be walked: theirs stays for when they are back. Stats every
+/// bundle, so it runs on a copy of the cache, not under its lock.
+fn check_cached_sizes(
+    app_paths: &[String],
+    cached: &HashMap<String, CachedSize>,
+) -> (Vec<AppSize>, Vec<String>) {
+    let mut known = Vec::new();
+    let mut stale = Vec::new();
+    for path in app_paths {
+        match cached.get(path) {
+            Some(c) if c.modified == bundle_modified(Path::new(path)) => known.push(AppSize {
+                path: path.clone(),
+                bytes: c.bytes,
+            }),
+            _ if Path::new(path).exists() => stale.push(path.clone()),
+            Some(c) => known.push(AppSize {
+                path: path.clone(),
+                bytes: c.bytes,
+            }),
+            None => {}
+        }
+    }
+    (known, stale)
+}
+
+/// Size every bundle in `app_paths` on a background thread, emitting
+/// "app-sizes" events: the cached sizes at once, then each walked one as
+/// it completes. A newer scan supersedes this one. Sizes of bundles no
+/// longer listed are dropped from the cache.
+pub(crate) fn spawn_size_scan(app: tauri::AppHandle, app_paths: Vec<String>) {
+    use tauri::Emitter;
+
+    let generation = SCAN_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    std::thread::spawn(move || {
+        let current = || SCAN_GENERATION.load(Ordering::SeqCst) == generation;
+
+        let cached = with_cache(|cache| prune_size_cache(cache, &app_paths));
+        let (known, stale) = check_cached_sizes(&app_paths, &cached);
+        if !known.is_empty() {
+            let _ = app.emit("app-sizes", &known);
+        }
+
+        for path in stale {
+            if !current() {
+                return;
+            }
+            if let Some(bytes) = bundle_size(Path::new(&path)) {
+                let size = AppSize { path, bytes };
+                let _ = app.emit("app-sizes", [size]);
+            }
+        }
+
+        if current() {
+            // Written from a copy: bundle_size calls elsewhere shouldn't
+            // wait on the disk
+            let snapshot = with_cache(|cache| cache.clone());
+            if let Err(e) = save_size_cache(&snapshot) {
+                eprintln!("Failed to save bundle sizes: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn path_of(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    fn cached(entries: &[(&str, u64, u64)]) -> HashMap<String, CachedSize> {
+        entries
+            .iter()
+            .map(|&(path, modified, bytes)| (path.to_string(), CachedSize { modified, bytes }))
+            .collect()
+    }
+
+    fn touch(path: &Path, seconds: u64) {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(seconds);
+        fs::File::open(path).unwrap().set_modified(time).unwrap();
+    }
+
+    #[test]
+    fn walks_files_once_per_inode() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("Test.app");
+        fs::create_dir_all(bundle.join("Contents/MacOS")).unwrap();
+        fs::write(bundle.join("Contents/MacOS/Test"), vec![1u8; 64 * 1024]).unwrap();
+        let walked = |path: &Path| walk(path, &Mutex::new(HashSet::new()));
+        let single = walked(&bundle);
+        assert!(single >= 64 * 1024);
+
+        // A hard link to the same file adds no blocks; a symlink only its own
+        fs::hard_link(
+            bundle.join("Contents/MacOS/Test"),
+            bundle.join("Contents/MacOS/Linked"),
+        )
+        .unwrap();
+        assert_eq!(walked(&bundle), single);
+        std::os::unix::fs::symlink(
+            bundle.join("Contents/MacOS/Test"),
+            bundle.join("Contents/Symlink"),
+        )
+        .unwrap();
+        assert!(walked(&bundle) < single + 64 * 1024);
+        assert_eq!(walked(&dir.path().join("Missing.app")), 0);
+    }
+
+    #[test]
+    fn cached_size_holds_until_the_bundle_mtime_moves() {
+        let dir = tempfile::tempdir().unwrap();
+        let bundle = dir.path().join("Cached.app");
+        fs::create_dir_all(bundle.join("Contents")).unwrap();
+        touch(&bundle, 1_000);
+
+        let walked = bundle_size(&bundle).unwrap();
+        // Stand-in bytes prove the next answer comes from the cache
+        with_cache(|cache| {
+            cache.sizes.get_mut(&path_of(&bundle)).unwrap().bytes = 7;
+        });
+        assert_eq!(bundle_size(&bundle), Some(7));
+
+        touch(&bundle, 2_000);
+        assert_eq!(bundle_size(&bundle), Some(walked));
+        let entry = with_cache(|cache| cache.sizes[&path_of(&bundle)]);
+        assert_eq!(entry.modified, 2_000_000);
+
+        assert_eq!(bundle_size(&dir.path().join("Missing.app")), None);
+    }
+
+    #[test]
+    fn checks_cached_sizes_against_the_bundles() {
+        let dir = tempfile::tempdir().unwrap();
+        let unchanged = dir.path().join("Unchanged.app");
+        let updated = dir.path().join("Updated.app");
+        let uncached = dir.path().join("New.app");
+        for bundle in [&unchanged, &updated, &uncached] {
+            fs::create_dir_all(bundle).unwrap();
+            touch(bundle, 1_000);
+        }
+        let unmounted = "/Volumes/Unplugged/Applications/Away.app";
+        let gone = "/Volumes/Unplugged/Applications/Never.app";
+        let app_paths = [
+            path_of(&unchanged),
+            path_of(&updated),
+            path_of(&uncached),
+            unmounted.to_string(),
+            gone.to_string(),
+        ];
+        let cache = cached(&[
+            (&app_paths[0], 1_000_000, 10),
+            (&app_paths[1], 500_000, 20),
+            (unmounted, 1, 30),
+        ]);
+
+        let (known, stale) = check_cached_sizes(&app_paths, &cache);
+        let known: Vec<(&str, u64)> = known
+            .iter()
+            .map(|size| (size.path.as_str(), size.bytes))
+            .collect();
+        assert_eq!(known, [(app_paths[0].as_str(), 10), (unmounted, 30)]);
+        assert_eq!(stale, [app_paths[1].clone(), app_paths[2].clone()]);
+    }
+
+    #[test]
+    fn pruning_drops_bundles_no_longer_listed() {
+        let mut cache = SizeCache {
+            version: SIZE_CACHE_VERSION,
+            sizes: cached(&[
+                ("/Applications/Kept.app", 1, 10),
+                ("/Applications/Removed.app", 1, 20),
+            ]),
+        };
+        let listed = ["/Applications/Kept.app".to_string()];
+        let copy = prune_size_cache(&mut cache, &listed);
+        assert_eq!(copy.keys().collect::<Vec<_>>(), ["/Applications/Kept.app"]);
+        assert_eq!(cache.sizes.len(), 1);
+        assert!(prune_size_cache(&mut cache, &[]).is_empty());
+        assert!(cache.sizes.is_empty());
+    }
+}
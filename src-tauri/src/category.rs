@@ -0,0 +1,608 @@
+//! App Store categories (LSApplicationCategoryType): the identifiers apps
+//! declare, as folder names in the user's language.
+
+use std::sync::OnceLock;
+
+const CATEGORY_PREFIX: &str = "public.app-category.";
+
+/// Every App Store category, by slug (the identifier minus the prefix)
+const CATEGORIES: [&str; 21] = [
+    "business",
+    "developer-tools",
+    "education",
+    "entertainment",
+    "finance",
+    "games",
+    "graphics-design",
+    "healthcare-fitness",
+    "lifestyle",
+    "medical",
+    "music",
+    "news",
+    "photography",
+    "productivity",
+    "reference",
+    "social-networking",
+    "sports",
+    "travel",
+    "utilities",
+    "video",
+    "weather",
+];
+
+/// Subcategories of games, which all show as Games, like Launchpad
+const GAME_SUBCATEGORIES: [&str; 19] = [
+    "action-games",
+    "adventure-games",
+    "arcade-games",
+    "board-games",
+    "card-games",
+    "casino-games",
+    "dice-games",
+    "educational-games",
+    "family-games",
+    "kids-games",
+    "music-games",
+    "puzzle-games",
+    "racing-games",
+    "role-playing-games",
+    "simulation-games",
+    "sports-games",
+    "strategy-games",
+    "trivia-games",
+    "word-games",
+];
+
+/// Index of games in CATEGORIES
+const GAMES: usize = 5;
+
+/// Display names in CATEGORIES order, as the App Store shows them, by
+/// language (the primary language subtag; Chinese is Simplified). English
+/// comes first: it is the fallback.
+const NAMES: [(&str, [&str; 21]); 10] = [
+    (
+        "en",
+        [
+            "Business",
+            "Developer Tools",
+            "Education",
+            "Entertainment",
+            "Finance",
+            "Games",
+            "Graphics & Design",
+            "Health & Fitness",
+            "Lifestyle",
+            "Medical",
+            "Music",
+            "News",
+            "Photography",
+            "Productivity",
+            "Reference",
+            "Social Networking",
+            "Sports",
+            "Travel",
+            "Utilities",
+            "Video",
+            "Weather",
+        ],
+    ),
+    (
+        "de",
+        [
+            "Wirtschaft",
+            "Entwickler-Tools",
+            "Bildung",
+            "Unterhaltung",
+            "Finanzen",
+            "Spiele",
+            "Grafik und Design",
+            "Gesundheit und Fitness",
+            "Lifestyle",
+            "Medizin",
+            "Musik",
+            "Nachrichten",
+            "Fotografie",
+            "Produktivität",
+            "Nachschlagewerke",
+            "Soziale Netze",
+            "Sport",
+            "Reisen",
+            "Dienstprogramme",
+            "Video",
+            "Wetter",
+        ],
+    ),
+    (
+        "es",
+        [
+            "Negocios",
+            "Herramientas para desarrolladores",
+            "Educación",
+            "Entretenimiento",
+            "Finanzas",
+            "Juegos",
+            "Gráficos y diseño",
+            "Salud y forma física",
+            "Estilo de vida",
+            "Medicina",
+            "Música",
+            "Noticias",
+            "Fotografía",
+            "Productividad",
+            "Referencia",
+            "Redes sociales",
+            "Deportes",
+            "Viajes",
+            "Utilidades",
+            "Vídeo",
+            "El tiempo",
+        ],
+    ),
+    (
+        "fr",
+        [
+            "Économie et entreprise",
+            "Outils de développement",
+            "Éducation",
+            "Divertissement",
+            "Finances",
+            "Jeux",
+            "Graphisme et design",
+            "Forme et santé",
+            "Style de vie",
+            "Médecine",
+            "Musique",
+            "Actualités",
+            "Photographie",
+            "Productivité",
+            "Référence",
+            "Réseaux sociaux",
+            "Sports",
+            "Voyages",
+            "Utilitaires",
+            "Vidéo",
+            "Météo",
+        ],
+    ),
+    (
+        "it",
+        [
+            "Economia",
+            "Strumenti per sviluppatori",
+            "Istruzione",
+            "Intrattenimento",
+            "Finanza",
+            "Giochi",
+            "Grafica e design",
+            "Salute e benessere",
+            "Stile di vita",
+            "Medicina",
+            "Musica",
+            "Notizie",
+            "Fotografia",
+            "Produttività",
+            "Consultazione",
+            "Social network",
+            "Sport",
+            "Viaggi",
+            "Utility",
+            "Video",
+            "Meteo",
+        ],
+    ),
+    (
+        "ja",
+        [
+            "ビジネス",
+            "開発ツール",
+            "教育",
+            "エンターテインメント",
+            "ファイナンス",
+            "ゲーム",
+            "グラフィック＆デザイン",
+            "ヘルスケア／フィットネス",
+            "ライフスタイル",
+            "メディカル",
+            "ミュージック",
+            "ニュース",
+            "写真",
+            "仕事効率化",
+            "辞書／辞典／その他",
+            "ソーシャルネットワーキング",
+            "スポーツ",
+            "旅行",
+            "ユーティリティ",
+            "ビデオ",
+            "天気",
+        ],
+    ),
+    (
+        "ko",
+        [
+            "비즈니스",
+            "개발자 도구",
+            "교육",
+            "엔터테인먼트",
+            "금융",
+            "게임",
+            "그래픽 및 디자인",
+            "건강 및 피트니스",
+            "라이프스타일",
+            "의료",
+            "음악",
+            "뉴스",
+            "사진",
+            "생산성",
+            "참고",
+            "소셜 네트워킹",
+            "스포츠",
+            "여행",
+            "유틸리티",
+            "비디오",
+            "날씨",
+        ],
+    ),
+    (
+        "nl",
+        [
+            "Zakelijk",
+            "Ontwikkelaarstools",
+            "Onderwijs",
+            "Amusement",
+            "Financiën",
+            "Games",
+            "Grafisch en ontwerp",
+            "Gezondheid en fitness",
+            "Lifestyle",
+            "Medisch",
+            "Muziek",
+            "Nieuws",
+            "Fotografie",
+            "Productiviteit",
+            "Naslagwerken",
+            "Sociale netwerken",
+            "Sport",
+            "Reizen",
+            "Hulpprogramma's",
+            "Video",
+            "Weer",
+        ],
+    ),
+    (
+        "pt",
+        [
+            "Negócios",
+            "Ferramentas de Desenvolvimento",
+            "Educação",
+            "Entretenimento",
+            "Finanças",
+            "Jogos",
+            "Gráficos e Design",
+            "Saúde e Fitness",
+            "Estilo de Vida",
+            "Medicina",
+            "Música",
+            "Notícias",
+            "Fotografia",
+            "Produtividade",
+            "Referência",
+            "Redes Sociais",
+            "Esportes",
+            "Viagens",
+            "Utilidades",
+            "Vídeo",
+            "Clima",
+        ],
+    ),
+    (
+        "zh",
+        [
+            "商务",
+            "开发者工具",
+            "教育",
+            "娱乐",
+            "财务",
+            "游戏",
+            "图形和设计",
+            "健康健美",
+            "生活",
+            "医疗",
+            "音乐",
+            "新闻",
+            "摄影",
+            "效率",
+            "参考资料",
+            "社交",
+            "体育",
+            "旅游",
+            "工具",
+            "视频",
+            "天气",
+        ],
+    ),
+];
+
+/// Freedesktop menu categories (a desktop entry's Categories) and the App
+/// Store category they show as, most specific first: the first listed
+/// here that an entry has wins
+const FREEDESKTOP_CATEGORIES: [(&str, &str); 19] = [
+    ("Photography", "photography"),
+    ("Finance", "finance"),
+    ("News", "news"),
+    ("Chat", "social-networking"),
+    ("InstantMessaging", "social-networking"),
+    ("Audio", "music"),
+    ("Video", "video"),
+    ("Game", "games"),
+    ("Development", "developer-tools"),
+    ("Education", "education"),
+    ("Science", "education"),
+    ("Graphics", "graphics-design"),
+    ("Office", "productivity"),
+    ("Email", "productivity"),
+    ("AudioVideo", "entertainment"),
+    ("Network", "utilities"),
+    ("Settings", "utilities"),
+    ("System", "utilities"),
+    ("Utility", "utilities"),
+];
+
+/// App Store category identifier for a desktop entry's Categories, so
+/// Linux apps fall into the same category folders
+pub(crate) fn freedesktop_category(categories: &[String]) -> Option<String> {
+    FREEDESKTOP_CATEGORIES
+        .iter()
+        .find(|(name, _)| categories.iter().any(|category| category == name))
+        .map(|(_, slug)| format!("{CATEGORY_PREFIX}{slug}"))
+}
+
+/// A category identifier's slug, e.g. "developer-tools"
+fn category_slug(category: &str) -> Option<&str> {
+    category
+        .strip_prefix(CATEGORY_PREFIX)
+        .filter(|slug| !slug.is_empty())
+}
+
+/// Index in CATEGORIES of the category an identifier shows as
+fn category_index(slug: &str) -> Option<usize> {
+    if GAME_SUBCATEGORIES.contains(&slug) {
+        return Some(GAMES);
+    }
+    CATEGORIES.iter().position(|known| *known == slug)
+}
+
+/// A slug Apple doesn't list, title-cased ("home-automation" → "Home
+/// Automation"), so newer categories still get a name
+fn title_case(slug: &str) -> Option<String> {
+    let words: Vec<String> = slug
+        .split('-')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// The user's languages in order of preference, as locale tags
+#[cfg(target_os = "macos")]
+pub(crate) fn preferred_languages() -> Vec<String> {
+    use objc2_foundation::{ns_string, NSUserDefaults};
+
+    NSUserDefaults::standardUserDefaults()
+        .stringArrayForKey(ns_string!("AppleLanguages"))
+        .map(|languages| languages.iter().map(|tag| tag.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// The user's languages in order of preference: LANGUAGE's list, then the
+/// locale of messages
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn preferred_languages() -> Vec<String> {
+    let list = std::env::var("LANGUAGE").unwrap_or_default();
+    list.split(':')
+        .map(str::to_string)
+        .chain(
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|var| std::env::var(var).ok()),
+        )
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+/// The NAMES entry for a locale tag ("de-CH", "pt_BR.UTF-8", "zh-Hans-CN")
+fn names_for(tag: &str) -> Option<&'static [&'static str; 21]> {
+    let tag = tag
+        .split(['.', '@'])
+        .next()
+        .unwrap_or_default()
+        .replace('_', "-")
+        .to_lowercase();
+    if tag == "c" || tag == "posix" {
+        return Some(&NAMES[0].1);
+    }
+    let mut subtags = tag.split('-');
+    let language = subtags.next()?;
+    // Only Simplified Chinese is listed
+    if language == "zh" && subtags.any(|s| matches!(s, "hant" | "tw" | "hk" | "mo")) {
+        return None;
+    }
+    NAMES
+        .iter()
+        .find(|(code, _)| *code == language)
+        .map(|(_, names)| names)
+}
+
+/// Display names in the first preferred language listed, else English
+fn display_names() -> &'static [&'static str; 21] {
+    static NAMES_IN_USE: OnceLock<&'static [&'static str; 21]> = OnceLock::new();
+    NAMES_IN_USE.get_or_init(|| {
+        preferred_languages()
+            .iter()
+            .find_map(|tag| names_for(tag))
+            .unwrap_or(&NAMES[0].1)
+    })
+}
+
+/// English display name of a category identifier, e.g.
+/// "public.app-category.developer-tools" → "Developer Tools". Game
+/// subcategories collapse to "Games", like Launchpad. None for
+/// identifiers that aren't App Store categories.
+pub(crate) fn category_display_name(category: &str) -> Option<String> {
+    let slug = category_slug(category)?;
+    match category_index(slug) {
+        Some(index) => Some(NAMES[0].1[index].to_string()),
+        None => title_case(slug),
+    }
+}
+
+/// Display name of a category identifier in the user's language, falling
+/// back to English (see category_display_name)
+pub(crate) fn localized_category_name(category: &str) -> Option<String> {
+    let slug = category_slug(category)?;
+    match category_index(slug) {
+        Some(index) => Some(display_names()[index].to_string()),
+        None => title_case(slug),
+    }
+}
+
+/// Localized name of the category most of `categories` show as; ties go
+/// to the one listed first. None when none is an App Store category.
+pub(crate) fn dominant_category_name<'a>(
+    categories: impl IntoIterator<Item = &'a str>,
+) -> Option<String> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for name in categories.into_iter().filter_map(localized_category_name) {
+        match counts.iter_mut().find(|(counted, _)| *counted == name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((name, 1)),
+        }
+    }
+    // max_by_key keeps the last of equal maxima: reversed, that's the first
+    counts
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_category_has_a_name_in_every_language() {
+        for (language, names) in NAMES {
+            assert!(
+                names.iter().all(|name| !name.is_empty()),
+                "{language} has an empty name"
+            );
+        }
+        for slug in CATEGORIES.iter().chain(&GAME_SUBCATEGORIES) {
+            assert!(category_index(slug).is_some(), "{slug}");
+        }
+    }
+
+    #[test]
+    fn maps_identifiers_to_english_names() {
+        assert_eq!(
+            category_display_name("public.app-category.developer-tools").as_deref(),
+            Some("Developer Tools")
+        );
+        assert_eq!(
+            category_display_name("public.app-category.graphics-design").as_deref(),
+            Some("Graphics & Design")
+        );
+        // Game subcategories are all Games, like Launchpad
+        assert_eq!(
+            category_display_name("public.app-category.puzzle-games").as_deref(),
+            Some("Games")
+        );
+    }
+
+    #[test]
+    fn unknown_categories_are_title_cased() {
+        assert_eq!(
+            category_display_name("public.app-category.home-automation").as_deref(),
+            Some("Home Automation")
+        );
+        assert_eq!(
+            localized_category_name("public.app-category.ai--tools-").as_deref(),
+            Some("Ai Tools")
+        );
+        // Not App Store categories at all
+        assert_eq!(category_display_name("public.app-category."), None);
+        assert_eq!(category_display_name("public.app-category.-"), None);
+        assert_eq!(category_display_name("com.example.category.tools"), None);
+        assert_eq!(category_display_name(""), None);
+    }
+
+    #[test]
+    fn picks_names_by_locale_tag() {
+        let name = |tag: &str| names_for(tag).map(|names| names[1]);
+        assert_eq!(name("de-CH"), Some("Entwickler-Tools"));
+        assert_eq!(name("pt_BR.UTF-8"), Some("Ferramentas de Desenvolvimento"));
+        assert_eq!(name("fr_FR@euro"), Some("Outils de développement"));
+        assert_eq!(name("zh-Hans-CN"), Some("开发者工具"));
+        assert_eq!(name("C"), Some("Developer Tools"));
+        assert_eq!(name("POSIX"), Some("Developer Tools"));
+        // Traditional Chinese and unlisted languages fall through
+        assert_eq!(name("zh-Hant-TW"), None);
+        assert_eq!(name("zh_HK"), None);
+        assert_eq!(name("sv-SE"), None);
+        assert_eq!(name(""), None);
+    }
+
+    #[test]
+    fn freedesktop_categories_map_most_specific_first() {
+        let category = |names: &[&str]| {
+            let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+            freedesktop_category(&names)
+        };
+        assert_eq!(
+            category(&["AudioVideo", "Video", "Player"]).as_deref(),
+            Some("public.app-category.video")
+        );
+        assert_eq!(
+            category(&["Utility", "Development", "IDE"]).as_deref(),
+            Some("public.app-category.developer-tools")
+        );
+        assert_eq!(
+            category(&["Network", "InstantMessaging"]).as_deref(),
+            Some("public.app-category.social-networking")
+        );
+        assert_eq!(category(&["X-GNOME-Utilities"]), None);
+        assert_eq!(category(&[]), None);
+    }
+
+    #[test]
+    fn dominant_category_names_the_folder() {
+        let games = localized_category_name("public.app-category.games");
+        let productivity = localized_category_name("public.app-category.productivity");
+        // Subcategories count toward Games
+        assert_eq!(
+            dominant_category_name([
+                "public.app-category.productivity",
+                "public.app-category.card-games",
+                "public.app-category.puzzle-games",
+                "com.example.not-a-category",
+            ]),
+            games
+        );
+        // A tie goes to the one listed first
+        assert_eq!(
+            dominant_category_name([
+                "public.app-category.productivity",
+                "public.app-category.games",
+            ]),
+            productivity
+        );
+        assert_eq!(dominant_category_name(["com.example.other"]), None);
+        assert_eq!(dominant_category_name([]), None);
+    }
+}
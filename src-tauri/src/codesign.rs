@@ -0,0 +1,955 @@
+//! Code signature inspection, in pure Rust: who signed an app's main
+//! executable. Reads the embedded signature the LC_CODE_SIGNATURE load
+//! command points at — a SuperBlob holding the CodeDirectory (signing
+//! identifier, Team ID, ad-hoc flag), the requirements (the designated
+//! requirement names Apple or the team as anchor) and the CMS signature
+//! (the signing certificate). Nothing is verified: this reports what the
+//! signature claims, for display and filtering, like `codesign -dv`.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::macho::{read_slices, Slice};
+
+const MH_MAGIC: u32 = 0xfeed_face;
+const MH_MAGIC_64: u32 = 0xfeed_facf;
+const LC_CODE_SIGNATURE: u32 = 0x1d;
+
+const CSMAGIC_EMBEDDED_SIGNATURE: u32 = 0xfade_0cc0;
+const CSMAGIC_CODEDIRECTORY: u32 = 0xfade_0c02;
+const CSMAGIC_REQUIREMENTS: u32 = 0xfade_0c01;
+const CSMAGIC_REQUIREMENT: u32 = 0xfade_0c00;
+const CSMAGIC_BLOBWRAPPER: u32 = 0xfade_0b01;
+
+const CSSLOT_CODEDIRECTORY: u32 = 0;
+const CSSLOT_REQUIREMENTS: u32 = 2;
+const CSSLOT_SIGNATURESLOT: u32 = 0x10000;
+
+/// Requirement set entry for the designated requirement
+const DESIGNATED_REQUIREMENT: u32 = 3;
+
+/// CodeDirectory flags: signed without an identity (CS_ADHOC), and the
+/// linker's automatic ad-hoc signature on arm64 (CS_LINKER_SIGNED)
+const CS_ADHOC: u32 = 0x0000_0002;
+const CS_LINKER_SIGNED: u32 = 0x0002_0000;
+
+/// First CodeDirectory version with a Team ID field
+const CD_VERSION_TEAM_ID: u32 = 0x20200;
+
+/// Load commands are a few KB; the signature blobs read here (everything
+/// but the page hashes) stay well under this
+const MAX_READ_LEN: u64 = 1024 * 1024;
+
+/// Nesting bound for requirement expressions (and/or/not trees) and
+/// BER values
+const MAX_EXPR_DEPTH: usize = 32;
+
+/// OIDs of the Name attributes read from certificates
+const OID_COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+const OID_ORGANIZATIONAL_UNIT: &[u8] = &[0x55, 0x04, 0x0b];
+
+/// How an executable is signed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignatureStatus {
+    /// No code signature at all
+    Unsigned,
+    /// Signed without an identity (ad hoc, or by the linker): no one
+    /// vouches for it
+    AdHoc,
+    /// Signed by Apple (designated requirement "anchor apple")
+    Apple,
+    /// Signed with a certificate: Developer ID, App Store or development
+    Signed,
+}
+
+/// What an executable's code signature says about who signed it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeSignature {
+    pub status: SignatureStatus,
+    /// Signing identifier, usually the bundle identifier
+    pub identifier: Option<String>,
+    /// The developer team's ID (e.g. "EQHXZ8M8AV")
+    pub team_id: Option<String>,
+    /// Common name of the signing certificate, e.g. "Developer ID
+    /// Application: Example Inc (EQHXZ8M8AV)"
+    pub signer: Option<String>,
+}
+
+impl CodeSignature {
+    fn unsigned() -> Self {
+        Self {
+            status: SignatureStatus::Unsigned,
+            identifier: None,
+            team_id: None,
+            signer: None,
+        }
+    }
+}
+
+fn read_u32_be(bytes: &[u8], at: usize) -> Option<u32> {
+    let raw: [u8; 4] = bytes.get(at..at.checked_add(4)?)?.try_into().ok()?;
+    Some(u32::from_be_bytes(raw))
+}
+
+fn read_u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    let raw: [u8; 4] = bytes.get(at..at.checked_add(4)?)?.try_into().ok()?;
+    Some(u32::from_le_bytes(raw))
+}
+
+/// NUL-terminated string starting at `at`
+fn c_string(bytes: &[u8], at: usize) -> Option<String> {
+    let tail = bytes.get(at..)?;
+    let end = tail.iter().position(|&b| b == 0)?;
+    String::from_utf8(tail[..end].to_vec()).ok()
+}
+
+fn read_at(file: &mut File, offset: u64, len: u64) -> Option<Vec<u8>> {
+    if len > MAX_READ_LEN {
+        return None;
+    }
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut bytes = Vec::with_capacity(len as usize);
+    file.by_ref().take(len).read_to_end(&mut bytes).ok()?;
+    (bytes.len() as u64 == len).then_some(bytes)
+}
+
+/// The slice inspected: the one this Mac runs, else the first
+fn preferred_slice(slices: &[Slice]) -> Option<&Slice> {
+    const CPU_TYPE_ARM64: i32 = 12 | 0x0100_0000;
+    const CPU_TYPE_X86_64: i32 = 7 | 0x0100_0000;
+    let native = if cfg!(target_arch = "aarch64") {
+        CPU_TYPE_ARM64
+    } else {
+        CPU_TYPE_X86_64
+    };
+    slices
+        .iter()
+        .find(|slice| slice.cpu_type == native)
+        .or_else(|| slices.first())
+}
+
+/// File offset and size of a slice's embedded signature
+/// (LC_CODE_SIGNATURE). None when the slice has none.
+fn signature_range(file: &mut File, slice: &Slice) -> Option<(u64, u64)> {
+    let header = read_at(file, slice.offset, 32)?;
+    let header_len = match read_u32_le(&header, 0)? {
+        MH_MAGIC_64 => 32,
+        MH_MAGIC => 28,
+        _ => return None,
+    };
+    let command_count = read_u32_le(&header, 16)?;
+    let commands_len = read_u32_le(&header, 20)?;
+    let commands = read_at(file, slice.offset + header_len, u64::from(commands_len))?;
+
+    let mut at = 0usize;
+    for _ in 0..command_count {
+        let command = read_u32_le(&commands, at)?;
+        let command_len = read_u32_le(&commands, at + 4)? as usize;
+        if command == LC_CODE_SIGNATURE {
+            let offset = read_u32_le(&commands, at + 8)?;
+            let size = read_u32_le(&commands, at + 12)?;
+            return Some((slice.offset + u64::from(offset), u64::from(size)));
+        }
+        if command_len < 8 {
+            return None;
+        }
+        at = at.checked_add(command_len)?;
+    }
+    None
+}
+
+/// Parsed CodeDirectory fields
+struct CodeDirectory {
+    flags: u32,
+    identifier: Option<String>,
+    team_id: Option<String>,
+}
+
+/// Parse a CodeDirectory from its leading bytes (up to the hashes)
+fn parse_code_directory(blob: &[u8]) -> Option<CodeDirectory> {
+    if read_u32_be(blob, 0)? != CSMAGIC_CODEDIRECTORY {
+        return None;
+    }
+    let version = read_u32_be(blob, 8)?;
+    let identifier_offset = read_u32_be(blob, 20)? as usize;
+    let team_offset = if version >= CD_VERSION_TEAM_ID {
+        read_u32_be(blob, 48)? as usize
+    } else {
+        0
+    };
+    Some(CodeDirectory {
+        flags: read_u32_be(blob, 12)?,
+        identifier: c_string(blob, identifier_offset),
+        team_id: (team_offset != 0)
+            .then(|| c_string(blob, team_offset))
+            .flatten(),
+    })
+}
+
+/// What the designated requirement pins the code to
+#[derive(Default)]
+struct Anchor {
+    /// "anchor apple": Apple's own code
+    apple: bool,
+    /// certificate leaf[subject.OU] = <team>: the developer team
+    team_id: Option<String>,
+}
+
+/// Reader over a requirement expression: a prefix tree of big-endian u32
+/// opcodes and length-prefixed, 4-byte-aligned data
+struct Expr<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> Expr<'a> {
+    fn word(&mut self) -> Option<u32> {
+        let word = read_u32_be(self.bytes, self.at)?;
+        self.at += 4;
+        Some(word)
+    }
+
+    fn data(&mut self) -> Option<&'a [u8]> {
+        let len = self.word()? as usize;
+        let data = self.bytes.get(self.at..self.at.checked_add(len)?)?;
+        self.at += len.checked_add(3)? & !3;
+        Some(data)
+    }
+
+    /// A match suffix (certificate and Info.plist field tests)
+    fn skip_match(&mut self) -> Option<Option<&'a [u8]>> {
+        match self.word()? {
+            // exists, absent
+            0 | 14 => Some(None),
+            // equal, contains, begins/ends with, ordering comparisons
+            1..=8 => self.data().map(Some),
+            // date comparisons: an 8-byte absolute time
+            9..=13 => {
+                self.at += 8;
+                Some(None)
+            }
+            _ => None,
+        }
+    }
+
+    /// Walk one expression, noting the anchors it names. None on an
+    /// opcode this reader doesn't know (the caller then knows nothing).
+    fn walk(&mut self, anchor: &mut Anchor, depth: usize) -> Option<()> {
+        if depth > MAX_EXPR_DEPTH {
+            return None;
+        }
+        // The high byte carries flags (e.g. "unknown ops evaluate false")
+        match self.word()? & 0x00ff_ffff {
+            // false, true, anchor apple generic, trusted certs, notarized,
+            // legacy Developer ID
+            0 | 1 | 13 | 15 | 21 | 23 => {}
+            // anchor apple
+            3 => anchor.apple = true,
+            // identifier, cdhash, named anchor, named code
+            2 | 8 | 18 | 19 => {
+                self.data()?;
+            }
+            // anchor hash: cert slot, hash
+            4 => {
+                self.word()?;
+                self.data()?;
+            }
+            // Info.plist key = value (legacy form)
+            5 => {
+                self.data()?;
+                self.data()?;
+            }
+            // and, or
+            6 | 7 => {
+                self.walk(anchor, depth + 1)?;
+                self.walk(anchor, depth + 1)?;
+            }
+            // not
+            9 => self.walk(anchor, depth + 1)?,
+            // Info.plist / entitlement field match
+            10 | 16 => {
+                self.data()?;
+                self.skip_match()?;
+            }
+            // certificate field, generic (OID) field, policy, field date
+            11 | 14 | 17 | 22 => {
+                let slot = self.word()?;
+                let field = self.data()?;
+                let value = self.skip_match()?;
+                // Slot 0 is the leaf
+                if slot == 0 && field == b"subject.OU" {
+                    anchor.team_id = value.and_then(|v| String::from_utf8(v.to_vec()).ok());
+                }
+            }
+            // trusted cert slot, platform
+            12 | 20 => {
+                self.word()?;
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+/// The offset of the `wanted` entry in the index of a SuperBlob or
+/// requirement set: (type, offset) pairs after the 12-byte header. The
+/// count is the blob's to claim, so only entries it holds are read.
+fn index_offset(blob: &[u8], wanted: u32) -> Option<u32> {
+    let count = read_u32_be(blob, 8)? as usize;
+    blob.get(12..)?
+        .chunks_exact(8)
+        .take(count)
+        .find(|entry| read_u32_be(entry, 0) == Some(wanted))
+        .and_then(|entry| read_u32_be(entry, 4))
+}
+
+/// The designated requirement's anchors, from a requirement set blob
+fn designated_anchor(blob: &[u8]) -> Option<Anchor> {
+    if read_u32_be(blob, 0)? != CSMAGIC_REQUIREMENTS {
+        return None;
+    }
+    let offset = index_offset(blob, DESIGNATED_REQUIREMENT)? as usize;
+
+    let requirement = blob.get(offset..)?;
+    if read_u32_be(requirement, 0)? != CSMAGIC_REQUIREMENT {
+        return None;
+    }
+    let len = read_u32_be(requirement, 4)? as usize;
+    let mut expr = Expr {
+        bytes: requirement.get(12..len)?,
+        at: 0,
+    };
+    let mut anchor = Anchor::default();
+    expr.walk(&mut anchor, 0)?;
+    Some(anchor)
+}
+
+/// One DER element: its tag, its contents, and where the next one starts.
+/// Also reads BER's indefinite lengths (contents up to an end-of-contents
+/// marker), which Apple's CMS signatures use.
+fn der(bytes: &[u8], at: usize) -> Option<(u8, &[u8], usize)> {
+    der_nested(bytes, at, 0)
+}
+
+fn der_nested(bytes: &[u8], at: usize, depth: usize) -> Option<(u8, &[u8], usize)> {
+    if depth > MAX_EXPR_DEPTH {
+        return None;
+    }
+    let tag = *bytes.get(at)?;
+    let first = *bytes.get(at + 1)?;
+    let start = at + 2;
+    if first == 0x80 {
+        // Indefinite: walk the children to the 00 00 marker
+        let mut end = start;
+        while bytes.get(end..end + 2)? != [0, 0] {
+            end = der_nested(bytes, end, depth + 1)?.2;
+        }
+        return Some((tag, &bytes[start..end], end + 2));
+    }
+    let (len, start) = if first < 0x80 {
+        (first as usize, start)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count > 4 {
+            return None;
+        }
+        let len = bytes
+            .get(start..start + count)?
+            .iter()
+            .fold(0usize, |len, &b| (len << 8) | b as usize);
+        (len, start + count)
+    };
+    let end = start.checked_add(len)?;
+    Some((tag, bytes.get(start..end)?, end))
+}
+
+/// The elements of a constructed DER value, in order
+fn der_children(contents: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut children = Vec::new();
+    let mut at = 0;
+    while let Some((tag, value, next)) = der(contents, at) {
+        children.push((tag, value));
+        at = next;
+    }
+    children
+}
+
+/// An attribute of an X.501 Name (SEQUENCE OF SET OF {OID, value})
+fn name_attribute(name: &[u8], oid: &[u8]) -> Option<String> {
+    der_children(name).into_iter().find_map(|(_, set)| {
+        let (_, pair, _) = der(set, 0)?;
+        let pair = der_children(pair);
+        let (0x06, attribute) = *pair.first()? else {
+            return None;
+        };
+        (attribute == oid).then(|| String::from_utf8(pair.get(1)?.1.to_vec()).ok())?
+    })
+}
+
+/// A certificate's serial number and subject Name
+fn certificate_identity(certificate: &[u8]) -> Option<(&[u8], &[u8])> {
+    let tbs = der_children(der_children(certificate).first()?.1);
+    // The version is an optional explicit [0] ahead of the serial
+    let fields: Vec<_> = tbs
+        .into_iter()
+        .skip_while(|(tag, _)| *tag == 0xa0)
+        .collect();
+    // serial, signature algorithm, issuer, validity, subject
+    Some((fields.first()?.1, fields.get(4)?.1))
+}
+
+/// Subject Name of the certificate that made a CMS signature: the one
+/// whose serial the first SignerInfo names (the chain's other
+/// certificates, intermediate and root, ride along)
+fn signer_subject(cms: &[u8]) -> Option<&[u8]> {
+    let (_, content_info, _) = der(cms, 0)?;
+    let content_info = der_children(content_info);
+    let (0xa0, signed_data) = *content_info.get(1)? else {
+        return None;
+    };
+    let (_, signed_data, _) = der(signed_data, 0)?;
+    let fields = der_children(signed_data);
+
+    let certificates: Vec<&[u8]> = fields
+        .iter()
+        .find(|(tag, _)| *tag == 0xa0)
+        .map(|(_, set)| der_children(set).into_iter().map(|(_, c)| c).collect())
+        .unwrap_or_default();
+    let (_, signer_infos) = fields.last()?;
+    let (_, signer_info, _) = der(signer_infos, 0)?;
+    let signer_info = der_children(signer_info);
+    // version, then sid: IssuerAndSerialNumber {issuer, serial}
+    let (0x30, sid) = *signer_info.get(1)? else {
+        return None;
+    };
+    let serial = der_children(sid).get(1)?.1;
+
+    certificates.into_iter().find_map(|certificate| {
+        let (cert_serial, subject) = certificate_identity(certificate)?;
+        (cert_serial == serial).then_some(subject)
+    })
+}
+
+/// Parse an embedded signature (SuperBlob) read through `blob_at`, which
+/// returns the blob at an offset into it, bounded to what is needed
+fn parse_signature(
+    superblob: &[u8],
+    blob_at: impl Fn(u32, usize) -> Option<Vec<u8>>,
+) -> Option<CodeSignature> {
+    if read_u32_be(superblob, 0)? != CSMAGIC_EMBEDDED_SIGNATURE {
+        return None;
+    }
+    let slot = |wanted: u32| index_offset(superblob, wanted);
+
+    let code_directory = slot(CSSLOT_CODEDIRECTORY).and_then(|offset| {
+        // The fixed header and strings come before the hashes
+        let header = blob_at(offset, 88)?;
+        let hash_offset = read_u32_be(&header, 16)? as usize;
+        parse_code_directory(&blob_at(offset, hash_offset)?)
+    })?;
+    let anchor = slot(CSSLOT_REQUIREMENTS)
+        .and_then(|offset| designated_anchor(&blob_at(offset, usize::MAX)?))
+        .unwrap_or_default();
+    let signer = slot(CSSLOT_SIGNATURESLOT).and_then(|offset| {
+        let wrapper = blob_at(offset, usize::MAX)?;
+        if read_u32_be(&wrapper, 0)? != CSMAGIC_BLOBWRAPPER {
+            return None;
+        }
+        signer_subject(wrapper.get(8..)?).map(|subject| {
+            (
+                name_attribute(subject, OID_COMMON_NAME),
+                name_attribute(subject, OID_ORGANIZATIONAL_UNIT),
+            )
+        })
+    });
+    let (signer, signer_team) = signer.unwrap_or_default();
+
+    let status = if code_directory.flags & (CS_ADHOC | CS_LINKER_SIGNED) != 0 {
+        SignatureStatus::AdHoc
+    } else if anchor.apple {
+        SignatureStatus::Apple
+    } else {
+        SignatureStatus::Signed
+    };
+    Some(CodeSignature {
+        status,
+        identifier: code_directory.identifier,
+        team_id: code_directory.team_id.or(anchor.team_id).or(signer_team),
+        signer,
+    })
+}
+
+/// Code signature of the Mach-O executable at `path` (its native slice,
+/// for universal binaries). None when the file can't be read or isn't
+/// Mach-O; an unsigned executable reports SignatureStatus::Unsigned.
+pub(crate) fn read_code_signature(path: &Path) -> Option<CodeSignature> {
+    let slices = read_slices(path)?;
+    let slice = preferred_slice(&slices)?;
+    let mut file = File::open(path).ok()?;
+    let Some((start, size)) = signature_range(&mut file, slice) else {
+        return Some(CodeSignature::unsigned());
+    };
+
+    let superblob = read_at(&mut file, start, 12)?;
+    let count = u64::from(read_u32_be(&superblob, 8)?);
+    let superblob = read_at(&mut file, start, 12 + count * 8)?;
+    let file = std::cell::RefCell::new(file);
+    let blob_at = |offset: u32, limit: usize| {
+        let offset = u64::from(offset);
+        let header = read_at(&mut file.borrow_mut(), start + offset, 8)?;
+        let len = u64::from(read_u32_be(&header, 4)?)
+            .min(limit as u64)
+            .min(size.checked_sub(offset)?);
+        read_at(&mut file.borrow_mut(), start + offset, len)
+    };
+    parse_signature(&superblob, blob_at)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn be(words: &[u32]) -> Vec<u8> {
+        words.iter().flat_map(|w| w.to_be_bytes()).collect()
+    }
+
+    /// Length-prefixed, 4-byte-aligned requirement data
+    fn data(bytes: &[u8]) -> Vec<u8> {
+        let mut out = be(&[bytes.len() as u32]);
+        out.extend_from_slice(bytes);
+        out.resize(4 + bytes.len().div_ceil(4) * 4, 0);
+        out
+    }
+
+    /// A version 0x20400 CodeDirectory (88-byte header) with the given
+    /// flags, identifier and Team ID, and one code hash
+    fn code_directory(flags: u32, identifier: &str, team_id: Option<&str>) -> Vec<u8> {
+        let identifier_offset = 88;
+        let mut strings = identifier.as_bytes().to_vec();
+        strings.push(0);
+        let team_offset = team_id.map_or(0, |team| {
+            let offset = identifier_offset + strings.len() as u32;
+            strings.extend_from_slice(team.as_bytes());
+            strings.push(0);
+            offset
+        });
+        let hash_offset = identifier_offset + strings.len() as u32;
+        let len = hash_offset + 32;
+        let mut blob = be(&[
+            CSMAGIC_CODEDIRECTORY,
+            len,
+            0x20400,
+            flags,
+            hash_offset,
+            identifier_offset,
+            0,
+            1,
+            0x4000,
+            0x2002_000c,
+            0,
+            0,
+            team_offset,
+        ]);
+        blob.resize(88, 0);
+        blob.extend_from_slice(&strings);
+        blob.extend_from_slice(&[0xab; 32]);
+        blob
+    }
+
+    /// A requirement set holding `expression` as the designated requirement
+    fn requirements(expression: &[u8]) -> Vec<u8> {
+        let mut requirement = be(&[CSMAGIC_REQUIREMENT, 12 + expression.len() as u32, 1]);
+        requirement.extend_from_slice(expression);
+        let mut blob = be(&[
+            CSMAGIC_REQUIREMENTS,
+            20 + requirement.len() as u32,
+            1,
+            DESIGNATED_REQUIREMENT,
+            20,
+        ]);
+        blob.extend_from_slice(&requirement);
+        blob
+    }
+
+    /// identifier "<id>" and anchor apple generic and
+    /// certificate leaf[subject.OU] = "<team>"
+    fn developer_id_requirement(identifier: &str, team_id: &str) -> Vec<u8> {
+        let mut expr = be(&[6]);
+        expr.extend(be(&[2]));
+        expr.extend(data(identifier.as_bytes()));
+        expr.extend(be(&[6, 15, 11, 0]));
+        expr.extend(data(b"subject.OU"));
+        expr.extend(be(&[1]));
+        expr.extend(data(team_id.as_bytes()));
+        expr
+    }
+
+    /// identifier "<id>" and anchor apple
+    fn apple_requirement(identifier: &str) -> Vec<u8> {
+        let mut expr = be(&[6, 2]);
+        expr.extend(data(identifier.as_bytes()));
+        expr.extend(be(&[3]));
+        expr
+    }
+
+    /// A SuperBlob of (slot, blob) entries
+    fn superblob(blobs: &[(u32, Vec<u8>)]) -> Vec<u8> {
+        let header_len = 12 + blobs.len() * 8;
+        let total: usize = header_len + blobs.iter().map(|(_, b)| b.len()).sum::<usize>();
+        let mut out = be(&[CSMAGIC_EMBEDDED_SIGNATURE, total as u32, blobs.len() as u32]);
+        let mut offset = header_len;
+        for (slot, blob) in blobs {
+            out.extend(be(&[*slot, offset as u32]));
+            offset += blob.len();
+        }
+        for (_, blob) in blobs {
+            out.extend_from_slice(blob);
+        }
+        out
+    }
+
+    /// A thin arm64 executable, its signature (if any) at 0x1000
+    pub(crate) fn executable(signature: Option<&[u8]>) -> Vec<u8> {
+        let mut file: Vec<u8> = [MH_MAGIC_64, 12 | 0x0100_0000, 0, 2, 2, 40, 0, 0]
+            .iter()
+            .flat_map(|w| w.to_le_bytes())
+            .collect();
+        // LC_UUID, then LC_CODE_SIGNATURE when signed
+        file.extend([0x1b, 24].iter().flat_map(|w: &u32| w.to_le_bytes()));
+        file.extend_from_slice(&[0x11; 16]);
+        if let Some(signature) = signature {
+            file.extend(
+                [LC_CODE_SIGNATURE, 16, 0x1000, signature.len() as u32]
+                    .iter()
+                    .flat_map(|w| w.to_le_bytes()),
+            );
+            file.resize(0x1000, 0);
+            file.extend_from_slice(signature);
+        } else {
+            file[16..20].copy_from_slice(&1u32.to_le_bytes());
+            file[20..24].copy_from_slice(&24u32.to_le_bytes());
+        }
+        file
+    }
+
+    fn signature_of(contents: &[u8]) -> Option<CodeSignature> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Test");
+        std::fs::write(&path, contents).unwrap();
+        read_code_signature(&path)
+    }
+
+    #[test]
+    fn unsigned_executable() {
+        assert_eq!(
+            signature_of(&executable(None)),
+            Some(CodeSignature::unsigned())
+        );
+        assert_eq!(signature_of(b"#!/bin/sh\n"), None);
+    }
+
+    #[test]
+    fn ad_hoc_signature() {
+        let directory = code_directory(CS_ADHOC, "Test-5555494411", None);
+        assert_eq!(parse_code_directory(&directory).unwrap().flags, CS_ADHOC);
+        let signature = superblob(&[(CSSLOT_CODEDIRECTORY, directory)]);
+        assert_eq!(
+            signature_of(&executable(Some(&signature))),
+            Some(CodeSignature {
+                status: SignatureStatus::AdHoc,
+                identifier: Some("Test-5555494411".into()),
+                team_id: None,
+                signer: None,
+            })
+        );
+
+        let linker = code_directory(CS_LINKER_SIGNED, "a.out", None);
+        let signature = superblob(&[(CSSLOT_CODEDIRECTORY, linker)]);
+        assert_eq!(
+            signature_of(&executable(Some(&signature))).unwrap().status,
+            SignatureStatus::AdHoc
+        );
+    }
+
+    #[test]
+    fn developer_id_signature() {
+        let signature = superblob(&[
+            (
+                CSSLOT_CODEDIRECTORY,
+                code_directory(0x1_0000, "com.example.app", Some("EQHXZ8M8AV")),
+            ),
+            (
+                CSSLOT_REQUIREMENTS,
+                requirements(&developer_id_requirement("com.example.app", "EQHXZ8M8AV")),
+            ),
+        ]);
+        assert_eq!(
+            signature_of(&executable(Some(&signature))),
+            Some(CodeSignature {
+                status: SignatureStatus::Signed,
+                identifier: Some("com.example.app".into()),
+                team_id: Some("EQHXZ8M8AV".into()),
+                signer: None,
+            })
+        );
+    }
+
+    #[test]
+    fn team_id_falls_back_to_the_designated_requirement() {
+        let blob = requirements(&developer_id_requirement("com.example.old", "ABCDE12345"));
+        let anchor = designated_anchor(&blob).unwrap();
+        assert!(!anchor.apple);
+        assert_eq!(anchor.team_id.as_deref(), Some("ABCDE12345"));
+
+        // A CodeDirectory from before Team IDs were recorded in it
+        let mut directory = code_directory(0, "com.example.old", None);
+        directory[8..12].copy_from_slice(&0x20100u32.to_be_bytes());
+        let signature = superblob(&[
+            (CSSLOT_CODEDIRECTORY, directory),
+            (CSSLOT_REQUIREMENTS, blob),
+        ]);
+        let signature = signature_of(&executable(Some(&signature))).unwrap();
+        assert_eq!(signature.status, SignatureStatus::Signed);
+        assert_eq!(signature.team_id.as_deref(), Some("ABCDE12345"));
+    }
+
+    #[test]
+    fn apple_anchor() {
+        let anchor = designated_anchor(&requirements(&apple_requirement("com.apple.Safari")));
+        assert!(anchor.is_some_and(|anchor| anchor.apple && anchor.team_id.is_none()));
+
+        let signature = superblob(&[
+            (
+                CSSLOT_CODEDIRECTORY,
+                code_directory(0, "com.apple.Safari", None),
+            ),
+            (
+                CSSLOT_REQUIREMENTS,
+                requirements(&apple_requirement("com.apple.Safari")),
+            ),
+        ]);
+        let signature = signature_of(&executable(Some(&signature))).unwrap();
+        assert_eq!(signature.status, SignatureStatus::Apple);
+        assert_eq!(signature.identifier.as_deref(), Some("com.apple.Safari"));
+    }
+
+    #[test]
+    fn unknown_requirement_opcodes_leave_the_anchor_unknown() {
+        let mut expression = apple_requirement("com.example.app");
+        expression.truncate(expression.len() - 4);
+        expression.extend(be(&[0x99]));
+        assert!(designated_anchor(&requirements(&expression)).is_none());
+
+        // An and/or tree nested past the depth bound
+        let deep = be(&[6; MAX_EXPR_DEPTH + 2]);
+        assert!(designated_anchor(&requirements(&deep)).is_none());
+    }
+
+    #[test]
+    fn truncated_or_garbage_superblob() {
+        let signature = superblob(&[(
+            CSSLOT_CODEDIRECTORY,
+            code_directory(0, "com.example.app", Some("EQHXZ8M8AV")),
+        )]);
+        assert_eq!(signature_of(&executable(Some(&signature[..40]))), None);
+        let mut truncated = executable(Some(&signature));
+        truncated.truncate(0x1000 + 20);
+        assert_eq!(signature_of(&truncated), None);
+
+        let garbage: Vec<u8> = (0..200u32).map(|i| (i * 37 % 251) as u8).collect();
+        assert_eq!(signature_of(&executable(Some(&garbage))), None);
+        assert!(parse_signature(&garbage, |_, _| None).is_none());
+
+        // A SuperBlob listing a CodeDirectory slot past its end
+        let mut dangling = be(&[
+            CSMAGIC_EMBEDDED_SIGNATURE,
+            20,
+            1,
+            CSSLOT_CODEDIRECTORY,
+            0x4000,
+        ]);
+        dangling.extend_from_slice(&[0; 8]);
+        assert_eq!(signature_of(&executable(Some(&dangling))), None);
+    }
+
+    /// A DER element: `tag`, its definite length, `contents`
+    fn tlv(tag: u8, contents: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        match contents.len() {
+            len @ 0..0x80 => out.push(len as u8),
+            len @ 0x80..0x100 => out.extend([0x81, len as u8]),
+            len => out.extend([0x82, (len >> 8) as u8, len as u8]),
+        }
+        out.extend_from_slice(contents);
+        out
+    }
+
+    fn sequence(elements: &[Vec<u8>]) -> Vec<u8> {
+        tlv(0x30, &elements.concat())
+    }
+
+    /// An X.501 Name of (attribute OID, UTF8String value) pairs
+    fn name(attributes: &[(&[u8], &str)]) -> Vec<u8> {
+        let sets: Vec<Vec<u8>> = attributes
+            .iter()
+            .map(|(oid, value)| {
+                tlv(
+                    0x31,
+                    &sequence(&[tlv(0x06, oid), tlv(0x0c, value.as_bytes())]),
+                )
+            })
+            .collect();
+        sequence(&sets)
+    }
+
+    const SHA256_WITH_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+    const SIGNED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x02];
+    const DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x01];
+
+    fn issuer() -> Vec<u8> {
+        name(&[(OID_COMMON_NAME, "Developer ID Certification Authority")])
+    }
+
+    /// A v3 certificate with `serial` and `subject`
+    fn certificate(serial: &[u8], subject: &[u8]) -> Vec<u8> {
+        let algorithm = sequence(&[tlv(0x06, SHA256_WITH_RSA), tlv(0x05, &[])]);
+        let validity = sequence(&[tlv(0x17, b"240101000000Z"), tlv(0x17, b"290101000000Z")]);
+        let tbs = sequence(&[
+            tlv(0xa0, &tlv(0x02, &[2])),
+            tlv(0x02, serial),
+            algorithm.clone(),
+            issuer(),
+            validity,
+            subject.to_vec(),
+            sequence(&[algorithm.clone(), tlv(0x03, &[0, 1, 2, 3])]),
+        ]);
+        sequence(&[tbs, algorithm, tlv(0x03, &[0, 4, 5, 6])])
+    }
+
+    /// A CMS SignedData ContentInfo carrying an intermediate and the leaf
+    /// certificate (in that order), signed by the leaf. The outer element
+    /// uses BER's indefinite length, as codesign writes it.
+    fn cms(leaf_subject: &[u8]) -> Vec<u8> {
+        let leaf_serial = [0x4a, 0x11, 0x7e];
+        let intermediate = certificate(
+            &[0x01],
+            &name(&[(OID_COMMON_NAME, "Developer ID Certification Authority")]),
+        );
+        let leaf = certificate(&leaf_serial, leaf_subject);
+        let signer_info = sequence(&[
+            tlv(0x02, &[1]),
+            sequence(&[issuer(), tlv(0x02, &leaf_serial)]),
+            sequence(&[tlv(0x06, SHA256_WITH_RSA)]),
+            tlv(0x04, &[0xaa; 16]),
+        ]);
+        let signed_data = sequence(&[
+            tlv(0x02, &[1]),
+            tlv(0x31, &sequence(&[tlv(0x06, SHA256_WITH_RSA)])),
+            sequence(&[tlv(0x06, DATA)]),
+            tlv(0xa0, &[intermediate, leaf].concat()),
+            tlv(0x31, &signer_info),
+        ]);
+        let mut content_info = vec![0x30, 0x80];
+        content_info.extend(tlv(0x06, SIGNED_DATA));
+        content_info.extend(tlv(0xa0, &signed_data));
+        content_info.extend([0, 0]);
+        content_info
+    }
+
+    fn developer_id_subject() -> Vec<u8> {
+        name(&[
+            (OID_ORGANIZATIONAL_UNIT, "EQHXZ8M8AV"),
+            (
+                OID_COMMON_NAME,
+                "Developer ID Application: Example, Inc. (EQHXZ8M8AV)",
+            ),
+        ])
+    }
+
+    #[test]
+    fn signer_is_the_leaf_certificate_subject() {
+        let cms = cms(&developer_id_subject());
+        let subject = signer_subject(&cms).unwrap();
+        assert_eq!(subject, &developer_id_subject()[2..]);
+        assert_eq!(
+            name_attribute(subject, OID_COMMON_NAME).as_deref(),
+            Some("Developer ID Application: Example, Inc. (EQHXZ8M8AV)")
+        );
+        assert_eq!(
+            name_attribute(subject, OID_ORGANIZATIONAL_UNIT).as_deref(),
+            Some("EQHXZ8M8AV")
+        );
+        let no_ou = name(&[(OID_COMMON_NAME, "Apple Development: Jane Doe")]);
+        assert_eq!(name_attribute(&no_ou[2..], OID_ORGANIZATIONAL_UNIT), None);
+    }
+
+    #[test]
+    fn signer_and_team_come_from_the_cms_signature() {
+        let cms = cms(&developer_id_subject());
+        let mut wrapper = be(&[CSMAGIC_BLOBWRAPPER, 8 + cms.len() as u32]);
+        wrapper.extend_from_slice(&cms);
+        let signature = superblob(&[
+            (
+                CSSLOT_CODEDIRECTORY,
+                code_directory(0x1_0000, "com.example.app", None),
+            ),
+            (CSSLOT_SIGNATURESLOT, wrapper),
+        ]);
+        assert_eq!(
+            signature_of(&executable(Some(&signature))),
+            Some(CodeSignature {
+                status: SignatureStatus::Signed,
+                identifier: Some("com.example.app".into()),
+                team_id: Some("EQHXZ8M8AV".into()),
+                signer: Some("Developer ID Application: Example, Inc. (EQHXZ8M8AV)".into()),
+            })
+        );
+    }
+
+    #[test]
+    fn truncated_or_unmatched_cms_has_no_signer() {
+        let cms = cms(&developer_id_subject());
+        for len in 0..cms.len() {
+            assert_eq!(signer_subject(&cms[..len]), None, "{len} bytes");
+        }
+        // A signer whose certificate isn't included
+        let mut unmatched = cms.clone();
+        let serial = unmatched
+            .windows(3)
+            .rposition(|w| w == [0x4a, 0x11, 0x7e])
+            .unwrap();
+        unmatched[serial] = 0x4b;
+        assert_eq!(signer_subject(&unmatched), None);
+        // Lengths past the end, and more length bytes than fit a usize
+        assert_eq!(der(&[0x30, 0x05, 0x01], 0), None);
+        assert_eq!(der(&[0x30, 0x89, 1, 2, 3, 4, 5, 6, 7, 8, 9], 0), None);
+    }
+
+    #[test]
+    fn corrupt_index_counts_read_only_what_the_blob_holds() {
+        let mut blob = requirements(&apple_requirement("com.apple.Safari"));
+        blob[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(designated_anchor(&blob).is_some_and(|anchor| anchor.apple));
+
+        let mut signature = superblob(&[(
+            CSSLOT_CODEDIRECTORY,
+            code_directory(CS_ADHOC, "com.example.app", None),
+        )]);
+        signature[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        let directory = code_directory(CS_ADHOC, "com.example.app", None);
+        let blob_at = |offset: u32, limit: usize| {
+            let start = offset as usize - 20;
+            Some(directory[start..directory.len().min(start + limit)].to_vec())
+        };
+        assert_eq!(
+            parse_signature(&signature, blob_at).map(|signature| signature.status),
+            Some(SignatureStatus::AdHoc)
+        );
+        assert_eq!(index_offset(&be(&[CSMAGIC_REQUIREMENTS, 12, 3]), 3), None);
+    }
+}
@@ -1,13 +1,31 @@
 use rayon::prelude::*;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
-use crate::app_discovery::{app_category, discover_apps_and_folders, get_applications_dirs};
+use crate::app_details::read_app_details;
+use crate::app_discovery::get_applications_dirs;
+use crate::appimage::is_appimage;
+use crate::bundle_audit::{audit_bundle, AppAudit};
+use crate::bundle_size::spawn_size_scan;
+use crate::category::{category_display_name, dominant_category_name, localized_category_name};
 use crate::config::{
-    get_config_path, AppConfig, AppInfo, AppsResponse, FolderInfo, FolderMetadata, LayoutMode,
-    OrderConfig, CONFIG_STATE, CONFIG_VERSION,
+    current_settings, get_config_path, now_ms, AppConfig, AppDetails, AppsDiff, AppsResponse,
+    DuplicatePolicy, FolderMetadata, LayoutMode, OrderConfig, PlacementPolicy, ReconciledLayout,
+    ViewToggle, CONFIG_STATE, CONFIG_VERSION,
 };
-use crate::icon_cache::{cleanup_orphaned_icons, get_icon_if_cached};
+use crate::desktop_launch::{launch_appimage, launch_desktop_entry};
+use crate::discovery_index::{load_index, save_index, scan_apps, ServedApps, SERVED_INDEX};
+use crate::duplicates::resolve_duplicates;
+#[cfg(target_os = "linux")]
+use crate::file_manager::reveal_in_file_manager;
+use crate::icon_cache::{
+    cleanup_orphaned_icons, generate_and_cache_icon, get_icon_if_cached, get_nearest_cached_icon,
+    preferred_icon_size, set_preferred_icon_size,
+};
+use crate::placement::{reconcile, AppFacts, Installed};
+use crate::quarantine::read_quarantine;
+use crate::seen_apps::{mark_seen, save_seen_apps, seed_seen_apps};
 use crate::AppError;
 
 /// Load app config from disk and seed the in-memory snapshot that all
@@ -51,6 +69,190 @@ pub(crate) async fn set_layout(layout: LayoutMode) -> Result<(), AppError> {
     crate::config::save_config_to_disk()
 }
 
+/// Persist one on/off setting immediately, like set_layout. Still field-
+/// level: the toggle names the one field written.
+#[tauri::command]
+pub(crate) async fn set_toggle(setting: ViewToggle, enabled: bool) -> Result<(), AppError> {
+    {
+        let mut state = CONFIG_STATE.lock().unwrap_or_else(|p| p.into_inner());
+        let Some(config) = state.as_mut() else {
+            return Err(AppError::Validation("Config not loaded".into()));
+        };
+        *config.settings.toggle_mut(setting) = enabled;
+    }
+    crate::config::save_config_to_disk()
+}
+
+/// Persist the duplicate policy immediately, like set_layout. The frontend
+/// follows up with refresh_apps, whose diff carries the copies it shows or
+/// hides.
+#[tauri::command]
+pub(crate) async fn set_duplicate_policy(policy: DuplicatePolicy) -> Result<(), AppError> {
+    {
+        let mut state = CONFIG_STATE.lock().unwrap_or_else(|p| p.into_inner());
+        let Some(config) = state.as_mut() else {
+            return Err(AppError::Validation("Config not loaded".into()));
+        };
+        config.settings.duplicate_policy = policy;
+    }
+    crate::config::save_config_to_disk()
+}
+
+/// Persist where new apps land, like set_layout. Applies from the next
+/// reconcile_layout on; nothing already placed moves.
+#[tauri::command]
+pub(crate) async fn set_placement_policy(policy: PlacementPolicy) -> Result<(), AppError> {
+    {
+        let mut state = CONFIG_STATE.lock().unwrap_or_else(|p| p.into_inner());
+        let Some(config) = state.as_mut() else {
+            return Err(AppError::Validation("Config not loaded".into()));
+        };
+        config.settings.placement_policy = policy;
+    }
+    crate::config::save_config_to_disk()
+}
+
+/// Longest a missing app may keep its slot: a year (a ghost is meant to
+/// outlast an update or an unmounted volume, not to pin the slot forever)
+const MAX_GHOST_GRACE_PERIOD_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Persist how long a missing app keeps its slot, like set_layout. Applies
+/// from the next reconcile_layout on, to existing ghosts too: their
+/// timestamps are when the app went missing, not when they expire.
+#[tauri::command]
+pub(crate) async fn set_ghost_grace_period(secs: u64) -> Result<(), AppError> {
+    if secs == 0 || secs > MAX_GHOST_GRACE_PERIOD_SECS {
+        return Err(AppError::Validation(format!(
+            "Grace period must be between 1 second and {MAX_GHOST_GRACE_PERIOD_SECS} seconds"
+        )));
+    }
+    {
+        let mut state = CONFIG_STATE.lock().unwrap_or_else(|p| p.into_inner());
+        let Some(config) = state.as_mut() else {
+            return Err(AppError::Validation("Config not loaded".into()));
+        };
+        config.settings.ghost_grace_period_secs = secs;
+    }
+    crate::config::save_config_to_disk()
+}
+
+/// Reconcile an arrangement with the main-grid apps at `app_paths` and the
+/// physical folders' apps at `folder_app_paths`: what's gone leaves a
+/// ghost holding its slot, what's back returns to it, what's new is placed
+/// by the placement policy. Runs when the grid is seeded and when apps
+/// turn up later; `page_capacity` is how many items fit a page on screen,
+/// when the paged layout has measured it. The ghosts stay with the config
+/// snapshot, saved with the arrangement.
+#[tauri::command]
+pub(crate) fn reconcile_layout(
+    pages: Vec<Vec<String>>,
+    folders: Vec<FolderMetadata>,
+    app_paths: Vec<String>,
+    folder_app_paths: Vec<String>,
+    page_capacity: Option<usize>,
+) -> Result<ReconciledLayout, AppError> {
+    // Folder names an app belongs in: its Finder tags (smart folders from
+    // grouping by tag), then its category
+    let mut facts: HashMap<String, AppFacts> = SERVED_INDEX
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .as_ref()
+        .map(|served| {
+            let view = &served.view;
+            view.apps
+                .iter()
+                .chain(view.folders.iter().flat_map(|folder| folder.apps.iter()))
+                .map(|bundle| {
+                    // The localized name is the one folders get; a folder
+                    // named in English matches too
+                    let category = bundle.category.as_deref();
+                    let folder_names = bundle
+                        .tags
+                        .iter()
+                        .map(|tag| tag.name.clone())
+                        .chain(category.and_then(localized_category_name))
+                        .chain(category.and_then(category_display_name))
+                        .collect();
+                    let facts = AppFacts {
+                        identifier: bundle.identifier.clone(),
+                        folder_names,
+                    };
+                    (bundle.path.clone(), facts)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut state = CONFIG_STATE.lock().unwrap_or_else(|p| p.into_inner());
+    let Some(config) = state.as_mut() else {
+        return Err(AppError::Validation("Config not loaded".into()));
+    };
+    // Apps already gone from the index are known by the identifier they
+    // had when last arranged, so their ghosts can match a copy elsewhere
+    for (path, identifier) in &config.order.identifiers {
+        facts.entry(path.clone()).or_insert_with(|| AppFacts {
+            identifier: Some(identifier.clone()),
+            ..AppFacts::default()
+        });
+    }
+    let installed = Installed {
+        app_paths: &app_paths,
+        folder_app_paths: &folder_app_paths,
+        facts: &facts,
+    };
+    let now = now_ms();
+    config
+        .order
+        .collect_ghosts(config.settings.ghost_grace_period_secs, now);
+    let saved = OrderConfig {
+        pages,
+        folders,
+        ghosts: std::mem::take(&mut config.order.ghosts),
+        identifiers: HashMap::new(),
+    };
+    let (reconciled, ghosts) = reconcile(
+        saved,
+        &installed,
+        page_capacity,
+        config.settings.placement_policy,
+        now,
+    );
+    config.order.ghosts = ghosts;
+    config.order.identifiers = reconciled
+        .pages
+        .iter()
+        .flatten()
+        .chain(
+            reconciled
+                .folders
+                .iter()
+                .flat_map(|folder| &folder.app_paths),
+        )
+        .filter_map(|id| Some((id.clone(), facts.get(id)?.identifier.clone()?)))
+        .collect();
+    Ok(reconciled)
+}
+
+/// Suggest a name for a folder of the apps at `paths`: the display name,
+/// in the user's language, of the App Store category most of them belong
+/// to (ties go to the earliest path's). None when none declares one.
+#[tauri::command]
+pub(crate) fn suggest_folder_name(paths: Vec<String>) -> Option<String> {
+    let state = SERVED_INDEX.lock().unwrap_or_else(|p| p.into_inner());
+    let view = &state.as_ref()?.view;
+    let categories: HashMap<&str, &str> = view
+        .apps
+        .iter()
+        .chain(view.folders.iter().flat_map(|folder| folder.apps.iter()))
+        .filter_map(|bundle| Some((bundle.path.as_str(), bundle.category.as_deref()?)))
+        .collect();
+    dominant_category_name(
+        paths
+            .iter()
+            .filter_map(|path| categories.get(path.as_str()).copied()),
+    )
+}
+
 /// Update order in memory (called on every change from frontend)
 /// Disk write happens only on window close for safety
 #[tauri::command]
@@ -92,98 +294,123 @@ pub(crate) fn update_order(
     let Some(config) = state.as_mut() else {
         return Err(AppError::Validation("Config not loaded".into()));
     };
-    config.order = OrderConfig { pages, folders };
+    // Ghosts and identifiers aren't the frontend's: they ride along
+    // untouched
+    config.order.pages = pages;
+    config.order.folders = folders;
     Ok(())
 }
 
-/// Generate icon for a single app (called from frontend for progressive loading)
+/// Generate icon for a single app (called from frontend for progressive
+/// loading), at the cached size covering `size` pixels (default: the app
+/// list's). Falls back to the nearest size cached when it can't be made.
 #[tauri::command]
-pub(crate) async fn get_app_icon(path: String) -> Option<String> {
+pub(crate) async fn get_app_icon(path: String, size: Option<u32>) -> Option<String> {
     let path_buf = PathBuf::from(&path);
-    if !path_buf.is_absolute() || path_buf.extension().is_none_or(|ext| ext != "app") {
+    if !path_buf.is_absolute() || !has_app_extension(&path_buf) {
+        return None;
+    }
+    // A missing bundle (an offline volume's app) would get a generic icon
+    if !path_buf.exists() {
         return None;
     }
 
+    let size = size.unwrap_or_else(preferred_icon_size);
     // Check cache first
-    if let Some(cached) = get_icon_if_cached(&path) {
+    if let Some(cached) = get_icon_if_cached(&path, size) {
         return Some(cached);
     }
     // Generate if not cached
-    #[cfg(target_os = "macos")]
-    return crate::icon_cache::generate_and_cache_icon(&path);
-    #[cfg(not(target_os = "macos"))]
-    None
+    generate_and_cache_icon(&path, size).or_else(|| get_nearest_cached_icon(&path, size))
 }
 
-/// Build the AppInfo for an .app path. Category stays None here — it only
-/// feeds folder-name suggestions, so callers add it where that applies.
-fn app_info_from_path(path: &std::path::Path) -> Option<AppInfo> {
-    let name = path.file_stem()?.to_string_lossy().to_string();
-    let path_str = path.to_string_lossy().to_string();
-    let icon = get_icon_if_cached(&path_str);
-    Some(AppInfo {
-        name,
-        path: path_str,
-        category: None,
-        icon,
-    })
-}
-
-/// Get all apps and folders - loads icons in parallel for speed
+/// Get all apps and folders. Served from the discovery index when there is
+/// one, so no directory walk or Info.plist read stands between launch and
+/// show_window; without one (first run, or a new index format) this scans
+/// once and writes it. Either way the frontend follows up with refresh_apps.
+/// Icons come at the cached size covering `icon_size` pixels, which the
+/// list's later icons (refresh_apps) keep to.
 #[tauri::command]
-pub(crate) async fn get_apps(app: tauri::AppHandle) -> Result<AppsResponse, AppError> {
-    let (app_paths, folder_data) = discover_apps_and_folders(&app.config().identifier);
-
-    // Load app icons in parallel
-    let mut apps: Vec<AppInfo> = app_paths
-        .into_par_iter()
-        .filter_map(|path| {
-            let mut info = app_info_from_path(&path)?;
-            // Only main-grid apps participate in folder creation
-            info.category = app_category(&info.path);
-            Some(info)
-        })
-        .collect();
+pub(crate) async fn get_apps(
+    app: tauri::AppHandle,
+    icon_size: Option<u32>,
+) -> Result<AppsResponse, AppError> {
+    if let Some(size) = icon_size {
+        set_preferred_icon_size(size);
+    }
+    let index = match load_index() {
+        Some(index) => index,
+        None => {
+            let index = scan_apps(&app.config().identifier, None, &current_settings());
+            if let Err(e) = save_index(&index) {
+                eprintln!("Failed to save discovery index: {}", e);
+            }
+            index
+        }
+    };
 
-    apps.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+    seed_seen_apps(&index);
+    let (view, duplicates) = resolve_duplicates(
+        &index,
+        current_settings().duplicate_policy,
+        dirs::home_dir().as_deref(),
+    );
+    let response = view.to_response(duplicates);
+    *SERVED_INDEX.lock().unwrap_or_else(|p| p.into_inner()) = Some(ServedApps { index, view });
+    Ok(response)
+}
 
-    // Load folder icons in parallel
-    let folders: Vec<FolderInfo> = folder_data
-        .into_par_iter()
-        .filter_map(|(folder_path, sub_app_paths)| {
-            let raw_name = folder_path.file_name()?.to_string_lossy().to_string();
-            let name = raw_name
-                .strip_suffix(".localized")
-                .unwrap_or(&raw_name)
-                .to_string();
-            let path_str = folder_path.to_string_lossy().to_string();
-
-            let folder_apps: Vec<AppInfo> = sub_app_paths
-                .into_par_iter()
-                .filter_map(|app_path| app_info_from_path(&app_path))
-                .collect();
-
-            Some(FolderInfo {
-                name,
-                path: path_str,
-                apps: folder_apps,
-            })
-        })
-        .collect();
+/// Re-validate the served app list against the file system and return only
+/// what changed. Bundles with an unchanged mtime keep their indexed
+/// metadata, so this mostly costs the directory walk. Also rewrites the
+/// index and prunes orphaned icons, both of which need the fresh list.
+/// The duplicate policy is re-applied too, so a changed policy shows up
+/// here as copies added and removed.
+#[tauri::command]
+pub(crate) async fn refresh_apps(app: tauri::AppHandle) -> Result<AppsDiff, AppError> {
+    let served = SERVED_INDEX
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .clone()
+        .ok_or_else(|| AppError::Validation("Apps not loaded".into()))?;
+
+    let settings = current_settings();
+    let fresh = scan_apps(&app.config().identifier, Some(&served.index), &settings);
+    let (view, duplicates) = resolve_duplicates(
+        &fresh,
+        settings.duplicate_policy,
+        dirs::home_dir().as_deref(),
+    );
+    let diff = AppsDiff {
+        duplicates,
+        ..served.view.diff(&view)
+    };
 
-    // Clean up orphaned icon cache entries in the background
-    let all_app_paths: Vec<String> = apps
-        .iter()
-        .map(|a| a.path.clone())
-        .chain(
-            folders
-                .iter()
-                .flat_map(|f| f.apps.iter().map(|a| a.path.clone())),
-        )
-        .collect();
+    if fresh != served.index {
+        if let Err(e) = save_index(&fresh) {
+            eprintln!("Failed to save discovery index: {}", e);
+        }
+    }
+    // From the full scan: copies the policy hides keep their icons (and
+    // their sizes)
+    let all_app_paths = fresh.all_paths();
+    spawn_size_scan(app.clone(), all_app_paths.clone());
     std::thread::spawn(move || cleanup_orphaned_icons(&all_app_paths));
 
-    Ok(AppsResponse { apps, folders })
+    *SERVED_INDEX.lock().unwrap_or_else(|p| p.into_inner()) =
+        Some(ServedApps { index: fresh, view });
+    Ok(diff)
+}
+
+/// Whether `path` is named like the paths apps are listed by: .app
+/// bundles, or on Linux the .desktop files of desktop entries and
+/// AppImages
+fn has_app_extension(path: &Path) -> bool {
+    if cfg!(target_os = "linux") {
+        path.extension().is_some_and(|ext| ext == "desktop") || is_appimage(path)
+    } else {
+        path.extension().is_some_and(|ext| ext == "app")
+    }
 }
 
 /// Validate that a path is an .app bundle inside an allowed applications
@@ -191,7 +418,10 @@ pub(crate) async fn get_apps(app: tauri::AppHandle) -> Result<AppsResponse, AppE
 /// canonicalize: system apps like Safari are cryptex symlinks in
 /// /Applications whose resolved target (/System/Volumes/Preboot/…) is
 /// outside every allowed directory. Traversal is rejected lexically
-/// instead, and the bundle must exist.
+/// instead, and the bundle must exist. On Linux the same rules apply to a
+/// .desktop file in an XDG applications directory, which must also be the
+/// one served for its desktop-file id: a file another one shadows isn't
+/// what the desktop would launch.
 pub(crate) fn validated_app_path(path: &str) -> Result<PathBuf, AppError> {
     let path_buf = PathBuf::from(path);
 
@@ -202,14 +432,28 @@ pub(crate) fn validated_app_path(path: &str) -> Result<PathBuf, AppError> {
                 std::path::Component::RootDir | std::path::Component::Normal(_)
             )
         });
-    if !lexically_clean || path_buf.extension().is_none_or(|ext| ext != "app") {
+    if !lexically_clean || !has_app_extension(&path_buf) {
         return Err(AppError::Validation("Invalid app path".into()));
     }
 
-    let allowed = get_applications_dirs();
-    if !allowed.iter().any(|dir| path_buf.starts_with(dir)) {
+    // Aliased apps may live anywhere; only those actually served pass
+    let (aliased, served) = SERVED_INDEX
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .as_ref()
+        .map_or((false, false), |served| {
+            (
+                served.index.has_alias_target(path),
+                served.index.all_paths().iter().any(|p| p == path),
+            )
+        });
+    let allowed = get_applications_dirs(&current_settings());
+    if !aliased && !allowed.iter().any(|dir| path_buf.starts_with(dir)) {
         return Err(AppError::Validation("App not in allowed directory".into()));
     }
+    if cfg!(target_os = "linux") && !served {
+        return Err(AppError::Validation("App not served".into()));
+    }
 
     if !path_buf.exists() {
         return Err(AppError::Validation("App does not exist".into()));
@@ -218,19 +462,60 @@ pub(crate) fn validated_app_path(path: &str) -> Result<PathBuf, AppError> {
     Ok(path_buf)
 }
 
+/// Identifier the served index holds for the bundle at `path`
+fn served_identifier(path: &str) -> Option<String> {
+    SERVED_INDEX
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .as_ref()
+        .and_then(|served| served.index.identifier_of(path))
+}
+
+/// Launch the app, which also clears its "new" flag
 #[tauri::command]
 pub(crate) async fn launch_app(path: String) -> Result<(), AppError> {
     let validated = validated_app_path(&path)?;
-    Command::new("open").arg(validated).spawn()?;
+    if cfg!(target_os = "linux") {
+        if is_appimage(&validated) {
+            launch_appimage(&validated)?;
+        } else {
+            let id = served_identifier(&path)
+                .ok_or_else(|| AppError::Validation("App not served".into()))?;
+            launch_desktop_entry(&validated, &id, &[])?;
+        }
+    } else {
+        Command::new("open").arg(validated).spawn()?;
+    }
+    let seen = SERVED_INDEX
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .as_ref()
+        .map(|served| mark_seen(&served.index, Some(&[path.as_str()])));
+    if let Err(e) = seen.as_ref().map_or(Ok(()), save_seen_apps) {
+        eprintln!("Failed to save seen apps: {}", e);
+    }
     Ok(())
 }
 
+/// Clear every app's "new" flag (options menu "Mark All as Seen")
+#[tauri::command]
+pub(crate) fn mark_all_apps_seen() -> Result<(), AppError> {
+    let seen = SERVED_INDEX
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .as_ref()
+        .map(|served| mark_seen(&served.index, None))
+        .ok_or_else(|| AppError::Validation("Apps not loaded".into()))?;
+    save_seen_apps(&seen)
+}
+
 /// Reveal the app bundle in Finder (context menu "Show in Finder").
 /// Calls NSWorkspace directly: `open -R` reaches Finder via Apple Events
 /// (Automation consent prompt), and the opener plugin canonicalizes first,
 /// which would reveal cryptex-symlinked apps like Safari in the cryptex
 /// directory instead of /Applications. Sync command: runs on the main
-/// thread, which AppKit requires.
+/// thread, which AppKit requires. On Linux the desktop's file manager
+/// shows it instead (see file_manager).
 #[tauri::command]
 pub(crate) fn reveal_app(path: String) -> Result<(), AppError> {
     let validated = validated_app_path(&path)?;
@@ -246,32 +531,55 @@ pub(crate) fn reveal_app(path: String) -> Result<(), AppError> {
         Ok(())
     }
 
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(target_os = "linux")]
+    {
+        reveal_in_file_manager(&validated)
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
     {
         let _ = validated;
-        Err(AppError::Validation("Reveal is only available on macOS".into()))
+        Err(AppError::Validation(
+            "Revealing apps isn't supported on this platform".into(),
+        ))
     }
 }
 
-/// Open Finder's Get Info panel for the app (context menu "Get Info").
-/// Finder exposes no CLI/API for this, so it goes through AppleScript;
-/// the path travels as argv rather than being spliced into the script.
-/// First use triggers the macOS Automation consent prompt (→ Finder).
+/// Details for the built-in info sheet (context menu "Get Info"), read
+/// from the bundle itself: Finder's own Get Info window is only reachable
+/// through AppleScript, behind an Automation consent prompt.
 #[tauri::command]
-pub(crate) async fn show_get_info(path: String) -> Result<(), AppError> {
+pub(crate) async fn get_app_details(path: String) -> Result<AppDetails, AppError> {
     let validated = validated_app_path(&path)?;
-    Command::new("osascript")
-        .args([
-            "-e", "on run argv",
-            "-e", "tell application \"Finder\"",
-            "-e", "activate",
-            "-e", "open information window of (POSIX file (item 1 of argv) as alias)",
-            "-e", "end tell",
-            "-e", "end run",
-        ])
-        .arg(validated)
-        .spawn()?;
-    Ok(())
+    Ok(read_app_details(&validated, served_identifier(&path)))
+}
+
+/// Check every served bundle for structural problems (see bundle_audit),
+/// listing the bundles that have any. Offline placeholders are skipped:
+/// their bundle can't be checked.
+#[tauri::command]
+pub(crate) async fn audit_apps() -> Vec<AppAudit> {
+    let paths: Vec<String> = SERVED_INDEX
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .as_ref()
+        .map(|served| {
+            let view = &served.view;
+            view.apps
+                .iter()
+                .chain(view.folders.iter().flat_map(|folder| folder.apps.iter()))
+                .filter(|bundle| !bundle.offline)
+                .map(|bundle| bundle.path.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    paths
+        .into_par_iter()
+        .filter_map(|path| {
+            let problems = audit_bundle(Path::new(&path));
+            (!problems.is_empty()).then_some(AppAudit { path, problems })
+        })
+        .collect()
 }
 
 /// Show a Quick Look preview of the app bundle (context menu "Quick Look").
@@ -320,6 +628,20 @@ pub(crate) async fn quick_look(
     Ok(())
 }
 
+/// Open the page a downloaded app came from in the default browser
+/// (context menu "Show Origin"), through the opener plugin: open on
+/// macOS, xdg-open elsewhere. The URL is read from the bundle's own
+/// download record, never taken from the caller, and is always http(s).
+#[tauri::command]
+pub(crate) async fn show_origin(path: String) -> Result<(), AppError> {
+    let validated = validated_app_path(&path)?;
+    let origin = read_quarantine(&validated)
+        .and_then(|quarantine| quarantine.origin)
+        .ok_or_else(|| AppError::Validation("No download origin recorded".into()))?;
+    tauri_plugin_opener::open_url(origin, None::<&str>)
+        .map_err(|e| AppError::Validation(format!("Could not open the origin: {e}")))
+}
+
 /// Resolve the bundled Quick Look helper binary. The tauri CLI copies
 /// externalBin beside the app binary for both `tauri dev` and bundled
 /// builds; the triple-suffixed build output in src-tauri/binaries is the
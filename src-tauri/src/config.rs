@@ -1,17 +1,101 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
 use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::bundle_audit::AppProblem;
+use crate::codesign::CodeSignature;
+use crate::finder_tags::FinderTag;
+use crate::macho::Architecture;
+use crate::quarantine::Quarantine;
 use crate::AppError;
 
+/// Whether a discovered bundle can run as a regular, windowed app, and if
+/// not, why. Bundles that can't are hidden unless the user opts to see
+/// them (greyed out).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum Launchability {
+    #[default]
+    Launchable,
+    /// LSBackgroundOnly: a daemon-style helper with no UI at all
+    BackgroundOnly,
+    /// LSUIElement: an agent (menu bar extra, helper) with no window to show
+    Agent,
+    /// No Info.plist, no CFBundleExecutable, or the executable is missing
+    MissingExecutable,
+    /// LSMinimumSystemVersion is newer than the running macOS
+    RequiresNewerSystem { required: String },
+}
+
+/// Which discovery provider found an app
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum AppSource {
+    /// The applications directories themselves (and their subfolders)
+    #[default]
+    Applications,
+    /// Chromium-browser web apps (PWAs): ~/Applications/Chrome Apps.localized etc.
+    WebApp,
+    /// Setapp's apps in /Applications/Setapp
+    Setapp,
+    /// Homebrew casks installed to a custom --appdir
+    Homebrew,
+    /// iPhone/iPad apps running on Apple Silicon (a Wrapper/ bundle)
+    IosApp,
+    /// /Volumes/<name>/Applications on a mounted external volume
+    ExternalVolume,
+    /// Flatpak's exported desktop entries (Linux)
+    Flatpak,
+    /// Snap's exported desktop entries (Linux)
+    Snap,
+    /// A loose AppImage in ~/Applications (Linux)
+    AppImage,
+}
+
 #[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AppInfo {
     pub name: String,
     pub path: String,
     pub icon: Option<String>,
     /// App Store category identifier (LSApplicationCategoryType), if declared
     pub category: Option<String>,
+    /// The category's display name in the user's language (a new folder's
+    /// suggested name)
+    pub category_name: Option<String>,
+    pub launchability: Launchability,
+    /// CPU architectures of the main executable, if it is a readable Mach-O
+    pub architecture: Option<Architecture>,
+    /// Intel-only on an Apple Silicon Mac: runs through Rosetta
+    pub needs_rosetta: bool,
+    /// Who signed the main executable, if it is a readable Mach-O
+    pub signature: Option<CodeSignature>,
+    /// Download record while quarantined (downloaded, maybe never opened)
+    pub quarantine: Option<Quarantine>,
+    /// Finder tags on the bundle, in Finder's order (then its freedesktop
+    /// tags, on Linux)
+    pub tags: Vec<FinderTag>,
+    /// Installed or updated since the user last saw it: not launched
+    /// since, nor marked seen
+    pub is_new: bool,
+    /// When it was updated (bundle mtime, ms since the epoch), if it is
+    /// new by way of an update rather than a fresh install
+    pub updated_at: Option<u64>,
+    /// Set when another copy of the same app is shown too: its version, or
+    /// its location when the versions match, to tell the tiles apart
+    pub disambiguation: Option<String>,
+    pub source: AppSource,
+    /// Name of the external volume the app is on (/Volumes/<name>)
+    pub volume: Option<String>,
+    /// On an external volume that isn't mounted: a placeholder keeping the
+    /// app's place until the volume is back
+    pub offline: bool,
+    /// The Finder alias or symlink the app was found through, if any (the
+    /// path above is then its resolved target)
+    pub via: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -21,10 +105,123 @@ pub struct FolderInfo {
     pub apps: Vec<AppInfo>,
 }
 
+/// Copies of one app (same bundle identifier) found in several places,
+/// and which of them the duplicate policy shows
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub bundle_id: String,
+    /// Paths of the copies shown, preferred first
+    pub shown: Vec<String>,
+    /// Paths of the copies the policy hides
+    pub hidden: Vec<String>,
+}
+
+/// Why an alias or symlink in an applications directory isn't shown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BrokenAliasReason {
+    /// Not readable as bookmark data (damaged, or an old-style alias)
+    Unreadable,
+    /// The app it points at is gone
+    TargetMissing,
+}
+
+/// An alias or symlink that should lead to an app but doesn't
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenAlias {
+    pub path: String,
+    /// Where it points, when that could be read
+    pub target: Option<String>,
+    pub reason: BrokenAliasReason,
+}
+
+/// Where a bundle came from, as far as the bundle itself tells
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Distribution {
+    /// Bundled with macOS (/System/Applications), or on Linux installed
+    /// with the system's packages (/usr)
+    System,
+    /// Carries a Mac App Store receipt (or, for iPhone/iPad apps, the
+    /// store's metadata)
+    AppStore,
+    /// Anything else: downloaded or installed directly
+    Direct,
+}
+
+/// A document type the app declares it can open or edit
+/// (CFBundleDocumentTypes)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentType {
+    pub name: Option<String>,
+    /// CFBundleTypeRole: "Editor", "Viewer", "Shell" or "None"
+    pub role: Option<String>,
+    /// Uniform type identifiers (LSItemContentTypes)
+    pub content_types: Vec<String>,
+    /// File extensions (CFBundleTypeExtensions, legacy declarations)
+    pub extensions: Vec<String>,
+}
+
+/// Everything the info sheet shows about one app. Dates are ms since
+/// the epoch.
 #[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppDetails {
+    pub name: String,
+    pub path: String,
+    pub bundle_id: Option<String>,
+    /// CFBundleShortVersionString
+    pub version: Option<String>,
+    /// CFBundleVersion
+    pub build: Option<String>,
+    pub architecture: Option<Architecture>,
+    pub needs_rosetta: bool,
+    pub signature: Option<CodeSignature>,
+    pub quarantine: Option<Quarantine>,
+    /// Bytes allocated on disk for the whole bundle
+    pub size_on_disk: Option<u64>,
+    /// When the app was added to its folder (Spotlight), else the bundle's
+    /// creation date
+    pub installed_at: Option<u64>,
+    pub modified_at: Option<u64>,
+    /// Last launch (Spotlight's usage history), else the executable's
+    /// access time
+    pub last_opened_at: Option<u64>,
+    pub distribution: Distribution,
+    pub document_types: Vec<DocumentType>,
+    /// Structural problems of the bundle (see bundle_audit), most severe
+    /// first
+    pub problems: Vec<AppProblem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct AppsResponse {
     pub apps: Vec<AppInfo>,
     pub folders: Vec<FolderInfo>,
+    pub duplicates: Vec<DuplicateGroup>,
+    pub broken_aliases: Vec<BrokenAlias>,
+}
+
+/// Changes found by re-validating a served app list (refresh_apps)
+#[derive(Debug, Clone, Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AppsDiff {
+    /// Main-grid apps that appeared
+    pub added: Vec<AppInfo>,
+    /// Main-grid apps whose bundle or metadata changed
+    pub updated: Vec<AppInfo>,
+    /// Paths of main-grid apps that are gone
+    pub removed: Vec<String>,
+    /// All physical folders, present only when any of them changed
+    pub folders: Option<Vec<FolderInfo>>,
+    /// The current duplicates report, in full
+    pub duplicates: Vec<DuplicateGroup>,
+    /// The current broken aliases report, in full
+    pub broken_aliases: Vec<BrokenAlias>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +240,84 @@ pub struct OrderConfig {
     /// is one page's items in order
     pub pages: Vec<Vec<String>>,
     pub folders: Vec<FolderMetadata>,
+    /// Apps that went missing from the arrangement, holding their slot
+    /// until they reappear or their grace period runs out
+    #[serde(default)]
+    pub ghosts: Vec<Ghost>,
+    /// Bundle identifiers of the arranged apps, as last seen in the index:
+    /// an app that went missing while the launcher wasn't looking still
+    /// leaves a ghost that knows what it was
+    #[serde(default)]
+    pub identifiers: HashMap<String, String>,
+}
+
+impl OrderConfig {
+    /// Drop ghosts missing for longer than `grace_secs`
+    pub(crate) fn collect_ghosts(&mut self, grace_secs: u64, now: u64) {
+        let grace_ms = grace_secs.saturating_mul(1000);
+        self.ghosts
+            .retain(|ghost| now.saturating_sub(ghost.missing_since) < grace_ms);
+    }
+}
+
+/// An app that was in the arrangement but wasn't found: updaters delete a
+/// bundle and recreate it moments later, and an arrangement saved in
+/// between would otherwise lose its slot for good
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Ghost {
+    /// The app's id in the arrangement
+    pub path: String,
+    /// Its bundle identifier, when it was known: a copy that reappears at
+    /// another path is the same app
+    pub identifier: Option<String>,
+    pub slot: GhostSlot,
+    /// When it went missing, ms since the epoch
+    pub missing_since: u64,
+}
+
+/// Where a ghost's app was; ordered by page and slot, then folder and
+/// index
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum GhostSlot {
+    /// On the main grid: page index and slot within it
+    Page { page: usize, slot: usize },
+    /// In a folder, at this index
+    Folder { folder_id: String, index: usize },
+}
+
+/// Where reconciliation put an item that wasn't in the arrangement yet,
+/// for the frontend to animate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(
+    tag = "kind",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum Placement {
+    /// On the main grid: page index and slot within it
+    Page {
+        id: String,
+        page: usize,
+        slot: usize,
+    },
+    /// Appended to a folder
+    Folder { id: String, folder_id: String },
+}
+
+/// The saved arrangement reconciled with what is installed now
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconciledLayout {
+    pub pages: Vec<Vec<String>>,
+    pub folders: Vec<FolderMetadata>,
+    /// Every item placed anew, in placement order
+    pub placements: Vec<Placement>,
 }
 
 /// How the main grid presents apps
@@ -54,9 +329,115 @@ pub enum LayoutMode {
     Paged,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+/// Which copy is shown when the same app (bundle identifier) is installed
+/// in more than one place
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicatePolicy {
+    /// The copy outside the home folder (/Applications), as LaunchServices
+    /// prefers when opening by bundle identifier
+    #[default]
+    PreferSystem,
+    /// The copy with the highest version (CFBundleShortVersionString, then
+    /// CFBundleVersion)
+    PreferNewest,
+    /// The copy in the home folder (~/Applications)
+    PreferUser,
+    /// Every copy, each labelled with its version or location
+    ShowAll,
+}
+
+/// Where an app new to the arrangement (just installed, or first seen)
+/// lands in the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum PlacementPolicy {
+    /// After the last item, like Launchpad
+    #[default]
+    EndOfLastPage,
+    /// The first page with room left
+    FirstFreeSlot,
+    /// A page of their own after the last one
+    NewPage,
+    /// Into the folder named after one of its Finder tags or its App Store
+    /// category (a smart or category folder); the end of the last page when
+    /// there is none
+    CategoryFolder,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
 pub struct AppSettings {
     pub layout: LayoutMode,
+    /// Show bundles that can't run (see Launchability) as greyed-out tiles
+    /// instead of hiding them
+    pub show_unlaunchable: bool,
+    pub duplicate_policy: DuplicatePolicy,
+    pub placement_policy: PlacementPolicy,
+    /// How long a missing app keeps its slot (see Ghost), in seconds
+    pub ghost_grace_period_secs: u64,
+    /// Discovery providers beyond the applications directories (AppSource)
+    pub show_web_apps: bool,
+    pub show_setapp_apps: bool,
+    pub show_homebrew_apps: bool,
+    pub show_ios_apps: bool,
+    pub show_external_volumes: bool,
+    pub show_flatpak_apps: bool,
+    pub show_snap_apps: bool,
+    pub show_appimages: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            layout: LayoutMode::default(),
+            show_unlaunchable: false,
+            duplicate_policy: DuplicatePolicy::default(),
+            placement_policy: PlacementPolicy::default(),
+            ghost_grace_period_secs: 24 * 60 * 60,
+            show_web_apps: true,
+            show_setapp_apps: true,
+            show_homebrew_apps: true,
+            show_ios_apps: true,
+            show_external_volumes: false,
+            show_flatpak_apps: true,
+            show_snap_apps: true,
+            show_appimages: true,
+        }
+    }
+}
+
+/// The on/off settings, named like their AppSettings fields — the options
+/// menu's toggles, all persisted through set_toggle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(clippy::enum_variant_names)]
+pub enum ViewToggle {
+    ShowUnlaunchable,
+    ShowWebApps,
+    ShowSetappApps,
+    ShowHomebrewApps,
+    ShowIosApps,
+    ShowExternalVolumes,
+    ShowFlatpakApps,
+    ShowSnapApps,
+    ShowAppimages,
+}
+
+impl AppSettings {
+    pub(crate) fn toggle_mut(&mut self, toggle: ViewToggle) -> &mut bool {
+        match toggle {
+            ViewToggle::ShowUnlaunchable => &mut self.show_unlaunchable,
+            ViewToggle::ShowWebApps => &mut self.show_web_apps,
+            ViewToggle::ShowSetappApps => &mut self.show_setapp_apps,
+            ViewToggle::ShowHomebrewApps => &mut self.show_homebrew_apps,
+            ViewToggle::ShowIosApps => &mut self.show_ios_apps,
+            ViewToggle::ShowExternalVolumes => &mut self.show_external_volumes,
+            ViewToggle::ShowFlatpakApps => &mut self.show_flatpak_apps,
+            ViewToggle::ShowSnapApps => &mut self.show_snap_apps,
+            ViewToggle::ShowAppimages => &mut self.show_appimages,
+        }
+    }
 }
 
 /// Format of the config file this build reads and writes. The file must
@@ -100,18 +481,53 @@ pub(crate) fn get_config_path() -> Option<PathBuf> {
     get_config_dir().map(|p| p.join("config.json"))
 }
 
+/// Current settings for backend work that can run before load_config
+/// (get_apps starts in parallel with it): the in-memory snapshot when
+/// seeded, otherwise the file as it is on disk, otherwise the defaults.
+/// Read-only — never seeds the snapshot.
+pub(crate) fn current_settings() -> AppSettings {
+    if let Some(config) = CONFIG_STATE
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .as_ref()
+    {
+        return config.settings.clone();
+    }
+    get_config_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str::<AppConfig>(&contents).ok())
+        .filter(|config| config.version == CONFIG_VERSION)
+        .map(|config| config.settings)
+        .unwrap_or_default()
+}
+
+/// Milliseconds since the epoch
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
 /// Save the in-memory config snapshot to disk (order changes ride the
 /// exit-time call; settings changes save immediately). A no-op until
 /// load_config has seeded the snapshot — a save can never invent a config
-/// or clobber a file it hasn't read.
+/// or clobber a file it hasn't read. Ghosts past their grace period are
+/// collected first.
 pub(crate) fn save_config_to_disk() -> Result<(), AppError> {
     let _save_guard = SAVE_LOCK.lock().unwrap_or_else(|p| p.into_inner());
 
-    // Clone and release the state lock quickly to avoid blocking updates
+    // Collect, clone and release the state lock quickly to avoid blocking
+    // updates
     let Some(config) = CONFIG_STATE
         .lock()
         .unwrap_or_else(|p| p.into_inner())
-        .clone()
+        .as_mut()
+        .map(|config| {
+            let grace = config.settings.ghost_grace_period_secs;
+            config.order.collect_ghosts(grace, now_ms());
+            config.clone()
+        })
     else {
         return Ok(()); // Nothing loaded, nothing to save
     };
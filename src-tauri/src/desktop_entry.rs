@@ -0,0 +1,446 @@
+//! Freedesktop Desktop Entry files (.desktop), the Linux counterpart of a
+//! bundle's Info.plist: an INI-style [Desktop Entry] group naming the app,
+//! the command it runs and where it may be shown.
+//!
+//! Values are unescaped as the spec defines (\s \n \t \r \\, and \; in
+//! lists). Localized keys ("Name[de_CH]") are matched against the user's
+//! languages lang_COUNTRY@MODIFIER first, then lang_COUNTRY, lang@MODIFIER
+//! and lang, before falling back to the unlocalized key.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::category::preferred_languages;
+use crate::config::Launchability;
+
+const DESKTOP_ENTRY_GROUP: &str = "Desktop Entry";
+
+/// The [Desktop Entry] group of a .desktop file
+pub(crate) struct DesktopEntry {
+    /// Raw (still escaped) values by key, localized variants included
+    /// under their full key ("Name[de]")
+    entries: HashMap<String, String>,
+}
+
+/// The groups of a freedesktop key file (.desktop, index.theme) by name,
+/// each with its raw values by key; the first of duplicate keys wins
+pub(crate) fn parse_key_file(contents: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut groups: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut group: Option<String> = None;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            groups.entry(name.to_string()).or_default();
+            group = Some(name.to_string());
+            continue;
+        }
+        let (Some(entries), Some((key, value))) = (
+            group.as_ref().and_then(|name| groups.get_mut(name)),
+            line.split_once('='),
+        ) else {
+            continue;
+        };
+        entries
+            .entry(key.trim_end().to_string())
+            .or_insert_with(|| value.trim_start().to_string());
+    }
+    groups
+}
+
+/// Undo the spec's escapes in a string value
+fn unescape(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => unescaped.push(' '),
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('r') => unescaped.push('\r'),
+            Some(other) => unescaped.push(other),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// Split a list value at its unescaped semicolons; the trailing one is
+/// optional, and empty items are dropped
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => item.push(';'),
+                Some(other) => {
+                    item.push('\\');
+                    item.push(other);
+                }
+                None => item.push('\\'),
+            },
+            ';' => items.push(std::mem::take(&mut item)),
+            c => item.push(c),
+        }
+    }
+    items.push(item);
+    items
+        .iter()
+        .filter(|item| !item.is_empty())
+        .map(|item| unescape(item))
+        .collect()
+}
+
+/// Locale suffixes to look up for a locale tag, best match first:
+/// "de_CH.UTF-8@euro" → de_CH@euro, de_CH, de@euro, de
+fn locale_candidates(tag: &str) -> Vec<String> {
+    let (rest, modifier) = match tag.split_once('@') {
+        Some((rest, modifier)) => (rest, Some(modifier)),
+        None => (tag, None),
+    };
+    let rest = rest.split('.').next().unwrap_or_default();
+    let (lang, country) = match rest.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (rest, None),
+    };
+    if lang.is_empty() || lang == "C" || lang == "POSIX" {
+        return Vec::new();
+    }
+    let mut candidates = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        candidates.push(format!("{lang}_{country}@{modifier}"));
+    }
+    if let Some(country) = country {
+        candidates.push(format!("{lang}_{country}"));
+    }
+    if let Some(modifier) = modifier {
+        candidates.push(format!("{lang}@{modifier}"));
+    }
+    candidates.push(lang.to_string());
+    candidates
+}
+
+/// Locale suffixes for each of `tags` in turn, without repeats
+fn locales_for(tags: &[String]) -> Vec<String> {
+    let mut locales: Vec<String> = Vec::new();
+    for candidate in tags.iter().flat_map(|tag| locale_candidates(tag)) {
+        if !locales.contains(&candidate) {
+            locales.push(candidate);
+        }
+    }
+    locales
+}
+
+/// Locale suffixes for every preferred language, in order, read once
+fn user_locales() -> &'static [String] {
+    static LOCALES: OnceLock<Vec<String>> = OnceLock::new();
+    LOCALES.get_or_init(|| locales_for(&preferred_languages()))
+}
+
+/// The desktop environments running, from XDG_CURRENT_DESKTOP
+/// ("ubuntu:GNOME" → ["ubuntu", "GNOME"])
+pub(crate) fn current_desktops() -> Vec<String> {
+    desktops_in(&std::env::var("XDG_CURRENT_DESKTOP").unwrap_or_default())
+}
+
+/// The desktop environments of an XDG_CURRENT_DESKTOP value
+fn desktops_in(value: &str) -> Vec<String> {
+    value
+        .split(':')
+        .filter(|desktop| !desktop.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether `path` is a regular file with an exec bit set
+pub(crate) fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+/// Resolve a program as the shell would: a path as it is, a bare name
+/// through PATH
+pub(crate) fn find_program(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return is_executable(&path).then_some(path);
+    }
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(program))
+        .find(|candidate| is_executable(candidate))
+}
+
+impl DesktopEntry {
+    pub(crate) fn read(path: &Path) -> Option<Self> {
+        Self::parse(&fs::read_to_string(path).ok()?)
+    }
+
+    /// Parse a .desktop file's contents; None without a [Desktop Entry]
+    /// group. Keys of other groups (actions) are skipped.
+    pub(crate) fn parse(contents: &str) -> Option<Self> {
+        let entries = parse_key_file(contents).remove(DESKTOP_ENTRY_GROUP)?;
+        Some(Self { entries })
+    }
+
+    /// A string value, unescaped
+    pub(crate) fn string(&self, key: &str) -> Option<String> {
+        self.entries.get(key).map(|value| unescape(value))
+    }
+
+    /// A localestring value in the user's language, else unlocalized
+    pub(crate) fn localized(&self, key: &str) -> Option<String> {
+        self.localized_in(key, user_locales())
+    }
+
+    /// A localestring value in the first of `locales` it has, else
+    /// unlocalized
+    fn localized_in(&self, key: &str, locales: &[String]) -> Option<String> {
+        locales
+            .iter()
+            .find_map(|locale| self.entries.get(&format!("{key}[{locale}]")))
+            .or_else(|| self.entries.get(key))
+            .map(|value| unescape(value))
+    }
+
+    /// A boolean value. The spec says "true" or "false"; older files
+    /// write 1 and 0, which desktop environments still accept.
+    pub(crate) fn flag(&self, key: &str) -> bool {
+        matches!(
+            self.entries.get(key).map(String::as_str),
+            Some("true" | "1")
+        )
+    }
+
+    /// A semicolon-separated list value
+    pub(crate) fn list(&self, key: &str) -> Vec<String> {
+        self.entries
+            .get(key)
+            .map(|value| split_list(value))
+            .unwrap_or_default()
+    }
+
+    /// The display name (Name, localized)
+    pub(crate) fn name(&self) -> Option<String> {
+        self.localized("Name").filter(|name| !name.is_empty())
+    }
+
+    /// Whether menus list this entry in the given desktop environments: an
+    /// application that isn't NoDisplay or Hidden (deleted), and whose
+    /// OnlyShowIn/NotShowIn lists let it show
+    pub(crate) fn is_listed(&self, desktops: &[String]) -> bool {
+        if self.string("Type").as_deref() != Some("Application")
+            || self.flag("NoDisplay")
+            || self.flag("Hidden")
+        {
+            return false;
+        }
+        let only = self.list("OnlyShowIn");
+        if !only.is_empty() && !only.iter().any(|d| desktops.contains(d)) {
+            return false;
+        }
+        !self.list("NotShowIn").iter().any(|d| desktops.contains(d))
+    }
+}
+
+/// Whether the entry can be started here: it needs an Exec line (or D-Bus
+/// activation), and a TryExec program that is installed
+pub(crate) fn classify_desktop_launchability(entry: Option<&DesktopEntry>) -> Launchability {
+    let Some(entry) = entry else {
+        return Launchability::MissingExecutable;
+    };
+    if entry
+        .string("Exec")
+        .is_none_or(|exec| exec.trim().is_empty())
+        && !entry.flag("DBusActivatable")
+    {
+        return Launchability::MissingExecutable;
+    }
+    if let Some(try_exec) = entry.string("TryExec").filter(|t| !t.is_empty()) {
+        if find_program(&try_exec).is_none() {
+            return Launchability::MissingExecutable;
+        }
+    }
+    Launchability::Launchable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(keys: &str) -> DesktopEntry {
+        DesktopEntry::parse(&format!("[Desktop Entry]\nType=Application\n{keys}")).unwrap()
+    }
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn only_the_desktop_entry_group_is_read() {
+        assert!(DesktopEntry::parse("[Desktop Action new]\nName=New\n").is_none());
+        let entry = DesktopEntry::parse(
+            "# comment\n[Desktop Entry]\nName = Files \nName=Twice\n\
+             [Desktop Action new]\nExec=files --new\n",
+        )
+        .unwrap();
+        assert_eq!(entry.string("Name").as_deref(), Some("Files"));
+        assert!(entry.string("Exec").is_none());
+    }
+
+    #[test]
+    fn locale_candidates_go_from_most_to_least_specific() {
+        assert_eq!(
+            locale_candidates("sr_RS.UTF-8@latin"),
+            ["sr_RS@latin", "sr_RS", "sr@latin", "sr"]
+        );
+        assert_eq!(locale_candidates("de_CH"), ["de_CH", "de"]);
+        assert_eq!(locale_candidates("sr@latin"), ["sr@latin", "sr"]);
+        assert!(locale_candidates("C.UTF-8").is_empty());
+        assert!(locale_candidates("POSIX").is_empty());
+        assert_eq!(
+            locales_for(&strings(&["de_CH.UTF-8", "de_DE", "en"])),
+            ["de_CH", "de", "de_DE", "en"]
+        );
+    }
+
+    #[test]
+    fn localized_names_fall_back_in_locale_order() {
+        let locales = locales_for(&strings(&["sr_RS@latin", "fr"]));
+        let all = entry(
+            "Name=Files\nName[sr]=Датотеке\nName[sr@latin]=Datoteke\n\
+             Name[sr_RS]=Датотеке (RS)\nName[sr_RS@latin]=Datoteke (RS)\nName[fr]=Fichiers\n",
+        );
+        assert_eq!(
+            all.localized_in("Name", &locales).as_deref(),
+            Some("Datoteke (RS)")
+        );
+        let no_full = entry(
+            "Name=Files\nName[sr]=Датотеке\nName[sr_RS]=Датотеке (RS)\nName[sr@latin]=Datoteke\n",
+        );
+        assert_eq!(
+            no_full.localized_in("Name", &locales).as_deref(),
+            Some("Датотеке (RS)")
+        );
+        let modifier = entry("Name=Files\nName[sr]=Датотеке\nName[sr@latin]=Datoteke\n");
+        assert_eq!(
+            modifier.localized_in("Name", &locales).as_deref(),
+            Some("Datoteke")
+        );
+        let second_language = entry("Name=Files\nName[fr]=Fichiers\n");
+        assert_eq!(
+            second_language.localized_in("Name", &locales).as_deref(),
+            Some("Fichiers")
+        );
+        let unlocalized = entry("Name=Files\nName[de]=Dateien\n");
+        assert_eq!(
+            unlocalized.localized_in("Name", &locales).as_deref(),
+            Some("Files")
+        );
+    }
+
+    #[test]
+    fn values_are_unescaped() {
+        let entry = entry(r"Comment=Two\sspaces\tand\na\\backslash\rdone\");
+        assert_eq!(
+            entry.string("Comment").as_deref(),
+            Some("Two spaces\tand\na\\backslash\rdone\\")
+        );
+    }
+
+    #[test]
+    fn lists_split_at_unescaped_semicolons() {
+        let entry = entry(
+            "Categories=Utility;Development;\nKeywords=a\\;b;c\\sd;;e\nMimeType=text/plain\n",
+        );
+        assert_eq!(entry.list("Categories"), ["Utility", "Development"]);
+        assert_eq!(entry.list("Keywords"), ["a;b", "c d", "e"]);
+        assert_eq!(entry.list("MimeType"), ["text/plain"]);
+        assert!(entry.list("Actions").is_empty());
+    }
+
+    #[test]
+    fn flags_take_true_and_1() {
+        let entry = entry("Terminal=true\nStartupNotify=1\nNoDisplay=false\nHidden=yes\n");
+        assert!(entry.flag("Terminal"));
+        assert!(entry.flag("StartupNotify"));
+        assert!(!entry.flag("NoDisplay"));
+        assert!(!entry.flag("Hidden"));
+        assert!(!entry.flag("DBusActivatable"));
+    }
+
+    #[test]
+    fn listing_follows_nodisplay_hidden_and_show_in() {
+        let gnome = desktops_in("ubuntu:GNOME");
+        assert_eq!(gnome, ["ubuntu", "GNOME"]);
+        let kde = desktops_in("KDE");
+        let none = desktops_in("");
+        assert!(none.is_empty());
+
+        assert!(entry("Name=Files\n").is_listed(&gnome));
+        assert!(!entry("NoDisplay=true\n").is_listed(&gnome));
+        assert!(!entry("Hidden=true\n").is_listed(&gnome));
+        let link = DesktopEntry::parse("[Desktop Entry]\nType=Link\nURL=https://example.com\n");
+        assert!(!link.unwrap().is_listed(&gnome));
+
+        let only = entry("OnlyShowIn=GNOME;XFCE;\n");
+        assert!(only.is_listed(&gnome));
+        assert!(!only.is_listed(&kde));
+        assert!(!only.is_listed(&none));
+
+        let not = entry("NotShowIn=ubuntu;\n");
+        assert!(!not.is_listed(&gnome));
+        assert!(not.is_listed(&kde));
+        assert!(not.is_listed(&none));
+    }
+
+    #[test]
+    fn try_exec_must_be_installed() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("files");
+        fs::write(&program, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+        let data = dir.path().join("data");
+        fs::write(&data, "").unwrap();
+        let try_exec = |path: &Path| {
+            let entry = entry(&format!("Exec=files %U\nTryExec={}\n", path.display()));
+            classify_desktop_launchability(Some(&entry))
+        };
+
+        assert_eq!(try_exec(&program), Launchability::Launchable);
+        assert_eq!(try_exec(&data), Launchability::MissingExecutable);
+        assert_eq!(
+            try_exec(&dir.path().join("uninstalled")),
+            Launchability::MissingExecutable
+        );
+        assert_eq!(try_exec(dir.path()), Launchability::MissingExecutable);
+    }
+
+    #[test]
+    fn launchability_needs_exec_or_dbus_activation() {
+        let launchable = |keys: &str| classify_desktop_launchability(Some(&entry(keys)));
+        assert_eq!(launchable("Exec=files\n"), Launchability::Launchable);
+        assert_eq!(
+            launchable("DBusActivatable=true\n"),
+            Launchability::Launchable
+        );
+        assert_eq!(launchable("Exec= \n"), Launchability::MissingExecutable);
+        assert_eq!(launchable("Name=Files\n"), Launchability::MissingExecutable);
+        assert_eq!(
+            classify_desktop_launchability(None),
+            Launchability::MissingExecutable
+        );
+    }
+}
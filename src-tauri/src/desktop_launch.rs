@@ -0,0 +1,456 @@
+//! Launching Linux desktop entries, as the Desktop Entry spec describes:
+//! the Exec line split into arguments with its quoting rules and field
+//! codes expanded, run in a terminal for Terminal=true, in the Path=
+//! directory, with a startup notification id when StartupNotify=true.
+//! DBusActivatable=true entries are activated over the session bus
+//! first, falling back to Exec when that fails.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::desktop_entry::{find_program, DesktopEntry};
+use crate::AppError;
+
+/// What the Exec field codes expand to
+pub(crate) struct ExecFields<'a> {
+    /// Files or URLs to open: %f/%u take the first, %F/%U all of them
+    pub files: &'a [String],
+    /// The Icon key, for %i
+    pub icon: Option<&'a str>,
+    /// The translated Name, for %c
+    pub name: Option<&'a str>,
+    /// The desktop file's location, for %k
+    pub desktop_file: &'a Path,
+}
+
+/// One piece of an Exec argument: text from inside double quotes is
+/// taken literally, field codes are only expanded outside them
+struct Segment {
+    text: String,
+    quoted: bool,
+}
+
+/// Split an Exec value (already unescaped as a string) into arguments:
+/// separated by unquoted spaces, with double quotes grouping, inside
+/// which only \" \` \$ and \\ are escapes. Reserved characters outside
+/// quotes are tolerated, as desktop environments do.
+fn split_exec(exec: &str) -> Result<Vec<Vec<Segment>>, AppError> {
+    let mut args: Vec<Vec<Segment>> = Vec::new();
+    let mut arg: Vec<Segment> = Vec::new();
+    let mut text = String::new();
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' => {
+                if !text.is_empty() {
+                    arg.push(Segment {
+                        text: std::mem::take(&mut text),
+                        quoted: false,
+                    });
+                }
+                if !arg.is_empty() {
+                    args.push(std::mem::take(&mut arg));
+                }
+            }
+            '"' => {
+                if !text.is_empty() {
+                    arg.push(Segment {
+                        text: std::mem::take(&mut text),
+                        quoted: false,
+                    });
+                }
+                let mut quoted = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped @ ('"' | '`' | '$' | '\\')) => quoted.push(escaped),
+                            Some(other) => {
+                                quoted.push('\\');
+                                quoted.push(other);
+                            }
+                            None => {
+                                return Err(AppError::Validation(
+                                    "Unterminated quote in Exec".into(),
+                                ))
+                            }
+                        },
+                        Some(other) => quoted.push(other),
+                        None => {
+                            return Err(AppError::Validation("Unterminated quote in Exec".into()))
+                        }
+                    }
+                }
+                // An empty quoted argument ("") is still an argument
+                arg.push(Segment {
+                    text: quoted,
+                    quoted: true,
+                });
+            }
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        arg.push(Segment {
+            text,
+            quoted: false,
+        });
+    }
+    if !arg.is_empty() {
+        args.push(arg);
+    }
+    Ok(args)
+}
+
+/// Expand the field codes in unquoted text. Deprecated codes (%d %D %n
+/// %N %v %m) expand to nothing; unknown ones make the Exec line invalid.
+fn expand_codes(text: &str, fields: &ExecFields) -> Result<String, AppError> {
+    let mut expanded = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('f' | 'u' | 'F' | 'U') => {
+                expanded.push_str(fields.files.first().map_or("", String::as_str))
+            }
+            Some('i') => {
+                if let Some(icon) = fields.icon {
+                    expanded.push_str(icon);
+                }
+            }
+            Some('c') => expanded.push_str(fields.name.unwrap_or_default()),
+            Some('k') => expanded.push_str(&fields.desktop_file.to_string_lossy()),
+            Some('d' | 'D' | 'n' | 'N' | 'v' | 'm') => {}
+            Some(other) => {
+                return Err(AppError::Validation(format!(
+                    "Unknown field code %{other} in Exec"
+                )))
+            }
+            None => return Err(AppError::Validation("Dangling % in Exec".into())),
+        }
+    }
+    Ok(expanded)
+}
+
+/// The argument vector an Exec value runs. A whole-argument %F or %U
+/// becomes one argument per file and %i becomes "--icon <Icon>"; an
+/// unquoted argument left empty by expansion is dropped.
+pub(crate) fn parse_exec(exec: &str, fields: &ExecFields) -> Result<Vec<String>, AppError> {
+    let mut argv = Vec::new();
+    for arg in split_exec(exec)? {
+        if let [Segment {
+            text,
+            quoted: false,
+        }] = arg.as_slice()
+        {
+            match text.as_str() {
+                "%F" | "%U" => {
+                    argv.extend(fields.files.iter().cloned());
+                    continue;
+                }
+                "%i" => {
+                    if let Some(icon) = fields.icon {
+                        argv.push("--icon".to_string());
+                        argv.push(icon.to_string());
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        let mut expanded = String::new();
+        for segment in &arg {
+            if segment.quoted {
+                expanded.push_str(&segment.text);
+            } else {
+                expanded.push_str(&expand_codes(&segment.text, fields)?);
+            }
+        }
+        if !expanded.is_empty() || arg.iter().any(|segment| segment.quoted) {
+            argv.push(expanded);
+        }
+    }
+    if argv.is_empty() {
+        return Err(AppError::Validation("Empty Exec".into()));
+    }
+    Ok(argv)
+}
+
+/// Terminal emulators tried for Terminal=true entries after $TERMINAL,
+/// each with the option that makes it run the rest of its arguments
+const TERMINALS: [(&str, &str); 9] = [
+    ("x-terminal-emulator", "-e"),
+    ("gnome-terminal", "--"),
+    ("konsole", "-e"),
+    ("xfce4-terminal", "-x"),
+    ("kitty", "--"),
+    ("alacritty", "-e"),
+    ("foot", "--"),
+    ("wezterm", "start"),
+    ("xterm", "-e"),
+];
+
+/// The user's terminal emulator and its run option: $TERMINAL when set
+/// (run with -e, the common denominator), else the first installed of
+/// TERMINALS
+fn terminal_command() -> Option<(PathBuf, &'static str)> {
+    if let Some(terminal) = std::env::var("TERMINAL")
+        .ok()
+        .and_then(|terminal| find_program(&terminal))
+    {
+        return Some((terminal, "-e"));
+    }
+    TERMINALS
+        .iter()
+        .find_map(|(name, option)| Some((find_program(name)?, *option)))
+}
+
+/// Ask the session bus to activate the app named by desktop-file id `id`
+/// (org.freedesktop.Application.Activate on the object path derived from
+/// it), through gdbus. Whether it was activated.
+fn dbus_activate(id: &str) -> bool {
+    let object_path = format!("/{}", id.replace('.', "/").replace('-', "_"));
+    Command::new("gdbus")
+        .args(["call", "--session", "--dest", id, "--object-path"])
+        .arg(object_path)
+        .args(["--method", "org.freedesktop.Application.Activate", "{}"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// A startup notification id for the launched app to complete (xdg
+/// startup-notification's DESKTOP_STARTUP_ID format)
+pub(crate) fn startup_id(id: &str) -> String {
+    format!(
+        "wafflepad-{}-{}_TIME{}",
+        std::process::id(),
+        id,
+        crate::config::now_ms()
+    )
+}
+
+/// Launch the desktop entry at `path`, whose desktop-file id is `id`,
+/// opening `files` with it
+pub(crate) fn launch_desktop_entry(
+    path: &Path,
+    id: &str,
+    files: &[String],
+) -> Result<(), AppError> {
+    let entry = DesktopEntry::read(path)
+        .ok_or_else(|| AppError::Validation("Unreadable desktop entry".into()))?;
+
+    if entry.flag("DBusActivatable") && files.is_empty() && dbus_activate(id) {
+        return Ok(());
+    }
+
+    let exec = entry
+        .string("Exec")
+        .ok_or_else(|| AppError::Validation("Desktop entry has no Exec".into()))?;
+    let icon = entry.string("Icon");
+    let name = entry.name();
+    let mut argv = parse_exec(
+        &exec,
+        &ExecFields {
+            files,
+            icon: icon.as_deref(),
+            name: name.as_deref(),
+            desktop_file: path,
+        },
+    )?;
+    if entry.flag("Terminal") {
+        let (terminal, option) = terminal_command()
+            .ok_or_else(|| AppError::Validation("No terminal emulator found".into()))?;
+        argv.splice(
+            0..0,
+            [terminal.to_string_lossy().to_string(), option.to_string()],
+        );
+    }
+
+    let program = find_program(&argv[0])
+        .ok_or_else(|| AppError::Validation(format!("{} not found", argv[0])))?;
+    let mut command = Command::new(program);
+    command
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(dir) = entry.string("Path").filter(|dir| Path::new(dir).is_dir()) {
+        command.current_dir(dir);
+    }
+    if entry.flag("StartupNotify") {
+        command.env("DESKTOP_STARTUP_ID", startup_id(id));
+    }
+    // Its own process group: the app outlives the launcher quitting
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    command.spawn()?;
+    Ok(())
+}
+
+/// Launch the AppImage at `path`: it is its own executable
+pub(crate) fn launch_appimage(path: &Path) -> Result<(), AppError> {
+    let mut command = Command::new(path);
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(dir) = path.parent() {
+        command.current_dir(dir);
+    }
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    command.spawn()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    fn parse(exec: &str, files: &[String], icon: Option<&str>) -> Result<Vec<String>, AppError> {
+        parse_exec(
+            exec,
+            &ExecFields {
+                files,
+                icon,
+                name: Some("Text Editor"),
+                desktop_file: Path::new("/usr/share/applications/org.gnome.TextEditor.desktop"),
+            },
+        )
+    }
+
+    fn argv(exec: &str) -> Vec<String> {
+        parse(exec, &[], None).unwrap()
+    }
+
+    #[test]
+    fn splits_on_unquoted_whitespace() {
+        assert_eq!(
+            argv("gimp-2.10  --new-instance\t-n"),
+            ["gimp-2.10", "--new-instance", "-n"]
+        );
+        assert_eq!(
+            argv(r#""/opt/My App/bin/app" --title="Two Words""#),
+            ["/opt/My App/bin/app", "--title=Two Words"]
+        );
+    }
+
+    #[test]
+    fn unescapes_inside_quotes() {
+        assert_eq!(
+            argv(r#"sh -c "echo \"hi\" \`date\` \$HOME \\ done""#),
+            ["sh", "-c", r#"echo "hi" `date` $HOME \ done"#]
+        );
+        // Any other backslash is kept as it is
+        assert_eq!(argv(r#"app "C:\dir\n""#), ["app", r"C:\dir\n"]);
+    }
+
+    #[test]
+    fn keeps_an_empty_quoted_argument() {
+        assert_eq!(argv(r#"app "" --flag"#), ["app", "", "--flag"]);
+        assert_eq!(argv(r#"app """#), ["app", ""]);
+    }
+
+    #[test]
+    fn single_file_codes_take_the_first_file() {
+        for code in ["%f", "%u"] {
+            let exec = format!("app {code}");
+            assert_eq!(parse(&exec, &[], None).unwrap(), ["app"]);
+            assert_eq!(
+                parse(&exec, &files(&["/a.txt"]), None).unwrap(),
+                ["app", "/a.txt"]
+            );
+            assert_eq!(
+                parse(&exec, &files(&["/a.txt", "/b.txt"]), None).unwrap(),
+                ["app", "/a.txt"]
+            );
+        }
+        assert_eq!(
+            parse("app --open=%f", &files(&["/a.txt"]), None).unwrap(),
+            ["app", "--open=/a.txt"]
+        );
+    }
+
+    #[test]
+    fn list_codes_take_every_file() {
+        for code in ["%F", "%U"] {
+            let exec = format!("app {code} --new-window");
+            assert_eq!(parse(&exec, &[], None).unwrap(), ["app", "--new-window"]);
+            assert_eq!(
+                parse(&exec, &files(&["/a.txt"]), None).unwrap(),
+                ["app", "/a.txt", "--new-window"]
+            );
+            assert_eq!(
+                parse(&exec, &files(&["/a.txt", "/b c.txt", "/d.txt"]), None).unwrap(),
+                ["app", "/a.txt", "/b c.txt", "/d.txt", "--new-window"]
+            );
+        }
+    }
+
+    #[test]
+    fn icon_code_expands_to_an_option() {
+        assert_eq!(
+            parse("app %i", &[], Some("org.gnome.TextEditor")).unwrap(),
+            ["app", "--icon", "org.gnome.TextEditor"]
+        );
+        assert_eq!(parse("app %i", &[], None).unwrap(), ["app"]);
+    }
+
+    #[test]
+    fn expands_name_location_and_percent() {
+        assert_eq!(
+            argv("app --class %c --desktop %k 100%%"),
+            [
+                "app",
+                "--class",
+                "Text Editor",
+                "--desktop",
+                "/usr/share/applications/org.gnome.TextEditor.desktop",
+                "100%",
+            ]
+        );
+    }
+
+    #[test]
+    fn leaves_codes_in_quotes_alone() {
+        assert_eq!(
+            parse(r#"app "%f" "%i""#, &files(&["/a.txt"]), Some("icon")).unwrap(),
+            ["app", "%f", "%i"]
+        );
+    }
+
+    #[test]
+    fn deprecated_codes_expand_to_nothing() {
+        assert_eq!(argv("app %d %D %n %N %v %m -x"), ["app", "-x"]);
+        assert_eq!(argv("app --dir=%d"), ["app", "--dir="]);
+    }
+
+    #[test]
+    fn rejects_malformed_exec() {
+        for exec in [
+            "app %z",
+            "app 100%",
+            r#"app "unterminated"#,
+            r#"app "ends in an escape\"#,
+        ] {
+            assert!(parse(exec, &[], None).is_err(), "{exec}");
+        }
+    }
+
+    #[test]
+    fn rejects_an_exec_that_expands_to_nothing() {
+        assert!(parse("", &[], None).is_err());
+        assert!(parse("  \t", &[], None).is_err());
+        assert!(parse("%f", &[], None).is_err());
+        assert!(parse("%U %i", &[], None).is_err());
+        assert_eq!(parse("%f", &files(&["/a.sh"]), None).unwrap(), ["/a.sh"]);
+    }
+}
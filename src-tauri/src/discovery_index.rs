@@ -0,0 +1,655 @@
+//! On-disk index of discovered app bundles, for near-instant startup.
+//!
+//! A full scan walks every applications directory and reads each bundle's
+//! Info.plist, all before the window may show. The index remembers the
+//! last scan — bundles keyed by path, with the bundle mtime the metadata
+//! was read at — so get_apps can answer from it alone. refresh_apps then
+//! re-scans in the background, re-reading only bundles whose mtime moved,
+//! and hands the frontend just the differences.
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use crate::app_discovery::{discover_apps_and_folders, DiscoveredApp};
+use crate::app_sources::{volume_mounted, DiscoveryRoots};
+use crate::appimage::{classify_appimage_launchability, AppImage};
+use crate::bundle_info::{classify_launchability, BundleInfo};
+use crate::category::{freedesktop_category, localized_category_name};
+use crate::codesign::{read_code_signature, CodeSignature};
+use crate::config::{
+    AppInfo, AppSettings, AppSource, AppsDiff, AppsResponse, BrokenAlias, DuplicateGroup,
+    FolderInfo, Launchability,
+};
+use crate::desktop_entry::{classify_desktop_launchability, DesktopEntry};
+use crate::finder_tags::{read_finder_tags, FinderTag};
+use crate::icon_cache::{get_cache_dir, get_icon_if_cached, get_stale_icon, preferred_icon_size};
+use crate::icon_theme::forget_icon_themes;
+use crate::macho::{host_needs_rosetta_for_intel, read_architecture, Architecture};
+use crate::quarantine::{read_quarantine, Quarantine};
+use crate::seen_apps::newness;
+use crate::AppError;
+
+/// Format of the index file. It is a cache: a file of any other version
+/// is ignored and replaced by the next scan, never migrated.
+const INDEX_VERSION: u32 = 12;
+
+/// One discovered .app bundle and the metadata read from it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct IndexedBundle {
+    pub path: String,
+    /// Name the app declares, for desktop entries (their localized Name);
+    /// bundles go by their file name
+    pub name: Option<String>,
+    /// Bundle mtime (ms since the epoch) the metadata below was read at
+    pub modified: u64,
+    pub category: Option<String>,
+    pub launchability: Launchability,
+    pub architecture: Option<Architecture>,
+    /// The main executable's code signature, if it is a readable Mach-O
+    pub signature: Option<CodeSignature>,
+    /// Download record, while quarantined. Opening the app changes it
+    /// without touching the bundle mtime, so it is re-read every scan.
+    pub quarantine: Option<Quarantine>,
+    /// Finder tags, re-read every scan for the same reason
+    pub tags: Vec<FinderTag>,
+    /// CFBundleIdentifier: copies of one app share it
+    pub identifier: Option<String>,
+    /// CFBundleShortVersionString
+    pub version: Option<String>,
+    /// CFBundleVersion
+    pub build: Option<String>,
+    pub source: AppSource,
+    /// External volume the bundle is on (/Volumes/<name>)
+    pub volume: Option<String>,
+    /// Carried over from an earlier scan while its volume is unmounted
+    pub offline: bool,
+    /// Alias or symlink the bundle was found through, when not listed
+    /// directly
+    pub via: Option<String>,
+    /// Derived by the duplicate policy for each view, never stored
+    #[serde(skip)]
+    pub disambiguation: Option<String>,
+}
+
+/// A subdirectory of an applications directory holding 2+ apps
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct IndexedFolder {
+    pub path: String,
+    pub apps: Vec<IndexedBundle>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct DiscoveryIndex {
+    pub version: u32,
+    /// Main-grid apps, sorted by name
+    pub apps: Vec<IndexedBundle>,
+    /// Physical folders, sorted by name
+    pub folders: Vec<IndexedFolder>,
+    /// Aliases and symlinks to apps whose target is gone
+    pub broken_aliases: Vec<BrokenAlias>,
+}
+
+/// What the frontend's current app list came from
+#[derive(Clone)]
+pub(crate) struct ServedApps {
+    /// The scan, every copy included: the next scan reuses its metadata
+    pub index: DiscoveryIndex,
+    /// The index after the duplicate policy, as served: refresh_apps diffs
+    /// against it, so every diff applies to exactly what was served
+    pub view: DiscoveryIndex,
+}
+
+pub(crate) static SERVED_INDEX: Mutex<Option<ServedApps>> = Mutex::new(None);
+
+/// Index file: ~/Library/Caches/com.helpermedia.wafflepad/discovery-index.json
+fn get_index_path() -> Option<PathBuf> {
+    get_cache_dir().map(|p| p.join("discovery-index.json"))
+}
+
+/// Read the index left by the last scan. None when there is none yet or it
+/// can't be used (unreadable, corrupt, another format version).
+pub(crate) fn load_index() -> Option<DiscoveryIndex> {
+    parse_index(&fs::read_to_string(get_index_path()?).ok()?)
+}
+
+/// An index file's contents, if they are an index of this format version
+fn parse_index(contents: &str) -> Option<DiscoveryIndex> {
+    let index: DiscoveryIndex = serde_json::from_str(contents).ok()?;
+    (index.version == INDEX_VERSION).then_some(index)
+}
+
+/// Write the index atomically (temp file + rename, like the config), so a
+/// crash mid-write can't leave a truncated index behind.
+pub(crate) fn save_index(index: &DiscoveryIndex) -> Result<(), AppError> {
+    let index_path = get_index_path()
+        .ok_or_else(|| AppError::Validation("Could not determine cache directory".into()))?;
+    if let Some(dir) = index_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string(index)?;
+    let tmp_path = index_path.with_extension("json.tmp");
+    fs::write(&tmp_path, json)?;
+    fs::rename(&tmp_path, &index_path)?;
+    Ok(())
+}
+
+/// Bundle mtime in ms since the epoch (0 when unreadable). Replacing or
+/// updating an app rewrites its bundle directory, which moves this.
+pub(crate) fn bundle_modified(path: &Path) -> u64 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Index one bundle, reusing the previous scan's metadata when the bundle
+/// hasn't been modified since it was read.
+fn index_bundle(app: &DiscoveredApp, known: &HashMap<&str, &IndexedBundle>) -> IndexedBundle {
+    let path = app.path.as_path();
+    let path_str = path.to_string_lossy().to_string();
+    let modified = bundle_modified(path);
+    let via = app
+        .via
+        .as_ref()
+        .map(|via| via.to_string_lossy().to_string());
+    if let Some(prev) = known.get(path_str.as_str()) {
+        if prev.modified == modified && prev.source == app.source {
+            return IndexedBundle {
+                volume: app.volume.clone(),
+                offline: false,
+                via,
+                quarantine: read_quarantine(path),
+                tags: read_finder_tags(path),
+                ..(*prev).clone()
+            };
+        }
+    }
+    if let Some(id) = &app.desktop_id {
+        return index_desktop_entry(app, id, path_str, modified);
+    }
+    if app.source == AppSource::AppImage {
+        return index_appimage(app, path_str, modified);
+    }
+    let info = BundleInfo::read(path);
+    let executable = info.as_ref().and_then(BundleInfo::executable_path);
+    let string = |key| {
+        info.as_ref()
+            .and_then(|info| info.string(key))
+            .map(str::to_string)
+    };
+    IndexedBundle {
+        name: None,
+        identifier: string("CFBundleIdentifier"),
+        version: string("CFBundleShortVersionString"),
+        build: string("CFBundleVersion"),
+        source: app.source,
+        volume: app.volume.clone(),
+        offline: false,
+        via,
+        disambiguation: None,
+        category: info
+            .as_ref()
+            .and_then(|info| info.category())
+            .map(str::to_string),
+        launchability: classify_launchability(info.as_ref()),
+        architecture: executable.as_deref().and_then(read_architecture),
+        signature: executable.as_deref().and_then(read_code_signature),
+        quarantine: read_quarantine(path),
+        tags: read_finder_tags(path),
+        path: path_str,
+        modified,
+    }
+}
+
+/// Index a Linux desktop entry: its id stands in for the bundle
+/// identifier, its Categories for the App Store category. It has no
+/// version, nor an executable of its own to inspect.
+fn index_desktop_entry(
+    app: &DiscoveredApp,
+    id: &str,
+    path: String,
+    modified: u64,
+) -> IndexedBundle {
+    let entry = DesktopEntry::read(&app.path);
+    IndexedBundle {
+        name: entry.as_ref().and_then(DesktopEntry::name),
+        identifier: Some(id.to_string()),
+        version: None,
+        build: None,
+        source: app.source,
+        volume: None,
+        offline: false,
+        via: None,
+        disambiguation: None,
+        category: entry
+            .as_ref()
+            .and_then(|entry| freedesktop_category(&entry.list("Categories"))),
+        launchability: classify_desktop_launchability(entry.as_ref()),
+        architecture: None,
+        signature: None,
+        quarantine: read_quarantine(&app.path),
+        tags: read_finder_tags(&app.path),
+        path,
+        modified,
+    }
+}
+
+/// Index an AppImage from the desktop entry in its payload; the file
+/// name stands in for what an image without one doesn't say
+fn index_appimage(app: &DiscoveredApp, path: String, modified: u64) -> IndexedBundle {
+    let embedded = AppImage::open(&app.path).and_then(|appimage| appimage.desktop_entry());
+    let entry = embedded.as_ref().map(|(_, entry)| entry);
+    IndexedBundle {
+        name: entry.and_then(DesktopEntry::name),
+        identifier: embedded
+            .as_ref()
+            .map(|(id, _)| id.clone())
+            .or_else(|| Some(app.path.file_stem()?.to_string_lossy().to_string())),
+        version: entry.and_then(|entry| entry.string("X-AppImage-Version")),
+        build: None,
+        source: app.source,
+        volume: None,
+        offline: false,
+        via: None,
+        disambiguation: None,
+        category: entry.and_then(|entry| freedesktop_category(&entry.list("Categories"))),
+        launchability: classify_appimage_launchability(&app.path),
+        architecture: None,
+        signature: None,
+        quarantine: read_quarantine(&app.path),
+        tags: read_finder_tags(&app.path),
+        path,
+        modified,
+    }
+}
+
+/// Walk the applications directories (and the providers `settings`
+/// enable) and build a fresh index. Bundles already in `previous` with an
+/// unchanged mtime skip the Info.plist read.
+pub(crate) fn scan_apps(
+    own_bundle_id: &str,
+    previous: Option<&DiscoveryIndex>,
+    settings: &AppSettings,
+) -> DiscoveryIndex {
+    let known: HashMap<&str, &IndexedBundle> = previous
+        .map(|index| {
+            index
+                .apps
+                .iter()
+                .chain(index.folders.iter().flat_map(|f| f.apps.iter()))
+                .map(|bundle| (bundle.path.as_str(), bundle))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Icons rendered after this scan see the icon theme as it is now
+    forget_icon_themes();
+    let roots = DiscoveryRoots::system();
+    let discovery = discover_apps_and_folders(own_bundle_id, &roots, settings);
+
+    // Metadata reads in parallel: Info.plist parsing dominates a cold scan
+    let apps: Vec<IndexedBundle> = discovery
+        .apps
+        .par_iter()
+        .map(|app| index_bundle(app, &known))
+        .collect();
+
+    let folders: Vec<IndexedFolder> = discovery
+        .folders
+        .par_iter()
+        .map(|(folder_path, sub_apps)| IndexedFolder {
+            path: folder_path.to_string_lossy().to_string(),
+            apps: sub_apps
+                .par_iter()
+                .map(|app| index_bundle(app, &known))
+                .collect(),
+        })
+        .collect();
+
+    let mut index = DiscoveryIndex {
+        version: INDEX_VERSION,
+        apps,
+        folders,
+        broken_aliases: discovery.broken_aliases,
+    };
+    if let (Some(previous), true) = (previous, settings.show_external_volumes) {
+        index.carry_offline(previous, &roots);
+    }
+    index
+}
+
+impl IndexedBundle {
+    fn to_app_info(&self) -> Option<AppInfo> {
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => Path::new(&self.path)
+                .file_stem()?
+                .to_string_lossy()
+                .to_string(),
+        };
+        let (is_new, updated_at) = newness(self);
+        Some(AppInfo {
+            name,
+            // The bundle can't be checked for changes while offline, so the
+            // last icon cached for it stands in
+            icon: if self.offline {
+                get_stale_icon(&self.path, preferred_icon_size())
+            } else {
+                get_icon_if_cached(&self.path, preferred_icon_size())
+            },
+            category: self.category.clone(),
+            category_name: self.category.as_deref().and_then(localized_category_name),
+            launchability: self.launchability.clone(),
+            architecture: self.architecture,
+            needs_rosetta: self.architecture == Some(Architecture::X86_64)
+                && host_needs_rosetta_for_intel(),
+            signature: self.signature.clone(),
+            quarantine: self.quarantine.clone(),
+            tags: self.tags.clone(),
+            is_new,
+            updated_at,
+            disambiguation: self.disambiguation.clone(),
+            source: self.source,
+            volume: self.volume.clone(),
+            offline: self.offline,
+            via: self.via.clone(),
+            path: self.path.clone(),
+        })
+    }
+}
+
+impl IndexedFolder {
+    fn to_folder_info(&self) -> Option<FolderInfo> {
+        let raw_name = Path::new(&self.path)
+            .file_name()?
+            .to_string_lossy()
+            .to_string();
+        let name = raw_name
+            .strip_suffix(".localized")
+            .unwrap_or(&raw_name)
+            .to_string();
+        Some(FolderInfo {
+            name,
+            path: self.path.clone(),
+            apps: self
+                .apps
+                .iter()
+                .filter_map(IndexedBundle::to_app_info)
+                .collect(),
+        })
+    }
+}
+
+impl DiscoveryIndex {
+    /// Keep what `previous` found on external volumes that are no longer
+    /// mounted, marked offline: an unplugged volume's apps keep their place
+    /// (and their saved layout positions) until it is back.
+    fn carry_offline(&mut self, previous: &DiscoveryIndex, roots: &DiscoveryRoots) {
+        let unmounted = |bundle: &IndexedBundle| {
+            bundle
+                .volume
+                .as_deref()
+                .is_some_and(|name| !volume_mounted(roots, name))
+        };
+        let offline = |bundle: &IndexedBundle| IndexedBundle {
+            offline: true,
+            ..bundle.clone()
+        };
+        self.apps
+            .extend(previous.apps.iter().filter(|b| unmounted(b)).map(offline));
+        self.folders.extend(
+            previous
+                .folders
+                .iter()
+                .filter(|folder| folder.apps.first().is_some_and(unmounted))
+                .map(|folder| IndexedFolder {
+                    path: folder.path.clone(),
+                    apps: folder.apps.iter().map(offline).collect(),
+                }),
+        );
+    }
+
+    pub(crate) fn to_response(&self, duplicates: Vec<DuplicateGroup>) -> AppsResponse {
+        AppsResponse {
+            duplicates,
+            broken_aliases: self.broken_aliases.clone(),
+            apps: self
+                .apps
+                .iter()
+                .filter_map(IndexedBundle::to_app_info)
+                .collect(),
+            folders: self
+                .folders
+                .iter()
+                .filter_map(IndexedFolder::to_folder_info)
+                .collect(),
+        }
+    }
+
+    /// Whether `path` is a bundle this index reached through an alias or
+    /// symlink: its target may sit outside every applications directory
+    pub(crate) fn has_alias_target(&self, path: &str) -> bool {
+        self.apps
+            .iter()
+            .chain(self.folders.iter().flat_map(|f| f.apps.iter()))
+            .any(|bundle| bundle.path == path && bundle.via.is_some() && !bundle.offline)
+    }
+
+    /// Identifier of the bundle at `path`: on Linux a desktop entry's
+    /// desktop-file id, which its file name alone doesn't give
+    pub(crate) fn identifier_of(&self, path: &str) -> Option<String> {
+        self.apps
+            .iter()
+            .chain(self.folders.iter().flat_map(|f| f.apps.iter()))
+            .find(|bundle| bundle.path == path)
+            .and_then(|bundle| bundle.identifier.clone())
+    }
+
+    /// Every bundle path in the index, main grid and folders alike
+    pub(crate) fn all_paths(&self) -> Vec<String> {
+        self.apps
+            .iter()
+            .chain(self.folders.iter().flat_map(|f| f.apps.iter()))
+            .map(|bundle| bundle.path.clone())
+            .collect()
+    }
+
+    /// What changed from this index to `newer`, as the frontend applies it:
+    /// main-grid apps added, removed or re-read (mtime or metadata moved),
+    /// and the physical folders in full, only when any of them changed.
+    /// The broken aliases are reported in full every time; the duplicates
+    /// report is the caller's to fill in.
+    pub(crate) fn diff(&self, newer: &DiscoveryIndex) -> AppsDiff {
+        let old: HashMap<&str, &IndexedBundle> =
+            self.apps.iter().map(|b| (b.path.as_str(), b)).collect();
+        let new_paths: HashSet<&str> = newer.apps.iter().map(|b| b.path.as_str()).collect();
+
+        let mut diff = AppsDiff {
+            broken_aliases: newer.broken_aliases.clone(),
+            ..AppsDiff::default()
+        };
+        for bundle in &newer.apps {
+            match old.get(bundle.path.as_str()) {
+                None => diff.added.extend(bundle.to_app_info()),
+                Some(prev) if *prev != bundle => diff.updated.extend(bundle.to_app_info()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = self
+            .apps
+            .iter()
+            .filter(|b| !new_paths.contains(b.path.as_str()))
+            .map(|b| b.path.clone())
+            .collect();
+        if self.folders != newer.folders {
+            diff.folders = Some(
+                newer
+                    .folders
+                    .iter()
+                    .filter_map(IndexedFolder::to_folder_info)
+                    .collect(),
+            );
+        }
+        diff
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn bundle(path: &str, modified: u64) -> IndexedBundle {
+        IndexedBundle {
+            path: path.to_string(),
+            name: None,
+            modified,
+            category: None,
+            launchability: Launchability::Launchable,
+            architecture: None,
+            signature: None,
+            quarantine: None,
+            tags: Vec::new(),
+            identifier: None,
+            version: None,
+            build: None,
+            source: AppSource::Applications,
+            volume: None,
+            offline: false,
+            via: None,
+            disambiguation: None,
+        }
+    }
+
+    pub(crate) fn index(apps: Vec<IndexedBundle>) -> DiscoveryIndex {
+        DiscoveryIndex {
+            version: INDEX_VERSION,
+            apps,
+            ..DiscoveryIndex::default()
+        }
+    }
+
+    fn paths(apps: &[AppInfo]) -> Vec<&str> {
+        apps.iter().map(|app| app.path.as_str()).collect()
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_bundles() {
+        let old = index(vec![
+            bundle("/Applications/Kept.app", 1),
+            bundle("/Applications/Gone.app", 1),
+            bundle("/Applications/Bumped.app", 1),
+        ]);
+        let new = index(vec![
+            bundle("/Applications/Kept.app", 1),
+            bundle("/Applications/Bumped.app", 2),
+            bundle("/Applications/New.app", 1),
+        ]);
+        let diff = old.diff(&new);
+        assert_eq!(paths(&diff.added), ["/Applications/New.app"]);
+        assert_eq!(paths(&diff.updated), ["/Applications/Bumped.app"]);
+        assert_eq!(diff.removed, ["/Applications/Gone.app"]);
+        assert!(diff.folders.is_none());
+    }
+
+    #[test]
+    fn diff_of_identical_indexes_is_empty() {
+        let old = index(vec![bundle("/Applications/Kept.app", 1)]);
+        let diff = old.diff(&old.clone());
+        assert!(diff.added.is_empty() && diff.updated.is_empty() && diff.removed.is_empty());
+        assert!(diff.folders.is_none());
+    }
+
+    #[test]
+    fn diff_sends_all_folders_when_one_changed() {
+        let folder = |apps| IndexedFolder {
+            path: "/Applications/Utilities".into(),
+            apps,
+        };
+        let mut old = index(Vec::new());
+        old.folders = vec![folder(vec![bundle("/Applications/Utilities/A.app", 1)])];
+        let mut new = old.clone();
+        new.folders[0]
+            .apps
+            .push(bundle("/Applications/Utilities/B.app", 1));
+        let folders = old.diff(&new).folders.expect("folders changed");
+        assert_eq!(folders.len(), 1);
+        assert_eq!(folders[0].apps.len(), 2);
+    }
+
+    /// A bundle in `dir` whose Info.plist declares `identifier`
+    fn write_bundle(dir: &Path, identifier: &str) -> PathBuf {
+        let bundle = dir.join("Test.app");
+        fs::create_dir_all(bundle.join("Contents")).unwrap();
+        fs::write(
+            bundle.join("Contents/Info.plist"),
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<plist version="1.0"><dict>
+<key>CFBundleIdentifier</key><string>{identifier}</string>
+</dict></plist>"#
+            ),
+        )
+        .unwrap();
+        bundle
+    }
+
+    fn discovered(path: &Path) -> DiscoveredApp {
+        DiscoveredApp {
+            path: path.to_path_buf(),
+            source: AppSource::Applications,
+            volume: None,
+            via: None,
+            desktop_id: None,
+        }
+    }
+
+    #[test]
+    fn unchanged_bundle_reuses_indexed_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_bundle(dir.path(), "com.example.disk");
+        let path_str = path.to_string_lossy().to_string();
+        let previous = IndexedBundle {
+            identifier: Some("com.example.indexed".into()),
+            ..bundle(&path_str, bundle_modified(&path))
+        };
+        let known = HashMap::from([(path_str.as_str(), &previous)]);
+        let indexed = index_bundle(&discovered(&path), &known);
+        assert_eq!(indexed.identifier.as_deref(), Some("com.example.indexed"));
+    }
+
+    #[test]
+    fn modified_bundle_is_read_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_bundle(dir.path(), "com.example.disk");
+        let path_str = path.to_string_lossy().to_string();
+        let previous = IndexedBundle {
+            identifier: Some("com.example.indexed".into()),
+            ..bundle(&path_str, bundle_modified(&path) - 1)
+        };
+        let known = HashMap::from([(path_str.as_str(), &previous)]);
+        let indexed = index_bundle(&discovered(&path), &known);
+        assert_eq!(indexed.identifier.as_deref(), Some("com.example.disk"));
+        assert_eq!(indexed.modified, bundle_modified(&path));
+    }
+
+    #[test]
+    fn corrupt_or_other_version_index_is_ignored() {
+        let current = index(vec![bundle("/Applications/A.app", 1)]);
+        let json = serde_json::to_string(&current).unwrap();
+        assert_eq!(parse_index(&json), Some(current.clone()));
+
+        assert_eq!(parse_index(&json[..json.len() / 2]), None);
+        assert_eq!(parse_index("not json"), None);
+        let old = DiscoveryIndex {
+            version: INDEX_VERSION - 1,
+            ..current
+        };
+        assert_eq!(parse_index(&serde_json::to_string(&old).unwrap()), None);
+    }
+}
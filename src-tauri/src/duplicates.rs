@@ -0,0 +1,372 @@
+//! Duplicate apps: one bundle identifier installed in more than one place,
+//! e.g. in both /Applications and ~/Applications, or an older copy left in
+//! a subfolder. Without this they show as two identical tiles.
+//!
+//! Copies are grouped by CFBundleIdentifier and the DuplicatePolicy picks
+//! which to show. The result is a view of the discovery index — what the
+//! frontend is served — plus a report of every group found.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::bundle_info::version_older_than;
+use crate::config::{DuplicateGroup, DuplicatePolicy};
+use crate::discovery_index::{DiscoveryIndex, IndexedBundle};
+
+/// Whether the bundle is installed under the user's home folder
+fn is_user_copy(bundle: &IndexedBundle, home: Option<&Path>) -> bool {
+    home.is_some_and(|home| Path::new(&bundle.path).starts_with(home))
+}
+
+/// Compare dotted versions, a missing version sorting as the oldest
+fn compare_versions(a: Option<&str>, b: Option<&str>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if version_older_than(a, b) => Ordering::Less,
+        (Some(a), Some(b)) if version_older_than(b, a) => Ordering::Greater,
+        (Some(_), Some(_)) | (None, None) => Ordering::Equal,
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+    }
+}
+
+/// Order copies most preferred first; ties keep their index order
+fn sort_by_preference(copies: &mut [&IndexedBundle], policy: DuplicatePolicy, home: Option<&Path>) {
+    let system_first =
+        |a: &&IndexedBundle, b: &&IndexedBundle| is_user_copy(a, home).cmp(&is_user_copy(b, home));
+    match policy {
+        DuplicatePolicy::PreferSystem | DuplicatePolicy::ShowAll => copies.sort_by(system_first),
+        DuplicatePolicy::PreferUser => copies.sort_by(|a, b| system_first(a, b).reverse()),
+        DuplicatePolicy::PreferNewest => copies.sort_by(|a, b| {
+            compare_versions(b.version.as_deref(), a.version.as_deref())
+                .then_with(|| compare_versions(b.build.as_deref(), a.build.as_deref()))
+                .then_with(|| system_first(a, b))
+        }),
+    }
+}
+
+fn display_name(bundle: &IndexedBundle) -> String {
+    if let Some(name) = &bundle.name {
+        return name.clone();
+    }
+    Path::new(&bundle.path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Where a bundle lives, for its label: the containing directory, with the
+/// home folder abbreviated to ~
+fn location_label(bundle: &IndexedBundle, home: Option<&Path>) -> String {
+    let parent = Path::new(&bundle.path).parent().unwrap_or(Path::new("/"));
+    match home.and_then(|home| parent.strip_prefix(home).ok()) {
+        Some(relative) if relative.as_os_str().is_empty() => "~".to_string(),
+        Some(relative) => format!("~/{}", relative.display()),
+        None => parent.display().to_string(),
+    }
+}
+
+/// Labels for shown copies that would otherwise look alike (same name):
+/// their versions when those tell them apart, their locations otherwise
+fn disambiguate(shown: &[&IndexedBundle], home: Option<&Path>) -> HashMap<String, String> {
+    let mut by_name: HashMap<String, Vec<&IndexedBundle>> = HashMap::new();
+    for bundle in shown {
+        by_name
+            .entry(display_name(bundle))
+            .or_default()
+            .push(bundle);
+    }
+
+    let mut labels = HashMap::new();
+    for alike in by_name.values().filter(|alike| alike.len() > 1) {
+        let versions: HashSet<Option<&str>> = alike
+            .iter()
+            .map(|bundle| bundle.version.as_deref())
+            .collect();
+        let by_version = versions.len() == alike.len() && !versions.contains(&None);
+        for bundle in alike {
+            let label = match &bundle.version {
+                Some(version) if by_version => version.clone(),
+                _ => location_label(bundle, home),
+            };
+            labels.insert(bundle.path.clone(), label);
+        }
+    }
+    labels
+}
+
+/// Apply the duplicate policy to an index: the copies it hides are dropped
+/// (from the main grid and from physical folders alike), and copies shown
+/// side by side get a disambiguation. Returns that view and the report of
+/// every group of copies, in index order. `home` is the user's home
+/// folder, which tells user copies from system ones.
+pub(crate) fn resolve_duplicates(
+    index: &DiscoveryIndex,
+    policy: DuplicatePolicy,
+    home: Option<&Path>,
+) -> (DiscoveryIndex, Vec<DuplicateGroup>) {
+    let all_bundles = index
+        .apps
+        .iter()
+        .chain(index.folders.iter().flat_map(|f| f.apps.iter()));
+
+    let mut order: Vec<&str> = Vec::new();
+    let mut groups: HashMap<&str, Vec<&IndexedBundle>> = HashMap::new();
+    // Offline placeholders never compete with copies that can open
+    for bundle in all_bundles.filter(|bundle| !bundle.offline) {
+        let Some(identifier) = bundle.identifier.as_deref() else {
+            continue;
+        };
+        let copies = groups.entry(identifier).or_default();
+        if copies.is_empty() {
+            order.push(identifier);
+        }
+        copies.push(bundle);
+    }
+
+    let mut hidden: HashSet<String> = HashSet::new();
+    let mut labels: HashMap<String, String> = HashMap::new();
+    let mut report = Vec::new();
+    for identifier in order {
+        let mut copies = groups.remove(identifier).unwrap_or_default();
+        if copies.len() < 2 {
+            continue;
+        }
+        sort_by_preference(&mut copies, policy, home);
+        let shown_count = if policy == DuplicatePolicy::ShowAll {
+            copies.len()
+        } else {
+            1
+        };
+        let (shown, rest) = copies.split_at(shown_count);
+        labels.extend(disambiguate(shown, home));
+        hidden.extend(rest.iter().map(|bundle| bundle.path.clone()));
+        report.push(DuplicateGroup {
+            bundle_id: identifier.to_string(),
+            shown: shown.iter().map(|bundle| bundle.path.clone()).collect(),
+            hidden: rest.iter().map(|bundle| bundle.path.clone()).collect(),
+        });
+    }
+
+    let view_bundles = |bundles: &[IndexedBundle]| -> Vec<IndexedBundle> {
+        bundles
+            .iter()
+            .filter(|bundle| !hidden.contains(&bundle.path))
+            .map(|bundle| IndexedBundle {
+                disambiguation: labels.get(&bundle.path).cloned(),
+                ..bundle.clone()
+            })
+            .collect()
+    };
+    let mut view = index.clone();
+    view.apps = view_bundles(&index.apps);
+    for folder in &mut view.folders {
+        folder.apps = view_bundles(&folder.apps);
+    }
+
+    (view, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery_index::tests::{bundle, index};
+    use crate::discovery_index::IndexedFolder;
+
+    const HOME: &str = "/Users/me";
+
+    fn copy(path: &str, version: Option<&str>, build: Option<&str>) -> IndexedBundle {
+        IndexedBundle {
+            identifier: Some("com.example.Notes".into()),
+            version: version.map(str::to_string),
+            build: build.map(str::to_string),
+            ..bundle(path, 1)
+        }
+    }
+
+    fn resolve(
+        apps: Vec<IndexedBundle>,
+        policy: DuplicatePolicy,
+    ) -> (DiscoveryIndex, Vec<DuplicateGroup>) {
+        resolve_duplicates(&index(apps), policy, Some(Path::new(HOME)))
+    }
+
+    fn shown(view: &DiscoveryIndex) -> Vec<(&str, Option<&str>)> {
+        view.apps
+            .iter()
+            .map(|app| (app.path.as_str(), app.disambiguation.as_deref()))
+            .collect()
+    }
+
+    fn system_and_user_copies() -> Vec<IndexedBundle> {
+        vec![
+            copy("/Users/me/Applications/Notes.app", Some("2.0"), None),
+            copy("/Applications/Notes.app", Some("2.0"), None),
+        ]
+    }
+
+    #[test]
+    fn prefer_system_shows_the_applications_copy() {
+        let (view, report) = resolve(system_and_user_copies(), DuplicatePolicy::PreferSystem);
+        assert_eq!(shown(&view), [("/Applications/Notes.app", None)]);
+        assert_eq!(
+            report,
+            [DuplicateGroup {
+                bundle_id: "com.example.Notes".into(),
+                shown: vec!["/Applications/Notes.app".into()],
+                hidden: vec!["/Users/me/Applications/Notes.app".into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn prefer_user_shows_the_home_copy() {
+        let (view, report) = resolve(system_and_user_copies(), DuplicatePolicy::PreferUser);
+        assert_eq!(shown(&view), [("/Users/me/Applications/Notes.app", None)]);
+        assert_eq!(report[0].hidden, ["/Applications/Notes.app"]);
+    }
+
+    #[test]
+    fn without_a_home_folder_every_copy_is_a_system_copy() {
+        let (view, _) = resolve_duplicates(
+            &index(system_and_user_copies()),
+            DuplicatePolicy::PreferSystem,
+            None,
+        );
+        // Equal preference: index order decides
+        assert_eq!(shown(&view), [("/Users/me/Applications/Notes.app", None)]);
+    }
+
+    #[test]
+    fn prefer_newest_compares_versions_then_builds() {
+        let apps = vec![
+            copy("/Applications/Notes.app", Some("2.0"), Some("200")),
+            copy("/Users/me/Applications/Notes.app", Some("2.10"), None),
+        ];
+        let (view, _) = resolve(apps, DuplicatePolicy::PreferNewest);
+        assert_eq!(shown(&view), [("/Users/me/Applications/Notes.app", None)]);
+
+        let apps = vec![
+            copy("/Applications/Notes.app", Some("2.0"), Some("200")),
+            copy("/Users/me/Applications/Notes.app", Some("2.0"), Some("201")),
+        ];
+        let (view, _) = resolve(apps, DuplicatePolicy::PreferNewest);
+        assert_eq!(shown(&view), [("/Users/me/Applications/Notes.app", None)]);
+
+        // A missing version is the oldest
+        let apps = vec![
+            copy("/Applications/Notes.app", None, None),
+            copy("/Users/me/Applications/Notes.app", Some("0.1"), None),
+        ];
+        let (view, _) = resolve(apps, DuplicatePolicy::PreferNewest);
+        assert_eq!(shown(&view), [("/Users/me/Applications/Notes.app", None)]);
+    }
+
+    #[test]
+    fn prefer_newest_breaks_a_version_tie_with_the_system_copy() {
+        let (view, report) = resolve(system_and_user_copies(), DuplicatePolicy::PreferNewest);
+        assert_eq!(shown(&view), [("/Applications/Notes.app", None)]);
+        assert_eq!(report[0].hidden, ["/Users/me/Applications/Notes.app"]);
+    }
+
+    #[test]
+    fn show_all_labels_copies_by_location_when_versions_tie() {
+        let (view, report) = resolve(system_and_user_copies(), DuplicatePolicy::ShowAll);
+        assert_eq!(
+            shown(&view),
+            [
+                ("/Users/me/Applications/Notes.app", Some("~/Applications")),
+                ("/Applications/Notes.app", Some("/Applications")),
+            ]
+        );
+        assert_eq!(
+            report[0].shown,
+            [
+                "/Applications/Notes.app",
+                "/Users/me/Applications/Notes.app"
+            ]
+        );
+        assert!(report[0].hidden.is_empty());
+    }
+
+    #[test]
+    fn show_all_labels_copies_by_version_when_they_differ() {
+        let apps = vec![
+            copy("/Applications/Notes.app", Some("2.0"), None),
+            copy("/Applications/Old/Notes.app", Some("1.4"), None),
+            copy("/Users/me/Notes.app", Some("2.0"), None),
+        ];
+        let (view, _) = resolve(apps, DuplicatePolicy::ShowAll);
+        // Two copies share 2.0: locations it is, for all three
+        assert_eq!(
+            shown(&view),
+            [
+                ("/Applications/Notes.app", Some("/Applications")),
+                ("/Applications/Old/Notes.app", Some("/Applications/Old")),
+                ("/Users/me/Notes.app", Some("~")),
+            ]
+        );
+
+        let apps = vec![
+            copy("/Applications/Notes.app", Some("2.0"), None),
+            copy("/Applications/Old/Notes.app", Some("1.4"), None),
+        ];
+        let (view, _) = resolve(apps, DuplicatePolicy::ShowAll);
+        assert_eq!(
+            shown(&view),
+            [
+                ("/Applications/Notes.app", Some("2.0")),
+                ("/Applications/Old/Notes.app", Some("1.4")),
+            ]
+        );
+    }
+
+    #[test]
+    fn hides_copies_inside_folders_and_ignores_offline_ones() {
+        let mut catalog = index(vec![
+            copy("/Applications/Notes.app", Some("2.0"), None),
+            IndexedBundle {
+                offline: true,
+                ..copy("/Volumes/Backup/Applications/Notes.app", Some("3.0"), None)
+            },
+        ]);
+        catalog.folders.push(IndexedFolder {
+            path: "/Applications/Office".into(),
+            apps: vec![
+                copy("/Applications/Office/Notes.app", Some("2.0"), None),
+                bundle("/Applications/Office/Sheets.app", 1),
+            ],
+        });
+        let (view, report) = resolve_duplicates(
+            &catalog,
+            DuplicatePolicy::PreferNewest,
+            Some(Path::new(HOME)),
+        );
+        assert_eq!(
+            shown(&view),
+            [
+                ("/Applications/Notes.app", None),
+                ("/Volumes/Backup/Applications/Notes.app", None),
+            ]
+        );
+        let folder: Vec<&str> = view.folders[0]
+            .apps
+            .iter()
+            .map(|app| app.path.as_str())
+            .collect();
+        assert_eq!(folder, ["/Applications/Office/Sheets.app"]);
+        assert_eq!(report[0].hidden, ["/Applications/Office/Notes.app"]);
+    }
+
+    #[test]
+    fn single_copies_and_missing_identifiers_are_not_duplicates() {
+        let apps = vec![
+            copy("/Applications/Notes.app", Some("2.0"), None),
+            bundle("/Applications/Tool.app", 1),
+            bundle("/Users/me/Applications/Tool.app", 1),
+        ];
+        let (view, report) = resolve(apps, DuplicatePolicy::PreferSystem);
+        assert_eq!(view.apps.len(), 3);
+        assert!(report.is_empty());
+    }
+}
@@ -0,0 +1,280 @@
+//! Reveal on Linux: the file manager opened on an app's folder with the
+//! app selected, through the org.freedesktop.FileManager1 D-Bus
+//! interface that Nautilus, Dolphin, Nemo, Thunar and Caja implement.
+//! Without one on the session bus, the folder is opened with xdg-open,
+//! unselected.
+//!
+//! The call goes through gdbus to the bus DBUS_SESSION_BUS_ADDRESS names,
+//! so the tests run it on a private bus (dbus-run-session), with a stub
+//! FileManager1 service or a stub xdg-open, without a desktop.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::desktop_launch::startup_id;
+use crate::AppError;
+
+const FILE_MANAGER_SERVICE: &str = "org.freedesktop.FileManager1";
+const FILE_MANAGER_PATH: &str = "/org/freedesktop/FileManager1";
+
+/// A file:// URI for `path`, every byte outside the unreserved set (and
+/// "/") percent-encoded. That also keeps it a plain GVariant string
+/// literal: no quote or backslash survives the encoding.
+fn file_uri(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut uri = String::from("file://");
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    uri
+}
+
+/// gdbus's arguments for FileManager1.ShowItems on `path`: the URI list
+/// and the startup id, as GVariant text
+fn show_items_args(path: &Path, startup_id: &str) -> Vec<String> {
+    let mut args: Vec<String> = [
+        "call",
+        "--session",
+        "--dest",
+        FILE_MANAGER_SERVICE,
+        "--object-path",
+        FILE_MANAGER_PATH,
+        "--method",
+        "org.freedesktop.FileManager1.ShowItems",
+    ]
+    .map(str::to_string)
+    .to_vec();
+    args.push(format!("['{}']", file_uri(path)));
+    args.push(format!("'{startup_id}'"));
+    args
+}
+
+/// Ask the session's file manager to show `path` selected
+/// (FileManager1.ShowItems). Whether one took the call.
+fn show_items(path: &Path) -> bool {
+    Command::new("gdbus")
+        .args(show_items_args(path, &startup_id("reveal")))
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Show the (validated) app at `path` in the file manager: selected when
+/// the file manager supports it, else just its folder
+pub(crate) fn reveal_in_file_manager(path: &Path) -> Result<(), AppError> {
+    if show_items(path) {
+        return Ok(());
+    }
+    let folder = path
+        .parent()
+        .ok_or_else(|| AppError::Validation("App has no parent folder".into()))?;
+    let status = Command::new("xdg-open")
+        .arg(folder)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(AppError::Validation(
+            "No file manager could open the folder".into(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::desktop_entry::find_program;
+    use std::ffi::OsStr;
+    use std::fs;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+
+    /// Set in the test binary re-run under dbus-run-session: the file the
+    /// stub file manager and the stub xdg-open record their calls in
+    const RECORD_VAR: &str = "WAFFLEPAD_TEST_RECORD";
+
+    /// A FileManager1 that records the ShowItems calls it takes
+    struct StubFileManager {
+        record: PathBuf,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.FileManager1")]
+    impl StubFileManager {
+        fn show_items(&self, uris: Vec<String>, startup_id: String) {
+            fs::write(&self.record, format!("{}\n{startup_id}", uris.join(" "))).unwrap();
+        }
+    }
+
+    /// Re-run the ignored test `name` of this binary on a private session
+    /// bus, with `bin` first on PATH, and return what it recorded. None
+    /// (the test skips) without dbus-run-session or gdbus.
+    fn run_on_private_bus(name: &str, bin: Option<&Path>) -> Option<String> {
+        if find_program("dbus-run-session").is_none() || find_program("gdbus").is_none() {
+            eprintln!("skipped: needs dbus-run-session and gdbus");
+            return None;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let record = dir.path().join("record");
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let path = std::env::join_paths(
+            bin.map(Path::to_path_buf)
+                .into_iter()
+                .chain(std::env::split_paths(&path)),
+        )
+        .unwrap();
+        let output = Command::new("dbus-run-session")
+            .arg("--")
+            .arg(std::env::current_exe().unwrap())
+            .args(["--exact", name, "--ignored"])
+            .env(RECORD_VAR, &record)
+            .env("PATH", path)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{name} failed on the private bus:\n{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        Some(fs::read_to_string(record).unwrap())
+    }
+
+    #[test]
+    fn reveal_selects_the_app_through_file_manager1() {
+        let Some(record) =
+            run_on_private_bus("file_manager::tests::reveal_with_stub_file_manager", None)
+        else {
+            return;
+        };
+        let (uris, startup_id) = record.split_once('\n').unwrap();
+        assert_eq!(uris, "file:///opt/Sound%20%26%20Vision/vision.desktop");
+        assert!(startup_id.starts_with("wafflepad-"));
+        assert!(startup_id.contains("-reveal_TIME"));
+    }
+
+    #[test]
+    #[ignore = "run on a private bus by reveal_selects_the_app_through_file_manager1"]
+    fn reveal_with_stub_file_manager() {
+        let Some(record) = std::env::var_os(RECORD_VAR) else {
+            return;
+        };
+        let _connection = zbus::blocking::connection::Builder::session()
+            .unwrap()
+            .name(FILE_MANAGER_SERVICE)
+            .unwrap()
+            .serve_at(
+                FILE_MANAGER_PATH,
+                StubFileManager {
+                    record: record.into(),
+                },
+            )
+            .unwrap()
+            .build()
+            .unwrap();
+        reveal_in_file_manager(Path::new("/opt/Sound & Vision/vision.desktop")).unwrap();
+    }
+
+    #[test]
+    fn reveal_opens_the_folder_without_file_manager1() {
+        let bin = tempfile::tempdir().unwrap();
+        let xdg_open = bin.path().join("xdg-open");
+        // Like xdg-open, exit 2 for a folder that doesn't exist
+        fs::write(
+            &xdg_open,
+            format!(
+                "#!/bin/sh\nprintf '%s\\n' \"$1\" >> \"${RECORD_VAR}\"\n[ -d \"$1\" ] || exit 2\n"
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&xdg_open, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let Some(record) = run_on_private_bus(
+            "file_manager::tests::reveal_with_stub_xdg_open",
+            Some(bin.path()),
+        ) else {
+            return;
+        };
+        assert_eq!(record, "/tmp\n/nonexistent/Apps\n");
+    }
+
+    #[test]
+    #[ignore = "run on a private bus by reveal_opens_the_folder_without_file_manager1"]
+    fn reveal_with_stub_xdg_open() {
+        if std::env::var_os(RECORD_VAR).is_none() {
+            return;
+        }
+        reveal_in_file_manager(Path::new("/tmp/vision.desktop")).unwrap();
+        assert!(matches!(
+            reveal_in_file_manager(Path::new("/nonexistent/Apps/vision.desktop")),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn builds_the_show_items_call() {
+        let args = show_items_args(
+            Path::new("/usr/share/applications/firefox.desktop"),
+            "wafflepad-42-reveal_TIME1000",
+        );
+        assert_eq!(
+            args,
+            [
+                "call",
+                "--session",
+                "--dest",
+                "org.freedesktop.FileManager1",
+                "--object-path",
+                "/org/freedesktop/FileManager1",
+                "--method",
+                "org.freedesktop.FileManager1.ShowItems",
+                "['file:///usr/share/applications/firefox.desktop']",
+                "'wafflepad-42-reveal_TIME1000'",
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_unreserved_characters() {
+        assert_eq!(
+            file_uri(Path::new("/opt/My_App-2.0~beta/app.AppImage")),
+            "file:///opt/My_App-2.0~beta/app.AppImage"
+        );
+    }
+
+    #[test]
+    fn percent_encodes_everything_else() {
+        assert_eq!(
+            file_uri(Path::new("/home/me/Apps/Sound & Vision #1?.AppImage")),
+            "file:///home/me/Apps/Sound%20%26%20Vision%20%231%3F.AppImage"
+        );
+        assert_eq!(
+            file_uri(Path::new("/home/me/100%.desktop")),
+            "file:///home/me/100%25.desktop"
+        );
+        // UTF-8 byte by byte
+        assert_eq!(
+            file_uri(Path::new("/home/me/Café.desktop")),
+            "file:///home/me/Caf%C3%A9.desktop"
+        );
+        // Not UTF-8 at all
+        assert_eq!(
+            file_uri(Path::new(OsStr::from_bytes(b"/tmp/\xff.desktop"))),
+            "file:///tmp/%FF.desktop"
+        );
+    }
+
+    #[test]
+    fn quotes_cannot_break_out_of_the_gvariant_string() {
+        let args = show_items_args(Path::new("/tmp/it's \\ \"odd\".desktop"), "id");
+        assert_eq!(args[8], "['file:///tmp/it%27s%20%5C%20%22odd%22.desktop']");
+        assert!(!args[8][2..args[8].len() - 2].contains(['\'', '\\', '"']));
+    }
+}
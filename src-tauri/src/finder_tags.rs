@@ -0,0 +1,188 @@
+//! Finder tags: the com.apple.metadata:_kMDItemUserTags xattr Finder
+//! writes when a file is tagged. Its value is a binary plist array with
+//! one string per tag, the tag's name and, after a newline, its color
+//! index ("Work\n6"); tags without a color have no index.
+//!
+//! On Linux the freedesktop tags (user.xdg.tags, a comma-separated list
+//! that Dolphin and other file managers write) are read too, after
+//! Finder's: they carry no color.
+//!
+//! Tagging changes neither the bundle's contents nor its mtime, so tags
+//! are re-read on every scan, like quarantine.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::xattr::read_xattr;
+
+const USER_TAGS_XATTR: &str = "com.apple.metadata:_kMDItemUserTags";
+/// user.xdg.tags, as read_xattr names it on Linux
+const XDG_TAGS_XATTR: &str = "xdg.tags";
+
+/// Finder's tag colors, in the order of their color index (0 is none)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TagColor {
+    Gray,
+    Green,
+    Purple,
+    Blue,
+    Yellow,
+    Red,
+    Orange,
+}
+
+impl TagColor {
+    fn from_index(index: u32) -> Option<Self> {
+        const COLORS: [TagColor; 7] = [
+            TagColor::Gray,
+            TagColor::Green,
+            TagColor::Purple,
+            TagColor::Blue,
+            TagColor::Yellow,
+            TagColor::Red,
+            TagColor::Orange,
+        ];
+        COLORS.get(index.checked_sub(1)? as usize).copied()
+    }
+}
+
+/// One Finder tag on a bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FinderTag {
+    pub name: String,
+    pub color: Option<TagColor>,
+}
+
+/// Parse one entry of the tags array ("name" or "name\ncolor-index")
+fn parse_tag(entry: &str) -> Option<FinderTag> {
+    let (name, color) = match entry.rsplit_once('\n') {
+        Some((name, index)) => (
+            name,
+            index.trim().parse().ok().and_then(TagColor::from_index),
+        ),
+        None => (entry, None),
+    };
+    let name = name.trim();
+    (!name.is_empty()).then(|| FinderTag {
+        name: name.to_string(),
+        color,
+    })
+}
+
+/// Tags of a _kMDItemUserTags value, in Finder's order
+fn parse_user_tags(value: &[u8]) -> Vec<FinderTag> {
+    let Ok(tags) = plist::Value::from_reader(std::io::Cursor::new(value)) else {
+        return Vec::new();
+    };
+    tags.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.as_string())
+        .filter_map(parse_tag)
+        .collect()
+}
+
+/// Tags of a user.xdg.tags value: comma-separated names
+fn parse_xdg_tags(value: &[u8]) -> Vec<FinderTag> {
+    String::from_utf8_lossy(value)
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| FinderTag {
+            name: name.to_string(),
+            color: None,
+        })
+        .collect()
+}
+
+/// Finder tags of the bundle at `path`, in Finder's order (then, on
+/// Linux, its freedesktop tags); empty when it has none or the values
+/// can't be read
+pub(crate) fn read_finder_tags(path: &Path) -> Vec<FinderTag> {
+    let mut tags = read_xattr(path, USER_TAGS_XATTR)
+        .map(|value| parse_user_tags(&value))
+        .unwrap_or_default();
+    if cfg!(target_os = "linux") {
+        if let Some(value) = read_xattr(path, XDG_TAGS_XATTR) {
+            tags.extend(parse_xdg_tags(&value));
+        }
+    }
+    // Finder keeps names unique, but a hand-written value (or the same
+    // tag set both ways) needn't be
+    let mut seen = std::collections::HashSet::new();
+    tags.retain(|tag| seen.insert(tag.name.to_lowercase()));
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_tags(entries: &[&str]) -> Vec<u8> {
+        let array = entries
+            .iter()
+            .map(|entry| plist::Value::from(*entry))
+            .collect();
+        let mut value = Vec::new();
+        plist::Value::Array(array)
+            .to_writer_binary(&mut value)
+            .unwrap();
+        value
+    }
+
+    fn tag(name: &str, color: Option<TagColor>) -> FinderTag {
+        FinderTag {
+            name: name.into(),
+            color,
+        }
+    }
+
+    #[test]
+    fn parses_names_and_colors() {
+        assert_eq!(
+            parse_user_tags(&user_tags(&[
+                "Work\n6",
+                "Client-X",
+                "Design\n4",
+                "Odd\n9",
+                "\n2"
+            ])),
+            [
+                tag("Work", Some(TagColor::Red)),
+                tag("Client-X", None),
+                tag("Design", Some(TagColor::Blue)),
+                tag("Odd", None),
+            ]
+        );
+        assert!(parse_user_tags(b"garbage").is_empty());
+        assert_eq!(
+            parse_xdg_tags(b"work, Reading,,"),
+            [tag("work", None), tag("Reading", None)]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reads_finder_and_xdg_tags_from_xattrs() {
+        use crate::xattr::set_xattr;
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        assert!(read_finder_tags(file.path()).is_empty());
+        let finder = user_tags(&["Work\n6", "Design"]);
+        if !set_xattr(file.path(), USER_TAGS_XATTR, &finder) {
+            eprintln!("skipped: no user xattrs on this filesystem");
+            return;
+        }
+        assert!(set_xattr(file.path(), XDG_TAGS_XATTR, b"work,Reading"));
+        assert_eq!(
+            read_finder_tags(file.path()),
+            [
+                tag("Work", Some(TagColor::Red)),
+                tag("Design", None),
+                tag("Reading", None),
+            ]
+        );
+    }
+}
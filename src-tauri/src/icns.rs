@@ -0,0 +1,527 @@
+//! App icons read from the bundle's .icns file (CFBundleIconFile): no
+//! NSWorkspace round trip through a Swift interpreter, and it works on
+//! any platform.
+//!
+//! An .icns file is a list of elements, each a four-letter type and its
+//! data. Modern elements hold a PNG (or JPEG 2000) of a fixed pixel size;
+//! 16 and 32 pixel ones may instead hold "ARGB" followed by run-length
+//! encoded channels. The oldest elements hold run-length encoded RGB with
+//! the alpha channel in a separate mask element. JPEG 2000 elements go
+//! through the system's decoder: sips on macOS, OpenJPEG's
+//! opj_decompress elsewhere when it is installed.
+//!
+//! The element chosen is scaled to the size asked for.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use resvg::tiny_skia::{ColorU8, Pixmap};
+
+use crate::bundle_info::BundleInfo;
+use crate::icon_cache::scale_png;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// A JP2 file's signature box, and a bare JPEG 2000 codestream's start
+/// (SOC then SIZ markers)
+const JP2_SIGNATURE: &[u8] = b"\0\0\0\x0cjP  \r\n\x87\n";
+const J2K_SIGNATURE: &[u8] = b"\xff\x4f\xff\x51";
+
+/// Element types holding a PNG or JPEG 2000 (or for the smallest ones
+/// ARGB), with their edge length in pixels. The Retina (@2x) ones hold
+/// twice their point size: ic11, ic12, ic13, ic14 and ic10 are iconutil's
+/// 16, 32, 128, 256 and 512 point @2x images.
+const IMAGE_TYPES: [(&[u8; 4], u32); 13] = [
+    (b"icp4", 16),
+    (b"ic04", 16),
+    (b"icp5", 32),
+    (b"ic05", 32),
+    (b"ic11", 32),
+    (b"icp6", 64),
+    (b"ic12", 64),
+    (b"ic07", 128),
+    (b"ic08", 256),
+    (b"ic13", 256),
+    (b"ic09", 512),
+    (b"ic14", 512),
+    (b"ic10", 1024),
+];
+
+/// Legacy RGB element types, with their edge length and the element
+/// holding their alpha mask
+const RGB_TYPES: [(&[u8; 4], u32, &[u8; 4]); 4] = [
+    (b"is32", 16, b"s8mk"),
+    (b"il32", 32, b"l8mk"),
+    (b"ih32", 48, b"h8mk"),
+    (b"it32", 128, b"t8mk"),
+];
+
+/// The .icns file a bundle names in CFBundleIconFile, in its Resources
+/// (the name may omit the extension)
+pub(crate) fn bundle_icns_path(bundle: &Path) -> Option<PathBuf> {
+    let info = BundleInfo::read(bundle)?;
+    let name = info.string("CFBundleIconFile").filter(|n| !n.is_empty())?;
+    if name.contains('/') {
+        return None;
+    }
+    let file = if Path::new(name).extension().is_some() {
+        name.to_string()
+    } else {
+        format!("{name}.icns")
+    };
+    let path = bundle.join("Contents/Resources").join(file);
+    path.is_file().then_some(path)
+}
+
+/// The elements of an .icns file, by type
+fn elements(data: &[u8]) -> Option<HashMap<[u8; 4], &[u8]>> {
+    if data.get(..4)? != b"icns" {
+        return None;
+    }
+    let total = u32::from_be_bytes(data.get(4..8)?.try_into().ok()?) as usize;
+    let data = data.get(..total.min(data.len()))?;
+    let mut elements = HashMap::new();
+    let mut at = 8;
+    while at + 8 <= data.len() {
+        let kind: [u8; 4] = data[at..at + 4].try_into().ok()?;
+        let len = u32::from_be_bytes(data[at + 4..at + 8].try_into().ok()?) as usize;
+        if len < 8 {
+            break;
+        }
+        let Some(body) = data.get(at + 8..at + len) else {
+            break;
+        };
+        elements.entry(kind).or_insert(body);
+        at += len;
+    }
+    Some(elements)
+}
+
+/// Undo the icns run-length encoding until `len` bytes are out: a header
+/// byte below 0x80 is followed by that many plus one literal bytes, one
+/// from 0x80 up by a single byte repeated header - 125 times
+fn unpack_runs(data: &[u8], len: usize) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(len);
+    let mut bytes = data.iter();
+    while out.len() < len {
+        let header = *bytes.next()? as usize;
+        if header < 0x80 {
+            for _ in 0..=header {
+                out.push(*bytes.next()?);
+            }
+        } else {
+            let value = *bytes.next()?;
+            out.extend(std::iter::repeat_n(value, header - 125));
+        }
+    }
+    out.truncate(len);
+    Some(out)
+}
+
+/// Encode planar channels (alpha, red, green, blue; `size`² bytes each)
+/// as a PNG
+fn planes_to_png(
+    size: u32,
+    alpha: &[u8],
+    red: &[u8],
+    green: &[u8],
+    blue: &[u8],
+) -> Option<Vec<u8>> {
+    let mut pixmap = Pixmap::new(size, size)?;
+    for (i, pixel) in pixmap.pixels_mut().iter_mut().enumerate() {
+        *pixel = ColorU8::from_rgba(red[i], green[i], blue[i], alpha[i]).premultiply();
+    }
+    pixmap.encode_png().ok()
+}
+
+/// An "ARGB" element's image: the four channels run-length encoded one
+/// after the other
+fn decode_argb(data: &[u8], size: u32) -> Option<Vec<u8>> {
+    let pixels = (size * size) as usize;
+    let channels = unpack_runs(data, pixels * 4)?;
+    let (alpha, rest) = channels.split_at(pixels);
+    let (red, rest) = rest.split_at(pixels);
+    let (green, blue) = rest.split_at(pixels);
+    planes_to_png(size, alpha, red, green, blue)
+}
+
+/// A legacy RGB element's image, red, green and blue run-length encoded
+/// one after the other (unless stored raw), masked by `mask` when there
+/// is one
+fn decode_rgb(kind: &[u8; 4], data: &[u8], size: u32, mask: Option<&[u8]>) -> Option<Vec<u8>> {
+    let pixels = (size * size) as usize;
+    // it32 data starts with four zero bytes
+    let data = if kind == b"it32" {
+        data.get(4..)?
+    } else {
+        data
+    };
+    let channels = if data.len() == pixels * 3 {
+        data.to_vec()
+    } else {
+        unpack_runs(data, pixels * 3)?
+    };
+    let (red, rest) = channels.split_at(pixels);
+    let (green, blue) = rest.split_at(pixels);
+    let opaque = vec![u8::MAX; pixels];
+    let alpha = mask
+        .filter(|mask| mask.len() >= pixels)
+        .unwrap_or(&opaque[..]);
+    planes_to_png(size, alpha, red, green, blue)
+}
+
+/// The system's JPEG 2000 decoder, converting `input` to the PNG `output`:
+/// ImageIO (through sips) on macOS, else OpenJPEG's command line tool
+fn jpeg2000_command(input: &Path, output: &Path) -> Command {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut sips = Command::new("sips");
+        sips.args(["-s", "format", "png"])
+            .arg(input)
+            .arg("--out")
+            .arg(output);
+        sips
+    } else {
+        let mut opj = Command::new("opj_decompress");
+        opj.arg("-i").arg(input).arg("-o").arg(output);
+        opj
+    };
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    command
+}
+
+/// A JPEG 2000 element's image as a PNG. The decoders read and write
+/// files only, so it goes through two in a private temporary directory,
+/// named by extension as the decoders tell the formats apart.
+fn decode_jpeg2000(data: &[u8]) -> Option<Vec<u8>> {
+    let dir = tempfile::Builder::new()
+        .prefix("wafflepad-icns")
+        .tempdir()
+        .ok()?;
+    let extension = if data.starts_with(JP2_SIGNATURE) {
+        "jp2"
+    } else {
+        "j2k"
+    };
+    let input = dir.path().join(format!("element.{extension}"));
+    let output = dir.path().join("element.png");
+    fs::write(&input, data).ok()?;
+    jpeg2000_command(&input, &output)
+        .status()
+        .ok()
+        .filter(|status| status.success())
+        .and_then(|_| fs::read(&output).ok())
+        .filter(|png| png.starts_with(PNG_SIGNATURE))
+}
+
+/// A PNG element's real edge length, from its header
+fn png_size(data: &[u8]) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(16..20)?.try_into().ok()?))
+}
+
+/// One of the file's images, before decoding
+enum Candidate<'a> {
+    Image(&'a [u8]),
+    Rgb(&'a [u8; 4], &'a [u8], Option<&'a [u8]>),
+}
+
+impl Candidate<'_> {
+    fn decode(&self, size: u32) -> Option<Vec<u8>> {
+        match self {
+            Candidate::Image(data) if data.starts_with(PNG_SIGNATURE) => Some(data.to_vec()),
+            Candidate::Image(data)
+                if data.starts_with(JP2_SIGNATURE) || data.starts_with(J2K_SIGNATURE) =>
+            {
+                decode_jpeg2000(data)
+            }
+            Candidate::Image(data) => decode_argb(data.strip_prefix(b"ARGB")?, size),
+            Candidate::Rgb(kind, data, mask) => decode_rgb(kind, data, size, *mask),
+        }
+    }
+}
+
+/// PNG bytes of the .icns image that best fits `size` (the smallest at
+/// least that large, else the largest there is), scaled to `size`
+pub(crate) fn icns_to_png(data: &[u8], size: u32) -> Option<Vec<u8>> {
+    let elements = elements(data)?;
+    let mut candidates: Vec<(u32, Candidate)> = Vec::new();
+    for (kind, nominal) in IMAGE_TYPES {
+        if let Some(&data) = elements.get(kind) {
+            let edge = png_size(data)
+                .filter(|_| data.starts_with(PNG_SIGNATURE))
+                .unwrap_or(nominal);
+            candidates.push((edge, Candidate::Image(data)));
+        }
+    }
+    for (kind, edge, mask) in RGB_TYPES {
+        if let Some(&data) = elements.get(kind) {
+            let mask = elements.get(mask).copied();
+            candidates.push((edge, Candidate::Rgb(kind, data, mask)));
+        }
+    }
+    candidates.sort_by_key(|(edge, _)| {
+        if *edge >= size {
+            (false, edge - size)
+        } else {
+            (true, size - edge)
+        }
+    });
+    let png = candidates
+        .iter()
+        .find_map(|(edge, candidate)| candidate.decode(*edge))?;
+    scale_png(&png, size)
+}
+
+/// PNG bytes of a bundle's .icns icon at `size`, when it has one
+pub(crate) fn bundle_icns_icon(bundle: &Path, size: u32) -> Option<Vec<u8>> {
+    icns_to_png(&fs::read(bundle_icns_path(bundle)?).ok()?, size)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// An .icns file holding `elements` in order
+    pub(crate) fn icns(elements: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (kind, data) in elements {
+            body.extend_from_slice(*kind);
+            body.extend_from_slice(&(data.len() as u32 + 8).to_be_bytes());
+            body.extend_from_slice(data);
+        }
+        let mut file = b"icns".to_vec();
+        file.extend_from_slice(&(body.len() as u32 + 8).to_be_bytes());
+        file.extend(body);
+        file
+    }
+
+    /// A PNG of `size`² opaque pixels of one color
+    pub(crate) fn png(size: u32, [r, g, b]: [u8; 3]) -> Vec<u8> {
+        let mut pixmap = Pixmap::new(size, size).unwrap();
+        pixmap.fill(resvg::tiny_skia::Color::from_rgba8(r, g, b, 255));
+        pixmap.encode_png().unwrap()
+    }
+
+    /// Each of `values` repeated `count` times, run-length encoded
+    fn runs(values: &[u8], count: usize) -> Vec<u8> {
+        let mut packed = Vec::new();
+        for &value in values {
+            let mut left = count;
+            while left > 0 {
+                let run = left.min(130);
+                if run >= 3 {
+                    packed.extend_from_slice(&[(run + 125) as u8, value]);
+                } else {
+                    packed.push(run as u8 - 1);
+                    packed.extend(std::iter::repeat_n(value, run));
+                }
+                left -= run;
+            }
+        }
+        packed
+    }
+
+    fn decoded(png: &[u8]) -> Pixmap {
+        Pixmap::decode_png(png).unwrap()
+    }
+
+    /// A pixel's unpremultiplied (r, g, b, a)
+    fn pixel(pixmap: &Pixmap, x: u32, y: u32) -> (u8, u8, u8, u8) {
+        let color = pixmap.pixel(x, y).unwrap().demultiply();
+        (color.red(), color.green(), color.blue(), color.alpha())
+    }
+
+    #[test]
+    fn lists_elements_by_type() {
+        let file = icns(&[(b"TOC ", b"toc"), (b"ic07", b"first"), (b"ic07", b"second")]);
+        let found = elements(&file).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[b"ic07"], b"first");
+        assert_eq!(found[b"TOC "], b"toc");
+    }
+
+    #[test]
+    fn stops_at_damaged_elements() {
+        let mut file = icns(&[(b"ic07", b"whole"), (b"ic08", b"cut short")]);
+        file.truncate(file.len() - 2);
+        let found = elements(&file).unwrap();
+        assert_eq!(found.keys().collect::<Vec<_>>(), [b"ic07"]);
+
+        // An element shorter than its own header
+        let mut file = icns(&[(b"ic07", b"whole")]);
+        file.extend_from_slice(b"ic08\0\0\0\x04");
+        let total = file.len() as u32;
+        file[4..8].copy_from_slice(&total.to_be_bytes());
+        assert_eq!(elements(&file).unwrap().len(), 1);
+
+        assert!(elements(b"ICNS\0\0\0\x08").is_none());
+        assert!(elements(b"icn").is_none());
+    }
+
+    #[test]
+    fn unpacks_literal_and_repeated_runs() {
+        // Two literal bytes, then 0x83 - 125 = 6 repeats of 9
+        assert_eq!(
+            unpack_runs(&[1, 7, 8, 0x83, 9], 8),
+            Some(vec![7, 8, 9, 9, 9, 9, 9, 9])
+        );
+        // Stops once `len` bytes are out, mid-run
+        assert_eq!(unpack_runs(&[0xff, 1, 0xff, 2], 4), Some(vec![1; 4]));
+        // Runs past the data
+        assert_eq!(unpack_runs(&[5, 1, 2], 6), None);
+        assert_eq!(unpack_runs(&[0x80], 3), None);
+    }
+
+    #[test]
+    fn decodes_argb() {
+        let pixels = 16 * 16;
+        // Alpha, red, green, blue planes: opaque orange
+        let data = runs(&[255, 255, 128, 0], pixels);
+        let png = decode_argb(&data, 16).unwrap();
+        let image = decoded(&png);
+        assert_eq!((image.width(), image.height()), (16, 16));
+        assert_eq!(pixel(&image, 3, 9), (255, 128, 0, 255));
+        assert_eq!(decode_argb(&data[..data.len() - 2], 16), None);
+    }
+
+    #[test]
+    fn decodes_masked_rgb() {
+        let pixels = 16 * 16;
+        let data = runs(&[0, 0, 255], pixels);
+        let mut mask = vec![255u8; pixels];
+        mask[0] = 0;
+        let image = decoded(&decode_rgb(b"is32", &data, 16, Some(&mask)).unwrap());
+        assert_eq!(pixel(&image, 1, 0), (0, 0, 255, 255));
+        assert_eq!(pixel(&image, 0, 0).3, 0);
+
+        // Without a mask (or with a short one) it is opaque
+        let image = decoded(&decode_rgb(b"is32", &data, 16, Some(&mask[..10])).unwrap());
+        assert_eq!(pixel(&image, 0, 0), (0, 0, 255, 255));
+    }
+
+    #[test]
+    fn decodes_raw_and_it32_rgb() {
+        let pixels = 16 * 16;
+        let mut raw = vec![10u8; pixels];
+        raw.extend(vec![20u8; pixels]);
+        raw.extend(vec![30u8; pixels]);
+        let image = decoded(&decode_rgb(b"is32", &raw, 16, None).unwrap());
+        assert_eq!(pixel(&image, 5, 5), (10, 20, 30, 255));
+
+        let mut it32 = vec![0; 4];
+        it32.extend(runs(&[40, 50, 60], 128 * 128));
+        let image = decoded(&decode_rgb(b"it32", &it32, 128, None).unwrap());
+        assert_eq!(
+            (image.width(), pixel(&image, 127, 127)),
+            (128, (40, 50, 60, 255))
+        );
+    }
+
+    /// ARGB at 16, masked RGB at 16, PNGs at 128 and 256
+    fn sample() -> Vec<u8> {
+        let mut argb = b"ARGB".to_vec();
+        argb.extend(runs(&[255, 255, 0, 0], 16 * 16));
+        icns(&[
+            (b"ic04", &argb),
+            (b"is32", &runs(&[0, 255, 0], 16 * 16)),
+            (b"s8mk", &[255; 16 * 16]),
+            (b"ic07", &png(128, [0, 0, 255])),
+            (b"ic08", &png(256, [255, 255, 255])),
+        ])
+    }
+
+    /// The (r, g, b, a) of the center pixel of `size`² PNG `png`
+    fn center(png: &[u8], size: u32) -> (u8, u8, u8, u8) {
+        let image = decoded(png);
+        assert_eq!((image.width(), image.height()), (size, size));
+        pixel(&image, size / 2, size / 2)
+    }
+
+    #[test]
+    fn picks_the_smallest_image_covering_the_size() {
+        let file = sample();
+        let blue = (0, 0, 255, 255);
+        let white = (255, 255, 255, 255);
+        assert_eq!(center(&icns_to_png(&file, 100).unwrap(), 100), blue);
+        assert_eq!(center(&icns_to_png(&file, 128).unwrap(), 128), blue);
+        assert_eq!(center(&icns_to_png(&file, 129).unwrap(), 129), white);
+        // Nothing that large: the largest there is, scaled up
+        assert_eq!(center(&icns_to_png(&file, 512).unwrap(), 512), white);
+        // The ARGB element is listed before the legacy RGB one
+        assert_eq!(
+            center(&icns_to_png(&file, 16).unwrap(), 16),
+            (255, 0, 0, 255)
+        );
+    }
+
+    #[test]
+    fn scales_the_chosen_image_to_the_size() {
+        // A 1024-pixel element serving a 64-pixel request
+        let file = icns(&[(b"ic10", &png(1024, [10, 200, 30]))]);
+        assert_eq!(
+            center(&icns_to_png(&file, 64).unwrap(), 64),
+            (10, 200, 30, 255)
+        );
+        // An element of the size asked for is passed through as it is
+        let exact = png(128, [1, 2, 3]);
+        assert_eq!(icns_to_png(&icns(&[(b"ic07", &exact)]), 128), Some(exact));
+    }
+
+    #[test]
+    fn retina_elements_are_twice_their_point_size() {
+        let edge = |kind: &[u8; 4]| {
+            IMAGE_TYPES
+                .iter()
+                .find(|(listed, _)| *listed == kind)
+                .map(|(_, edge)| *edge)
+        };
+        assert_eq!(edge(b"ic11"), Some(32));
+        assert_eq!(edge(b"ic12"), Some(64));
+        assert_eq!(edge(b"ic13"), Some(256));
+        assert_eq!(edge(b"ic14"), Some(512));
+        assert_eq!(edge(b"ic10"), Some(1024));
+    }
+
+    #[test]
+    fn legacy_rgb_serves_when_alone() {
+        let file = icns(&[
+            (b"is32", &runs(&[0, 255, 0], 16 * 16)),
+            (b"s8mk", &[255; 16 * 16]),
+        ]);
+        assert_eq!(
+            center(&icns_to_png(&file, 64).unwrap(), 64),
+            (0, 255, 0, 255)
+        );
+    }
+
+    #[test]
+    fn goes_by_a_png_s_real_size() {
+        // A 64-pixel PNG in the 1024 slot
+        let file = icns(&[(b"ic10", &png(64, [1, 2, 3])), (b"ic08", &png(256, [0; 3]))]);
+        assert_eq!(
+            center(&icns_to_png(&file, 100).unwrap(), 100),
+            (0, 0, 0, 255)
+        );
+        assert_eq!(center(&icns_to_png(&file, 64).unwrap(), 64), (1, 2, 3, 255));
+    }
+
+    #[test]
+    fn falls_back_past_undecodable_elements() {
+        let mut jp2 = JP2_SIGNATURE.to_vec();
+        jp2.extend_from_slice(b"not really a JPEG 2000 image");
+        let file = icns(&[
+            (b"ic08", &jp2),
+            (b"ic09", b"neither PNG nor ARGB"),
+            (b"ic07", &png(128, [9, 9, 9])),
+        ]);
+        assert_eq!(
+            center(&icns_to_png(&file, 256).unwrap(), 256),
+            (9, 9, 9, 255)
+        );
+        assert_eq!(icns_to_png(&icns(&[(b"ic09", &jp2)]), 512), None);
+        assert_eq!(icns_to_png(b"not an icns file", 128), None);
+    }
+}
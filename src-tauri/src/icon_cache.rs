@@ -1,24 +1,107 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::appimage::{appimage_icon, is_appimage};
+use crate::asset_catalog::bundle_catalog_icon;
+use crate::icns::bundle_icns_icon;
+use crate::icon_theme::desktop_entry_icon;
+
+/// Edge lengths icons are cached at, in pixels: a request is served at
+/// the smallest of them that covers it
+const ICON_SIZES: [u32; 4] = [64, 128, 256, 512];
+
+/// Size of the icons served with the app list (AppInfo.icon): the
+/// frontend's tile size in device pixels, as get_apps last asked
+static PREFERRED_ICON_SIZE: AtomicU32 = AtomicU32::new(128);
+
+/// The cached size serving a request for `size` pixels
+fn cache_size(size: u32) -> u32 {
+    ICON_SIZES
+        .into_iter()
+        .find(|&cached| cached >= size)
+        .unwrap_or(ICON_SIZES[ICON_SIZES.len() - 1])
+}
+
+/// Cached sizes nearest `size` first, its own cache size leading
+fn sizes_nearest(size: u32) -> Vec<u32> {
+    let size = cache_size(size);
+    let mut sizes = ICON_SIZES.to_vec();
+    sizes.sort_by_key(|&cached| cached.abs_diff(size));
+    sizes
+}
+
+/// Set the size app list icons are served at (see PREFERRED_ICON_SIZE)
+pub(crate) fn set_preferred_icon_size(size: u32) {
+    PREFERRED_ICON_SIZE.store(cache_size(size), Ordering::Relaxed);
+}
+
+pub(crate) fn preferred_icon_size() -> u32 {
+    PREFERRED_ICON_SIZE.load(Ordering::Relaxed)
+}
+
+/// Scale a PNG to a `size`×`size` one, fit and centered; as it is when
+/// it already has that size. Halved first while more than twice too
+/// large, as a single bicubic pass would skip most source pixels.
+pub(crate) fn scale_png(png: &[u8], size: u32) -> Option<Vec<u8>> {
+    use resvg::tiny_skia::{FilterQuality, Pixmap, PixmapPaint, Transform};
+
+    let mut source = Pixmap::decode_png(png).ok()?;
+    if (source.width(), source.height()) == (size, size) {
+        return Some(png.to_vec());
+    }
+    let draw = |source: &Pixmap, width: u32, height: u32, quality| {
+        let mut target = Pixmap::new(width, height)?;
+        let scale =
+            (width as f32 / source.width() as f32).min(height as f32 / source.height() as f32);
+        let transform = Transform::from_scale(scale, scale).post_translate(
+            (width as f32 - source.width() as f32 * scale) / 2.0,
+            (height as f32 - source.height() as f32 * scale) / 2.0,
+        );
+        let paint = PixmapPaint {
+            quality,
+            ..PixmapPaint::default()
+        };
+        target.draw_pixmap(0, 0, source.as_ref(), &paint, transform, None);
+        Some(target)
+    };
+    while source.width().max(source.height()) > size * 2 {
+        let (width, height) = (source.width().div_ceil(2), source.height().div_ceil(2));
+        source = draw(&source, width, height, FilterQuality::Bilinear)?;
+    }
+    draw(&source, size, size, FilterQuality::Bicubic)?
+        .encode_png()
+        .ok()
+}
+
+/// Get cache directory: ~/Library/Caches/com.helpermedia.wafflepad/
+pub(crate) fn get_cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|p| p.join("com.helpermedia.wafflepad"))
+}
 
 /// Get icons cache directory
 fn get_icons_cache_dir() -> Option<PathBuf> {
-    dirs::cache_dir().map(|p| p.join("com.helpermedia.wafflepad").join("icons"))
+    get_cache_dir().map(|p| p.join("icons"))
 }
 
-/// Get a stable hash for an app path to use as icon filename
-fn get_icon_filename(app_path: &str) -> String {
+/// Get a stable hash for an app path and icon size to use as icon
+/// filename, so each size is a file of its own
+fn get_icon_filename(app_path: &str, size: u32) -> String {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
     let mut hasher = DefaultHasher::new();
     app_path.hash(&mut hasher);
-    format!("{:x}.png", hasher.finish())
+    format!("{:x}-{}.png", hasher.finish(), size)
 }
 
-/// Get cached icon path if it exists and is still fresh
-fn get_cached_icon_path(app_path: &str) -> Option<PathBuf> {
-    let icons_dir = get_icons_cache_dir()?;
-    let icon_file = icons_dir.join(get_icon_filename(app_path));
+/// The file:// URL the frontend loads a cached icon from
+fn file_url(icon_file: &Path) -> String {
+    format!("file://{}", icon_file.display())
+}
+
+/// Get cached icon path in `icons_dir` if it exists and is still fresh
+fn get_cached_icon_path(icons_dir: &Path, app_path: &str, size: u32) -> Option<PathBuf> {
+    let icon_file = icons_dir.join(get_icon_filename(app_path, size));
     if !icon_file.exists() {
         return None;
     }
@@ -33,18 +116,30 @@ fn get_cached_icon_path(app_path: &str) -> Option<PathBuf> {
     Some(icon_file)
 }
 
-/// Save icon PNG bytes to cache and return the file path
-fn save_icon_to_cache(app_path: &str, png_bytes: &[u8]) -> Option<PathBuf> {
-    let icons_dir = get_icons_cache_dir()?;
-    fs::create_dir_all(&icons_dir).ok()?;
-    let icon_file = icons_dir.join(get_icon_filename(app_path));
+/// The fresh cached icon in `icons_dir` nearest `size`
+fn nearest_cached_icon_path(icons_dir: &Path, app_path: &str, size: u32) -> Option<PathBuf> {
+    sizes_nearest(size)
+        .into_iter()
+        .find_map(|cached| get_cached_icon_path(icons_dir, app_path, cached))
+}
+
+/// Save icon PNG bytes to `icons_dir` and return the file path
+fn save_icon_to_cache(
+    icons_dir: &Path,
+    app_path: &str,
+    size: u32,
+    png_bytes: &[u8],
+) -> Option<PathBuf> {
+    fs::create_dir_all(icons_dir).ok()?;
+    let icon_file = icons_dir.join(get_icon_filename(app_path, size));
     fs::write(&icon_file, png_bytes).ok()?;
     Some(icon_file)
 }
 
-/// Get icon using NSWorkspace via Swift (handles all icon types on macOS)
+/// Get icon using NSWorkspace via Swift (handles all icon types on macOS;
+/// slow, so only for bundles whose icon files can't be read)
 #[cfg(target_os = "macos")]
-fn get_icon_nsworkspace_bytes(app_path: &str) -> Option<Vec<u8>> {
+fn get_icon_nsworkspace_bytes(app_path: &str, size: u32) -> Option<Vec<u8>> {
     use base64::Engine;
     use std::process::Command;
 
@@ -52,17 +147,17 @@ fn get_icon_nsworkspace_bytes(app_path: &str) -> Option<Vec<u8>> {
 import Cocoa
 import Foundation
 
-guard CommandLine.arguments.count > 1 else { exit(1) }
+guard CommandLine.arguments.count > 2, let size = Double(CommandLine.arguments[2]) else { exit(1) }
 // Resolve symlinks first: system apps like Safari are cryptex symlinks in
 // /Applications, and iconForFile badges a symlink with the alias arrow
 let path = URL(fileURLWithPath: CommandLine.arguments[1]).resolvingSymlinksInPath().path
 let workspace = NSWorkspace.shared
 let icon = workspace.icon(forFile: path)
-icon.size = NSSize(width: 128, height: 128)
+icon.size = NSSize(width: size, height: size)
 
-let cgImage = icon.cgImage(forProposedRect: nil, context: nil, hints: nil)!
-let bitmap = NSBitmapImageRep(cgImage: cgImage)
-let pngData = bitmap.representation(using: .png, properties: [:])!
+guard let cgImage = icon.cgImage(forProposedRect: nil, context: nil, hints: nil),
+      let pngData = NSBitmapImageRep(cgImage: cgImage).representation(using: .png, properties: [:])
+else { exit(1) }
 print(pngData.base64EncodedString())
 "#;
 
@@ -70,6 +165,7 @@ print(pngData.base64EncodedString())
         .arg("-e")
         .arg(swift_code)
         .arg(app_path)
+        .arg(size.to_string())
         .output()
         .ok()?;
 
@@ -83,9 +179,27 @@ print(pngData.base64EncodedString())
     None
 }
 
-/// Get cached icon only (doesn't generate new icons)
-pub(crate) fn get_icon_if_cached(app_path: &str) -> Option<String> {
-    get_cached_icon_path(app_path).map(|p| format!("file://{}", p.display()))
+/// Get cached icon only (doesn't generate new icons), at the cache size
+/// serving `size`
+pub(crate) fn get_icon_if_cached(app_path: &str, size: u32) -> Option<String> {
+    get_cached_icon_path(&get_icons_cache_dir()?, app_path, cache_size(size)).map(|p| file_url(&p))
+}
+
+/// Get the fresh cached icon nearest `size`, whichever sizes are cached
+pub(crate) fn get_nearest_cached_icon(app_path: &str, size: u32) -> Option<String> {
+    nearest_cached_icon_path(&get_icons_cache_dir()?, app_path, size).map(|p| file_url(&p))
+}
+
+/// Get the cached icon nearest `size` without checking it against the
+/// bundle, for apps whose bundle can't be read right now (on an unmounted
+/// volume)
+pub(crate) fn get_stale_icon(app_path: &str, size: u32) -> Option<String> {
+    let icons_dir = get_icons_cache_dir()?;
+    sizes_nearest(size)
+        .into_iter()
+        .map(|cached| icons_dir.join(get_icon_filename(app_path, cached)))
+        .find(|icon_file| icon_file.exists())
+        .map(|icon_file| file_url(&icon_file))
 }
 
 /// Remove cached icons for apps that no longer exist on disk
@@ -99,7 +213,7 @@ pub(crate) fn cleanup_orphaned_icons(valid_app_paths: &[String]) {
 
     let valid_filenames: std::collections::HashSet<String> = valid_app_paths
         .iter()
-        .map(|p| get_icon_filename(p))
+        .flat_map(|p| ICON_SIZES.map(|size| get_icon_filename(p, size)))
         .collect();
 
     for entry in entries.flatten() {
@@ -110,10 +224,161 @@ pub(crate) fn cleanup_orphaned_icons(valid_app_paths: &[String]) {
     }
 }
 
-/// Generate icon and save to cache, returns file:// URL
-#[cfg(target_os = "macos")]
-pub(crate) fn generate_and_cache_icon(app_path: &str) -> Option<String> {
-    let png_bytes = get_icon_nsworkspace_bytes(app_path)?;
-    let saved_path = save_icon_to_cache(app_path, &png_bytes)?;
-    Some(format!("file://{}", saved_path.display()))
+/// Render an app's icon as PNG bytes: a Linux desktop entry's from the
+/// icon theme, an AppImage's from its payload, a bundle's from its asset
+/// catalog (CFBundleIconName, what Finder shows when set) or .icns file,
+/// else through NSWorkspace
+fn render_icon_bytes(app_path: &str, size: u32) -> Option<Vec<u8>> {
+    if app_path.ends_with(".desktop") {
+        return desktop_entry_icon(Path::new(app_path), size);
+    }
+    if is_appimage(Path::new(app_path)) {
+        return appimage_icon(Path::new(app_path), size);
+    }
+    if let Some(png) = bundle_catalog_icon(Path::new(app_path), size)
+        .or_else(|| bundle_icns_icon(Path::new(app_path), size))
+    {
+        return Some(png);
+    }
+    #[cfg(target_os = "macos")]
+    return get_icon_nsworkspace_bytes(app_path, size);
+    #[cfg(not(target_os = "macos"))]
+    None
+}
+
+/// Render the icon at the cache size serving `size` into `icons_dir`,
+/// scaled to that size whatever size the source had
+fn generate_icon(icons_dir: &Path, app_path: &str, size: u32) -> Option<PathBuf> {
+    let size = cache_size(size);
+    let png_bytes = scale_png(&render_icon_bytes(app_path, size)?, size)?;
+    save_icon_to_cache(icons_dir, app_path, size, &png_bytes)
+}
+
+/// Generate icon at the cache size serving `size` and save to cache,
+/// returns file:// URL
+pub(crate) fn generate_and_cache_icon(app_path: &str, size: u32) -> Option<String> {
+    generate_icon(&get_icons_cache_dir()?, app_path, size).map(|p| file_url(&p))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle_info::tests::bundle;
+    use crate::icns::tests::{icns, png};
+    use resvg::tiny_skia::Pixmap;
+
+    /// A bundle whose .icns holds a single 512-pixel image
+    fn app(dir: &Path) -> String {
+        let bundle = bundle(
+            dir,
+            "<key>CFBundleIconFile</key><string>AppIcon</string>",
+            false,
+        );
+        fs::create_dir(bundle.join("Contents/Resources")).unwrap();
+        fs::write(
+            bundle.join("Contents/Resources/AppIcon.icns"),
+            icns(&[(b"ic09", &png(512, [200, 40, 90]))]),
+        )
+        .unwrap();
+        bundle.to_string_lossy().into_owned()
+    }
+
+    fn edge(icon_file: &Path) -> (u32, u32) {
+        let image = Pixmap::decode_png(&fs::read(icon_file).unwrap()).unwrap();
+        (image.width(), image.height())
+    }
+
+    #[test]
+    fn requests_are_served_at_their_cache_size() {
+        assert_eq!(cache_size(1), 64);
+        assert_eq!(cache_size(64), 64);
+        assert_eq!(cache_size(100), 128);
+        assert_eq!(cache_size(512), 512);
+        assert_eq!(cache_size(2048), 512);
+    }
+
+    #[test]
+    fn generated_icons_have_the_size_asked_for_and_are_cached() {
+        let dir = tempfile::tempdir().unwrap();
+        let icons = dir.path().join("icons");
+        let app = app(dir.path());
+
+        for size in ICON_SIZES {
+            let icon_file = generate_icon(&icons, &app, size).unwrap();
+            assert_eq!(edge(&icon_file), (size, size));
+            assert_eq!(get_cached_icon_path(&icons, &app, size), Some(icon_file));
+        }
+        // Between cache sizes: the next one up
+        let icon_file = generate_icon(&icons, &app, 100).unwrap();
+        assert_eq!(edge(&icon_file), (128, 128));
+
+        // Served from the cache once the source is gone
+        fs::remove_file(Path::new(&app).join("Contents/Resources/AppIcon.icns")).unwrap();
+        assert!(generate_icon(&icons, &app, 64).is_none());
+        assert_eq!(
+            get_cached_icon_path(&icons, &app, 64).map(|p| edge(&p)),
+            Some((64, 64))
+        );
+    }
+
+    #[test]
+    fn missing_sizes_are_generated_when_asked_for() {
+        let dir = tempfile::tempdir().unwrap();
+        let icons = dir.path().join("icons");
+        let app = app(dir.path());
+
+        generate_icon(&icons, &app, 256).unwrap();
+        assert!(get_cached_icon_path(&icons, &app, 64).is_none());
+        assert_eq!(fs::read_dir(&icons).unwrap().count(), 1);
+
+        generate_icon(&icons, &app, 64).unwrap();
+        assert!(get_cached_icon_path(&icons, &app, 64).is_some());
+        assert_eq!(fs::read_dir(&icons).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn the_nearest_cached_size_stands_in() {
+        assert_eq!(sizes_nearest(64), [64, 128, 256, 512]);
+        assert_eq!(sizes_nearest(300), [512, 256, 128, 64]);
+        assert_eq!(sizes_nearest(200), [256, 128, 64, 512]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let icons = dir.path().join("icons");
+        let app = app(dir.path());
+        assert!(nearest_cached_icon_path(&icons, &app, 64).is_none());
+
+        let large = generate_icon(&icons, &app, 512).unwrap();
+        assert_eq!(nearest_cached_icon_path(&icons, &app, 64), Some(large));
+        let medium = generate_icon(&icons, &app, 256).unwrap();
+        assert_eq!(nearest_cached_icon_path(&icons, &app, 64), Some(medium));
+        let small = generate_icon(&icons, &app, 64).unwrap();
+        assert_eq!(nearest_cached_icon_path(&icons, &app, 100), Some(small));
+    }
+
+    #[test]
+    fn icons_older_than_the_bundle_are_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let icons = dir.path().join("icons");
+        let app = app(dir.path());
+        generate_icon(&icons, &app, 128).unwrap();
+
+        let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+        fs::File::open(&app).unwrap().set_modified(later).unwrap();
+        assert!(get_cached_icon_path(&icons, &app, 128).is_none());
+        assert!(nearest_cached_icon_path(&icons, &app, 128).is_none());
+    }
+
+    #[test]
+    fn scaling_fits_and_centers() {
+        let wide = {
+            let mut pixmap = Pixmap::new(200, 100).unwrap();
+            pixmap.fill(resvg::tiny_skia::Color::from_rgba8(0, 0, 255, 255));
+            pixmap.encode_png().unwrap()
+        };
+        let image = Pixmap::decode_png(&scale_png(&wide, 64).unwrap()).unwrap();
+        assert_eq!((image.width(), image.height()), (64, 64));
+        assert_eq!(image.pixel(32, 2).unwrap().alpha(), 0);
+        assert_eq!(image.pixel(32, 32).unwrap().demultiply().blue(), 255);
+        assert!(scale_png(b"not a PNG", 64).is_none());
+    }
 }
@@ -0,0 +1,569 @@
+//! Freedesktop icon themes: a desktop entry's Icon name resolved to an
+//! image file as the Icon Theme spec does it, then rendered to the PNG
+//! the icon cache serves.
+//!
+//! A theme is a directory of the same name in any of the base directories
+//! (~/.icons, then each XDG data directory's icons), described by its
+//! index.theme: the subdirectories holding icons and the sizes each one
+//! serves. The user's theme is searched first, then the themes it
+//! Inherits, depth first, then hicolor; an icon in none of them is looked
+//! up in /usr/share/pixmaps. Within a theme an exact size match wins,
+//! else the subdirectory closest in size.
+//!
+//! Sizes are asked for in device pixels (the icon cache's sizes), so a
+//! HiDPI directory ("48x48@2": Size=48, Scale=2) serves a 96-pixel
+//! request just as a 96x96 one does. The theme chain is resolved once
+//! per scan (see IconThemes) rather than for every icon.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+use crate::app_sources::DiscoveryRoots;
+use crate::desktop_entry::{parse_key_file, DesktopEntry};
+
+/// The theme every other one implicitly inherits
+const FALLBACK_THEME: &str = "hicolor";
+
+/// Icon file extensions in lookup order. XPM, the spec's third format,
+/// is skipped: there is nothing to decode it with.
+const EXTENSIONS: [&str; 2] = ["png", "svg"];
+
+/// How deep Inherits chains are followed, against themes inheriting in a
+/// circle
+const MAX_INHERIT_DEPTH: usize = 16;
+
+/// Where icons are looked up
+pub(crate) struct IconSearch {
+    /// Base directories holding themes, in lookup order
+    pub base_dirs: Vec<PathBuf>,
+    /// Unthemed icons' directory, searched last
+    pub pixmaps: PathBuf,
+}
+
+impl IconSearch {
+    pub(crate) fn new(roots: &DiscoveryRoots) -> Self {
+        Self {
+            base_dirs: roots.icon_theme_dirs(),
+            pixmaps: roots.pixmaps_dir(),
+        }
+    }
+}
+
+/// How a theme subdirectory's icons match requested sizes (Type)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SizeType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+/// A subdirectory of a theme, as its index.theme group describes it
+#[derive(Debug)]
+struct ThemeDir {
+    name: String,
+    size: u32,
+    scale: u32,
+    size_type: SizeType,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+}
+
+impl ThemeDir {
+    fn parse(name: &str, keys: &HashMap<String, String>) -> Option<Self> {
+        let number = |key: &str| keys.get(key).and_then(|v| v.trim().parse::<u32>().ok());
+        let size = number("Size")?;
+        Some(Self {
+            name: name.to_string(),
+            size,
+            scale: number("Scale").unwrap_or(1),
+            size_type: match keys.get("Type").map(|t| t.trim()) {
+                Some("Fixed") => SizeType::Fixed,
+                Some("Scalable") => SizeType::Scalable,
+                _ => SizeType::Threshold,
+            },
+            min_size: number("MinSize").unwrap_or(size),
+            max_size: number("MaxSize").unwrap_or(size),
+            threshold: number("Threshold").unwrap_or(2),
+        })
+    }
+
+    /// DirectoryMatchesSize, for a request of `pixels` device pixels: the
+    /// directory's scale must divide it
+    fn matches(&self, pixels: u32) -> bool {
+        if self.scale == 0 || !pixels.is_multiple_of(self.scale) {
+            return false;
+        }
+        let size = pixels / self.scale;
+        match self.size_type {
+            SizeType::Fixed => self.size == size,
+            SizeType::Scalable => (self.min_size..=self.max_size).contains(&size),
+            SizeType::Threshold => (self.size.saturating_sub(self.threshold)
+                ..=self.size + self.threshold)
+                .contains(&size),
+        }
+    }
+
+    /// DirectorySizeDistance, in device pixels
+    fn distance(&self, pixels: u32) -> u32 {
+        let (low, high) = match self.size_type {
+            SizeType::Fixed => (self.size, self.size),
+            SizeType::Scalable => (self.min_size, self.max_size),
+            SizeType::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+        let (low, high) = (low * self.scale, high * self.scale);
+        if pixels < low {
+            low - pixels
+        } else {
+            pixels.saturating_sub(high)
+        }
+    }
+}
+
+/// A theme's index.theme, and every base directory it has files in
+struct Theme {
+    roots: Vec<PathBuf>,
+    inherits: Vec<String>,
+    dirs: Vec<ThemeDir>,
+}
+
+/// Split an index.theme list (comma-separated)
+fn comma_list(value: Option<&String>) -> Vec<String> {
+    value
+        .map(|v| {
+            v.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl Theme {
+    /// Load the theme `name`: its first index.theme found describes it
+    fn load(name: &str, search: &IconSearch) -> Option<Self> {
+        let roots: Vec<PathBuf> = search
+            .base_dirs
+            .iter()
+            .map(|base| base.join(name))
+            .filter(|dir| dir.is_dir())
+            .collect();
+        let contents = roots
+            .iter()
+            .find_map(|root| fs::read_to_string(root.join("index.theme")).ok())?;
+        let groups = parse_key_file(&contents);
+        let header = groups.get("Icon Theme")?;
+        let mut dir_names = comma_list(header.get("Directories"));
+        dir_names.extend(comma_list(header.get("ScaledDirectories")));
+        let mut seen = HashSet::new();
+        let dirs = dir_names
+            .iter()
+            .filter(|dir| seen.insert(dir.as_str()))
+            .filter_map(|dir| ThemeDir::parse(dir, groups.get(dir)?))
+            .collect();
+        Some(Self {
+            roots,
+            inherits: comma_list(header.get("Inherits")),
+            dirs,
+        })
+    }
+
+    /// The icon's file in `dir`, in any base directory and format
+    fn file_in(&self, dir: &ThemeDir, icon: &str) -> Option<PathBuf> {
+        self.roots.iter().find_map(|root| {
+            EXTENSIONS
+                .iter()
+                .map(|ext| root.join(&dir.name).join(format!("{icon}.{ext}")))
+                .find(|file| file.is_file())
+        })
+    }
+
+    /// LookupIcon: an exact size match, else the closest one
+    fn lookup(&self, icon: &str, pixels: u32) -> Option<PathBuf> {
+        if let Some(file) = self
+            .dirs
+            .iter()
+            .filter(|dir| dir.matches(pixels))
+            .find_map(|dir| self.file_in(dir, icon))
+        {
+            return Some(file);
+        }
+        self.dirs
+            .iter()
+            .filter_map(|dir| Some((dir.distance(pixels), self.file_in(dir, icon)?)))
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, file)| file)
+    }
+}
+
+/// The themes icons are searched in, loaded: the user's theme, what it
+/// inherits (depth first, as FindIconHelper walks it), then hicolor
+pub(crate) struct IconThemes {
+    themes: Vec<Theme>,
+    pixmaps: PathBuf,
+}
+
+impl IconThemes {
+    /// Load `theme` and everything it inherits
+    pub(crate) fn resolve(theme: &str, search: &IconSearch) -> Self {
+        let mut themes = Vec::new();
+        let mut visited = HashSet::new();
+        for name in [theme, FALLBACK_THEME] {
+            Self::load_chain(name, search, &mut visited, &mut themes);
+        }
+        Self {
+            themes,
+            pixmaps: search.pixmaps.clone(),
+        }
+    }
+
+    fn load_chain(
+        name: &str,
+        search: &IconSearch,
+        visited: &mut HashSet<String>,
+        themes: &mut Vec<Theme>,
+    ) {
+        if visited.len() > MAX_INHERIT_DEPTH || !visited.insert(name.to_string()) {
+            return;
+        }
+        let Some(theme) = Theme::load(name, search) else {
+            return;
+        };
+        let inherits = theme.inherits.clone();
+        themes.push(theme);
+        for parent in &inherits {
+            Self::load_chain(parent, search, visited, themes);
+        }
+    }
+
+    /// FindIcon: the icon named `icon` at `pixels` device pixels, from the
+    /// first theme in the chain that has it, else the pixmaps directory
+    pub(crate) fn find_icon(&self, icon: &str, pixels: u32) -> Option<PathBuf> {
+        // Some entries still name the file ("foo.png"), which the spec
+        // deprecates; the lookup wants the bare name
+        let icon = EXTENSIONS
+            .iter()
+            .chain(["xpm"].iter())
+            .find_map(|ext| icon.strip_suffix(&format!(".{ext}")))
+            .unwrap_or(icon);
+        self.themes
+            .iter()
+            .find_map(|theme| theme.lookup(icon, pixels))
+            .or_else(|| {
+                EXTENSIONS
+                    .iter()
+                    .map(|ext| self.pixmaps.join(format!("{icon}.{ext}")))
+                    .find(|file| file.is_file())
+            })
+    }
+}
+
+/// The user's theme chain, shared by every icon rendered until the next
+/// scan: resolved on first use, dropped by forget_icon_themes
+static ICON_THEMES: Mutex<Option<Arc<IconThemes>>> = Mutex::new(None);
+
+/// Drop the resolved theme chain, so a theme switched (or installed)
+/// since is picked up. Called as each scan starts.
+pub(crate) fn forget_icon_themes() {
+    *ICON_THEMES.lock().unwrap_or_else(|p| p.into_inner()) = None;
+}
+
+fn icon_themes() -> Arc<IconThemes> {
+    ICON_THEMES
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .get_or_insert_with(|| {
+            let search = IconSearch::new(&DiscoveryRoots::system());
+            Arc::new(IconThemes::resolve(&current_icon_theme(), &search))
+        })
+        .clone()
+}
+
+/// The first value of `key` in a settings file's lines ("key=value")
+fn setting_in(path: &Path, key: &str) -> Option<String> {
+    let contents = fs::read_to_string(path).ok()?;
+    contents.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        (k.trim() == key)
+            .then(|| v.trim().trim_matches('"').to_string())
+            .filter(|v| !v.is_empty())
+    })
+}
+
+/// The user's icon theme: GTK's settings.ini, KDE's kdeglobals, then
+/// GNOME's setting; hicolor when none names one
+pub(crate) fn current_icon_theme() -> String {
+    let config = dirs::config_dir();
+    config
+        .as_ref()
+        .and_then(|dir| setting_in(&dir.join("gtk-3.0/settings.ini"), "gtk-icon-theme-name"))
+        .or_else(|| {
+            let kdeglobals = config.as_ref()?.join("kdeglobals");
+            let groups = parse_key_file(&fs::read_to_string(kdeglobals).ok()?);
+            groups.get("Icons")?.get("Theme").cloned()
+        })
+        .or_else(|| {
+            let output = Command::new("gsettings")
+                .args(["get", "org.gnome.desktop.interface", "icon-theme"])
+                .output()
+                .ok()?;
+            let theme = String::from_utf8(output.stdout).ok()?;
+            let theme = theme.trim().trim_matches('\'');
+            (output.status.success() && !theme.is_empty()).then(|| theme.to_string())
+        })
+        .unwrap_or_else(|| FALLBACK_THEME.to_string())
+}
+
+/// Rasterize an SVG to a `size`×`size` PNG, scaled to fit and centered
+fn render_svg(data: &[u8], size: u32) -> Option<Vec<u8>> {
+    use resvg::{tiny_skia, usvg};
+
+    let tree = usvg::Tree::from_data(data, &usvg::Options::default()).ok()?;
+    let mut pixmap = tiny_skia::Pixmap::new(size, size)?;
+    let (width, height) = (tree.size().width(), tree.size().height());
+    let scale = (size as f32 / width).min(size as f32 / height);
+    let transform = tiny_skia::Transform::from_scale(scale, scale).post_translate(
+        (size as f32 - width * scale) / 2.0,
+        (size as f32 - height * scale) / 2.0,
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+    pixmap.encode_png().ok()
+}
+
+/// PNG bytes of an icon file's contents: a PNG as it is, an SVG rendered
+/// at `size`
+pub(crate) fn render_icon_data(data: &[u8], size: u32) -> Option<Vec<u8>> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Some(data.to_vec());
+    }
+    render_svg(data, size)
+}
+
+/// PNG bytes of a desktop entry's icon at `size` pixels: its Icon key, a
+/// file path or a name looked up in the user's icon theme
+pub(crate) fn desktop_entry_icon(path: &Path, size: u32) -> Option<Vec<u8>> {
+    let icon = DesktopEntry::read(path)?
+        .string("Icon")
+        .filter(|icon| !icon.is_empty())?;
+    let file = if icon.starts_with('/') {
+        PathBuf::from(icon)
+    } else {
+        icon_themes().find_icon(&icon, size)?
+    };
+    render_icon_data(&fs::read(file).ok()?, size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Icon theme trees under a temporary directory: `usr` stands in for
+    /// /usr/share/icons, `home` for ~/.icons
+    struct Fixture {
+        dir: tempfile::TempDir,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            Self {
+                dir: tempfile::tempdir().unwrap(),
+            }
+        }
+
+        fn path(&self, relative: &str) -> PathBuf {
+            self.dir.path().join(relative)
+        }
+
+        fn search(&self) -> IconSearch {
+            IconSearch {
+                base_dirs: vec![self.path("home"), self.path("usr")],
+                pixmaps: self.path("pixmaps"),
+            }
+        }
+
+        /// A theme's index.theme in `base`, its directories given as
+        /// (name, keys) groups
+        fn theme(&self, base: &str, name: &str, inherits: &str, dirs: &[(&str, &str)]) {
+            let root = self.path(&format!("{base}/{name}"));
+            fs::create_dir_all(&root).unwrap();
+            let names: Vec<&str> = dirs.iter().map(|(dir, _)| *dir).collect();
+            let mut index = format!(
+                "[Icon Theme]\nName={name}\nInherits={inherits}\nDirectories={}\n",
+                names.join(",")
+            );
+            for (dir, keys) in dirs {
+                index.push_str(&format!("\n[{dir}]\n{keys}\n"));
+            }
+            fs::write(root.join("index.theme"), index).unwrap();
+        }
+
+        /// An icon file at `relative`; returns its path
+        fn icon(&self, relative: &str) -> PathBuf {
+            let file = self.path(relative);
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(&file, b"icon").unwrap();
+            file
+        }
+
+        fn find(&self, theme: &str, icon: &str, pixels: u32) -> Option<PathBuf> {
+            IconThemes::resolve(theme, &self.search()).find_icon(icon, pixels)
+        }
+    }
+
+    const FIXED_48: (&str, &str) = ("48x48/apps", "Size=48\nType=Fixed");
+    const FIXED_64: (&str, &str) = ("64x64/apps", "Size=64\nType=Fixed");
+
+    #[test]
+    fn user_theme_wins_over_what_it_inherits() {
+        let fixture = Fixture::new();
+        fixture.theme("usr", "Papirus", "breeze", &[FIXED_48]);
+        fixture.theme("usr", "breeze", "", &[FIXED_48]);
+        let own = fixture.icon("usr/Papirus/48x48/apps/firefox.png");
+        fixture.icon("usr/breeze/48x48/apps/firefox.png");
+        let inherited = fixture.icon("usr/breeze/48x48/apps/gimp.svg");
+
+        assert_eq!(fixture.find("Papirus", "firefox", 48), Some(own));
+        assert_eq!(fixture.find("Papirus", "gimp", 48), Some(inherited));
+    }
+
+    #[test]
+    fn inherits_depth_first() {
+        let fixture = Fixture::new();
+        fixture.theme("usr", "Mine", "Middle,Other", &[FIXED_48]);
+        fixture.theme("usr", "Middle", "Deep", &[FIXED_48]);
+        fixture.theme("usr", "Deep", "", &[FIXED_48]);
+        fixture.theme("usr", "Other", "", &[FIXED_48]);
+        let deep = fixture.icon("usr/Deep/48x48/apps/app.png");
+        fixture.icon("usr/Other/48x48/apps/app.png");
+
+        assert_eq!(fixture.find("Mine", "app", 48), Some(deep));
+    }
+
+    #[test]
+    fn falls_back_to_hicolor_then_pixmaps() {
+        let fixture = Fixture::new();
+        fixture.theme("usr", "Adwaita", "", &[FIXED_48]);
+        fixture.theme("usr", "hicolor", "", &[FIXED_48]);
+        let hicolor = fixture.icon("usr/hicolor/48x48/apps/vlc.png");
+        let pixmap = fixture.icon("pixmaps/xterm.png");
+
+        assert_eq!(fixture.find("Adwaita", "vlc", 48), Some(hicolor.clone()));
+        // An unknown theme still reaches hicolor
+        assert_eq!(fixture.find("Missing", "vlc", 48), Some(hicolor));
+        assert_eq!(fixture.find("Adwaita", "xterm", 48), Some(pixmap.clone()));
+        // A file name in Icon= is looked up by its bare name
+        assert_eq!(fixture.find("Adwaita", "xterm.png", 48), Some(pixmap));
+        assert_eq!(fixture.find("Adwaita", "nothing", 48), None);
+    }
+
+    #[test]
+    fn stops_at_circular_inheritance() {
+        let fixture = Fixture::new();
+        fixture.theme("usr", "A", "B", &[FIXED_48]);
+        fixture.theme("usr", "B", "A", &[FIXED_48]);
+        let icon = fixture.icon("usr/B/48x48/apps/app.png");
+
+        let themes = IconThemes::resolve("A", &fixture.search());
+        assert_eq!(themes.themes.len(), 2);
+        assert_eq!(themes.find_icon("app", 48), Some(icon));
+    }
+
+    #[test]
+    fn theme_spans_base_directories() {
+        let fixture = Fixture::new();
+        fixture.theme("usr", "breeze", "", &[FIXED_48]);
+        let own = fixture.icon("home/breeze/48x48/apps/app.png");
+        fixture.icon("usr/breeze/48x48/apps/app.png");
+        let system = fixture.icon("usr/breeze/48x48/apps/other.png");
+
+        assert_eq!(fixture.find("breeze", "app", 48), Some(own));
+        assert_eq!(fixture.find("breeze", "other", 48), Some(system));
+    }
+
+    #[test]
+    fn threshold_match_beats_a_closer_listed_directory() {
+        let fixture = Fixture::new();
+        fixture.theme(
+            "usr",
+            "hicolor",
+            "",
+            &[
+                FIXED_64,
+                ("48x48/apps", "Size=48\nType=Threshold\nThreshold=4"),
+            ],
+        );
+        fixture.icon("usr/hicolor/64x64/apps/app.png");
+        let threshold = fixture.icon("usr/hicolor/48x48/apps/app.png");
+
+        // 44..=52 match the 48 directory outright
+        assert_eq!(fixture.find("hicolor", "app", 52), Some(threshold.clone()));
+        assert_eq!(fixture.find("hicolor", "app", 44), Some(threshold.clone()));
+        // Past it, the closer directory wins: 60 is 8 from 52, 4 from 64
+        assert_eq!(
+            fixture.find("hicolor", "app", 60),
+            Some(fixture.path("usr/hicolor/64x64/apps/app.png"))
+        );
+        assert_eq!(fixture.find("hicolor", "app", 16), Some(threshold));
+    }
+
+    #[test]
+    fn scalable_covers_its_size_range() {
+        let fixture = Fixture::new();
+        fixture.theme(
+            "usr",
+            "hicolor",
+            "",
+            &[
+                ("32x32/apps", "Size=32\nType=Fixed"),
+                (
+                    "scalable/apps",
+                    "Size=128\nType=Scalable\nMinSize=16\nMaxSize=256",
+                ),
+            ],
+        );
+        let fixed = fixture.icon("usr/hicolor/32x32/apps/app.png");
+        let scalable = fixture.icon("usr/hicolor/scalable/apps/app.svg");
+
+        assert_eq!(fixture.find("hicolor", "app", 32), Some(fixed));
+        assert_eq!(fixture.find("hicolor", "app", 256), Some(scalable.clone()));
+        // Beyond MaxSize it is still the closest
+        assert_eq!(fixture.find("hicolor", "app", 512), Some(scalable));
+    }
+
+    #[test]
+    fn hidpi_directories_serve_device_pixels() {
+        let fixture = Fixture::new();
+        fixture.theme(
+            "usr",
+            "hicolor",
+            "",
+            &[FIXED_64, ("48x48@2/apps", "Size=48\nScale=2\nType=Fixed")],
+        );
+        fixture.icon("usr/hicolor/64x64/apps/app.png");
+        let hidpi = fixture.icon("usr/hicolor/48x48@2/apps/app.png");
+
+        assert_eq!(fixture.find("hicolor", "app", 96), Some(hidpi));
+        // 48 device pixels aren't a 48@2 icon (that is 96): 64 is closer
+        assert_eq!(
+            fixture.find("hicolor", "app", 48),
+            Some(fixture.path("usr/hicolor/64x64/apps/app.png"))
+        );
+    }
+
+    #[test]
+    fn prefers_png_to_svg_in_one_directory() {
+        let fixture = Fixture::new();
+        fixture.theme("usr", "hicolor", "", &[FIXED_48]);
+        let png = fixture.icon("usr/hicolor/48x48/apps/app.png");
+        fixture.icon("usr/hicolor/48x48/apps/app.svg");
+
+        assert_eq!(fixture.find("hicolor", "app", 48), Some(png));
+    }
+}
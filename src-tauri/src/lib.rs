@@ -1,10 +1,36 @@
+mod app_details;
 mod app_discovery;
 mod app_menu;
+mod app_sources;
+mod appimage;
+mod asset_catalog;
+mod bookmark;
+mod bundle_audit;
+mod bundle_info;
+mod bundle_size;
+mod category;
+mod codesign;
 mod commands;
 mod config;
+mod desktop_entry;
+mod desktop_launch;
+mod discovery_index;
 mod dock_drag;
+mod duplicates;
+#[cfg(target_os = "linux")]
+mod file_manager;
+mod finder_tags;
+mod icns;
 mod icon_cache;
+mod icon_theme;
+mod macho;
+mod placement;
+mod quarantine;
+mod seen_apps;
+mod squashfs;
 mod window;
+mod xattr;
+mod xdg_discovery;
 
 use serde::Serialize;
 use tauri::menu::{AboutMetadata, Menu, MenuItem, PredefinedMenuItem, Submenu};
@@ -98,15 +124,25 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             commands::get_apps,
+            commands::refresh_apps,
             commands::get_app_icon,
             commands::launch_app,
+            commands::mark_all_apps_seen,
             commands::reveal_app,
-            commands::show_get_info,
+            commands::get_app_details,
+            commands::audit_apps,
             commands::quick_look,
+            commands::show_origin,
             commands::show_window,
             commands::load_config,
             commands::update_order,
             commands::set_layout,
+            commands::set_toggle,
+            commands::set_duplicate_policy,
+            commands::set_placement_policy,
+            commands::set_ghost_grace_period,
+            commands::reconcile_layout,
+            commands::suggest_folder_name,
             commands::quit_app,
             commands::quit_after_delay,
             dock_drag::get_dock_drag_zone,
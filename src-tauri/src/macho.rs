@@ -0,0 +1,275 @@
+//! Minimal Mach-O header parsing, in pure Rust: which CPU architectures an
+//! executable carries, thin or fat (universal). Reads headers only, never
+//! the code, so it is cheap enough to run per bundle during discovery.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+const MH_MAGIC: u32 = 0xfeed_face;
+const MH_MAGIC_64: u32 = 0xfeed_facf;
+const FAT_MAGIC: u32 = 0xcafe_babe;
+const FAT_MAGIC_64: u32 = 0xcafe_babf;
+
+const CPU_ARCH_ABI64: i32 = 0x0100_0000;
+const CPU_TYPE_X86: i32 = 7;
+const CPU_TYPE_X86_64: i32 = CPU_TYPE_X86 | CPU_ARCH_ABI64;
+const CPU_TYPE_ARM: i32 = 12;
+const CPU_TYPE_ARM64: i32 = CPU_TYPE_ARM | CPU_ARCH_ABI64;
+
+/// Fat headers list a handful of slices; anything past this is not a
+/// Mach-O (Java class files share FAT_MAGIC and store a version there)
+const MAX_FAT_ARCHS: u32 = 32;
+
+/// Bytes read from the start of an executable: the fat header with room
+/// for MAX_FAT_ARCHS 64-bit entries, or a thin Mach-O header
+const HEADER_READ_LEN: usize = 8 + MAX_FAT_ARCHS as usize * 32;
+
+/// One architecture slice of a Mach-O file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Slice {
+    /// cputype from the Mach-O or fat header
+    pub cpu_type: i32,
+    /// Offset of the slice's own Mach-O header in the file
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// What a bundle's main executable runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Architecture {
+    #[serde(rename = "arm64")]
+    Arm64,
+    /// Intel only: needs Rosetta on Apple Silicon
+    #[serde(rename = "x86_64")]
+    X86_64,
+    /// Both arm64 and x86_64 slices
+    #[serde(rename = "universal")]
+    Universal,
+    /// Neither (32-bit Intel, PowerPC): runs nowhere current
+    #[serde(rename = "other")]
+    Other,
+}
+
+fn read_u32(bytes: &[u8], at: usize, big_endian: bool) -> Option<u32> {
+    let raw: [u8; 4] = bytes.get(at..at + 4)?.try_into().ok()?;
+    Some(if big_endian {
+        u32::from_be_bytes(raw)
+    } else {
+        u32::from_le_bytes(raw)
+    })
+}
+
+fn read_u64_be(bytes: &[u8], at: usize) -> Option<u64> {
+    let raw: [u8; 8] = bytes.get(at..at + 8)?.try_into().ok()?;
+    Some(u64::from_be_bytes(raw))
+}
+
+/// Architecture slices of a Mach-O file from its leading bytes (at least
+/// the fat header and its arch table). `file_len` sizes a thin file's one
+/// slice. None when the bytes are not a Mach-O file.
+pub(crate) fn parse_slices(header: &[u8], file_len: u64) -> Option<Vec<Slice>> {
+    // Fat headers are always big-endian
+    let fat_magic = read_u32(header, 0, true)?;
+    if fat_magic == FAT_MAGIC || fat_magic == FAT_MAGIC_64 {
+        let is_64 = fat_magic == FAT_MAGIC_64;
+        let count = read_u32(header, 4, true)?;
+        if count == 0 || count > MAX_FAT_ARCHS {
+            return None;
+        }
+        let entry_len = if is_64 { 32 } else { 20 };
+        return (0..count as usize)
+            .map(|i| {
+                let at = 8 + i * entry_len;
+                let cpu_type = read_u32(header, at, true)? as i32;
+                let (offset, size) = if is_64 {
+                    (read_u64_be(header, at + 8)?, read_u64_be(header, at + 16)?)
+                } else {
+                    (
+                        u64::from(read_u32(header, at + 8, true)?),
+                        u64::from(read_u32(header, at + 12, true)?),
+                    )
+                };
+                Some(Slice {
+                    cpu_type,
+                    offset,
+                    size,
+                })
+            })
+            .collect();
+    }
+
+    // Thin: the magic's byte order tells the header's (PowerPC files are
+    // big-endian, everything since little-endian)
+    let big_endian = match read_u32(header, 0, false)? {
+        MH_MAGIC | MH_MAGIC_64 => false,
+        magic if magic.swap_bytes() == MH_MAGIC || magic.swap_bytes() == MH_MAGIC_64 => true,
+        _ => return None,
+    };
+    let cpu_type = read_u32(header, 4, big_endian)? as i32;
+    Some(vec![Slice {
+        cpu_type,
+        offset: 0,
+        size: file_len,
+    }])
+}
+
+/// Classify a set of slices
+pub(crate) fn architecture_of(slices: &[Slice]) -> Architecture {
+    let has = |cpu| slices.iter().any(|s| s.cpu_type == cpu);
+    match (has(CPU_TYPE_ARM64), has(CPU_TYPE_X86_64)) {
+        (true, true) => Architecture::Universal,
+        (true, false) => Architecture::Arm64,
+        (false, true) => Architecture::X86_64,
+        (false, false) => Architecture::Other,
+    }
+}
+
+/// Read the slices of the Mach-O file at `path`
+pub(crate) fn read_slices(path: &Path) -> Option<Vec<Slice>> {
+    let mut file = File::open(path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+    let mut header = Vec::with_capacity(HEADER_READ_LEN);
+    file.by_ref()
+        .take(HEADER_READ_LEN as u64)
+        .read_to_end(&mut header)
+        .ok()?;
+    parse_slices(&header, file_len)
+}
+
+/// Architecture of the Mach-O executable at `path`; None when it can't be
+/// read or isn't Mach-O (e.g. a shell script as CFBundleExecutable)
+pub(crate) fn read_architecture(path: &Path) -> Option<Architecture> {
+    read_slices(path).map(|slices| architecture_of(&slices))
+}
+
+/// Whether this Mac runs Intel-only code through Rosetta
+pub(crate) fn host_needs_rosetta_for_intel() -> bool {
+    cfg!(all(target_os = "macos", target_arch = "aarch64"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A thin 64-bit little-endian Mach-O header for `cpu_type`
+    fn thin(cpu_type: i32) -> Vec<u8> {
+        let mut header = MH_MAGIC_64.to_le_bytes().to_vec();
+        header.extend_from_slice(&cpu_type.to_le_bytes());
+        header.extend_from_slice(&[0; 24]);
+        header
+    }
+
+    /// A fat header listing `slices` (cpu type, offset, size)
+    fn fat(is_64: bool, slices: &[(i32, u64, u64)]) -> Vec<u8> {
+        let magic = if is_64 { FAT_MAGIC_64 } else { FAT_MAGIC };
+        let mut header = magic.to_be_bytes().to_vec();
+        header.extend_from_slice(&(slices.len() as u32).to_be_bytes());
+        for &(cpu_type, offset, size) in slices {
+            header.extend_from_slice(&cpu_type.to_be_bytes());
+            header.extend_from_slice(&0u32.to_be_bytes());
+            if is_64 {
+                header.extend_from_slice(&offset.to_be_bytes());
+                header.extend_from_slice(&size.to_be_bytes());
+                header.extend_from_slice(&[0; 8]);
+            } else {
+                header.extend_from_slice(&(offset as u32).to_be_bytes());
+                header.extend_from_slice(&(size as u32).to_be_bytes());
+                header.extend_from_slice(&[0; 4]);
+            }
+        }
+        header
+    }
+
+    fn architecture(header: &[u8]) -> Option<Architecture> {
+        parse_slices(header, 1000).map(|slices| architecture_of(&slices))
+    }
+
+    #[test]
+    fn thin_executables() {
+        assert_eq!(
+            architecture(&thin(CPU_TYPE_ARM64)),
+            Some(Architecture::Arm64)
+        );
+        assert_eq!(
+            architecture(&thin(CPU_TYPE_X86_64)),
+            Some(Architecture::X86_64)
+        );
+        assert_eq!(
+            parse_slices(&thin(CPU_TYPE_ARM64), 1000),
+            Some(vec![Slice {
+                cpu_type: CPU_TYPE_ARM64,
+                offset: 0,
+                size: 1000,
+            }])
+        );
+        // 32-bit PowerPC: big-endian, runs nowhere current
+        let mut ppc = MH_MAGIC.to_be_bytes().to_vec();
+        ppc.extend_from_slice(&18i32.to_be_bytes());
+        assert_eq!(architecture(&ppc), Some(Architecture::Other));
+    }
+
+    #[test]
+    fn fat_executables() {
+        let universal = [
+            (CPU_TYPE_X86_64, 0x4000, 100),
+            (CPU_TYPE_ARM64, 0x8000, 200),
+        ];
+        assert_eq!(
+            architecture(&fat(false, &universal)),
+            Some(Architecture::Universal)
+        );
+        assert_eq!(
+            parse_slices(&fat(false, &universal), 1000).unwrap()[1],
+            Slice {
+                cpu_type: CPU_TYPE_ARM64,
+                offset: 0x8000,
+                size: 200,
+            }
+        );
+
+        let large = [(CPU_TYPE_ARM64, 0x1_0000_0000, 0x2_0000_0000)];
+        assert_eq!(
+            parse_slices(&fat(true, &large), 1000),
+            Some(vec![Slice {
+                cpu_type: CPU_TYPE_ARM64,
+                offset: 0x1_0000_0000,
+                size: 0x2_0000_0000,
+            }])
+        );
+        let intel = [(CPU_TYPE_X86, 0x1000, 10), (CPU_TYPE_X86_64, 0x2000, 10)];
+        assert_eq!(architecture(&fat(true, &intel)), Some(Architecture::X86_64));
+    }
+
+    #[test]
+    fn truncated_headers_are_rejected() {
+        assert_eq!(parse_slices(&thin(CPU_TYPE_ARM64)[..6], 1000), None);
+        let universal = fat(false, &[(CPU_TYPE_X86_64, 0, 1), (CPU_TYPE_ARM64, 0, 1)]);
+        assert_eq!(parse_slices(&universal[..universal.len() - 12], 1000), None);
+        assert_eq!(parse_slices(&[], 0), None);
+    }
+
+    #[test]
+    fn non_mach_o_files_are_rejected() {
+        assert_eq!(parse_slices(b"#!/bin/sh\nexec foo\n", 20), None);
+        assert_eq!(parse_slices(b"\x7fELF\x02\x01\x01\0", 8), None);
+        // A Java class file: FAT_MAGIC, then a version where the count is
+        let class = [0xca, 0xfe, 0xba, 0xbe, 0x00, 0x00, 0x00, 0x34];
+        assert_eq!(parse_slices(&class, 8), None);
+        assert_eq!(parse_slices(&fat(false, &[]), 8), None);
+    }
+
+    #[test]
+    fn reads_slices_from_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Test");
+        let mut contents = thin(CPU_TYPE_X86_64);
+        contents.resize(5000, 0);
+        std::fs::write(&path, &contents).unwrap();
+        assert_eq!(read_architecture(&path), Some(Architecture::X86_64));
+        assert_eq!(read_slices(&path).unwrap()[0].size, 5000);
+        assert_eq!(read_architecture(&dir.path().join("Missing")), None);
+    }
+}
@@ -0,0 +1,656 @@
+//! Reconciling the saved arrangement with what is installed, and where
+//! the items new to it land (the PlacementPolicy).
+//!
+//! Saved pages keep their items in place, minus ids that no longer exist,
+//! apps that live inside folders and repeats (every id appears once: the
+//! frontend's keys and drag indices depend on it). Folders not on a page
+//! go after the last item; apps are placed by the policy, each placement
+//! reported so the frontend can animate the arrival.
+//!
+//! An app that went missing leaves a ghost holding its slot (see Ghost):
+//! when the same app turns up again, by path or bundle identifier, it goes
+//! back to that exact slot instead of being placed as new.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::config::{
+    FolderMetadata, Ghost, GhostSlot, OrderConfig, Placement, PlacementPolicy, ReconciledLayout,
+};
+
+/// What reconciliation knows about one installed app
+#[derive(Debug, Default)]
+pub(crate) struct AppFacts {
+    pub identifier: Option<String>,
+    /// Folder names it belongs in (its Finder tags, then its category),
+    /// for the CategoryFolder policy
+    pub folder_names: Vec<String>,
+}
+
+/// The installed apps reconciliation works against
+pub(crate) struct Installed<'a> {
+    /// Main-grid apps: the ones placed on pages
+    pub app_paths: &'a [String],
+    /// Apps of physical folders, which stay in folders and never go on a
+    /// page
+    pub folder_app_paths: &'a [String],
+    /// Facts by path, where known
+    pub facts: &'a HashMap<String, AppFacts>,
+}
+
+impl Installed<'_> {
+    fn identifier(&self, path: &str) -> Option<&str> {
+        self.facts.get(path)?.identifier.as_deref()
+    }
+}
+
+/// The layout being reconciled, and the placements made so far
+struct Layout {
+    pages: Vec<Vec<String>>,
+    folders: Vec<FolderMetadata>,
+    /// Items a page holds before it is full
+    capacity: usize,
+    /// Page opened for this batch by the NewPage policy
+    new_page: Option<usize>,
+    placements: Vec<Placement>,
+}
+
+impl Layout {
+    fn insert(&mut self, id: &str, page: usize, slot: usize) {
+        while self.pages.len() <= page {
+            self.pages.push(Vec::new());
+        }
+        let slot = slot.min(self.pages[page].len());
+        self.pages[page].insert(slot, id.to_string());
+        self.placements.push(Placement::Page {
+            id: id.to_string(),
+            page,
+            slot,
+        });
+    }
+
+    fn put(&mut self, id: &str, page: usize) {
+        self.insert(id, page, usize::MAX);
+    }
+
+    /// After the last item, on a new page when the last one is full (the
+    /// frontend's overflow would cascade it there anyway)
+    fn end_of_last_page(&mut self, id: &str) {
+        let page = match self.pages.len() {
+            0 => 0,
+            len if self.pages[len - 1].len() >= self.capacity => len,
+            len => len - 1,
+        };
+        self.put(id, page);
+    }
+
+    fn first_free_slot(&mut self, id: &str) {
+        let page = self
+            .pages
+            .iter()
+            .position(|page| page.len() < self.capacity)
+            .unwrap_or(self.pages.len());
+        self.put(id, page);
+    }
+
+    fn new_page(&mut self, id: &str) {
+        let page = match self.new_page {
+            Some(page) if self.pages[page].len() < self.capacity => page,
+            _ => self.pages.len(),
+        };
+        self.new_page = Some(page);
+        self.put(id, page);
+    }
+
+    fn add_to_folder(&mut self, id: &str, index: usize, position: usize) {
+        let folder = &mut self.folders[index];
+        let position = position.min(folder.app_paths.len());
+        folder.app_paths.insert(position, id.to_string());
+        self.placements.push(Placement::Folder {
+            id: id.to_string(),
+            folder_id: folder.id.clone(),
+        });
+    }
+
+    /// Into the first folder named like one of `names` (case-insensitive)
+    fn add_to_named_folder(&mut self, id: &str, names: &[String]) -> bool {
+        let Some(index) = names.iter().find_map(|name| {
+            let name = name.to_lowercase();
+            self.folders
+                .iter()
+                .position(|folder| folder.name.to_lowercase() == name)
+        }) else {
+            return false;
+        };
+        self.add_to_folder(id, index, usize::MAX);
+        true
+    }
+
+    /// Back into a ghost's slot; false when the slot is gone (its page or
+    /// folder no longer exists)
+    fn restore(&mut self, id: &str, slot: &GhostSlot) -> bool {
+        match slot {
+            GhostSlot::Page { page, slot } if *page < self.pages.len() => {
+                self.insert(id, *page, *slot);
+                true
+            }
+            GhostSlot::Page { .. } => false,
+            GhostSlot::Folder { folder_id, index } => {
+                match self.folders.iter().position(|f| f.id == *folder_id) {
+                    Some(folder) => {
+                        self.add_to_folder(id, folder, *index);
+                        true
+                    }
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+/// Record that the app at `path` went missing from `slot`. An app already
+/// missing keeps the time it first went.
+fn bury(ghosts: &mut Vec<Ghost>, path: &str, identifier: Option<&str>, slot: GhostSlot, now: u64) {
+    if let Some(ghost) = ghosts.iter_mut().find(|ghost| ghost.path == path) {
+        ghost.slot = slot;
+        if ghost.identifier.is_none() {
+            ghost.identifier = identifier.map(str::to_string);
+        }
+        return;
+    }
+    ghosts.push(Ghost {
+        path: path.to_string(),
+        identifier: identifier.map(str::to_string),
+        slot,
+        missing_since: now,
+    });
+}
+
+/// Reconcile the `saved` arrangement with the `installed` apps, placing
+/// what's new by `policy`. `capacity` is how many items fit a page;
+/// without it, the fullest saved page stands in. Apps that went missing
+/// become ghosts, ghosts whose app is back are restored: the updated
+/// ghosts are returned alongside the layout.
+pub(crate) fn reconcile(
+    saved: OrderConfig,
+    installed: &Installed,
+    capacity: Option<usize>,
+    policy: PlacementPolicy,
+    now: u64,
+) -> (ReconciledLayout, Vec<Ghost>) {
+    let OrderConfig {
+        pages: saved_pages,
+        mut folders,
+        mut ghosts,
+        ..
+    } = saved;
+
+    // Folders keep the apps still installed; the others become ghosts, and
+    // a folder left empty goes once none of them holds a place in it
+    let installed_paths: HashSet<&str> = installed
+        .app_paths
+        .iter()
+        .chain(installed.folder_app_paths)
+        .map(String::as_str)
+        .collect();
+    for folder in &mut folders {
+        let mut index = 0;
+        folder.app_paths.retain(|path| {
+            let keep = installed_paths.contains(path.as_str());
+            if !keep {
+                let slot = GhostSlot::Folder {
+                    folder_id: folder.id.clone(),
+                    index,
+                };
+                bury(&mut ghosts, path, installed.identifier(path), slot, now);
+            }
+            index += 1;
+            keep
+        });
+    }
+    folders.retain(|folder| {
+        !folder.app_paths.is_empty()
+            || ghosts.iter().any(|ghost| {
+                matches!(&ghost.slot, GhostSlot::Folder { folder_id, .. } if *folder_id == folder.id)
+            })
+    });
+
+    let folder_contained: HashSet<String> = folders
+        .iter()
+        .flat_map(|folder| folder.app_paths.iter().cloned())
+        .collect();
+    let apps: Vec<&str> = installed
+        .app_paths
+        .iter()
+        .map(String::as_str)
+        .filter(|path| !folder_contained.contains(*path))
+        .collect();
+    let folder_ids: Vec<String> = folders.iter().map(|folder| folder.id.clone()).collect();
+    let known: HashSet<&str> = apps
+        .iter()
+        .copied()
+        .chain(folder_ids.iter().map(String::as_str))
+        .collect();
+
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut pages: Vec<Vec<String>> = Vec::with_capacity(saved_pages.len());
+    for (page_index, page) in saved_pages.into_iter().enumerate() {
+        let mut kept = Vec::with_capacity(page.len());
+        for (slot, id) in page.into_iter().enumerate() {
+            if known.contains(id.as_str()) {
+                if seen.insert(id.clone()) {
+                    kept.push(id);
+                }
+                continue;
+            }
+            // App ids are bundle paths; anything else (a folder that no
+            // longer exists) just goes, as do apps now in a folder
+            if Path::new(&id).is_absolute() && !folder_contained.contains(&id) {
+                let slot = GhostSlot::Page {
+                    page: page_index,
+                    slot,
+                };
+                bury(&mut ghosts, &id, installed.identifier(&id), slot, now);
+            }
+        }
+        pages.push(kept);
+    }
+    let capacity = capacity
+        .or_else(|| pages.iter().map(Vec::len).max())
+        .filter(|&capacity| capacity > 0)
+        .unwrap_or(usize::MAX);
+
+    // Apps back from missing, with the slot they go back to. Apps of
+    // physical folders only ever go back into a folder.
+    let new_apps: Vec<&str> = apps
+        .iter()
+        .copied()
+        .filter(|id| !seen.contains(*id))
+        .collect();
+    let returning_folder_apps = installed
+        .folder_app_paths
+        .iter()
+        .map(String::as_str)
+        .filter(|path| !folder_contained.contains(*path));
+    let mut restored: Vec<(&str, GhostSlot)> = Vec::new();
+    for (id, on_grid) in new_apps
+        .iter()
+        .map(|id| (*id, true))
+        .chain(returning_folder_apps.map(|id| (id, false)))
+    {
+        let identifier = installed.identifier(id);
+        let Some(index) = ghosts.iter().position(|ghost| {
+            let same = ghost.path == id
+                || (identifier.is_some() && ghost.identifier.as_deref() == identifier);
+            same && (on_grid || matches!(ghost.slot, GhostSlot::Folder { .. }))
+        }) else {
+            continue;
+        };
+        restored.push((id, ghosts.remove(index).slot));
+    }
+    // In slot order, so each lands where it was among the others
+    restored.sort_by(|(_, a), (_, b)| a.cmp(b));
+
+    let mut layout = Layout {
+        pages,
+        folders,
+        capacity,
+        new_page: None,
+        placements: Vec::new(),
+    };
+    let mut placed: HashSet<&str> = HashSet::new();
+    for (id, slot) in &restored {
+        if layout.restore(id, slot) {
+            placed.insert(id);
+        }
+    }
+    for id in new_apps.iter().filter(|id| !placed.contains(**id)) {
+        match policy {
+            PlacementPolicy::EndOfLastPage => layout.end_of_last_page(id),
+            PlacementPolicy::FirstFreeSlot => layout.first_free_slot(id),
+            PlacementPolicy::NewPage => layout.new_page(id),
+            PlacementPolicy::CategoryFolder => {
+                let names = installed
+                    .facts
+                    .get(*id)
+                    .map(|facts| facts.folder_names.as_slice())
+                    .unwrap_or_default();
+                if !layout.add_to_named_folder(id, names) {
+                    layout.end_of_last_page(id);
+                }
+            }
+        }
+    }
+    for id in folder_ids.iter().filter(|id| !seen.contains(*id)) {
+        layout.end_of_last_page(id);
+    }
+
+    let reconciled = ReconciledLayout {
+        pages: layout.pages,
+        folders: layout.folders,
+        placements: layout.placements,
+    };
+    (reconciled, ghosts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    fn folder(id: &str, apps: &[&str]) -> FolderMetadata {
+        FolderMetadata {
+            id: id.into(),
+            name: "Tools".into(),
+            app_paths: strings(apps),
+            created_at: 0,
+        }
+    }
+
+    fn facts(identifiers: &[(&str, &str)]) -> HashMap<String, AppFacts> {
+        identifiers
+            .iter()
+            .map(|(path, identifier)| {
+                let facts = AppFacts {
+                    identifier: Some(identifier.to_string()),
+                    ..AppFacts::default()
+                };
+                (path.to_string(), facts)
+            })
+            .collect()
+    }
+
+    fn run(
+        saved: OrderConfig,
+        apps: &[&str],
+        facts: &HashMap<String, AppFacts>,
+    ) -> (ReconciledLayout, Vec<Ghost>) {
+        let app_paths = strings(apps);
+        let installed = Installed {
+            app_paths: &app_paths,
+            folder_app_paths: &[],
+            facts,
+        };
+        reconcile(
+            saved,
+            &installed,
+            Some(4),
+            PlacementPolicy::EndOfLastPage,
+            1000,
+        )
+    }
+
+    #[test]
+    fn missing_app_ghost_keeps_its_last_known_identifier() {
+        let saved = OrderConfig {
+            pages: vec![strings(&["/Applications/A.app", "/Applications/Old.app"])],
+            ..OrderConfig::default()
+        };
+        // Old.app is gone from the index; its identifier is only known
+        // from when it was last arranged
+        let known = facts(&[("/Applications/Old.app", "com.example.old")]);
+        let (layout, ghosts) = run(saved, &["/Applications/A.app"], &known);
+        assert_eq!(layout.pages, [strings(&["/Applications/A.app"])]);
+        assert_eq!(ghosts.len(), 1);
+        assert_eq!(ghosts[0].identifier.as_deref(), Some("com.example.old"));
+
+        // The same app turns up at another path: back in its slot
+        let saved = OrderConfig {
+            pages: layout.pages,
+            ghosts,
+            ..OrderConfig::default()
+        };
+        let moved = facts(&[("/Applications/Moved.app", "com.example.old")]);
+        let (layout, ghosts) = run(
+            saved,
+            &["/Applications/A.app", "/Applications/Moved.app"],
+            &moved,
+        );
+        assert_eq!(
+            layout.pages,
+            [strings(&["/Applications/A.app", "/Applications/Moved.app"])]
+        );
+        assert!(ghosts.is_empty());
+    }
+
+    #[test]
+    fn emptied_folder_stays_while_its_ghosts_do() {
+        let saved = OrderConfig {
+            pages: vec![strings(&["/Applications/A.app", "folder-1"])],
+            folders: vec![folder(
+                "folder-1",
+                &["/Applications/B.app", "/Applications/C.app"],
+            )],
+            ..OrderConfig::default()
+        };
+        let (layout, ghosts) = run(saved, &["/Applications/A.app"], &HashMap::new());
+        assert_eq!(
+            layout.pages,
+            [strings(&["/Applications/A.app", "folder-1"])]
+        );
+        assert_eq!(layout.folders.len(), 1);
+        assert!(layout.folders[0].app_paths.is_empty());
+        assert_eq!(ghosts.len(), 2);
+
+        // B comes back into the folder it left
+        let saved = OrderConfig {
+            pages: layout.pages.clone(),
+            folders: layout.folders.clone(),
+            ghosts: ghosts.clone(),
+            ..OrderConfig::default()
+        };
+        let (back, _) = run(
+            saved,
+            &["/Applications/A.app", "/Applications/B.app"],
+            &HashMap::new(),
+        );
+        assert_eq!(back.folders[0].app_paths, strings(&["/Applications/B.app"]));
+        assert_eq!(back.pages, [strings(&["/Applications/A.app", "folder-1"])]);
+
+        // Once the ghosts are collected, the empty folder goes
+        let saved = OrderConfig {
+            pages: layout.pages,
+            folders: layout.folders,
+            ..OrderConfig::default()
+        };
+        let (gone, _) = run(saved, &["/Applications/A.app"], &HashMap::new());
+        assert!(gone.folders.is_empty());
+        assert_eq!(gone.pages, [strings(&["/Applications/A.app"])]);
+    }
+
+    /// Reconcile `pages` (four to a page) and `folders` with the apps in
+    /// them installed, plus `new_apps`, placing those by `policy`
+    fn place(
+        pages: &[&[&str]],
+        folders: Vec<FolderMetadata>,
+        new_apps: &[&str],
+        facts: &HashMap<String, AppFacts>,
+        policy: PlacementPolicy,
+    ) -> ReconciledLayout {
+        let app_paths: Vec<String> = pages
+            .iter()
+            .flat_map(|page| page.iter())
+            .filter(|id| id.starts_with('/'))
+            .chain(new_apps)
+            .map(|path| path.to_string())
+            .chain(folders.iter().flat_map(|f| f.app_paths.iter().cloned()))
+            .collect();
+        let saved = OrderConfig {
+            pages: pages.iter().map(|page| strings(page)).collect(),
+            folders,
+            ..OrderConfig::default()
+        };
+        let installed = Installed {
+            app_paths: &app_paths,
+            folder_app_paths: &[],
+            facts,
+        };
+        reconcile(saved, &installed, Some(4), policy, 1000).0
+    }
+
+    fn on_page(id: &str, page: usize, slot: usize) -> Placement {
+        Placement::Page {
+            id: id.into(),
+            page,
+            slot,
+        }
+    }
+
+    #[test]
+    fn end_of_last_page_appends_and_opens_a_page_when_full() {
+        let layout = place(
+            &[&["/A.app", "/B.app", "/C.app", "/D.app"], &["/E.app"]],
+            Vec::new(),
+            &["/New.app"],
+            &HashMap::new(),
+            PlacementPolicy::EndOfLastPage,
+        );
+        assert_eq!(layout.pages[1], strings(&["/E.app", "/New.app"]));
+        assert_eq!(layout.placements, [on_page("/New.app", 1, 1)]);
+
+        let layout = place(
+            &[&["/A.app", "/B.app", "/C.app", "/D.app"]],
+            Vec::new(),
+            &["/New.app", "/Newer.app"],
+            &HashMap::new(),
+            PlacementPolicy::EndOfLastPage,
+        );
+        assert_eq!(layout.pages.len(), 2);
+        assert_eq!(layout.pages[1], strings(&["/New.app", "/Newer.app"]));
+        assert_eq!(
+            layout.placements,
+            [on_page("/New.app", 1, 0), on_page("/Newer.app", 1, 1)]
+        );
+    }
+
+    #[test]
+    fn first_free_slot_fills_gaps_in_page_order() {
+        let layout = place(
+            &[
+                &["/A.app", "/B.app", "/C.app"],
+                &["/D.app", "/E.app", "/F.app", "/G.app"],
+                &["/H.app"],
+            ],
+            Vec::new(),
+            &["/New.app", "/Newer.app"],
+            &HashMap::new(),
+            PlacementPolicy::FirstFreeSlot,
+        );
+        assert_eq!(
+            layout.pages[0],
+            strings(&["/A.app", "/B.app", "/C.app", "/New.app"])
+        );
+        assert_eq!(layout.pages[2], strings(&["/H.app", "/Newer.app"]));
+        assert_eq!(
+            layout.placements,
+            [on_page("/New.app", 0, 3), on_page("/Newer.app", 2, 1)]
+        );
+
+        // Every page full: a new one after them
+        let layout = place(
+            &[&["/A.app", "/B.app", "/C.app", "/D.app"]],
+            Vec::new(),
+            &["/New.app"],
+            &HashMap::new(),
+            PlacementPolicy::FirstFreeSlot,
+        );
+        assert_eq!(layout.placements, [on_page("/New.app", 1, 0)]);
+    }
+
+    #[test]
+    fn new_page_gathers_the_batch_on_pages_of_its_own() {
+        let new_apps = ["/1.app", "/2.app", "/3.app", "/4.app", "/5.app"];
+        let layout = place(
+            &[&["/A.app"]],
+            Vec::new(),
+            &new_apps,
+            &HashMap::new(),
+            PlacementPolicy::NewPage,
+        );
+        // The first page had room, but the batch gets its own
+        assert_eq!(layout.pages[0], strings(&["/A.app"]));
+        assert_eq!(layout.pages[1], strings(&new_apps[..4]));
+        assert_eq!(layout.pages[2], strings(&new_apps[4..]));
+        assert_eq!(layout.placements[3], on_page("/4.app", 1, 3));
+        assert_eq!(layout.placements[4], on_page("/5.app", 2, 0));
+    }
+
+    #[test]
+    fn category_folder_takes_apps_into_the_matching_folder() {
+        let productivity = FolderMetadata {
+            name: "Productivity".into(),
+            ..folder("folder-1", &["/Sheets.app"])
+        };
+        let facts: HashMap<String, AppFacts> = [
+            ("/Notes.app", vec!["Work", "productivity"]),
+            ("/Game.app", vec!["Games"]),
+        ]
+        .into_iter()
+        .map(|(path, names)| {
+            let facts = AppFacts {
+                folder_names: strings(&names),
+                ..AppFacts::default()
+            };
+            (path.to_string(), facts)
+        })
+        .collect();
+        let layout = place(
+            &[&["/A.app", "/B.app", "/C.app", "folder-1"]],
+            vec![productivity],
+            &["/Notes.app", "/Game.app", "/Plain.app"],
+            &facts,
+            PlacementPolicy::CategoryFolder,
+        );
+        // Folder names match case-insensitively, in the app's own order
+        assert_eq!(
+            layout.folders[0].app_paths,
+            strings(&["/Sheets.app", "/Notes.app"])
+        );
+        // No Games folder yet, and no category at all: the end of the
+        // last page, a new one as that is full
+        assert_eq!(layout.pages[1], strings(&["/Game.app", "/Plain.app"]));
+        assert_eq!(
+            layout.placements,
+            [
+                Placement::Folder {
+                    id: "/Notes.app".into(),
+                    folder_id: "folder-1".into(),
+                },
+                on_page("/Game.app", 1, 0),
+                on_page("/Plain.app", 1, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn folders_off_every_page_go_after_the_last_item() {
+        let layout = place(
+            &[&["/A.app"]],
+            vec![folder("folder-1", &["/B.app", "/C.app"])],
+            &[],
+            &HashMap::new(),
+            PlacementPolicy::FirstFreeSlot,
+        );
+        assert_eq!(layout.pages, [strings(&["/A.app", "folder-1"])]);
+        assert_eq!(layout.placements, [on_page("folder-1", 0, 1)]);
+    }
+
+    #[test]
+    fn without_a_capacity_the_fullest_saved_page_stands_in() {
+        let saved = OrderConfig {
+            pages: vec![strings(&["/A.app", "/B.app"]), strings(&["/C.app"])],
+            ..OrderConfig::default()
+        };
+        let app_paths = strings(&["/A.app", "/B.app", "/C.app", "/New.app"]);
+        let installed = Installed {
+            app_paths: &app_paths,
+            folder_app_paths: &[],
+            facts: &HashMap::new(),
+        };
+        let (layout, _) = reconcile(saved, &installed, None, PlacementPolicy::FirstFreeSlot, 0);
+        assert_eq!(layout.placements, [on_page("/New.app", 1, 1)]);
+    }
+}
@@ -0,0 +1,171 @@
+//! Gatekeeper quarantine: the com.apple.quarantine xattr a browser (or
+//! any quarantine-aware downloader) puts on what it downloads, which an
+//! app bundle inherits when unpacked. Its first launch then stops at a
+//! Gatekeeper confirmation, after which macOS marks it approved.
+//!
+//! The value is "flags;timestamp;agent;event-id" (hex flags, hex Unix
+//! seconds). The download's URLs are kept apart, in the
+//! kMDItemWhereFroms xattr (a binary plist array: the file's URL, then
+//! the page it was downloaded from).
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::xattr::read_xattr;
+
+const QUARANTINE_XATTR: &str = "com.apple.quarantine";
+const WHERE_FROMS_XATTR: &str = "com.apple.metadata:kMDItemWhereFroms";
+
+/// Set once the user has confirmed the first launch (Gatekeeper approval)
+const QTN_FLAG_USER_APPROVED: u32 = 0x0040;
+
+/// A quarantined app's download record
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Quarantine {
+    /// The app that downloaded it, e.g. "Safari"
+    pub agent: Option<String>,
+    /// When it was downloaded (ms since the epoch)
+    pub downloaded_at: Option<u64>,
+    /// Where it came from: the download page when recorded, else the
+    /// file's own URL
+    pub origin: Option<String>,
+    /// Not launched (and approved) yet: the first launch will stop at a
+    /// Gatekeeper confirmation
+    pub never_opened: bool,
+}
+
+/// Parse a com.apple.quarantine value
+fn parse_quarantine(value: &str) -> Option<Quarantine> {
+    let mut fields = value.trim_end_matches('\0').split(';');
+    let flags = u32::from_str_radix(fields.next()?, 16).ok()?;
+    let downloaded_at = fields
+        .next()
+        .and_then(|t| u64::from_str_radix(t, 16).ok())
+        .filter(|&t| t > 0)
+        .map(|t| t * 1000);
+    let agent = fields
+        .next()
+        .filter(|agent| !agent.is_empty())
+        .map(str::to_string);
+    Some(Quarantine {
+        agent,
+        downloaded_at,
+        origin: None,
+        never_opened: flags & QTN_FLAG_USER_APPROVED == 0,
+    })
+}
+
+/// The download page, else the file's URL, from a kMDItemWhereFroms value
+fn parse_where_froms(value: &[u8]) -> Option<String> {
+    let urls = plist::Value::from_reader(std::io::Cursor::new(value)).ok()?;
+    let urls: Vec<&str> = urls
+        .as_array()?
+        .iter()
+        .filter_map(|url| url.as_string())
+        .filter(|url| url.starts_with("https://") || url.starts_with("http://"))
+        .collect();
+    urls.get(1).or(urls.first()).map(|url| url.to_string())
+}
+
+/// Quarantine record of the bundle at `path`; None when it isn't
+/// quarantined (installed by the App Store, a package, or built locally)
+pub(crate) fn read_quarantine(path: &Path) -> Option<Quarantine> {
+    let value = read_xattr(path, QUARANTINE_XATTR)?;
+    let mut quarantine = parse_quarantine(&String::from_utf8(value).ok()?)?;
+    quarantine.origin = read_xattr(path, WHERE_FROMS_XATTR)
+        .as_deref()
+        .and_then(parse_where_froms);
+    Some(quarantine)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn where_froms(urls: &[&str]) -> Vec<u8> {
+        let array = urls.iter().map(|url| plist::Value::from(*url)).collect();
+        let mut value = Vec::new();
+        plist::Value::Array(array)
+            .to_writer_binary(&mut value)
+            .unwrap();
+        value
+    }
+
+    #[test]
+    fn parses_a_browser_download() {
+        assert_eq!(
+            parse_quarantine("0083;65a1b2c3;Safari;8E6F4C3A-1B2D-4E5F-9A8B-7C6D5E4F3A2B"),
+            Some(Quarantine {
+                agent: Some("Safari".into()),
+                downloaded_at: Some(0x65a1_b2c3 * 1000),
+                origin: None,
+                never_opened: true,
+            })
+        );
+    }
+
+    #[test]
+    fn approved_flag_means_opened() {
+        let quarantine = parse_quarantine("00c3;65a1b2c3;Google Chrome;").unwrap();
+        assert!(!quarantine.never_opened);
+        assert_eq!(quarantine.agent.as_deref(), Some("Google Chrome"));
+    }
+
+    #[test]
+    fn missing_fields_are_none() {
+        // Written by C strings: may end in a NUL
+        assert_eq!(
+            parse_quarantine("0081;00000000;;\0"),
+            Some(Quarantine {
+                agent: None,
+                downloaded_at: None,
+                origin: None,
+                never_opened: true,
+            })
+        );
+        let bare = parse_quarantine("0001").unwrap();
+        assert_eq!((bare.agent, bare.downloaded_at), (None, None));
+        let bad_time = parse_quarantine("0001;zz;curl").unwrap();
+        assert_eq!(bad_time.downloaded_at, None);
+        assert_eq!(bad_time.agent.as_deref(), Some("curl"));
+    }
+
+    #[test]
+    fn malformed_flags_are_rejected() {
+        assert_eq!(parse_quarantine(""), None);
+        assert_eq!(parse_quarantine("q;65a1b2c3;Safari"), None);
+    }
+
+    #[test]
+    fn where_froms_prefers_the_download_page() {
+        let value = where_froms(&[
+            "https://cdn.example.com/Example-2.1.dmg",
+            "https://example.com/download",
+        ]);
+        assert_eq!(
+            parse_where_froms(&value).as_deref(),
+            Some("https://example.com/download")
+        );
+        let file_only = where_froms(&["https://cdn.example.com/Example-2.1.dmg"]);
+        assert_eq!(
+            parse_where_froms(&file_only).as_deref(),
+            Some("https://cdn.example.com/Example-2.1.dmg")
+        );
+    }
+
+    #[test]
+    fn where_froms_skips_non_web_urls() {
+        // AirDrop and Mail record sender details rather than URLs
+        let value = where_froms(&["Jane's iPhone", "message:%3Cabc@example.com%3E"]);
+        assert_eq!(parse_where_froms(&value), None);
+        assert_eq!(parse_where_froms(&where_froms(&[])), None);
+        assert_eq!(parse_where_froms(b"bplist00garbage"), None);
+
+        let mut not_array = Vec::new();
+        plist::Value::from("https://example.com")
+            .to_writer_binary(&mut not_array)
+            .unwrap();
+        assert_eq!(parse_where_froms(&not_array), None);
+    }
+}
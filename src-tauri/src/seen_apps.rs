@@ -0,0 +1,257 @@
+//! Which apps the user has already seen, for Launchpad's "new app" dot.
+//!
+//! Each app's identity (its bundle identifier, else its path) is recorded
+//! with the bundle mtime it was seen at. An app whose identity isn't
+//! recorded was installed since, one whose bundle is newer was updated
+//! since. Launching an app marks it seen, as does "Mark All as Seen".
+//!
+//! The first run has nothing to compare against, so it records every app
+//! it serves: only apps installed after that are ever flagged.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::config::get_config_dir;
+use crate::discovery_index::{DiscoveryIndex, IndexedBundle};
+use crate::AppError;
+
+/// Format of the seen-apps file; any other version counts as a first run
+const SEEN_APPS_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct SeenApps {
+    /// SEEN_APPS_VERSION once seeded; 0 while there is no record
+    version: u32,
+    /// Identity → bundle mtime (ms since the epoch) it was last seen at
+    apps: HashMap<String, u64>,
+}
+
+/// The record, loaded from disk on first use
+static SEEN_APPS: Mutex<Option<SeenApps>> = Mutex::new(None);
+
+/// ~/Library/Application Support/com.helpermedia.wafflepad/seen-apps.json
+fn get_seen_apps_path() -> Option<PathBuf> {
+    get_config_dir().map(|p| p.join("seen-apps.json"))
+}
+
+fn load_seen_apps() -> SeenApps {
+    get_seen_apps_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str::<SeenApps>(&contents).ok())
+        .filter(|seen| seen.version == SEEN_APPS_VERSION)
+        .unwrap_or_default()
+}
+
+/// Write the record atomically (temp file + rename, like the config).
+/// Called on a copy, with no lock held: a slow disk must not hold up the
+/// commands reading the record or the served list.
+pub(crate) fn save_seen_apps(seen: &SeenApps) -> Result<(), AppError> {
+    let path = get_seen_apps_path()
+        .ok_or_else(|| AppError::Validation("Could not determine config directory".into()))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string(seen)?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json)?;
+    fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+fn with_seen<T>(f: impl FnOnce(&mut SeenApps) -> T) -> T {
+    let mut state = SEEN_APPS.lock().unwrap_or_else(|p| p.into_inner());
+    f(state.get_or_insert_with(load_seen_apps))
+}
+
+fn identity(bundle: &IndexedBundle) -> &str {
+    bundle.identifier.as_deref().unwrap_or(&bundle.path)
+}
+
+/// Every bundle of `index`, main grid and folders alike
+fn bundles(index: &DiscoveryIndex) -> impl Iterator<Item = &IndexedBundle> {
+    index
+        .apps
+        .iter()
+        .chain(index.folders.iter().flat_map(|f| f.apps.iter()))
+}
+
+/// Whether the bundle is new to the user, and when it was updated if it
+/// is new by way of an update (None for a fresh install). Offline
+/// placeholders are never new: their bundle can't be checked.
+pub(crate) fn newness(bundle: &IndexedBundle) -> (bool, Option<u64>) {
+    if bundle.offline {
+        return (false, None);
+    }
+    with_seen(|seen| newness_in(seen, bundle))
+}
+
+/// newness against the record `seen`; nothing is new before it is seeded
+fn newness_in(seen: &SeenApps, bundle: &IndexedBundle) -> (bool, Option<u64>) {
+    if seen.version != SEEN_APPS_VERSION {
+        return (false, None);
+    }
+    match seen.apps.get(identity(bundle)) {
+        None => (true, None),
+        Some(&at) if bundle.modified > at => (true, Some(bundle.modified)),
+        Some(_) => (false, None),
+    }
+}
+
+/// Record the bundles as seen at their current mtime
+fn record<'a>(seen: &mut SeenApps, bundles: impl Iterator<Item = &'a IndexedBundle>) {
+    seen.version = SEEN_APPS_VERSION;
+    for bundle in bundles.filter(|bundle| !bundle.offline) {
+        let at = seen.apps.entry(identity(bundle).to_string()).or_insert(0);
+        *at = (*at).max(bundle.modified);
+    }
+}
+
+/// On a first run (no record yet), record everything `index` lists so
+/// the apps already installed aren't flagged
+pub(crate) fn seed_seen_apps(index: &DiscoveryIndex) {
+    let seeded = with_seen(|seen| {
+        (seen.version != SEEN_APPS_VERSION).then(|| {
+            record(seen, bundles(index));
+            seen.clone()
+        })
+    });
+    if let Err(e) = seeded.as_ref().map_or(Ok(()), save_seen_apps) {
+        eprintln!("Failed to save seen apps: {}", e);
+    }
+}
+
+/// The bundles of `index` at `paths`, or all of them when `paths` is None
+fn bundles_at<'a>(
+    index: &'a DiscoveryIndex,
+    paths: Option<&'a [&str]>,
+) -> impl Iterator<Item = &'a IndexedBundle> {
+    bundles(index)
+        .filter(move |bundle| paths.is_none_or(|paths| paths.contains(&bundle.path.as_str())))
+}
+
+/// Mark the bundles of `index` at `paths` as seen, or all of them when
+/// `paths` is None. Returns the updated record for save_seen_apps, to be
+/// called once the caller's locks are released.
+pub(crate) fn mark_seen(index: &DiscoveryIndex, paths: Option<&[&str]>) -> SeenApps {
+    with_seen(|seen| {
+        record(seen, bundles_at(index, paths));
+        seen.clone()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery_index::tests::{bundle, index};
+    use crate::discovery_index::IndexedFolder;
+
+    fn app(path: &str, identifier: Option<&str>, modified: u64) -> IndexedBundle {
+        IndexedBundle {
+            identifier: identifier.map(str::to_string),
+            ..bundle(path, modified)
+        }
+    }
+
+    fn seeded(apps: &[IndexedBundle]) -> SeenApps {
+        let mut seen = SeenApps::default();
+        record(&mut seen, apps.iter());
+        seen
+    }
+
+    #[test]
+    fn nothing_is_new_before_the_first_run_is_seeded() {
+        let notes = app("/Applications/Notes.app", Some("com.example.Notes"), 10);
+        assert_eq!(newness_in(&SeenApps::default(), &notes), (false, None));
+
+        let seen = seeded(std::slice::from_ref(&notes));
+        assert_eq!(seen.version, SEEN_APPS_VERSION);
+        assert_eq!(newness_in(&seen, &notes), (false, None));
+    }
+
+    #[test]
+    fn installed_and_updated_apps_are_new_until_seen() {
+        let mut seen = seeded(&[app(
+            "/Applications/Notes.app",
+            Some("com.example.Notes"),
+            10,
+        )]);
+
+        let installed = app("/Applications/Sheets.app", Some("com.example.Sheets"), 20);
+        assert_eq!(newness_in(&seen, &installed), (true, None));
+        let updated = app("/Applications/Notes.app", Some("com.example.Notes"), 30);
+        assert_eq!(newness_in(&seen, &updated), (true, Some(30)));
+
+        record(&mut seen, [&installed, &updated].into_iter());
+        assert_eq!(newness_in(&seen, &installed), (false, None));
+        assert_eq!(newness_in(&seen, &updated), (false, None));
+
+        // An older copy seen later doesn't make the newer one new again
+        record(
+            &mut seen,
+            [&app(
+                "/Users/me/Applications/Notes.app",
+                Some("com.example.Notes"),
+                5,
+            )]
+            .into_iter(),
+        );
+        assert_eq!(newness_in(&seen, &updated), (false, None));
+    }
+
+    #[test]
+    fn apps_are_known_by_identifier_else_by_path() {
+        let seen = seeded(&[
+            app("/Applications/Notes.app", Some("com.example.Notes"), 10),
+            app("/Applications/Tool.app", None, 10),
+        ]);
+        // Moved, same bundle: not new
+        let moved = app(
+            "/Applications/Office/Notes.app",
+            Some("com.example.Notes"),
+            10,
+        );
+        assert_eq!(newness_in(&seen, &moved), (false, None));
+        // Without an identifier, a moved app is a new one
+        let tool = app("/Applications/Tool.app", None, 10);
+        assert_eq!(newness_in(&seen, &tool), (false, None));
+        let moved_tool = app("/Applications/Utilities/Tool.app", None, 10);
+        assert_eq!(newness_in(&seen, &moved_tool), (true, None));
+    }
+
+    #[test]
+    fn offline_placeholders_are_never_recorded_or_new() {
+        let offline = IndexedBundle {
+            offline: true,
+            ..app(
+                "/Volumes/Backup/Applications/Old.app",
+                Some("com.example.Old"),
+                10,
+            )
+        };
+        let seen = seeded(std::slice::from_ref(&offline));
+        assert!(seen.apps.is_empty());
+        assert_eq!(newness(&offline), (false, None));
+    }
+
+    #[test]
+    fn marks_the_listed_paths_of_grid_and_folders() {
+        let mut catalog = index(vec![
+            bundle("/Applications/A.app", 1),
+            bundle("/Applications/B.app", 1),
+        ]);
+        catalog.folders.push(IndexedFolder {
+            path: "/Applications/Tools".into(),
+            apps: vec![bundle("/Applications/Tools/C.app", 1)],
+        });
+        let paths = ["/Applications/B.app", "/Applications/Tools/C.app"];
+        let marked: Vec<&str> = bundles_at(&catalog, Some(&paths))
+            .map(|bundle| bundle.path.as_str())
+            .collect();
+        assert_eq!(marked, paths);
+        assert_eq!(bundles_at(&catalog, None).count(), 3);
+        assert_eq!(bundles_at(&catalog, Some(&[])).count(), 0);
+    }
+}
@@ -0,0 +1,812 @@
+//! Minimal SquashFS 4.0 reading, in pure Rust: enough to look up a path
+//! in an image and read the (small) file there, following symlinks. Used
+//! for AppImages, whose payload is a SquashFS image appended to the
+//! runtime executable, so they can be read without being run.
+//!
+//! Metadata (inodes, directory listings, the fragment table) is stored in
+//! blocks of up to 8 KiB, each compressed on its own; file contents are
+//! stored in data blocks, with a file's tail possibly packed into a shared
+//! fragment block. gzip, xz and zstd images are supported, which covers
+//! what appimagetool writes.
+
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+const MAGIC: u32 = 0x7371_7368;
+const SUPERBLOCK_LEN: usize = 96;
+
+pub(crate) const COMPRESSION_GZIP: u16 = 1;
+const COMPRESSION_XZ: u16 = 4;
+const COMPRESSION_ZSTD: u16 = 6;
+
+/// Uncompressed size of a full metadata block
+const METADATA_BLOCK_LEN: usize = 8192;
+/// Set in a metadata block's header when it is stored uncompressed
+const METADATA_UNCOMPRESSED: u16 = 0x8000;
+/// Set in a data or fragment block's size when it is stored uncompressed
+const DATA_UNCOMPRESSED: u32 = 1 << 24;
+/// A file's fragment index when its tail has no fragment
+const NO_FRAGMENT: u32 = 0xffff_ffff;
+/// Fragment table entries per metadata block (16 bytes each)
+const FRAGMENTS_PER_BLOCK: u32 = 512;
+
+const INODE_DIR: u16 = 1;
+const INODE_FILE: u16 = 2;
+const INODE_SYMLINK: u16 = 3;
+const INODE_EXT_DIR: u16 = 8;
+const INODE_EXT_FILE: u16 = 9;
+const INODE_EXT_SYMLINK: u16 = 10;
+
+/// Files read are desktop entries and icons: anything bigger is refused
+const MAX_FILE_LEN: u64 = 16 * 1024 * 1024;
+/// Symlinks followed per lookup, against links in a circle
+const MAX_SYMLINK_HOPS: usize = 8;
+
+fn u16_at(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// The output of a decompressor that only writes (lzma-rs has no reader
+/// to take() from): refuses to grow past `limit` bytes, failing the
+/// decompression instead. lzma-rs hands over each LZMA2 chunk (at most
+/// 2 MiB unpacked) as it completes, so a bomb stops at the first one.
+struct CappedWriter {
+    bytes: Vec<u8>,
+    limit: usize,
+}
+
+impl Write for CappedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.bytes.len() + buf.len() > self.limit {
+            return Err(io::Error::other("block unpacks past its size"));
+        }
+        self.bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A regular file's inode: where its blocks are and how big it is
+struct FileInode {
+    blocks_start: u64,
+    len: u64,
+    fragment: u32,
+    fragment_offset: u32,
+    /// Stored size of each full data block
+    block_sizes: Vec<u32>,
+}
+
+enum Inode {
+    /// A directory's listing: metadata block, offset in it, listing size
+    Dir {
+        block: u32,
+        offset: u16,
+        len: u32,
+    },
+    File(FileInode),
+    Symlink(String),
+}
+
+/// Where a path lookup ended
+enum Walked {
+    Found(Inode),
+    /// The component at this index is a symlink to the target
+    Link {
+        at: usize,
+        target: String,
+    },
+}
+
+/// A SquashFS image, within a file
+pub(crate) struct SquashFs {
+    file: File,
+    /// Where the image starts in the file
+    offset: u64,
+    compressor: u16,
+    block_size: u32,
+    root_inode: u64,
+    inode_table: u64,
+    directory_table: u64,
+    fragment_table: u64,
+}
+
+/// Split a path into its components, resolving "." and ".."
+fn components<'a>(parts: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut resolved: Vec<String> = Vec::new();
+    for part in parts.into_iter().flat_map(|part| part.split('/')) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                resolved.pop();
+            }
+            name => resolved.push(name.to_string()),
+        }
+    }
+    resolved
+}
+
+impl SquashFs {
+    /// Open the image starting `offset` bytes into the file at `path`
+    pub(crate) fn open(path: &Path, offset: u64) -> Option<Self> {
+        let file = File::open(path).ok()?;
+        let mut superblock = [0u8; SUPERBLOCK_LEN];
+        file.read_exact_at(&mut superblock, offset).ok()?;
+        if u32_at(&superblock, 0)? != MAGIC || u16_at(&superblock, 28)? != 4 {
+            return None;
+        }
+        Some(Self {
+            file,
+            offset,
+            compressor: u16_at(&superblock, 20)?,
+            block_size: u32_at(&superblock, 12)?,
+            root_inode: u64_at(&superblock, 32)?,
+            inode_table: u64_at(&superblock, 64)?,
+            directory_table: u64_at(&superblock, 72)?,
+            fragment_table: u64_at(&superblock, 80)?,
+        })
+    }
+
+    fn read_at(&self, position: u64, len: usize) -> Option<Vec<u8>> {
+        let mut bytes = vec![0u8; len];
+        self.file
+            .read_exact_at(&mut bytes, self.offset.checked_add(position)?)
+            .ok()?;
+        Some(bytes)
+    }
+
+    /// Decompress one block, which unpacks to at most `limit` bytes: the
+    /// block size for data, 8 KiB for metadata. Nothing past the limit is
+    /// unpacked, so a crafted block can't make it balloon.
+    fn decompress(&self, data: &[u8], limit: usize) -> Option<Vec<u8>> {
+        let mut unpacked = Vec::new();
+        match self.compressor {
+            COMPRESSION_GZIP => {
+                flate2::read::ZlibDecoder::new(data)
+                    .take(limit as u64)
+                    .read_to_end(&mut unpacked)
+                    .ok()?;
+            }
+            COMPRESSION_XZ => {
+                let mut reader = data;
+                let mut capped = CappedWriter {
+                    bytes: Vec::new(),
+                    limit,
+                };
+                lzma_rs::xz_decompress(&mut reader, &mut capped).ok()?;
+                unpacked = capped.bytes;
+            }
+            COMPRESSION_ZSTD => {
+                ruzstd::decoding::StreamingDecoder::new(data)
+                    .ok()?
+                    .take(limit as u64)
+                    .read_to_end(&mut unpacked)
+                    .ok()?;
+            }
+            _ => return None,
+        }
+        Some(unpacked)
+    }
+
+    /// The metadata block at `position`, unpacked, and where the next one
+    /// starts
+    fn metadata_block(&self, position: u64) -> Option<(Vec<u8>, u64)> {
+        let header = u16_at(&self.read_at(position, 2)?, 0)?;
+        let stored = (header & !METADATA_UNCOMPRESSED) as usize;
+        let data = self.read_at(position + 2, stored)?;
+        let block = if header & METADATA_UNCOMPRESSED != 0 {
+            data
+        } else {
+            self.decompress(&data, METADATA_BLOCK_LEN)?
+        };
+        Some((block, position + 2 + stored as u64))
+    }
+
+    /// `len` bytes of metadata, from `offset` bytes into the block that
+    /// starts `block` bytes into `table`, running on into the blocks after
+    fn metadata(&self, table: u64, block: u64, offset: usize, len: usize) -> Option<Vec<u8>> {
+        let mut position = table.checked_add(block)?;
+        let mut bytes = Vec::new();
+        while bytes.len() < offset + len {
+            let (data, next) = self.metadata_block(position)?;
+            if data.is_empty() {
+                return None;
+            }
+            bytes.extend(data);
+            position = next;
+        }
+        Some(bytes[offset..offset + len].to_vec())
+    }
+
+    fn inode(&self, reference: u64) -> Option<Inode> {
+        let (block, offset) = (reference >> 16, (reference & 0xffff) as usize);
+        let read =
+            |start: usize, len: usize| self.metadata(self.inode_table, block, offset + start, len);
+        let kind = u16_at(&read(0, 16)?, 0)?;
+        match kind {
+            INODE_DIR => {
+                let dir = read(16, 16)?;
+                Some(Inode::Dir {
+                    block: u32_at(&dir, 0)?,
+                    len: u16_at(&dir, 8)? as u32,
+                    offset: u16_at(&dir, 10)?,
+                })
+            }
+            INODE_EXT_DIR => {
+                let dir = read(16, 24)?;
+                Some(Inode::Dir {
+                    len: u32_at(&dir, 4)?,
+                    block: u32_at(&dir, 8)?,
+                    offset: u16_at(&dir, 18)?,
+                })
+            }
+            INODE_FILE | INODE_EXT_FILE => {
+                let (fixed, file) = if kind == INODE_FILE {
+                    let file = read(16, 16)?;
+                    let inode = FileInode {
+                        blocks_start: u32_at(&file, 0)? as u64,
+                        fragment: u32_at(&file, 4)?,
+                        fragment_offset: u32_at(&file, 8)?,
+                        len: u32_at(&file, 12)? as u64,
+                        block_sizes: Vec::new(),
+                    };
+                    (32, inode)
+                } else {
+                    let file = read(16, 40)?;
+                    let inode = FileInode {
+                        blocks_start: u64_at(&file, 0)?,
+                        len: u64_at(&file, 8)?,
+                        fragment: u32_at(&file, 28)?,
+                        fragment_offset: u32_at(&file, 32)?,
+                        block_sizes: Vec::new(),
+                    };
+                    (56, inode)
+                };
+                if file.len > MAX_FILE_LEN || self.block_size == 0 {
+                    return None;
+                }
+                let block_size = self.block_size as u64;
+                // A tail in a fragment has no data block of its own
+                let blocks = if file.fragment == NO_FRAGMENT {
+                    file.len.div_ceil(block_size)
+                } else {
+                    file.len / block_size
+                };
+                let blocks = blocks as usize;
+                let sizes = read(fixed, blocks * 4)?;
+                let block_sizes = (0..blocks)
+                    .map(|i| u32_at(&sizes, i * 4))
+                    .collect::<Option<Vec<u32>>>()?;
+                Some(Inode::File(FileInode {
+                    block_sizes,
+                    ..file
+                }))
+            }
+            INODE_SYMLINK | INODE_EXT_SYMLINK => {
+                let link = read(16, 8)?;
+                let target_len = u32_at(&link, 4)? as usize;
+                if target_len > 4096 {
+                    return None;
+                }
+                let target = read(24, target_len)?;
+                Some(Inode::Symlink(String::from_utf8(target).ok()?))
+            }
+            _ => None,
+        }
+    }
+
+    /// A directory's entries: name and inode reference
+    fn dir_entries(&self, block: u32, offset: u16, len: u32) -> Option<Vec<(String, u64)>> {
+        // The stored size counts 3 bytes more than the listing holds
+        let len = (len as usize).saturating_sub(3);
+        let listing = self.metadata(self.directory_table, block as u64, offset as usize, len)?;
+        let mut entries = Vec::new();
+        let mut at = 0;
+        while at + 12 <= listing.len() {
+            let count = u32_at(&listing, at)? as usize + 1;
+            let start = u32_at(&listing, at + 4)? as u64;
+            if count > 256 {
+                return None;
+            }
+            at += 12;
+            for _ in 0..count {
+                let inode_offset = u16_at(&listing, at)? as u64;
+                let name_len = u16_at(&listing, at + 6)? as usize + 1;
+                let name = listing.get(at + 8..at + 8 + name_len)?;
+                entries.push((
+                    String::from_utf8_lossy(name).to_string(),
+                    (start << 16) | inode_offset,
+                ));
+                at += 8 + name_len;
+            }
+        }
+        Some(entries)
+    }
+
+    fn walk(&self, path: &[String]) -> Option<Walked> {
+        let mut inode = self.inode(self.root_inode)?;
+        for (at, name) in path.iter().enumerate() {
+            let Inode::Dir { block, offset, len } = inode else {
+                return None;
+            };
+            let (_, reference) = self
+                .dir_entries(block, offset, len)?
+                .into_iter()
+                .find(|(entry, _)| entry == name)?;
+            inode = self.inode(reference)?;
+            if let Inode::Symlink(target) = inode {
+                return Some(Walked::Link { at, target });
+            }
+        }
+        Some(Walked::Found(inode))
+    }
+
+    /// Look up `path` from the image root, following symlinks within the
+    /// image (absolute targets count from its root)
+    fn lookup(&self, path: &str) -> Option<Inode> {
+        let mut path = components([path]);
+        for _ in 0..=MAX_SYMLINK_HOPS {
+            match self.walk(&path)? {
+                Walked::Found(inode) => return Some(inode),
+                Walked::Link { at, target } => {
+                    let base = if target.starts_with('/') { 0 } else { at };
+                    let rest = path[at + 1..].join("/");
+                    let parent = path[..base].join("/");
+                    path = components([parent.as_str(), target.as_str(), rest.as_str()]);
+                }
+            }
+        }
+        None
+    }
+
+    /// Names in the image's root directory
+    pub(crate) fn root_names(&self) -> Vec<String> {
+        match self.inode(self.root_inode) {
+            Some(Inode::Dir { block, offset, len }) => self
+                .dir_entries(block, offset, len)
+                .map(|entries| entries.into_iter().map(|(name, _)| name).collect())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Contents of the file at `path`; None when it isn't a regular file
+    /// (after following symlinks) or is implausibly big
+    pub(crate) fn read_file(&self, path: &str) -> Option<Vec<u8>> {
+        let Inode::File(file) = self.lookup(path)? else {
+            return None;
+        };
+        let block_size = self.block_size as usize;
+        let mut data = Vec::with_capacity(file.len as usize);
+        let mut position = file.blocks_start;
+        for &stored in &file.block_sizes {
+            let stored_len = (stored & !DATA_UNCOMPRESSED) as usize;
+            if stored_len == 0 {
+                // A sparse block: all zeroes, nothing stored
+                data.resize(data.len() + block_size, 0);
+                continue;
+            }
+            let raw = self.read_at(position, stored_len)?;
+            position += stored_len as u64;
+            if stored & DATA_UNCOMPRESSED != 0 {
+                data.extend(raw);
+            } else {
+                data.extend(self.decompress(&raw, block_size)?);
+            }
+        }
+        if file.fragment != NO_FRAGMENT {
+            let pointer_at = self.fragment_table + (file.fragment / FRAGMENTS_PER_BLOCK) as u64 * 8;
+            let pointer = u64_at(&self.read_at(pointer_at, 8)?, 0)?;
+            let entry_at = (file.fragment % FRAGMENTS_PER_BLOCK) as usize * 16;
+            let entry = self.metadata(pointer, 0, entry_at, 16)?;
+            let (start, stored) = (u64_at(&entry, 0)?, u32_at(&entry, 8)?);
+            let raw = self.read_at(start, (stored & !DATA_UNCOMPRESSED) as usize)?;
+            let fragment = if stored & DATA_UNCOMPRESSED != 0 {
+                raw
+            } else {
+                self.decompress(&raw, block_size)?
+            };
+            let tail_start = file.fragment_offset as usize;
+            let tail_len = (file.len % self.block_size as u64) as usize;
+            data.extend_from_slice(fragment.get(tail_start..tail_start + tail_len)?);
+        }
+        data.truncate(file.len as usize);
+        Some(data)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// How a fixture file's data block is stored
+    pub(crate) enum Block<'a> {
+        Compressed(&'a [u8]),
+        Stored(&'a [u8]),
+        Sparse,
+    }
+
+    /// Builds small SquashFS 4.0 images, laid out as mksquashfs does:
+    /// superblock, data and fragment blocks, then the inode, directory
+    /// and fragment tables. Each table fits one metadata block.
+    pub(crate) struct ImageBuilder {
+        compressor: u16,
+        block_size: u32,
+        /// Everything after the superblock, up to the tables
+        data: Vec<u8>,
+        inodes: Vec<u8>,
+        listings: Vec<u8>,
+        fragments: Vec<u8>,
+    }
+
+    pub(crate) fn compress(compressor: u16, bytes: &[u8]) -> Vec<u8> {
+        match compressor {
+            COMPRESSION_GZIP => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(bytes).unwrap();
+                encoder.finish().unwrap()
+            }
+            COMPRESSION_XZ => {
+                let mut packed = Vec::new();
+                lzma_rs::xz_compress(&mut &bytes[..], &mut packed).unwrap();
+                packed
+            }
+            COMPRESSION_ZSTD => ruzstd::encoding::compress_to_vec(
+                bytes,
+                ruzstd::encoding::CompressionLevel::Fastest,
+            ),
+            _ => unreachable!(),
+        }
+    }
+
+    impl ImageBuilder {
+        pub(crate) fn new(compressor: u16, block_size: u32) -> Self {
+            Self {
+                compressor,
+                block_size,
+                data: Vec::new(),
+                inodes: Vec::new(),
+                listings: Vec::new(),
+                fragments: Vec::new(),
+            }
+        }
+
+        fn position(&self) -> u32 {
+            (SUPERBLOCK_LEN + self.data.len()) as u32
+        }
+
+        /// Append a block; its stored size word
+        fn push_block(&mut self, block: &Block) -> u32 {
+            match block {
+                Block::Compressed(bytes) => {
+                    let packed = compress(self.compressor, bytes);
+                    self.data.extend_from_slice(&packed);
+                    packed.len() as u32
+                }
+                Block::Stored(bytes) => {
+                    self.data.extend_from_slice(bytes);
+                    bytes.len() as u32 | DATA_UNCOMPRESSED
+                }
+                Block::Sparse => 0,
+            }
+        }
+
+        /// An inode with a zeroed common header; its reference
+        fn inode(&mut self, kind: u16, body: &[u8]) -> u64 {
+            let at = self.inodes.len() as u64;
+            self.inodes.extend_from_slice(&kind.to_le_bytes());
+            self.inodes.extend_from_slice(&[0; 14]);
+            self.inodes.extend_from_slice(body);
+            at
+        }
+
+        /// A file of `len` bytes: `blocks`, then `tail` in a fragment
+        pub(crate) fn file_blocks(&mut self, len: u32, blocks: &[Block], tail: &[u8]) -> u64 {
+            let blocks_start = self.position();
+            let sizes: Vec<u32> = blocks.iter().map(|block| self.push_block(block)).collect();
+            let fragment = if tail.is_empty() {
+                NO_FRAGMENT
+            } else {
+                let start = self.position() as u64;
+                let stored = self.push_block(&Block::Compressed(tail));
+                self.fragments.extend_from_slice(&start.to_le_bytes());
+                self.fragments.extend_from_slice(&stored.to_le_bytes());
+                self.fragments.extend_from_slice(&[0; 4]);
+                (self.fragments.len() / 16 - 1) as u32
+            };
+            let mut body = Vec::new();
+            for value in [blocks_start, fragment, 0, len] {
+                body.extend_from_slice(&value.to_le_bytes());
+            }
+            for size in sizes {
+                body.extend_from_slice(&size.to_le_bytes());
+            }
+            self.inode(INODE_FILE, &body)
+        }
+
+        /// A file holding `contents`: compressed full blocks, the rest in a
+        /// fragment
+        pub(crate) fn file(&mut self, contents: &[u8]) -> u64 {
+            let full = contents.len() - contents.len() % self.block_size as usize;
+            let blocks: Vec<Block> = contents[..full]
+                .chunks(self.block_size as usize)
+                .map(Block::Compressed)
+                .collect();
+            self.file_blocks(contents.len() as u32, &blocks, &contents[full..])
+        }
+
+        pub(crate) fn symlink(&mut self, target: &str) -> u64 {
+            let mut body = 1u32.to_le_bytes().to_vec();
+            body.extend_from_slice(&(target.len() as u32).to_le_bytes());
+            body.extend_from_slice(target.as_bytes());
+            self.inode(INODE_SYMLINK, &body)
+        }
+
+        /// A directory of (name, inode reference) entries
+        pub(crate) fn dir(&mut self, entries: &[(&str, u64)]) -> u64 {
+            let at = self.listings.len();
+            if !entries.is_empty() {
+                for value in [entries.len() as u32 - 1, 0, 0] {
+                    self.listings.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            for (name, reference) in entries {
+                self.listings
+                    .extend_from_slice(&(*reference as u16).to_le_bytes());
+                self.listings.extend_from_slice(&[0, 0, 1, 0]);
+                self.listings
+                    .extend_from_slice(&(name.len() as u16 - 1).to_le_bytes());
+                self.listings.extend_from_slice(name.as_bytes());
+            }
+            let len = (self.listings.len() - at + 3) as u16;
+            let mut body = Vec::new();
+            body.extend_from_slice(&0u32.to_le_bytes());
+            body.extend_from_slice(&2u32.to_le_bytes());
+            body.extend_from_slice(&len.to_le_bytes());
+            body.extend_from_slice(&(at as u16).to_le_bytes());
+            body.extend_from_slice(&0u32.to_le_bytes());
+            self.inode(INODE_DIR, &body)
+        }
+
+        fn metadata_block(&self, bytes: &[u8]) -> Vec<u8> {
+            assert!(bytes.len() <= METADATA_BLOCK_LEN);
+            let packed = compress(self.compressor, bytes);
+            let mut block = (packed.len() as u16).to_le_bytes().to_vec();
+            block.extend_from_slice(&packed);
+            block
+        }
+
+        /// The image, with the directory at `root` as its root
+        pub(crate) fn finish(self, root: u64) -> Vec<u8> {
+            let mut image = vec![0u8; SUPERBLOCK_LEN];
+            image.extend_from_slice(&self.data);
+            let inode_table = image.len() as u64;
+            image.extend(self.metadata_block(&self.inodes));
+            let directory_table = image.len() as u64;
+            image.extend(self.metadata_block(&self.listings));
+            let fragment_entries = image.len() as u64;
+            image.extend(self.metadata_block(&self.fragments));
+            let fragment_table = image.len() as u64;
+            image.extend_from_slice(&fragment_entries.to_le_bytes());
+
+            let mut put = |at: usize, bytes: &[u8]| {
+                image[at..at + bytes.len()].copy_from_slice(bytes);
+            };
+            put(0, &MAGIC.to_le_bytes());
+            put(12, &self.block_size.to_le_bytes());
+            put(16, &((self.fragments.len() / 16) as u32).to_le_bytes());
+            put(20, &self.compressor.to_le_bytes());
+            put(22, &(self.block_size.trailing_zeros() as u16).to_le_bytes());
+            put(28, &4u16.to_le_bytes());
+            put(32, &root.to_le_bytes());
+            put(64, &inode_table.to_le_bytes());
+            put(72, &directory_table.to_le_bytes());
+            put(80, &fragment_table.to_le_bytes());
+            let len = image.len() as u64;
+            image[40..48].copy_from_slice(&len.to_le_bytes());
+            image
+        }
+    }
+
+    const BLOCK_SIZE: u32 = 4096;
+    const COMPRESSORS: [u16; 3] = [COMPRESSION_GZIP, COMPRESSION_XZ, COMPRESSION_ZSTD];
+
+    /// Not all one byte: a block that compresses, but not to nothing
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// The image written to a file after `prefix`, opened
+    fn open(image: &[u8], prefix: &[u8]) -> (tempfile::NamedTempFile, SquashFs) {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(prefix).unwrap();
+        file.write_all(image).unwrap();
+        let squashfs = SquashFs::open(file.path(), prefix.len() as u64).unwrap();
+        (file, squashfs)
+    }
+
+    /// app.desktop (fragment only), big.bin (two blocks and a tail) and
+    /// usr/share/icon.png, with symlinks to them
+    fn sample(compressor: u16) -> Vec<u8> {
+        let mut builder = ImageBuilder::new(compressor, BLOCK_SIZE);
+        let desktop = builder.file(b"[Desktop Entry]\nName=Sample\n");
+        let big = builder.file(&pattern(2 * BLOCK_SIZE as usize + 100));
+        let icon = builder.file(b"\x89PNG\r\n\x1a\nicon");
+        let back = builder.symlink("../../app.desktop");
+        let share = builder.dir(&[("back", back), ("icon.png", icon)]);
+        let usr = builder.dir(&[("share", share)]);
+        let dir_icon = builder.symlink("usr/share/icon.png");
+        let absolute = builder.symlink("/usr/share/icon.png");
+        let share_link = builder.symlink("usr/share");
+        let root = builder.dir(&[
+            (".DirIcon", dir_icon),
+            ("absolute", absolute),
+            ("app.desktop", desktop),
+            ("big.bin", big),
+            ("share", share_link),
+            ("usr", usr),
+        ]);
+        builder.finish(root)
+    }
+
+    #[test]
+    fn reads_files_with_each_compressor() {
+        for compressor in COMPRESSORS {
+            let (_file, image) = open(&sample(compressor), b"");
+            assert_eq!(
+                image.root_names(),
+                [
+                    ".DirIcon",
+                    "absolute",
+                    "app.desktop",
+                    "big.bin",
+                    "share",
+                    "usr"
+                ]
+            );
+            assert_eq!(
+                image.read_file("app.desktop").unwrap(),
+                b"[Desktop Entry]\nName=Sample\n"
+            );
+            assert_eq!(
+                image.read_file("big.bin").unwrap(),
+                pattern(2 * BLOCK_SIZE as usize + 100),
+                "compressor {compressor}"
+            );
+            assert_eq!(
+                image.read_file("/usr/./share/icon.png").unwrap(),
+                b"\x89PNG\r\n\x1a\nicon"
+            );
+        }
+    }
+
+    #[test]
+    fn reads_an_image_after_a_runtime() {
+        let (_file, image) = open(&sample(COMPRESSION_GZIP), &[0x7f; 1000]);
+        assert!(image.read_file("app.desktop").is_some());
+    }
+
+    #[test]
+    fn follows_symlinks() {
+        let (_file, image) = open(&sample(COMPRESSION_ZSTD), b"");
+        let icon = b"\x89PNG\r\n\x1a\nicon".to_vec();
+        assert_eq!(image.read_file(".DirIcon"), Some(icon.clone()));
+        assert_eq!(image.read_file("absolute"), Some(icon.clone()));
+        // A link in the middle of the path
+        assert_eq!(image.read_file("share/icon.png"), Some(icon));
+        // Relative to the link's own directory
+        assert!(image.read_file("usr/share/back").is_some());
+    }
+
+    #[test]
+    fn gives_up_on_symlink_loops() {
+        let mut builder = ImageBuilder::new(COMPRESSION_GZIP, BLOCK_SIZE);
+        let a = builder.symlink("b");
+        let b = builder.symlink("a");
+        let root = builder.dir(&[("a", a), ("b", b)]);
+        let (_file, image) = open(&builder.finish(root), b"");
+        assert_eq!(image.read_file("a"), None);
+    }
+
+    #[test]
+    fn reads_stored_and_sparse_blocks() {
+        let block = pattern(BLOCK_SIZE as usize);
+        let mut builder = ImageBuilder::new(COMPRESSION_XZ, BLOCK_SIZE);
+        let file = builder.file_blocks(
+            3 * BLOCK_SIZE + 3,
+            &[
+                Block::Stored(&block),
+                Block::Sparse,
+                Block::Compressed(&block),
+            ],
+            b"end",
+        );
+        let root = builder.dir(&[("file", file)]);
+        let (_file, image) = open(&builder.finish(root), b"");
+
+        let mut expected = block.clone();
+        expected.resize(2 * BLOCK_SIZE as usize, 0);
+        expected.extend_from_slice(&block);
+        expected.extend_from_slice(b"end");
+        assert_eq!(image.read_file("file"), Some(expected));
+    }
+
+    #[test]
+    fn only_reads_regular_files() {
+        let (_file, image) = open(&sample(COMPRESSION_GZIP), b"");
+        assert_eq!(image.read_file("usr"), None);
+        assert_eq!(image.read_file("missing.png"), None);
+        assert_eq!(image.read_file("app.desktop/child"), None);
+    }
+
+    #[test]
+    fn rejects_other_images() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&[0; SUPERBLOCK_LEN]).unwrap();
+        assert!(SquashFs::open(file.path(), 0).is_none());
+
+        // SquashFS 3.x
+        let mut old = sample(COMPRESSION_GZIP);
+        old[28..30].copy_from_slice(&3u16.to_le_bytes());
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&old).unwrap();
+        assert!(SquashFs::open(file.path(), 0).is_none());
+
+        // Cut short: the superblock reads, the tables don't
+        let mut truncated = sample(COMPRESSION_GZIP);
+        truncated.truncate(SUPERBLOCK_LEN + 10);
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&truncated).unwrap();
+        let image = SquashFs::open(file.path(), 0).unwrap();
+        assert!(image.root_names().is_empty());
+        assert_eq!(image.read_file("app.desktop"), None);
+    }
+
+    #[test]
+    fn caps_what_a_block_unpacks_to() {
+        let bomb = vec![0u8; 1 << 20];
+        for compressor in COMPRESSORS {
+            let (_file, image) = open(&sample(compressor), b"");
+            let packed = compress(compressor, &bomb);
+            for limit in [METADATA_BLOCK_LEN, BLOCK_SIZE as usize] {
+                let unpacked = image.decompress(&packed, limit);
+                assert!(
+                    unpacked.is_none_or(|bytes| bytes.len() <= limit),
+                    "compressor {compressor}"
+                );
+            }
+            // Within the limit, a block unpacks whole
+            let block = pattern(BLOCK_SIZE as usize);
+            assert_eq!(
+                image.decompress(&compress(compressor, &block), BLOCK_SIZE as usize),
+                Some(block)
+            );
+        }
+    }
+
+    #[test]
+    fn an_oversized_data_block_is_cut_to_the_block_size() {
+        let oversized = pattern(4 * BLOCK_SIZE as usize);
+        let mut builder = ImageBuilder::new(COMPRESSION_ZSTD, BLOCK_SIZE);
+        let file = builder.file_blocks(BLOCK_SIZE, &[Block::Compressed(&oversized)], b"");
+        let root = builder.dir(&[("file", file)]);
+        let (_file, image) = open(&builder.finish(root), b"");
+        assert_eq!(
+            image.read_file("file"),
+            Some(oversized[..BLOCK_SIZE as usize].to_vec())
+        );
+    }
+}
@@ -0,0 +1,112 @@
+//! Extended attribute reads. Finder and Gatekeeper keep their per-file
+//! state (quarantine, tags, download origin) in xattrs, which std has no
+//! API for.
+//!
+//! Names are given as macOS spells them. Linux only lets unprivileged
+//! processes use the "user." namespace, so there the name is read under
+//! it: `setfattr -n user.com.apple.quarantine` on a Linux filesystem sets
+//! what macOS reads as com.apple.quarantine (and what Samba and netatalk
+//! store for Mac clients).
+
+use std::path::Path;
+
+/// The attribute name as the platform stores it
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn platform_name(name: &str) -> String {
+    if cfg!(target_os = "linux") {
+        format!("user.{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+/// Value of the extended attribute `name` on `path` (following symlinks).
+/// None when it isn't set or can't be read.
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+pub(crate) fn read_xattr(path: &Path, name: &str) -> Option<Vec<u8>> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let name = CString::new(platform_name(name)).ok()?;
+    let len = getxattr(&path, &name, &mut [])?;
+    let mut value = vec![0u8; len];
+    // A value that grew in between fails the read (ERANGE): reported unset
+    let read = getxattr(&path, &name, &mut value)?;
+    value.truncate(read);
+    Some(value)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub(crate) fn read_xattr(_path: &Path, _name: &str) -> Option<Vec<u8>> {
+    None
+}
+
+/// getxattr(2) into `buf`; an empty buffer asks for the value's size
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn getxattr(path: &std::ffi::CStr, name: &std::ffi::CStr, buf: &mut [u8]) -> Option<usize> {
+    let (ptr, size) = if buf.is_empty() {
+        (std::ptr::null_mut(), 0)
+    } else {
+        (buf.as_mut_ptr().cast::<libc::c_void>(), buf.len())
+    };
+    // SAFETY: both strings are NUL-terminated, and ptr is null (size 0) or
+    // points at `size` writable bytes
+    #[cfg(target_os = "macos")]
+    let result = unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), ptr, size, 0, 0) };
+    #[cfg(not(target_os = "macos"))]
+    let result = unsafe { libc::getxattr(path.as_ptr(), name.as_ptr(), ptr, size) };
+    usize::try_from(result).ok()
+}
+
+/// Set the extended attribute `name` on `path`, for tests. False when the
+/// filesystem doesn't take user xattrs (tmpfs before Linux 6.6, overlayfs).
+#[cfg(all(test, any(target_os = "macos", target_os = "linux")))]
+pub(crate) fn set_xattr(path: &Path, name: &str, value: &[u8]) -> bool {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    let name = CString::new(platform_name(name)).unwrap();
+    let value_ptr = value.as_ptr().cast::<libc::c_void>();
+    // SAFETY: both strings are NUL-terminated and value_ptr points at
+    // value.len() readable bytes
+    #[cfg(target_os = "macos")]
+    let result =
+        unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value_ptr, value.len(), 0, 0) };
+    #[cfg(not(target_os = "macos"))]
+    let result = unsafe { libc::setxattr(path.as_ptr(), name.as_ptr(), value_ptr, value.len(), 0) };
+    result == 0
+}
+
+#[cfg(all(test, any(target_os = "macos", target_os = "linux")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_what_was_set() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        if !set_xattr(
+            file.path(),
+            "com.apple.quarantine",
+            b"0083;65a1b2c3;Safari;",
+        ) {
+            eprintln!("skipped: no user xattrs on this filesystem");
+            return;
+        }
+        assert_eq!(
+            read_xattr(file.path(), "com.apple.quarantine").as_deref(),
+            Some(&b"0083;65a1b2c3;Safari;"[..])
+        );
+        assert!(set_xattr(file.path(), "com.example.empty", b""));
+        assert_eq!(
+            read_xattr(file.path(), "com.example.empty"),
+            Some(Vec::new())
+        );
+        assert_eq!(read_xattr(file.path(), "com.example.unset"), None);
+        assert_eq!(
+            read_xattr(Path::new("/nonexistent"), "com.apple.quarantine"),
+            None
+        );
+    }
+}
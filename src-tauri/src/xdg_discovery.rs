@@ -0,0 +1,296 @@
+//! App discovery on Linux: the .desktop files of the XDG applications
+//! directories, found as the Desktop Entry spec finds them.
+//!
+//! Each file is known by its desktop-file id, its path below the
+//! applications directory with "/" turned into "-" (kde4/foo.desktop is
+//! kde4-foo.desktop). Directories are read in priority order and the first
+//! file with an id shadows every later one, even when it is Hidden or
+//! NoDisplay: that is how a user hides or overrides a system entry.
+//!
+//! Subdirectories only prefix the id: unlike a subfolder of
+//! /Applications they group nothing, so their entries are listed with
+//! the rest.
+//!
+//! Flatpak and Snap export their apps' entries to directories of their
+//! own, which the session normally lists among the XDG data directories;
+//! entries found there are tagged with their source so either can be
+//! hidden. AppImages have no entry installed at all and are listed from
+//! ~/Applications by file.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::app_discovery::{DiscoveredApp, Discovery};
+use crate::app_sources::{appimage_dirs, desktop_entry_dirs, source_enabled, DiscoveryRoots};
+use crate::appimage::is_appimage;
+use crate::config::{AppSettings, AppSource};
+use crate::desktop_entry::{current_desktops, DesktopEntry};
+
+/// How deep below an applications directory .desktop files are looked
+/// for; symlinked directories could otherwise loop
+const MAX_DEPTH: usize = 8;
+
+/// The desktop-file id of a file at `relative` below its applications
+/// directory
+fn desktop_file_id(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Every .desktop file below `dir`, sorted so shadowing within one
+/// directory is deterministic
+fn desktop_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.extension().is_some_and(|ext| ext == "desktop") && path.is_file() {
+            files.push(path);
+        } else if depth < MAX_DEPTH && path.is_dir() {
+            desktop_files(&path, depth + 1, files);
+        }
+    }
+}
+
+/// The AppImages directly in `dir`
+fn appimages_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_appimage(path) && path.is_file())
+        .collect()
+}
+
+/// A listed entry and the name it sorts by
+type Named = (String, DiscoveredApp);
+
+fn sort_by_name(apps: &mut [Named]) {
+    apps.sort_by_cached_key(|(name, _)| name.to_lowercase());
+}
+
+/// The entries of `dirs` (in shadowing order) that menus in `desktops`
+/// would list. Unreadable files, entries that aren't listed and those of
+/// a disabled source still shadow their id.
+fn listed_entries(
+    dirs: &[(AppSource, PathBuf)],
+    desktops: &[String],
+    settings: &AppSettings,
+) -> Vec<Named> {
+    let mut ids: HashSet<String> = HashSet::new();
+    let mut apps: Vec<Named> = Vec::new();
+    for (source, dir) in dirs {
+        let mut files = Vec::new();
+        desktop_files(dir, 0, &mut files);
+        for file in files {
+            let Ok(relative) = file.strip_prefix(dir) else {
+                continue;
+            };
+            let id = desktop_file_id(relative);
+            if !ids.insert(id.clone()) {
+                continue;
+            }
+            let Some(entry) = DesktopEntry::read(&file) else {
+                continue;
+            };
+            if !source_enabled(*source, settings) || !entry.is_listed(desktops) {
+                continue;
+            }
+            let id = id.trim_end_matches(".desktop").to_string();
+            let name = entry.name().unwrap_or_else(|| id.clone());
+            apps.push((
+                name,
+                DiscoveredApp {
+                    path: file.clone(),
+                    source: *source,
+                    volume: None,
+                    via: None,
+                    desktop_id: Some(id),
+                },
+            ));
+        }
+    }
+    apps
+}
+
+/// Walk the XDG applications directories under `roots` for the entries
+/// menus would list in the running desktop environment, plus enabled
+/// sources' AppImages
+pub(crate) fn discover_desktop_entries(
+    roots: &DiscoveryRoots,
+    settings: &AppSettings,
+) -> Discovery {
+    let mut apps = listed_entries(&desktop_entry_dirs(roots), &current_desktops(), settings);
+
+    if settings.show_appimages {
+        for path in appimage_dirs(roots)
+            .iter()
+            .flat_map(|dir| appimages_in(dir))
+        {
+            let name = path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            apps.push((
+                name,
+                DiscoveredApp {
+                    path,
+                    source: AppSource::AppImage,
+                    volume: None,
+                    via: None,
+                    desktop_id: None,
+                },
+            ));
+        }
+    }
+    sort_by_name(&mut apps);
+
+    Discovery {
+        apps: apps.into_iter().map(|(_, app)| app).collect(),
+        folders: Vec::new(),
+        broken_aliases: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// ~/.local/share, /usr/local/share and /usr/share applications
+    /// directories under a temporary root, in XDG_DATA_DIRS order
+    fn data_dirs(root: &Path) -> Vec<(AppSource, PathBuf)> {
+        ["home/me/.local/share", "usr/local/share", "usr/share"]
+            .iter()
+            .map(|dir| (AppSource::Applications, root.join(dir).join("applications")))
+            .collect()
+    }
+
+    fn write_entry(dir: &Path, relative: &str, keys: &str) {
+        let path = dir.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            path,
+            format!("[Desktop Entry]\nType=Application\nExec=true\n{keys}"),
+        )
+        .unwrap();
+    }
+
+    /// The listed entries' names and desktop-file ids, sorted by name
+    fn listed(dirs: &[(AppSource, PathBuf)], desktops: &[&str]) -> Vec<(String, String)> {
+        let desktops: Vec<String> = desktops.iter().map(|d| d.to_string()).collect();
+        let mut apps = listed_entries(dirs, &desktops, &AppSettings::default());
+        sort_by_name(&mut apps);
+        apps.into_iter()
+            .map(|(name, app)| (name, app.desktop_id.unwrap()))
+            .collect()
+    }
+
+    fn named(items: &[(&str, &str)]) -> Vec<(String, String)> {
+        items
+            .iter()
+            .map(|(name, id)| (name.to_string(), id.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn subdirectories_prefix_the_desktop_file_id() {
+        assert_eq!(
+            desktop_file_id(Path::new("kde4/foo.desktop")),
+            "kde4-foo.desktop"
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let dirs = data_dirs(dir.path());
+        let usr = &dirs[2].1;
+        write_entry(usr, "kde4/konsole.desktop", "Name=Konsole\n");
+        write_entry(usr, "kde4/dolphin.desktop", "Name=Dolphin\n");
+        write_entry(usr, "gedit.desktop", "Name=Text Editor\n");
+        assert_eq!(
+            listed(&dirs, &[]),
+            named(&[
+                ("Dolphin", "kde4-dolphin"),
+                ("Konsole", "kde4-konsole"),
+                ("Text Editor", "gedit"),
+            ])
+        );
+    }
+
+    #[test]
+    fn earlier_data_dirs_shadow_later_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let dirs = data_dirs(dir.path());
+        let (home, local, usr) = (&dirs[0].1, &dirs[1].1, &dirs[2].1);
+        write_entry(usr, "gedit.desktop", "Name=Text Editor\n");
+        write_entry(local, "gedit.desktop", "Name=Local Editor\n");
+        write_entry(home, "gedit.desktop", "Name=My Editor\n");
+        // A user's override hides the system entry, under either spelling
+        write_entry(usr, "kde4/konsole.desktop", "Name=Konsole\n");
+        write_entry(home, "kde4-konsole.desktop", "Name=Konsole\nHidden=true\n");
+        write_entry(usr, "firefox.desktop", "Name=Firefox\n");
+        write_entry(home, "firefox.desktop", "Name=Firefox\nNoDisplay=true\n");
+        // Unreadable shadows too
+        write_entry(usr, "vim.desktop", "Name=Vim\n");
+        fs::write(home.join("vim.desktop"), "not a desktop entry").unwrap();
+        // Other ids don't
+        write_entry(usr, "kde4/dolphin.desktop", "Name=Dolphin\n");
+        write_entry(home, "dolphin.desktop", "Name=My Dolphin\n");
+
+        assert_eq!(
+            listed(&dirs, &[]),
+            named(&[
+                ("Dolphin", "kde4-dolphin"),
+                ("My Dolphin", "dolphin"),
+                ("My Editor", "gedit"),
+            ])
+        );
+    }
+
+    #[test]
+    fn entries_not_shown_in_the_current_desktop_still_shadow() {
+        let dir = tempfile::tempdir().unwrap();
+        let dirs = data_dirs(dir.path());
+        let (home, usr) = (&dirs[0].1, &dirs[2].1);
+        write_entry(usr, "settings.desktop", "Name=Settings\n");
+        write_entry(
+            home,
+            "settings.desktop",
+            "Name=GNOME Settings\nOnlyShowIn=GNOME;\n",
+        );
+        write_entry(usr, "files.desktop", "Name=Files\nNotShowIn=KDE;\n");
+
+        assert_eq!(
+            listed(&dirs, &["ubuntu", "GNOME"]),
+            named(&[("Files", "files"), ("GNOME Settings", "settings")])
+        );
+        assert!(listed(&dirs, &["KDE"]).is_empty());
+    }
+
+    #[test]
+    fn disabled_sources_still_shadow() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut dirs = data_dirs(dir.path());
+        let flatpak = dir
+            .path()
+            .join("var/lib/flatpak/exports/share/applications");
+        dirs.insert(1, (AppSource::Flatpak, flatpak.clone()));
+        write_entry(&flatpak, "org.gnome.Maps.desktop", "Name=Maps\n");
+        write_entry(&dirs[3].1, "org.gnome.Maps.desktop", "Name=Maps (system)\n");
+
+        let settings = AppSettings {
+            show_flatpak_apps: false,
+            ..AppSettings::default()
+        };
+        assert!(listed_entries(&dirs, &[], &settings).is_empty());
+        let apps = listed_entries(&dirs, &[], &AppSettings::default());
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].1.source, AppSource::Flatpak);
+    }
+}
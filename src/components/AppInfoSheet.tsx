@@ -0,0 +1,216 @@
+import { useEffect, useState, type ReactNode } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Icon } from "@/components/ui/Icon";
+import { cn } from "@/utils/cn";
+import { useCloseAnimation } from "@/hooks/useCloseAnimation";
+import { useLatestRef } from "@/hooks/useLatestRef";
+import type {
+  AppDetails,
+  AppInfo,
+  Architecture,
+  CodeSignature,
+  Distribution,
+  Quarantine,
+} from "@/types/app";
+
+interface AppInfoSheetProps {
+  app: AppInfo;
+  onClose: () => void;
+}
+
+const ARCHITECTURE_LABELS: Record<Architecture, string> = {
+  arm64: "Apple Silicon",
+  x86_64: "Intel",
+  universal: "Universal",
+  other: "Unsupported",
+};
+
+const DISTRIBUTION_LABELS: Record<Distribution, string> = {
+  system: "Included with the system",
+  appStore: "App Store",
+  direct: "Downloaded directly",
+};
+
+const dateFormat = new Intl.DateTimeFormat(undefined, {
+  dateStyle: "medium",
+  timeStyle: "short",
+});
+
+function formatDate(ms: number | null): string | null {
+  return ms === null ? null : dateFormat.format(new Date(ms));
+}
+
+/** Decimal units, like Finder: "1.2 GB" */
+function formatSize(bytes: number | null): string | null {
+  if (bytes === null) return null;
+  const units = ["bytes", "KB", "MB", "GB", "TB"];
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1000 && unit < units.length - 1) {
+    value /= 1000;
+    unit++;
+  }
+  return unit === 0 ? `${bytes} bytes` : `${value.toFixed(value < 10 ? 1 : 0)} ${units[unit]}`;
+}
+
+function signatureText(signature: CodeSignature | null): string | null {
+  switch (signature?.status) {
+    case "unsigned":
+      return "Not signed";
+    case "adHoc":
+      return "Signed ad hoc (no developer identity)";
+    case "apple":
+      return signature.signer ?? "Apple";
+    case "signed":
+      return signature.signer ?? "Signed";
+    default:
+      return null;
+  }
+}
+
+function downloadText(quarantine: Quarantine | null): string | null {
+  if (!quarantine) return null;
+  const parts = [
+    quarantine.agent && `by ${quarantine.agent}`,
+    formatDate(quarantine.downloadedAt),
+    quarantine.neverOpened && "not opened yet",
+  ].filter(Boolean);
+  return parts.length ? parts.join(", ") : "From the internet";
+}
+
+function versionText(details: AppDetails): string | null {
+  if (!details.version) return details.build;
+  return details.build && details.build !== details.version
+    ? `${details.version} (${details.build})`
+    : details.version;
+}
+
+function Row({ label, children }: { label: string; children: ReactNode }) {
+  if (children === null || children === undefined || children === "") return null;
+  return (
+    <>
+      <dt className="text-white/50 text-right whitespace-nowrap">{label}</dt>
+      <dd className="text-white break-all">{children}</dd>
+    </>
+  );
+}
+
+/**
+ * The app's Get Info sheet, built from get_app_details: no Finder window,
+ * so no Automation consent prompt. Sizing walks the whole bundle, so the
+ * sheet opens at once and fills in when the details arrive.
+ */
+export function AppInfoSheet({ app, onClose }: AppInfoSheetProps) {
+  const { isClosing, triggerClose } = useCloseAnimation();
+  const onCloseRef = useLatestRef(onClose);
+  const [details, setDetails] = useState<AppDetails | null>(null);
+  const [failed, setFailed] = useState(false);
+
+  useEffect(() => {
+    let cancelled = false;
+    invoke<AppDetails>("get_app_details", { path: app.path })
+      .then((result) => {
+        if (!cancelled) setDetails(result);
+      })
+      .catch((e) => {
+        console.error("Failed to load app details:", e);
+        if (!cancelled) setFailed(true);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, [app.path]);
+
+  function handleClose() {
+    if (!triggerClose()) return;
+    setTimeout(() => onCloseRef.current(), 200); // Match animation duration
+  }
+
+  // The sheet is modal: it takes every key press before the layers under
+  // it (folder modal, grid navigation, search field) can, so Escape closes
+  // only the sheet. Window capture runs ahead of their document listeners.
+  const handleCloseRef = useLatestRef(handleClose);
+  useEffect(() => {
+    function onKeyDown(e: KeyboardEvent) {
+      if (e.metaKey) return; // ⌘Q and friends still reach the app
+      e.stopPropagation();
+      e.preventDefault();
+      if (e.key === "Escape" && !e.repeat) handleCloseRef.current();
+    }
+    window.addEventListener("keydown", onKeyDown, true);
+    return () => window.removeEventListener("keydown", onKeyDown, true);
+  }, [handleCloseRef]);
+
+  const documentTypes = details?.documentTypes
+    .map((type) => type.name ?? type.contentTypes[0] ?? type.extensions[0])
+    .filter((name): name is string => Boolean(name));
+
+  return (
+    <div
+      data-keep-open
+      className={cn(
+        "fixed inset-0 z-50 flex items-center justify-center",
+        isClosing ? "animate-fade-out" : "animate-fade-in"
+      )}
+      onClick={(e) => {
+        if (e.target === e.currentTarget) handleClose();
+      }}
+    >
+      <div
+        className={cn(
+          "bg-white/15 backdrop-blur-xl rounded-3xl p-8 w-full max-w-lg max-h-[80vh] overflow-y-auto",
+          isClosing ? "animate-scale-out" : "animate-scale-in"
+        )}
+      >
+        <div className="flex items-center gap-4 mb-6">
+          <Icon icon={app.icon} alt={app.name} />
+          <div className="min-w-0">
+            <h2 className="text-white text-xl font-medium truncate">{app.name}</h2>
+            {details && <p className="text-white/60 text-sm">{versionText(details)}</p>}
+          </div>
+        </div>
+
+        {failed && <p className="text-white/60 text-sm">Details couldn't be read.</p>}
+        {!details && !failed && <p className="text-white/60 text-sm">Loading…</p>}
+
+        {details && (
+          <dl className="grid grid-cols-[auto_1fr] gap-x-4 gap-y-1.5 text-sm">
+            <Row label="Problems">
+              {details.problems.length > 0 ? (
+                <ul>
+                  {details.problems.map((problem) => (
+                    <li
+                      key={problem.kind}
+                      className={problem.severity === "info" ? "text-white/70" : "text-amber-300"}
+                    >
+                      {problem.message}
+                    </li>
+                  ))}
+                </ul>
+              ) : null}
+            </Row>
+            <Row label="Kind">
+              {details.architecture &&
+                `${ARCHITECTURE_LABELS[details.architecture]}${
+                  details.needsRosetta ? " (runs through Rosetta)" : ""
+                }`}
+            </Row>
+            <Row label="Source">{DISTRIBUTION_LABELS[details.distribution]}</Row>
+            <Row label="Size">{formatSize(details.sizeOnDisk)}</Row>
+            <Row label="Where">{details.path}</Row>
+            <Row label="Identifier">{details.bundleId}</Row>
+            <Row label="Tags">{app.tags.map((tag) => tag.name).join(", ")}</Row>
+            <Row label="Signed by">{signatureText(details.signature)}</Row>
+            <Row label="Team ID">{details.signature?.teamId}</Row>
+            <Row label="Downloaded">{downloadText(details.quarantine)}</Row>
+            <Row label="From">{details.quarantine?.origin}</Row>
+            <Row label="Installed">{formatDate(details.installedAt)}</Row>
+            <Row label="Modified">{formatDate(details.modifiedAt)}</Row>
+            <Row label="Last opened">{formatDate(details.lastOpenedAt)}</Row>
+            <Row label="Opens">{documentTypes?.length ? documentTypes.join(", ") : null}</Row>
+          </dl>
+        )}
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,101 @@
+import { useEffect } from "react";
+import { Icon } from "@/components/ui/Icon";
+import { cn } from "@/utils/cn";
+import { useCloseAnimation } from "@/hooks/useCloseAnimation";
+import { useLatestRef } from "@/hooks/useLatestRef";
+import type { AppInfo } from "@/types/app";
+
+interface LaunchWarningSheetProps {
+  app: AppInfo;
+  /** Go ahead with the launch */
+  onOpen: () => void;
+  onCancel: () => void;
+}
+
+const dateFormat = new Intl.DateTimeFormat(undefined, { dateStyle: "medium" });
+
+function downloadText(app: AppInfo): string {
+  const agent = app.quarantine?.agent;
+  const downloadedAt = app.quarantine?.downloadedAt;
+  const when = downloadedAt ? ` on ${dateFormat.format(new Date(downloadedAt))}` : "";
+  return agent
+    ? `“${app.name}” was downloaded by ${agent}${when} and hasn't been opened yet.`
+    : `“${app.name}” was downloaded from the internet${when} and hasn't been opened yet.`;
+}
+
+/**
+ * Shown before the first launch of a downloaded app. macOS asks to confirm
+ * that launch in a Gatekeeper dialog, which would open behind the
+ * full-screen launcher; the launcher closes once the user goes ahead, so
+ * the dialog is in view.
+ */
+export function LaunchWarningSheet({ app, onOpen, onCancel }: LaunchWarningSheetProps) {
+  const { isClosing, triggerClose } = useCloseAnimation();
+  const onCancelRef = useLatestRef(onCancel);
+  const origin = app.quarantine?.origin;
+
+  function handleCancel() {
+    if (!triggerClose()) return;
+    setTimeout(() => onCancelRef.current(), 200); // Match animation duration
+  }
+
+  // Modal like the info sheet: it takes every key press first (window
+  // capture), Return opens and Escape cancels
+  const handlersRef = useLatestRef({ onOpen, handleCancel });
+  useEffect(() => {
+    function onKeyDown(e: KeyboardEvent) {
+      if (e.metaKey) return; // ⌘Q and friends still reach the app
+      e.stopPropagation();
+      e.preventDefault();
+      if (e.repeat) return;
+      if (e.key === "Escape") handlersRef.current.handleCancel();
+      if (e.key === "Enter") handlersRef.current.onOpen();
+    }
+    window.addEventListener("keydown", onKeyDown, true);
+    return () => window.removeEventListener("keydown", onKeyDown, true);
+  }, [handlersRef]);
+
+  return (
+    <div
+      data-keep-open
+      className={cn(
+        "fixed inset-0 z-50 flex items-center justify-center",
+        isClosing ? "animate-fade-out" : "animate-fade-in"
+      )}
+      onClick={(e) => {
+        if (e.target === e.currentTarget) handleCancel();
+      }}
+    >
+      <div
+        className={cn(
+          "bg-white/15 backdrop-blur-xl rounded-3xl p-8 w-full max-w-md",
+          isClosing ? "animate-scale-out" : "animate-scale-in"
+        )}
+      >
+        <div className="flex items-center gap-4 mb-4">
+          <Icon icon={app.icon} alt={app.name} />
+          <h2 className="text-white text-xl font-medium min-w-0 truncate">{app.name}</h2>
+        </div>
+        <p className="text-white/80 text-sm mb-2">{downloadText(app)}</p>
+        <p className="text-white/60 text-sm mb-2">
+          macOS will ask you to confirm before opening it for the first time.
+        </p>
+        {origin && <p className="text-white/50 text-xs break-all mb-2">From {origin}</p>}
+        <div className="flex justify-end gap-3 mt-6">
+          <button
+            className="rounded-lg px-4 py-1.5 text-sm text-white bg-white/10 hover:bg-white/20"
+            onClick={handleCancel}
+          >
+            Cancel
+          </button>
+          <button
+            className="rounded-lg px-4 py-1.5 text-sm text-white bg-accent hover:brightness-110"
+            onClick={onOpen}
+          >
+            Open
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
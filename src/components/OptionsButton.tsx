@@ -1,19 +1,54 @@
 import { useConfig } from "@/hooks/useConfig";
 import { showOptionsMenu } from "@/utils/optionsMenu";
+import type { ViewToggle } from "@/types/app";
+
+interface OptionsButtonProps {
+  /** Group tagged apps into folders; the menu offers it only when set */
+  onGroupByTags?: () => void;
+  /** Clear every "new" dot; offered only when set */
+  onMarkAllSeen?: () => void;
+}
 
 /**
  * The "…" view-options button (Apps-app style), anchored to the right of
- * the search field. Opens a native menu for switching the grid layout.
+ * the search field. Opens a native menu for switching the grid layout, the
+ * on/off view settings, which copy of a duplicated app shows, where new
+ * apps land and how long missing apps keep their places, plus grouping by
+ * Finder tag and clearing the "new" dots when they apply.
  */
-export function OptionsButton() {
-  const { layout, setLayout } = useConfig();
+export function OptionsButton({ onGroupByTags, onMarkAllSeen }: OptionsButtonProps) {
+  const {
+    layout,
+    setLayout,
+    toggles,
+    toggleView,
+    duplicatePolicy,
+    setDuplicatePolicy,
+    placementPolicy,
+    setPlacementPolicy,
+    ghostGracePeriodSecs,
+    setGhostGracePeriod,
+  } = useConfig();
+
+  function handleClick() {
+    const enabled = (Object.keys(toggles) as ViewToggle[]).filter((setting) => toggles[setting]);
+    showOptionsMenu(layout, enabled, duplicatePolicy, placementPolicy, ghostGracePeriodSecs, {
+      onLayout: setLayout,
+      onToggle: toggleView,
+      onDuplicatePolicy: setDuplicatePolicy,
+      onPlacementPolicy: setPlacementPolicy,
+      onGhostGracePeriod: setGhostGracePeriod,
+      onGroupByTags,
+      onMarkAllSeen,
+    });
+  }
 
   return (
     <button
       type="button"
       data-keep-open
       aria-label="View options"
-      onClick={() => showOptionsMenu(layout, setLayout)}
+      onClick={handleClick}
       className="absolute left-full top-1/2 ml-2.5 flex h-8 w-8 -translate-y-1/2 items-center justify-center rounded-full bg-white/10 text-lg leading-none text-white/60 transition-colors hover:bg-white/20 hover:text-white/90"
     >
       ⋯
@@ -219,6 +219,9 @@ interface PagedGridProps {
   /** Registers how a folder drag-out lands in the visible page's window;
    *  called with null on unmount */
   registerFolderInsert: (insert: PagedFolderInsert | null) => void;
+  /** Registers how many items a page holds, for placing new apps; called
+   *  with null on unmount */
+  registerPageCapacity: (capacity: number | null) => void;
 }
 
 interface PageProps {
@@ -435,6 +438,7 @@ export function PagedGrid({
   dropTarget,
   coordinator,
   registerFolderInsert,
+  registerPageCapacity,
 }: PagedGridProps) {
   const viewportRef = useRef<HTMLDivElement>(null);
   const [rows, setRows] = useState(0);
@@ -569,6 +573,11 @@ export function PagedGrid({
     return () => registerFolderInsert(null);
   }, [registerFolderInsert, folderInsertRef]);
 
+  useEffect(() => {
+    registerPageCapacity(perPage > 0 ? perPage : null);
+    return () => registerPageCapacity(null);
+  }, [registerPageCapacity, perPage]);
+
   /** The gesture is over (drop, cancel or teardown): release the handle
    *  and retire the pages it emptied. Retiring a page before the current
    *  one shifts the content under the viewport, so the current index
@@ -7,14 +7,19 @@ import { useDocumentEscape } from "@/hooks/useDocumentEscape";
 import { useKeyboardNav } from "@/hooks/useKeyboardNav";
 import { AppItem } from "@/components/items/AppItem";
 import { FolderItem, type GridFolder } from "@/components/items/FolderItem";
+import { AppInfoSheet } from "@/components/AppInfoSheet";
 import { FolderModal } from "@/components/FolderModal";
+import { LaunchWarningSheet } from "@/components/LaunchWarningSheet";
 import { OptionsButton } from "@/components/OptionsButton";
 import { PagedGrid, type PagedDragHandle } from "@/components/PagedGrid";
 import { SearchField } from "@/components/SearchField";
 import { IconGrid } from "@/components/ui/IconGrid";
 import { GRID_COLUMNS } from "@/constants/grid";
 import { cn } from "@/utils/cn";
-import { searchApps } from "@/utils/searchUtils";
+import { collectSearchableApps, searchApps } from "@/utils/searchUtils";
+import { canOpen } from "@/utils/appUtils";
+import { setGetInfoHandler } from "@/utils/appContextMenu";
+import type { AppInfo } from "@/types/app";
 
 export function Wafflepad() {
   const {
@@ -35,11 +40,14 @@ export function Wafflepad() {
     pageDragHandlers,
     setActivePageEngine,
     setPagedFolderInsert,
+    setPageCapacity,
     handleOpenFolder,
     handleCloseFolder,
     handleRenameFolder,
     handleFolderOrderChange,
     handleUngroupFolder,
+    handleGroupByTags,
+    markAllSeen,
     getOpenFolderSavedOrder,
   } = useGrid();
 
@@ -54,6 +62,15 @@ export function Wafflepad() {
   const [query, setQuery] = useState("");
   const { isClosing, setIsClosing, isClosingRef, triggerClose } = useCloseAnimation();
   const { layout } = useConfig();
+  // The app whose Get Info sheet is open (context menu "Get Info")
+  const [infoApp, setInfoApp] = useState<AppInfo | null>(null);
+  // A downloaded app awaiting confirmation before its first launch
+  const [warnApp, setWarnApp] = useState<AppInfo | null>(null);
+
+  useEffect(() => {
+    setGetInfoHandler(setInfoApp);
+    return () => setGetInfoHandler(null);
+  }, []);
 
   // A drag inside a page engine (paged layout); the main grid's isDragging
   // can't see those, so host guards combine both. The active engine is
@@ -122,8 +139,19 @@ export function Wafflepad() {
     invoke("quit_after_delay", { delayMs: CLOSE_ANIMATION_MS });
   }
 
-  function handleLaunch(path: string) {
+  function handleLaunch(path: string, confirmed = false) {
     if (isClosingRef.current) return;
+    // Greyed-out tiles (apps that can't open, shown by choice, and offline
+    // volumes' placeholders) stay put: launching would do nothing or fail
+    // behind the closing launcher
+    const app = collectSearchableApps(items).find((a) => a.path === path);
+    if (app && !canOpen(app)) return;
+    // A never-opened download stops at a Gatekeeper dialog: say so first
+    if (app?.quarantine?.neverOpened && !confirmed) {
+      setWarnApp(app);
+      return;
+    }
+    setWarnApp(null);
     isClosingRef.current = true;
 
     // Launch app immediately
@@ -159,7 +187,7 @@ export function Wafflepad() {
   const { selectedId } = useKeyboardNav({
     ids: navigableIds,
     columns: GRID_COLUMNS,
-    enabled: !openFolder && !anyDragging && !isClosing && !renamingFolderId,
+    enabled: !openFolder && !infoApp && !warnApp && !anyDragging && !isClosing && !renamingFolderId,
     autoSelectFirst: searchResults !== null,
     resetKey: searchQuery,
     onActivate: handleActivate,
@@ -213,8 +241,8 @@ export function Wafflepad() {
 
   // Close on click outside (empty space)
   function handleBackgroundClick(e: React.MouseEvent) {
-    // Don't close if folder is open, dragging, or clicking on an item
-    if (openFolder || anyDragging) return;
+    // Don't close if folder or a sheet is open, dragging, or clicking on an item
+    if (openFolder || infoApp || warnApp || anyDragging) return;
     // A press that traveled isn't a click: a drag can release as a click
     // on the common ancestor of press and release — the background — and
     // must not quit the launcher. Consumed on read so a click with no
@@ -265,6 +293,16 @@ export function Wafflepad() {
       onClick={handleBackgroundClick}
       onMouseDown={handleRootMouseDown}
     >
+      {infoApp && <AppInfoSheet app={infoApp} onClose={() => setInfoApp(null)} />}
+
+      {warnApp && (
+        <LaunchWarningSheet
+          app={warnApp}
+          onOpen={() => handleLaunch(warnApp.path, true)}
+          onCancel={() => setWarnApp(null)}
+        />
+      )}
+
       {openFolder && (
         <FolderModal
           key={openFolder.id}
@@ -294,7 +332,16 @@ export function Wafflepad() {
           onChange={setQuery}
           readOnly={anyDragging}
         >
-          <OptionsButton />
+          <OptionsButton
+            onGroupByTags={
+              items.some((item) => item.type === "app" && item.data.tags.length > 0)
+                ? handleGroupByTags
+                : undefined
+            }
+            onMarkAllSeen={
+              collectSearchableApps(items).some((app) => app.isNew) ? markAllSeen : undefined
+            }
+          />
         </SearchField>
 
         {searchResults &&
@@ -387,6 +434,7 @@ export function Wafflepad() {
             dropTarget={dropTarget}
             coordinator={coordinator}
             registerFolderInsert={setPagedFolderInsert}
+            registerPageCapacity={setPageCapacity}
           />
         )}
       </div>
@@ -3,12 +3,18 @@ import { cn } from "@/utils/cn";
 import { Container } from "@/components/ui/Container";
 import { Icon } from "@/components/ui/Icon";
 import { Label } from "@/components/ui/Label";
+import { Badge } from "@/components/ui/Badge";
 import { DropTarget } from "@/components/items/DropTarget";
 import { showAppContextMenu } from "@/utils/appContextMenu";
+import { launchIssue } from "@/utils/appUtils";
 import type { AppInfo } from "@/types/app";
 import type { DropAction } from "@/hooks/useFolderCreation";
 
-export type GridItem = AppInfo & { id: string };
+export type GridItem = AppInfo & {
+  id: string;
+  /** Just placed by the placement policy: plays the place-in animation */
+  justPlaced?: boolean;
+};
 
 export function AppItem({
   item,
@@ -37,6 +43,14 @@ export function AppItem({
   // Outlines the icon while its context menu is open (like the Apps app);
   // popup() resolves when the menu closes, either by action or dismissal
   const [isMenuOpen, setIsMenuOpen] = useState(false);
+  // Shown only when the user opted to see apps that can't open: greyed
+  // out, with the reason as tooltip (the launch itself is refused upstream)
+  const issue = launchIssue(item.launchability);
+  // An unplugged volume's app holds its place, greyed out the same way
+  const offlineNote = item.offline ? `“${item.volume}” isn't connected` : null;
+  // The most severe problem the audit found, when it may keep the app from
+  // opening (cosmetic ones show only in Get Info)
+  const problem = item.problems?.find((p) => p.severity !== "info") ?? null;
 
   function handleClick() {
     // Only launch if not currently dragging
@@ -47,7 +61,8 @@ export function AppItem({
 
   async function handleContextMenu(e: React.MouseEvent) {
     e.preventDefault();
-    if (isDragActive) return;
+    // Every action needs the bundle, which an offline placeholder lacks
+    if (isDragActive || item.offline) return;
     setIsMenuOpen(true);
     try {
       await showAppContextMenu(item, {
@@ -65,8 +80,10 @@ export function AppItem({
       data-id={item.id}
       onClick={handleClick}
       onContextMenu={handleContextMenu}
+      title={offlineNote ?? issue ?? undefined}
       className={cn(
         "relative",
+        (issue || offlineNote) && "opacity-40 grayscale",
         // Transition for smooth shifting during drag
         isDragActive && "transition-transform duration-200",
         // Hide original when being dragged (ghost is visible instead)
@@ -76,7 +93,7 @@ export function AppItem({
         isSelected && "bg-white/15"
       )}
     >
-      <div className="relative" data-drag-handle>
+      <div className={cn("relative", item.justPlaced && "animate-place-in")} data-drag-handle>
         <DropTarget action={dropAction ?? null} />
         {isMenuOpen && (
           // The ring draws outward from this box, wrapping the icon's visible
@@ -87,8 +104,25 @@ export function AppItem({
           <div className="absolute inset-2.25 rounded-[20px] ring-3 ring-accent pointer-events-none" />
         )}
         <Icon icon={item.icon} alt={item.name} />
+        {item.offline ? (
+          <Badge>Offline</Badge>
+        ) : problem ? (
+          <Badge title={problem.message} className="bg-amber-500/80 text-black/90">
+            Problem
+          </Badge>
+        ) : item.quarantine?.neverOpened ? (
+          <Badge title="Downloaded from the internet, never opened">Downloaded</Badge>
+        ) : (
+          item.needsRosetta && <Badge title="Intel-only: runs through Rosetta">Intel</Badge>
+        )}
       </div>
-      <Label>{item.name}</Label>
+      <Label
+        detail={item.disambiguation}
+        isNew={item.isNew}
+        newTitle={item.updatedAt !== null ? "Updated" : "New"}
+      >
+        {item.name}
+      </Label>
     </Container>
   );
 }
@@ -12,6 +12,8 @@ export interface GridFolder {
   id: string;
   name: string;
   apps: AppInfo[];
+  /** A new app was just placed in it (same animation as AppItem's) */
+  justPlaced?: boolean;
 }
 
 /** Preview density tiers: capacity and the grid classes that render it */
@@ -189,7 +191,11 @@ export function FolderItem({
         isSelected && "bg-white/15"
       )}
     >
-      <div className="relative" data-drag-handle onClick={handleClick}>
+      <div
+        className={cn("relative", item.justPlaced && "animate-place-in")}
+        data-drag-handle
+        onClick={handleClick}
+      >
         <DropTarget action={dropAction ?? null} />
         {isMenuOpen && (
           // Unlike an app icon's PNG (transparent margins, see AppItem),
@@ -202,7 +208,9 @@ export function FolderItem({
       {isRenaming ? (
         <RenameInput name={item.name} onDone={(newName) => onRenameEnd(item, newName)} />
       ) : (
-        <Label>{item.name}</Label>
+        <Label isNew={item.apps.some((app) => app.isNew)} newTitle="Has new apps">
+          {item.name}
+        </Label>
       )}
     </Container>
   );
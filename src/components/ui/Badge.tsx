@@ -0,0 +1,27 @@
+import type { ReactNode } from "react";
+import { cn } from "@/utils/cn";
+
+interface BadgeProps {
+  children: ReactNode;
+  /** Native tooltip explaining the badge */
+  title?: string;
+  className?: string;
+}
+
+/**
+ * Small status pill pinned to the lower right of a tile's icon (inside
+ * the icon's transparent margin, so it sits on the artwork's corner).
+ */
+export function Badge({ children, title, className }: BadgeProps) {
+  return (
+    <span
+      title={title}
+      className={cn(
+        "absolute right-1 bottom-1 rounded-full bg-black/60 px-1.5 py-px text-[10px] font-medium leading-tight text-white/90 ring-1 ring-white/20",
+        className
+      )}
+    >
+      {children}
+    </span>
+  );
+}
@@ -5,6 +5,8 @@ interface ContainerProps {
   children: ReactNode;
   className?: string;
   style?: CSSProperties;
+  /** Native tooltip */
+  title?: string;
   ref?: Ref<HTMLDivElement>;
   onClick?: MouseEventHandler<HTMLDivElement>;
   onContextMenu?: MouseEventHandler<HTMLDivElement>;
@@ -16,6 +18,7 @@ export function Container({
   children,
   className,
   style,
+  title,
   ref,
   onClick,
   onContextMenu,
@@ -26,6 +29,7 @@ export function Container({
     <div
       ref={ref}
       style={style}
+      title={title}
       onClick={onClick}
       onContextMenu={onContextMenu}
       data-grid-item
@@ -2,12 +2,31 @@ import type { ReactNode } from "react";
 
 interface LabelProps {
   children: ReactNode;
+  /** Secondary line, e.g. what tells two copies of an app apart */
+  detail?: string | null;
+  /** Launchpad's blue dot before the name: installed or updated since seen */
+  isNew?: boolean;
+  /** Tooltip for the dot */
+  newTitle?: string;
 }
 
-export function Label({ children }: LabelProps) {
+export function Label({ children, detail, isNew, newTitle }: LabelProps) {
   return (
-    <span className="text-xs text-white mt-1 w-full text-center leading-normal line-clamp-2">
-      {children}
-    </span>
+    <>
+      <span className="text-xs text-white mt-1 w-full text-center leading-normal line-clamp-2">
+        {isNew && (
+          <span
+            title={newTitle}
+            className="inline-block size-1.5 rounded-full bg-sky-400 mr-1 align-middle"
+          />
+        )}
+        {children}
+      </span>
+      {detail && (
+        <span className="text-[10px] text-white/50 w-full text-center leading-tight truncate">
+          {detail}
+        </span>
+      )}
+    </>
   );
 }
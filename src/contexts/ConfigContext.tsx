@@ -1,6 +1,15 @@
 import { use, useState, type ReactNode } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import type { AppConfig, FolderMetadata, LayoutMode, OrderConfig } from "@/types/app";
+import type {
+  AppConfig,
+  AppSettings,
+  DuplicatePolicy,
+  FolderMetadata,
+  LayoutMode,
+  OrderConfig,
+  PlacementPolicy,
+  ViewToggle,
+} from "@/types/app";
 import { ConfigContext, type ConfigContextValue } from "./config";
 
 // Start loading config immediately at module load (parallel with app loading)
@@ -9,6 +18,33 @@ const configPromise: Promise<AppConfig | null> = invoke<AppConfig>("load_config"
   return null;
 });
 
+// Must match the backend's AppSettings defaults
+const DEFAULT_TOGGLES: Record<ViewToggle, boolean> = {
+  showUnlaunchable: false,
+  showWebApps: true,
+  showSetappApps: true,
+  showHomebrewApps: true,
+  showIosApps: true,
+  showExternalVolumes: false,
+  showFlatpakApps: true,
+  showSnapApps: true,
+  showAppimages: true,
+};
+
+function togglesOf(settings: AppSettings): Record<ViewToggle, boolean> {
+  return {
+    showUnlaunchable: settings.showUnlaunchable,
+    showWebApps: settings.showWebApps,
+    showSetappApps: settings.showSetappApps,
+    showHomebrewApps: settings.showHomebrewApps,
+    showIosApps: settings.showIosApps,
+    showExternalVolumes: settings.showExternalVolumes,
+    showFlatpakApps: settings.showFlatpakApps,
+    showSnapApps: settings.showSnapApps,
+    showAppimages: settings.showAppimages,
+  };
+}
+
 interface ConfigProviderProps {
   children: ReactNode;
 }
@@ -25,6 +61,19 @@ export function ConfigProvider({ children }: ConfigProviderProps) {
   // Paged by default: pages are the point of the app (must match the
   // backend's LayoutMode default, which the first save writes)
   const [layout, setLayoutState] = useState<LayoutMode>(config?.settings.layout ?? "paged");
+  const [toggles, setToggles] = useState(() =>
+    config ? togglesOf(config.settings) : DEFAULT_TOGGLES
+  );
+  const [duplicatePolicy, setDuplicatePolicyState] = useState<DuplicatePolicy>(
+    config?.settings.duplicatePolicy ?? "preferSystem"
+  );
+  const [placementPolicy, setPlacementPolicyState] = useState<PlacementPolicy>(
+    config?.settings.placementPolicy ?? "endOfLastPage"
+  );
+  // A day by default (must match the backend's AppSettings default)
+  const [ghostGracePeriodSecs, setGhostGracePeriodState] = useState(
+    config?.settings.ghostGracePeriodSecs ?? 24 * 60 * 60
+  );
 
   // Update order in Rust memory (no disk I/O); Rust saves to disk on
   // window close for safety. Rejected whenever the backend holds no config
@@ -46,11 +95,56 @@ export function ConfigProvider({ children }: ConfigProviderProps) {
     );
   }
 
+  // Same persistence as setLayout, through the field-level set_toggle. The
+  // state follows once the backend holds the value: provider toggles
+  // change what the next refresh_apps discovers (see setDuplicatePolicy)
+  function toggleView(setting: ViewToggle) {
+    const enabled = !toggles[setting];
+    invoke("set_toggle", { setting, enabled })
+      .then(() => setToggles((prev) => ({ ...prev, [setting]: enabled })))
+      .catch((e) => console.error("Failed to save settings:", e));
+  }
+
+  // Same persistence as setLayout, but the state only follows once the
+  // backend holds the policy: useApps re-validates the app list on the
+  // change, and that refresh must already see the new policy
+  function setDuplicatePolicy(policy: DuplicatePolicy) {
+    invoke("set_duplicate_policy", { policy })
+      .then(() => setDuplicatePolicyState(policy))
+      .catch((e) => console.error("Failed to save settings:", e));
+  }
+
+  // Like setDuplicatePolicy, the state follows once the backend holds the
+  // policy. It applies from the next reconciliation on: nothing already
+  // placed moves
+  function setPlacementPolicy(policy: PlacementPolicy) {
+    invoke("set_placement_policy", { policy })
+      .then(() => setPlacementPolicyState(policy))
+      .catch((e) => console.error("Failed to save settings:", e));
+  }
+
+  // Like setPlacementPolicy: applies from the next reconciliation on,
+  // when ghosts older than the new period are dropped
+  function setGhostGracePeriod(secs: number) {
+    invoke("set_ghost_grace_period", { secs })
+      .then(() => setGhostGracePeriodState(secs))
+      .catch((e) => console.error("Failed to save settings:", e));
+  }
+
   const value: ConfigContextValue = {
     orderConfig,
     saveOrder,
     layout,
     setLayout,
+    showUnlaunchable: toggles.showUnlaunchable,
+    toggles,
+    toggleView,
+    duplicatePolicy,
+    setDuplicatePolicy,
+    placementPolicy,
+    setPlacementPolicy,
+    ghostGracePeriodSecs,
+    setGhostGracePeriod,
   };
 
   return (
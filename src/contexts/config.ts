@@ -1,5 +1,12 @@
 import { createContext } from "react";
-import type { FolderMetadata, LayoutMode, OrderConfig } from "@/types/app";
+import type {
+  DuplicatePolicy,
+  FolderMetadata,
+  LayoutMode,
+  OrderConfig,
+  PlacementPolicy,
+  ViewToggle,
+} from "@/types/app";
 
 export interface ConfigContextValue {
   // Order config (for reading initial state)
@@ -11,6 +18,21 @@ export interface ConfigContextValue {
   // View settings
   layout: LayoutMode;
   setLayout: (layout: LayoutMode) => void;
+  /** Show bundles that can't run, greyed out */
+  showUnlaunchable: boolean;
+  /** Every on/off view setting, by name */
+  toggles: Record<ViewToggle, boolean>;
+  /** Flip an on/off view setting (persisted immediately, like layout) */
+  toggleView: (setting: ViewToggle) => void;
+  /** Which copy of a duplicated app is shown */
+  duplicatePolicy: DuplicatePolicy;
+  setDuplicatePolicy: (policy: DuplicatePolicy) => void;
+  /** Where apps new to the arrangement land */
+  placementPolicy: PlacementPolicy;
+  setPlacementPolicy: (policy: PlacementPolicy) => void;
+  /** How long a missing app keeps its slot, in seconds */
+  ghostGracePeriodSecs: number;
+  setGhostGracePeriod: (secs: number) => void;
 }
 
 export const ConfigContext = createContext<ConfigContextValue | null>(null);
@@ -1,83 +1,220 @@
-import { use, useState, useEffect, useRef } from "react";
+import { use, useState, useEffect, useRef, type Dispatch, type SetStateAction } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import type { AppInfo, FolderInfo } from "@/types/app";
+import { listen } from "@tauri-apps/api/event";
+import type { AppAudit, AppInfo, AppProblem, AppSize, AppsDiff, FolderInfo } from "@/types/app";
 import { getAppsPromise } from "@/lib/appsApi";
+import { buildAppsMap } from "@/utils/appUtils";
+import { iconPixelSize } from "@/utils/iconUtils";
+import { useLatestRef } from "@/hooks/useLatestRef";
+
+interface UseAppsOptions {
+  /** Main-grid apps that turned up after the list was served (the
+   *  background re-validation found them), for placement in the grid */
+  onAppsAdded?: (apps: AppInfo[]) => void;
+  /** Re-validate the list whenever this changes: settings that shape what
+   *  the backend discovers and serves (providers, the duplicate policy) */
+  refreshKey?: unknown;
+}
 
 interface UseAppsResult {
   apps: AppInfo[];
   folders: FolderInfo[];
+  /** Clear every app's "new" flag, here and in the backend's record */
+  markAllSeen: () => void;
+}
+
+/**
+ * Generate missing icons one request per app, handing loaded icons to
+ * `apply` in batches to reduce re-renders. Returns a cancel function.
+ */
+function loadIcons(
+  apps: AppInfo[],
+  apply: (batch: Map<string, string>) => void
+): () => void {
+  // Offline placeholders have no bundle to take an icon from
+  const appsWithoutIcons = apps.filter((app) => !app.icon && !app.offline);
+  if (appsWithoutIcons.length === 0) return () => {};
+
+  // Accumulate loaded icons, flush in batches
+  const pendingIcons = new Map<string, string>();
+  let remaining = appsWithoutIcons.length;
+  let cancelled = false;
+
+  function flush() {
+    if (cancelled || pendingIcons.size === 0) return;
+    const batch = new Map(pendingIcons);
+    pendingIcons.clear();
+    apply(batch);
+  }
+
+  const interval = setInterval(flush, 100);
+  const size = iconPixelSize();
+
+  for (const app of appsWithoutIcons) {
+    invoke<string | null>("get_app_icon", { path: app.path, size })
+      .then((icon) => {
+        if (icon) pendingIcons.set(app.path, icon);
+      })
+      .catch((e) => console.error(`Failed to load icon for ${app.path}:`, e))
+      .finally(() => {
+        remaining--;
+        if (remaining === 0) {
+          clearInterval(interval);
+          flush();
+        }
+      });
+  }
+
+  return () => {
+    cancelled = true;
+    clearInterval(interval);
+  };
+}
+
+/** Swap loaded icons into the apps and physical folders */
+function applyIcons(
+  batch: Map<string, string>,
+  setApps: Dispatch<SetStateAction<AppInfo[]>>,
+  setFolders: Dispatch<SetStateAction<FolderInfo[]>>
+) {
+  setApps((prev) =>
+    prev.map((a) => {
+      const icon = batch.get(a.path);
+      return icon ? { ...a, icon } : a;
+    })
+  );
+  setFolders((prev) =>
+    prev.map((folder) => ({
+      ...folder,
+      apps: folder.apps.map((a) => {
+        const icon = batch.get(a.path);
+        return icon ? { ...a, icon } : a;
+      }),
+    }))
+  );
+}
+
+/** Set every app's audited problems (none for apps the audit didn't list) */
+function applyProblems(
+  audits: AppAudit[],
+  setApps: Dispatch<SetStateAction<AppInfo[]>>,
+  setFolders: Dispatch<SetStateAction<FolderInfo[]>>
+) {
+  const byPath = new Map<string, AppProblem[]>(
+    audits.map((audit) => [audit.path, audit.problems])
+  );
+  const withProblems = (a: AppInfo) => ({ ...a, problems: byPath.get(a.path) ?? [] });
+  setApps((prev) => prev.map(withProblems));
+  setFolders((prev) =>
+    prev.map((folder) => ({ ...folder, apps: folder.apps.map(withProblems) }))
+  );
+}
+
+/** Set the sizes known so far on every app that has one */
+function applySizes(
+  sizes: Map<string, number>,
+  setApps: Dispatch<SetStateAction<AppInfo[]>>,
+  setFolders: Dispatch<SetStateAction<FolderInfo[]>>
+) {
+  const withSize = (a: AppInfo) => {
+    const sizeOnDisk = sizes.get(a.path);
+    return sizeOnDisk === undefined || sizeOnDisk === a.sizeOnDisk ? a : { ...a, sizeOnDisk };
+  };
+  setApps((prev) => prev.map(withSize));
+  setFolders((prev) =>
+    prev.map((folder) => ({ ...folder, apps: folder.apps.map(withSize) }))
+  );
 }
 
-export function useApps(): UseAppsResult {
+export function useApps({ onAppsAdded, refreshKey }: UseAppsOptions = {}): UseAppsResult {
   // use() suspends until the promise resolves - data is available immediately after
   const initialData = use(getAppsPromise());
 
   // State for progressive icon updates
   const [apps, setApps] = useState(initialData.apps);
   const [folders, setFolders] = useState(initialData.folders);
-  const iconsLoadedRef = useRef(false);
+  const loadedRef = useRef(false);
+  const refreshedKeyRef = useRef<{ key: unknown } | null>(null);
+  const onAppsAddedRef = useLatestRef(onAppsAdded);
+  // Every size the scan has reported: a refresh diff may replace apps
+  // after their size came in, so it is applied again then
+  const sizesRef = useRef(new Map<string, number>());
 
-  // Load icons progressively after initial render, batching state updates
+  // Sizes stream in from the background scan each refresh starts
   useEffect(() => {
-    if (iconsLoadedRef.current) return;
-    iconsLoadedRef.current = true;
+    const unlisten = listen<AppSize[]>("app-sizes", (event) => {
+      for (const { path, bytes } of event.payload) sizesRef.current.set(path, bytes);
+      applySizes(sizesRef.current, setApps, setFolders);
+    });
+    unlisten.catch((e) => console.error("Failed to listen for app sizes:", e));
+    return () => {
+      unlisten.then((stop) => stop()).catch(() => {});
+    };
+  }, []);
+
+  // Load icons progressively after initial render
+  useEffect(() => {
+    if (loadedRef.current) return;
+    loadedRef.current = true;
 
     const allApps = [
       ...initialData.apps,
       ...initialData.folders.flatMap((folder) => folder.apps),
     ];
-    const appsWithoutIcons = allApps.filter((app) => !app.icon);
-    if (appsWithoutIcons.length === 0) return;
-
-    // Accumulate loaded icons, flush in batches to reduce re-renders
-    const pendingIcons = new Map<string, string>();
-    let remaining = appsWithoutIcons.length;
-    let cancelled = false;
-
-    function flush() {
-      if (cancelled || pendingIcons.size === 0) return;
-      const batch = new Map(pendingIcons);
-      pendingIcons.clear();
-
-      setApps((prev) =>
-        prev.map((a) => {
-          const icon = batch.get(a.path);
-          return icon ? { ...a, icon } : a;
-        })
-      );
-      setFolders((prev) =>
-        prev.map((folder) => ({
-          ...folder,
-          apps: folder.apps.map((a) => {
-            const icon = batch.get(a.path);
-            return icon ? { ...a, icon } : a;
-          }),
-        }))
-      );
-    }
-
-    const interval = setInterval(flush, 100);
-
-    for (const app of appsWithoutIcons) {
-      invoke<string | null>("get_app_icon", { path: app.path })
-        .then((icon) => {
-          if (icon) pendingIcons.set(app.path, icon);
-        })
-        .catch((e) => console.error(`Failed to load icon for ${app.path}:`, e))
-        .finally(() => {
-          remaining--;
-          if (remaining === 0) {
-            clearInterval(interval);
-            flush();
-          }
-        });
-    }
-
-    return () => {
-      cancelled = true;
-      clearInterval(interval);
-    };
+    return loadIcons(
+      allApps,
+      (batch) => applyIcons(batch, setApps, setFolders)
+    );
   }, [initialData]);
 
-  return { apps, folders };
+  // Re-validate the served list: it may come from the discovery index (the
+  // previous launch's scan), so apply whatever changed on disk since. Runs
+  // again whenever refreshKey changes (settings that shape the list). Once
+  // per key, and a diff is applied even after cleanup: the backend moves
+  // its served list on with every refresh, so a dropped diff is lost.
+  useEffect(() => {
+    if (refreshedKeyRef.current && Object.is(refreshedKeyRef.current.key, refreshKey)) return;
+    refreshedKeyRef.current = { key: refreshKey };
+
+    invoke<AppsDiff>("refresh_apps")
+      .then((diff) => {
+        const removed = new Set(diff.removed);
+        const updated = buildAppsMap(diff.updated);
+        setApps((prev) => [
+          ...prev.filter((a) => !removed.has(a.path)).map((a) => updated.get(a.path) ?? a),
+          ...diff.added,
+        ]);
+        if (diff.folders) setFolders(diff.folders);
+        applySizes(sizesRef.current, setApps, setFolders);
+        if (diff.added.length > 0) {
+          onAppsAddedRef.current?.(diff.added);
+        }
+
+        // Changed bundles invalidate their cached icon
+        const changed = [
+          ...diff.added,
+          ...diff.updated,
+          ...(diff.folders?.flatMap((folder) => folder.apps) ?? []),
+        ];
+        loadIcons(
+          changed,
+          (batch) => applyIcons(batch, setApps, setFolders)
+        );
+
+        // Audit against the list just applied (the backend's served list)
+        return invoke<AppAudit[]>("audit_apps")
+          .then((audits) => applyProblems(audits, setApps, setFolders))
+          .catch((e) => console.error("Failed to audit apps:", e));
+      })
+      .catch((e) => console.error("Failed to refresh apps:", e));
+  }, [refreshKey, onAppsAddedRef]);
+
+  function markAllSeen() {
+    const seen = (a: AppInfo) => (a.isNew ? { ...a, isNew: false, updatedAt: null } : a);
+    setApps((prev) => prev.map(seen));
+    setFolders((prev) => prev.map((folder) => ({ ...folder, apps: folder.apps.map(seen) })));
+    invoke("mark_all_apps_seen").catch((e) => console.error("Failed to mark apps seen:", e));
+  }
+
+  return { apps, folders, markAllSeen };
 }
@@ -1,8 +1,12 @@
 import { useState } from "react";
 import type { AppInfo, FolderMetadata } from "@/types/app";
-import { dissolveFolder, removeAppFromFolder, updateFolderById } from "@/utils/folderUtils";
+import {
+  dissolveFolder,
+  groupByTags,
+  removeAppFromFolder,
+  updateFolderById,
+} from "@/utils/folderUtils";
 import { removeFromPages, replaceInPages } from "@/utils/pageUtils";
-import { categoryDisplayName } from "@/utils/appUtils";
 import type { GridFolder } from "@/components/items/FolderItem";
 
 interface UseFolderOperationsOptions {
@@ -53,10 +57,12 @@ export function useFolderOperations({
 
   function handleCreateFolder(sourceAppId: string, targetAppId: string) {
     // Suggest a name from the apps' App Store category, like Launchpad:
-    // prefer the target's category, fall back to the source's
+    // prefer the target's category, fall back to the source's (what
+    // suggest_folder_name picks for two apps; a drop can't wait for the
+    // round trip)
     const suggestedName =
-      categoryDisplayName(appsMap.get(targetAppId)?.category) ??
-      categoryDisplayName(appsMap.get(sourceAppId)?.category) ??
+      appsMap.get(targetAppId)?.categoryName ??
+      appsMap.get(sourceAppId)?.categoryName ??
       undefined;
 
     const newFolder = createNewFolder([targetAppId, sourceAppId], suggestedName);
@@ -117,6 +123,14 @@ export function useFolderOperations({
     setPages(newPages);
   }
 
+  /** Gather tagged apps into folders named after their Finder tags
+   *  (options menu "Group by Finder Tags") */
+  function handleGroupByTags() {
+    const { newPages, updatedFolders } = groupByTags(pages, folders, appsMap);
+    setFolders(updatedFolders);
+    setPages(newPages);
+  }
+
   function getOpenFolderSavedOrder(): string[] | undefined {
     if (!openFolderId) return undefined;
     return folders.find((f) => f.id === openFolderId)?.appPaths;
@@ -134,6 +148,7 @@ export function useFolderOperations({
     handleCreateFolder,
     handleAddToFolder,
     handleUngroupFolder,
+    handleGroupByTags,
     getOpenFolderSavedOrder,
   };
 }
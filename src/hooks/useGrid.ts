@@ -1,4 +1,5 @@
 import { useEffect, useRef, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import { useApps } from "@/hooks/useApps";
 import { arrayMove, useDragGrid } from "@/hooks/useDragGrid";
 import { useFolders } from "@/hooks/useFolders";
@@ -6,7 +7,7 @@ import { useFolderCreation } from "@/hooks/useFolderCreation";
 import { useConfig, useDndSettings } from "@/hooks/useConfig";
 import { useGridData } from "@/hooks/useGridData";
 import { useFolderOperations } from "@/hooks/useFolderOperations";
-import { resolveFolderApps } from "@/utils/folderUtils";
+import { isFolderId, resolveFolderApps } from "@/utils/folderUtils";
 import {
   compactPages,
   flattenPages,
@@ -20,7 +21,9 @@ import { useDragHandoff, type PagedFolderInsert } from "@/hooks/useDragHandoff";
 import { useDockDrag } from "@/hooks/useDockDrag";
 import type { DragMoveInfo, DragEndInfo, DropAnimationInfo } from "@/hooks/useDragGrid";
 import type { DragEngine, DropAnimationTarget } from "@/lib/helper-dnd";
-import type { FolderMetadata } from "@/types/app";
+import type { AppInfo, FolderMetadata, Placement, ReconciledLayout } from "@/types/app";
+import { useLatestRef } from "@/hooks/useLatestRef";
+import { isLaunchable } from "@/utils/appUtils";
 
 export type { GridItemUnion } from "@/hooks/useGridData";
 
@@ -37,8 +40,36 @@ export interface PageDragHandlers {
 }
 
 export function useGrid() {
-  const { apps, folders: physicalFolders } = useApps();
-  const { orderConfig, saveOrder, layout } = useConfig();
+  const { orderConfig, saveOrder, layout, showUnlaunchable, toggles, duplicatePolicy } =
+    useConfig();
+  // Settings that change what the backend discovers or serves: the list
+  // is re-validated whenever one of them changes
+  const discoveryKey = [
+    duplicatePolicy,
+    toggles.showWebApps,
+    toggles.showSetappApps,
+    toggles.showHomebrewApps,
+    toggles.showIosApps,
+    toggles.showExternalVolumes,
+    toggles.showFlatpakApps,
+    toggles.showSnapApps,
+    toggles.showAppimages,
+  ].join();
+  const {
+    apps: discoveredApps,
+    folders: discoveredFolders,
+    markAllSeen,
+  } = useApps({
+    onAppsAdded: placeAddedApps,
+    refreshKey: discoveryKey,
+  });
+
+  // Bundles that can't run (helpers, damaged apps, apps for a newer macOS)
+  // stay out of the grid unless the user opted to see them greyed out
+  const apps = showUnlaunchable ? discoveredApps : discoveredApps.filter(isLaunchable);
+  const physicalFolders = showUnlaunchable
+    ? discoveredFolders
+    : discoveredFolders.map((folder) => ({ ...folder, apps: folder.apps.filter(isLaunchable) }));
 
   // Folders management — seeded from config by useGridData's init pass,
   // then local state is the single source of truth. (A derived fallback to
@@ -137,6 +168,98 @@ export function useGrid() {
   });
 
   const pages = splitBySizes(dragGrid.order ?? [], pageSizes);
+  const latestLayoutRef = useLatestRef({ pages, folders });
+
+  // While the paged layout is active, PagedGrid registers how many items
+  // a page holds, so placements fill pages the way they are shown
+  // (scroll layout: null, the backend goes by the fullest saved page)
+  const pageCapacityRef = useRef<number | null>(null);
+  const [setPageCapacity] = useState(
+    () =>
+      (capacity: number | null) => {
+        pageCapacityRef.current = capacity;
+      }
+  );
+
+  // Items the placement policy just placed play the place-in animation;
+  // the set clears once it has run
+  const [placedIds, setPlacedIds] = useState<ReadonlySet<string>>(() => new Set());
+  const placedTimerRef = useRef<ReturnType<typeof setTimeout> | null>(null);
+  function markPlaced(placements: Placement[]) {
+    if (placements.length === 0) return;
+    setPlacedIds(
+      new Set(placements.map((p) => (p.kind === "folder" ? p.folderId : p.id)))
+    );
+    if (placedTimerRef.current) clearTimeout(placedTimerRef.current);
+    placedTimerRef.current = setTimeout(() => setPlacedIds(new Set()), 600);
+  }
+
+  /** Apps that turned up after seeding (the launch list came from the
+   *  discovery index) are placed by the placement policy, like apps found
+   *  new at launch, or go back to their ghost's slot: the backend
+   *  reconciles the current arrangement plus them. Everything already
+   *  arranged counts as installed, so nothing in it becomes a ghost.
+   *  Should the arrangement change while it does (a drag, a folder edit),
+   *  its answer is stale and they join the end of the last page instead —
+   *  functional on the flat order alone: entries beyond the page sizes
+   *  join the last page (splitBySizes). Before seeding this is a no-op —
+   *  the seed pass places them from the app list. */
+  function placeAddedApps(added: AppInfo[]) {
+    if (!dragGrid.order) return;
+    const known = new Set(dragGrid.order);
+    const fresh = (showUnlaunchable ? added : added.filter(isLaunchable))
+      .map((a) => a.path)
+      .filter((path) => !known.has(path));
+    if (fresh.length === 0) return;
+
+    function appendFresh() {
+      dragGrid.setOrder((prev) => {
+        if (!prev) return prev;
+        const current = new Set(prev);
+        const missing = fresh.filter((path) => !current.has(path));
+        return missing.length > 0 ? [...prev, ...missing] : prev;
+      });
+    }
+
+    const snapshot = latestLayoutRef.current;
+    invoke<ReconciledLayout>("reconcile_layout", {
+      pages: snapshot.pages,
+      folders: snapshot.folders,
+      appPaths: [
+        ...snapshot.pages.flat().filter((id) => !isFolderId(id)),
+        ...snapshot.folders.flatMap((folder) => folder.appPaths),
+        ...fresh,
+      ],
+      folderAppPaths: [],
+      pageCapacity: pageCapacityRef.current,
+    })
+      .then((result) => {
+        const latest = latestLayoutRef.current;
+        if (latest.folders !== snapshot.folders || !pagesEqual(latest.pages, snapshot.pages)) {
+          appendFresh();
+          return;
+        }
+        setFolders(result.folders);
+        setPages(result.pages);
+        markPlaced(result.placements);
+      })
+      .catch((e) => {
+        console.error("Failed to place new apps:", e);
+        appendFresh();
+      });
+  }
+
+  // Turning "Show Apps That Can't Open" on mid-session places the newly
+  // shown apps like any late arrival. Turning it off needs nothing: their
+  // ids no longer resolve, so they don't render, and the next launch's
+  // reconciliation prunes them.
+  const [placedUnlaunchable, setPlacedUnlaunchable] = useState(showUnlaunchable);
+  if (placedUnlaunchable !== showUnlaunchable) {
+    setPlacedUnlaunchable(showUnlaunchable);
+    if (showUnlaunchable) {
+      placeAddedApps(discoveredApps.filter((app) => !isLaunchable(app)));
+    }
+  }
 
   /** Make a page structure current. Empty pages are retired unless kept:
    *  a live paged drag keeps the page it emptied in place until the
@@ -199,6 +322,9 @@ export function useGrid() {
     setPages,
     setFolders,
     activeId: dragGrid.activeId,
+    getPageCapacity: () => pageCapacityRef.current,
+    placedIds,
+    onPlaced: markPlaced,
   });
 
   // Launchpad-style drag-to-Dock pinning
@@ -300,6 +426,7 @@ export function useGrid() {
     pageDragHandlers: sharedDragHandlers,
     setActivePageEngine,
     setPagedFolderInsert,
+    setPageCapacity,
 
     // Folder handlers
     handleOpenFolder: folderOps.handleOpenFolder,
@@ -308,6 +435,8 @@ export function useGrid() {
     handleFolderOrderChange: folderOps.handleFolderOrderChange,
     handleRemoveFromFolder: folderOps.handleRemoveFromFolder,
     handleUngroupFolder: folderOps.handleUngroupFolder,
+    handleGroupByTags: folderOps.handleGroupByTags,
+    markAllSeen,
     getOpenFolderSavedOrder: folderOps.getOpenFolderSavedOrder,
   };
 }
@@ -1,4 +1,13 @@
-import type { AppInfo, FolderInfo, FolderMetadata, OrderConfig } from "@/types/app";
+import { useEffect, useRef } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import type {
+  AppInfo,
+  FolderInfo,
+  FolderMetadata,
+  OrderConfig,
+  Placement,
+  ReconciledLayout,
+} from "@/types/app";
 import { buildAppsMap } from "@/utils/appUtils";
 import { isFolderId, resolveFolderApps, resolveOrderToAppItems, convertPhysicalFolders, buildInitialPages, healFolders } from "@/utils/folderUtils";
 import type { GridItem } from "@/components/items/AppItem";
@@ -18,6 +27,12 @@ interface UseGridDataOptions {
   setPages: (pages: string[][]) => void;
   setFolders: (folders: FolderMetadata[]) => void;
   activeId: string | null;
+  /** Items a page holds, when the paged layout has measured it */
+  getPageCapacity: () => number | null;
+  /** Ids (apps, or the folders they went into) placed moments ago */
+  placedIds: ReadonlySet<string>;
+  /** Reports where the seed pass placed apps new to the arrangement */
+  onPlaced: (placements: Placement[]) => void;
 }
 
 export function useGridData({
@@ -29,6 +44,9 @@ export function useGridData({
   setPages,
   setFolders,
   activeId,
+  getPageCapacity,
+  placedIds,
+  onPlaced,
 }: UseGridDataOptions) {
   // Create apps map for resolving folder apps
   // Include both top-level apps AND apps from physical folders (for initial conversion)
@@ -55,14 +73,23 @@ export function useGridData({
     return currentOrder
       .map((id): GridItemUnion | null => {
         const appItem = resolvedApps.get(id);
-        if (appItem) return { type: "app", data: appItem };
+        if (appItem) {
+          return placedIds.has(id)
+            ? { type: "app", data: { ...appItem, justPlaced: true } }
+            : { type: "app", data: appItem };
+        }
 
         if (isFolderId(id)) {
           const folder = foldersMap.get(id);
           if (folder) {
             return {
               type: "folder",
-              data: { id, name: folder.name, apps: resolveFolderApps(folder.appPaths, appsMap) },
+              data: {
+                id,
+                name: folder.name,
+                apps: resolveFolderApps(folder.appPaths, appsMap),
+                justPlaced: placedIds.has(id),
+              },
             };
           }
         }
@@ -72,32 +99,53 @@ export function useGridData({
       .filter((item): item is GridItemUnion => item !== null);
   }
 
-  // Initialize order once apps/folders load
-  if (order === null && (apps.length > 0 || physicalFolders.length > 0)) {
-    // Check if we have saved folders or need to convert physical folders,
-    // then heal them: drop uninstalled apps and folders left empty
+  // Initialize order once apps/folders load. The backend reconciles the
+  // saved arrangement with what is installed: apps that went missing keep
+  // their slot as ghosts (an updater replacing a bundle), apps back from
+  // missing return to it and what's new is placed by the placement policy.
+  // Until it answers nothing is seeded, so the guard keeps the renders in
+  // between from asking again.
+  const seedingRef = useRef(false);
+  const canSeed = order === null && (apps.length > 0 || physicalFolders.length > 0);
+  useEffect(() => {
+    if (!canSeed || seedingRef.current) return;
+    seedingRef.current = true;
+
+    // Check if we have saved folders or need to convert physical folders;
+    // the backend heals them (uninstalled apps out, empty folders gone)
     const savedFolders = orderConfig?.folders ?? [];
-    const effectiveFolders = healFolders(
-      savedFolders.length > 0 ? savedFolders : convertPhysicalFolders(physicalFolders),
-      new Set(appsMap.keys())
-    );
+    const effectiveFolders =
+      savedFolders.length > 0 ? savedFolders : convertPhysicalFolders(physicalFolders);
+    const savedPages = orderConfig?.pages ?? [];
+    const appPaths = apps.map((a) => a.path);
 
     // Seed local folder state — from here on it is the single source of
-    // truth (mutations append/update it, so it must start complete)
-    if (effectiveFolders.length > 0) {
-      setFolders(effectiveFolders);
-    }
-
-    // Build pages from saved config (healing stale/duplicate/folder-contained
-    // entries) or from scratch on first launch — same reconciliation either way
-    setPages(
-      buildInitialPages(
-        orderConfig?.pages ?? [],
-        apps.map((a) => a.path),
-        effectiveFolders
-      )
-    );
-  }
+    // truth (mutations append/update it, so it must start complete). The
+    // reconciled folders are the ones seeded: healed, and the policy may
+    // have put apps into them.
+    invoke<ReconciledLayout>("reconcile_layout", {
+      pages: savedPages,
+      folders: effectiveFolders,
+      appPaths,
+      folderAppPaths: physicalFolders.flatMap((folder) => folder.apps.map((a) => a.path)),
+      pageCapacity: getPageCapacity(),
+    })
+      .then((result) => {
+        if (result.folders.length > 0) {
+          setFolders(result.folders);
+        }
+        setPages(result.pages);
+        onPlaced(result.placements);
+      })
+      .catch((e) => {
+        console.error("Failed to reconcile layout:", e);
+        const healedFolders = healFolders(effectiveFolders, new Set(appsMap.keys()));
+        if (healedFolders.length > 0) {
+          setFolders(healedFolders);
+        }
+        setPages(buildInitialPages(savedPages, appPaths, healedFolders));
+      });
+  });
 
   // Build items from current order
   const items = buildItems(order);
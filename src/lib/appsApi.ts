@@ -1,12 +1,13 @@
 import { invoke } from "@tauri-apps/api/core";
 import type { AppsResponse } from "@/types/app";
+import { iconPixelSize } from "@/utils/iconUtils";
 
 // Cache the promise at module level - starts fetching immediately on import
 let appsPromise: Promise<AppsResponse> | null = null;
 
 export function getAppsPromise(): Promise<AppsResponse> {
   if (!appsPromise) {
-    appsPromise = invoke<AppsResponse>("get_apps").then(async (response) => {
+    appsPromise = invoke<AppsResponse>("get_apps", { iconSize: iconPixelSize() }).then(async (response) => {
       // Show window after data is ready
       await invoke("show_window");
       return response;
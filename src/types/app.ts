@@ -1,9 +1,187 @@
+/** Whether a bundle can run as a regular, windowed app, and if not, why */
+export type Launchability =
+  | { status: "launchable" }
+  /** LSBackgroundOnly: a daemon-style helper with no UI at all */
+  | { status: "backgroundOnly" }
+  /** LSUIElement: an agent (menu bar extra, helper) with no window to show */
+  | { status: "agent" }
+  /** No Info.plist, no CFBundleExecutable, or the executable is missing */
+  | { status: "missingExecutable" }
+  /** LSMinimumSystemVersion is newer than the running macOS */
+  | { status: "requiresNewerSystem"; required: string };
+
+/** Discovery providers: the applications directories themselves, Chromium
+ *  web apps (PWAs), Setapp, Homebrew casks in a custom --appdir,
+ *  iPhone/iPad apps on Apple Silicon, external volumes, and on Linux
+ *  Flatpak, Snap and AppImages */
+export type AppSource =
+  | "applications"
+  | "webApp"
+  | "setapp"
+  | "homebrew"
+  | "iosApp"
+  | "externalVolume"
+  | "flatpak"
+  | "snap"
+  | "appImage";
+
+/** CPU architectures of an app's main executable */
+export type Architecture = "arm64" | "x86_64" | "universal" | "other";
+
+/** How an app's main executable is signed: not at all, ad hoc (no
+ *  identity), by Apple, or with a developer certificate */
+export type SignatureStatus = "unsigned" | "adHoc" | "apple" | "signed";
+
+/** What an executable's code signature says about who signed it */
+export interface CodeSignature {
+  status: SignatureStatus;
+  /** Signing identifier, usually the bundle identifier */
+  identifier: string | null;
+  teamId: string | null;
+  /** Common name of the signing certificate */
+  signer: string | null;
+}
+
+/** Gatekeeper's download record for a quarantined app */
+export interface Quarantine {
+  /** The app that downloaded it, e.g. "Safari" */
+  agent: string | null;
+  /** ms since the epoch */
+  downloadedAt: number | null;
+  /** The download page when recorded, else the file's own URL */
+  origin: string | null;
+  /** Not opened yet: the first launch stops at a Gatekeeper confirmation */
+  neverOpened: boolean;
+}
+
+/** Finder's tag colors */
+export type TagColor = "gray" | "green" | "purple" | "blue" | "yellow" | "red" | "orange";
+
+/** A Finder tag on an app bundle */
+export interface FinderTag {
+  name: string;
+  color: TagColor | null;
+}
+
 export interface AppInfo {
   name: string;
   path: string;
   icon: string | null;
   /** App Store category identifier (LSApplicationCategoryType), if declared */
   category: string | null;
+  /** The category's display name in the user's language */
+  categoryName: string | null;
+  launchability: Launchability;
+  /** Null when the executable isn't a readable Mach-O file */
+  architecture: Architecture | null;
+  /** Intel-only on an Apple Silicon Mac: runs through Rosetta */
+  needsRosetta: boolean;
+  /** Null when the executable isn't a readable Mach-O file */
+  signature: CodeSignature | null;
+  /** Download record while quarantined */
+  quarantine: Quarantine | null;
+  /** Finder tags, in Finder's order (then freedesktop tags, on Linux) */
+  tags: FinderTag[];
+  /** Installed or updated since the user last saw it (launched it, or
+   *  marked all apps seen) */
+  isNew: boolean;
+  /** ms since the epoch: set when the app is new by way of an update */
+  updatedAt: number | null;
+  /** Set when another copy of the same app is shown too: its version, or
+   *  its location when the versions match */
+  disambiguation: string | null;
+  /** Which discovery provider found the app */
+  source: AppSource;
+  /** External volume the app is on (/Volumes/<name>) */
+  volume: string | null;
+  /** On an external volume that isn't mounted: a placeholder keeping the
+   *  app's place until the volume is back */
+  offline: boolean;
+  /** The Finder alias or symlink the app was found through; `path` is
+   *  then its target */
+  via: string | null;
+  /** Structural problems of the bundle, most severe first. Not served with
+   *  the list: filled in by audit_apps once the list is re-validated */
+  problems?: AppProblem[];
+  /** Bytes allocated on disk for the whole bundle. Not served with the
+   *  list: filled in from the background size scan's "app-sizes" events */
+  sizeOnDisk?: number;
+}
+
+/** How bad a bundle problem is */
+export type Severity = "info" | "warning" | "error";
+
+export type ProblemKind =
+  | "missingInfoPlist"
+  | "damagedInfoPlist"
+  | "noExecutableDeclared"
+  | "missingExecutable"
+  | "emptyExecutable"
+  | "notExecutable"
+  | "unreadableExecutable"
+  | "unsupportedArchitecture"
+  | "damagedSignature"
+  | "missingResources"
+  | "noBundleIdentifier"
+  | "missingIcon"
+  | "quarantined";
+
+/** Something structurally wrong with a bundle (a half-deleted app, a
+ *  damaged Info.plist, an executable without its exec bit) */
+export interface AppProblem {
+  kind: ProblemKind;
+  severity: Severity;
+  message: string;
+}
+
+/** The problems of one bundle, as listed by audit_apps */
+export interface AppAudit {
+  path: string;
+  problems: AppProblem[];
+}
+
+/** Where a bundle came from: bundled with macOS, the App Store, or
+ *  downloaded directly */
+export type Distribution = "system" | "appStore" | "direct";
+
+/** A document type an app declares it can open (CFBundleDocumentTypes) */
+export interface DocumentType {
+  name: string | null;
+  /** "Editor", "Viewer", "Shell" or "None" */
+  role: string | null;
+  /** Uniform type identifiers */
+  contentTypes: string[];
+  extensions: string[];
+}
+
+/** Everything the info sheet shows about an app (get_app_details); dates
+ *  are ms since the epoch */
+export interface AppDetails {
+  name: string;
+  path: string;
+  bundleId: string | null;
+  version: string | null;
+  build: string | null;
+  architecture: Architecture | null;
+  needsRosetta: boolean;
+  signature: CodeSignature | null;
+  quarantine: Quarantine | null;
+  /** Bytes allocated on disk for the whole bundle */
+  sizeOnDisk: number | null;
+  installedAt: number | null;
+  modifiedAt: number | null;
+  lastOpenedAt: number | null;
+  distribution: Distribution;
+  documentTypes: DocumentType[];
+  /** Structural problems, most severe first */
+  problems: AppProblem[];
+}
+
+/** A bundle's size on disk, streamed by the background size scan in
+ *  "app-sizes" events (an array of these per event) */
+export interface AppSize {
+  path: string;
+  bytes: number;
 }
 
 export interface FolderInfo {
@@ -19,9 +197,46 @@ export interface FolderMetadata {
   createdAt: number;
 }
 
+/** Copies of one app (same bundle identifier) found in several places */
+export interface DuplicateGroup {
+  bundleId: string;
+  /** Paths of the copies shown, preferred first */
+  shown: string[];
+  /** Paths of the copies the duplicate policy hides */
+  hidden: string[];
+}
+
+export type BrokenAliasReason = "unreadable" | "targetMissing";
+
+/** A Finder alias or symlink to an app that no longer leads to one */
+export interface BrokenAlias {
+  path: string;
+  /** Where it pointed, when that could be read */
+  target: string | null;
+  reason: BrokenAliasReason;
+}
+
 export interface AppsResponse {
   apps: AppInfo[];
   folders: FolderInfo[];
+  duplicates: DuplicateGroup[];
+  brokenAliases: BrokenAlias[];
+}
+
+/** Changes found by re-validating the served app list (refresh_apps) */
+export interface AppsDiff {
+  /** Main-grid apps that appeared */
+  added: AppInfo[];
+  /** Main-grid apps whose bundle or metadata changed */
+  updated: AppInfo[];
+  /** Paths of main-grid apps that are gone */
+  removed: string[];
+  /** All physical folders, present only when any of them changed */
+  folders: FolderInfo[] | null;
+  /** The current duplicates report, in full */
+  duplicates: DuplicateGroup[];
+  /** The current broken aliases report, in full */
+  brokenAliases: BrokenAlias[];
 }
 
 export interface OrderConfig {
@@ -33,10 +248,58 @@ export interface OrderConfig {
 /** How the main grid presents apps */
 export type LayoutMode = "scroll" | "paged";
 
+/** Which copy is shown when the same app is installed in several places */
+export type DuplicatePolicy = "preferSystem" | "preferNewest" | "preferUser" | "showAll";
+
+/** Where apps new to the arrangement land: after the last item, in the
+ *  first page with room, on a page of their own, or in the folder named
+ *  after their Finder tag or App Store category */
+export type PlacementPolicy = "endOfLastPage" | "firstFreeSlot" | "newPage" | "categoryFolder";
+
+/** Where reconcile_layout put an item new to the arrangement */
+export type Placement =
+  | { kind: "page"; id: string; page: number; slot: number }
+  | { kind: "folder"; id: string; folderId: string };
+
+/** An arrangement reconciled with the installed apps (reconcile_layout) */
+export interface ReconciledLayout {
+  pages: string[][];
+  folders: FolderMetadata[];
+  /** Every item placed anew, in placement order */
+  placements: Placement[];
+}
+
 export interface AppSettings {
   layout: LayoutMode;
+  /** Show bundles that can't run as greyed-out tiles instead of hiding them */
+  showUnlaunchable: boolean;
+  duplicatePolicy: DuplicatePolicy;
+  placementPolicy: PlacementPolicy;
+  /** How long a missing app keeps its slot, in seconds */
+  ghostGracePeriodSecs: number;
+  /** Discovery providers beyond the applications directories */
+  showWebApps: boolean;
+  showSetappApps: boolean;
+  showHomebrewApps: boolean;
+  showIosApps: boolean;
+  showExternalVolumes: boolean;
+  showFlatpakApps: boolean;
+  showSnapApps: boolean;
+  showAppimages: boolean;
 }
 
+/** On/off view settings, by AppSettings field name (the options menu's toggles) */
+export type ViewToggle =
+  | "showUnlaunchable"
+  | "showWebApps"
+  | "showSetappApps"
+  | "showHomebrewApps"
+  | "showIosApps"
+  | "showExternalVolumes"
+  | "showFlatpakApps"
+  | "showSnapApps"
+  | "showAppimages";
+
 export interface DndSettings {
   /** How long to hold over an app before folder creation ring appears (ms) */
   folderCreationDelay: number;
@@ -9,12 +9,18 @@ export interface AppContextMenuCallbacks {
   onCloseApp: () => void;
 }
 
-type MenuAction = "open" | "show-in-finder" | "get-info" | "quick-look";
+type MenuAction =
+  | "open"
+  | "show-in-finder"
+  | "get-info"
+  | "quick-look"
+  | "show-origin"
+  | "show-problem";
 
 const ACTION_COMMANDS = {
   "show-in-finder": "reveal_app",
-  "get-info": "show_get_info",
   "quick-look": "quick_look",
+  "show-origin": "show_origin",
 } as const;
 
 // The menu itself is native (an NSMenu with SF Symbol icons, built by the
@@ -24,6 +30,13 @@ const ACTION_COMMANDS = {
 // action event can arrive after the popup call resolves.
 let current: { app: AppInfo; callbacks: AppContextMenuCallbacks } | null = null;
 let actionListener: Promise<unknown> | null = null;
+// Get Info opens the host's own info sheet (see setGetInfoHandler)
+let getInfoHandler: ((app: AppInfo) => void) | null = null;
+
+/** Register where "Get Info" shows its sheet (null to unregister) */
+export function setGetInfoHandler(handler: ((app: AppInfo) => void) | null) {
+  getInfoHandler = handler;
+}
 
 function handleAction(action: MenuAction, path: string) {
   if (action === "open") {
@@ -34,11 +47,19 @@ function handleAction(action: MenuAction, path: string) {
     }
     return;
   }
+  if (action === "get-info" || action === "show-problem") {
+    // Stays inside the launcher: nothing hands off, nothing closes. The
+    // info sheet lists the bundle's problems.
+    if (current && current.app.path === path) {
+      getInfoHandler?.(current.app);
+    }
+    return;
+  }
   const command = ACTION_COMMANDS[action];
   invoke(command, { path }).catch((e) => console.error(`${command} failed:`, e));
   // Quick Look floats its panel above the launcher and returns focus on
   // close (backend keeps the launcher alive); the other actions hand off
-  // to Finder, so the launcher closes
+  // to Finder or the browser, so the launcher closes
   if (action !== "quick-look") {
     current?.callbacks.onCloseApp();
   }
@@ -1,4 +1,4 @@
-import type { AppInfo } from "@/types/app";
+import type { AppInfo, Launchability } from "@/types/app";
 
 /**
  * Build a lookup map from an array of apps, keyed by app path.
@@ -7,36 +7,31 @@ export function buildAppsMap(apps: AppInfo[]): Map<string, AppInfo> {
   return new Map(apps.map((app) => [app.path, app]));
 }
 
-/** Category slugs whose display name isn't plain title-casing */
-const CATEGORY_NAMES: Record<string, string> = {
-  "developer-tools": "Developer Tools",
-  "graphics-design": "Graphics & Design",
-  "healthcare-fitness": "Health & Fitness",
-  "food-drink": "Food & Drink",
-  "social-networking": "Social Networking",
-};
+/** Whether a tile can open right now: a launchable app, not an offline
+ *  volume's placeholder */
+export function canOpen(app: AppInfo): boolean {
+  return isLaunchable(app) && !app.offline;
+}
+
+export function isLaunchable(app: AppInfo): boolean {
+  return app.launchability.status === "launchable";
+}
 
 /**
- * Human-readable name for an LSApplicationCategoryType identifier,
- * e.g. "public.app-category.developer-tools" -> "Developer Tools".
- * Game subcategories collapse to "Games" like original Launchpad.
- * Returns null for missing or unrecognizable identifiers.
+ * Why a bundle can't run as a regular app, for the greyed-out tile's
+ * tooltip. Returns null for launchable apps.
  */
-const CATEGORY_PREFIX = "public.app-category.";
-
-export function categoryDisplayName(categoryType: string | null | undefined): string | null {
-  if (!categoryType?.startsWith(CATEGORY_PREFIX)) return null;
-  const slug = categoryType.slice(CATEGORY_PREFIX.length);
-  if (!slug) return null;
-
-  if (slug === "games" || slug.endsWith("-games")) return "Games";
-
-  const special = CATEGORY_NAMES[slug];
-  if (special) return special;
-
-  return slug
-    .split("-")
-    .filter(Boolean)
-    .map((word) => word.charAt(0).toUpperCase() + word.slice(1))
-    .join(" ");
+export function launchIssue(launchability: Launchability): string | null {
+  switch (launchability.status) {
+    case "launchable":
+      return null;
+    case "backgroundOnly":
+      return "Runs in the background only";
+    case "agent":
+      return "Helper without a window (menu bar or background agent)";
+    case "missingExecutable":
+      return "Damaged: the app's program is missing";
+    case "requiresNewerSystem":
+      return `Requires macOS ${launchability.required} or later`;
+  }
 }
@@ -69,13 +69,13 @@ export function healFolders(
 }
 
 /**
- * Build the initial pages from the saved pages and the discovered items:
- * reconciles the saved arrangement with what is installed now. Drops ids
- * that no longer exist, apps that live inside folders and repeated ids
- * (every id appears once — keys and drag indices depend on it), then
- * appends newly discovered items to the last page, from where the paged
- * layout cascades them onto further pages as capacity requires. A page
- * left empty is retired by the caller's commit.
+ * Build the initial pages from the saved pages and the discovered items
+ * when the backend's reconciliation (reconcile_layout) can't be reached:
+ * the same pruning of ids that no longer exist, apps that live inside
+ * folders and repeated ids (every id appears once — keys and drag indices
+ * depend on it), with newly discovered items appended to the last page
+ * whatever the placement policy. A page left empty is retired by the
+ * caller's commit.
  */
 export function buildInitialPages(
   savedPages: string[][],
@@ -147,6 +147,49 @@ export function removeAppFromFolder(
   return { newPages, updatedFolders, dissolved: false };
 }
 
+/**
+ * Smart grouping by Finder tag: every app loose on the grid that carries a
+ * tag moves into the folder named after its first tag. A folder of that
+ * name (case-insensitive) takes the apps in; otherwise a new one is made
+ * in the slot of the first of them, provided at least two apps share the
+ * tag. Apps already in folders stay where the user put them.
+ */
+export function groupByTags(
+  pages: string[][],
+  folders: FolderMetadata[],
+  appsMap: Map<string, AppInfo>
+): { newPages: string[][]; updatedFolders: FolderMetadata[] } {
+  const groups = new Map<string, { name: string; appPaths: string[] }>();
+  for (const id of pages.flat()) {
+    const tag = appsMap.get(id)?.tags[0];
+    if (!tag) continue;
+    const key = tag.name.toLowerCase();
+    const group = groups.get(key) ?? { name: tag.name, appPaths: [] };
+    group.appPaths.push(id);
+    groups.set(key, group);
+  }
+
+  let newPages = pages;
+  let updatedFolders = folders;
+  for (const [key, group] of groups) {
+    const existing = updatedFolders.find((f) => f.name.toLowerCase() === key);
+    if (existing) {
+      updatedFolders = updateFolderById(updatedFolders, existing.id, {
+        appPaths: [...existing.appPaths, ...group.appPaths],
+      });
+      for (const id of group.appPaths) newPages = removeFromPages(newPages, id);
+      continue;
+    }
+    if (group.appPaths.length < 2) continue;
+    const folder = createFolder(group.appPaths, group.name);
+    updatedFolders = [...updatedFolders, folder];
+    const [first, ...rest] = group.appPaths;
+    for (const id of rest) newPages = removeFromPages(newPages, id);
+    newPages = replaceInPages(newPages, first, [folder.id]);
+  }
+  return { newPages, updatedFolders };
+}
+
 /**
  * Return a new folders array with one folder updated by id.
  */
@@ -14,6 +14,15 @@ export const DEFAULT_ICON = `data:image/svg+xml,${encodeURIComponent(`
   </svg>
 `)}`;
 
+/** Edge length icons are shown at, in CSS pixels (the tiles' w-24 h-24) */
+export const ICON_DISPLAY_SIZE = 96;
+
+/** Icon edge length to request from the backend: the display size in
+ *  device pixels, so icons stay sharp on Retina screens */
+export function iconPixelSize(): number {
+  return Math.round(ICON_DISPLAY_SIZE * window.devicePixelRatio);
+}
+
 export function getIconSrc(icon: string | null): string {
   if (!icon) return DEFAULT_ICON;
   if (icon.startsWith("file://")) {
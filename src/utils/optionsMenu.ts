@@ -1,24 +1,80 @@
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
-import type { LayoutMode } from "@/types/app";
+import type { DuplicatePolicy, LayoutMode, PlacementPolicy, ViewToggle } from "@/types/app";
+
+export interface OptionsMenuCallbacks {
+  /** A layout was picked */
+  onLayout: (layout: LayoutMode) => void;
+  /** An on/off view setting was picked (flip it) */
+  onToggle: (setting: ViewToggle) => void;
+  /** A duplicate policy was picked */
+  onDuplicatePolicy: (policy: DuplicatePolicy) => void;
+  /** A placement policy for new apps was picked */
+  onPlacementPolicy: (policy: PlacementPolicy) => void;
+  /** How long missing apps keep their places was picked (seconds) */
+  onGhostGracePeriod: (secs: number) => void;
+  /** One-shot actions, each offered only when given */
+  onGroupByTags?: () => void;
+  onMarkAllSeen?: () => void;
+}
+
+type OptionsAction =
+  | { kind: "layout"; layout: LayoutMode }
+  | { kind: "toggle"; setting: ViewToggle }
+  | { kind: "duplicatePolicy"; policy: DuplicatePolicy }
+  | { kind: "placementPolicy"; policy: PlacementPolicy }
+  | { kind: "ghostGracePeriod"; secs: number }
+  | { kind: "action"; action: OptionsMenuAction };
+
+/** Ids of the one-shot actions (see OPTION_ACTIONS in app_menu.rs) */
+type OptionsMenuAction = "groupByTags" | "markAllSeen";
 
 // Mirrors appContextMenu: the menu itself is native (show_options_menu,
-// with the active layout check-marked) and the choice comes back as an
-// "options-menu-action" event. Only one menu can be open at a time, so
-// the select callback is swapped into module state before showing.
-let onSelect: ((layout: LayoutMode) => void) | null = null;
+// with the active layout, enabled toggles, policies and grace period
+// check-marked) and the choice
+// comes back as an "options-menu-action" event. Only one menu can be open
+// at a time, so the callbacks are swapped into module state before showing.
+let current: OptionsMenuCallbacks | null = null;
 let actionListener: Promise<unknown> | null = null;
 
+function handleAction(action: OptionsAction) {
+  switch (action.kind) {
+    case "layout":
+      current?.onLayout(action.layout);
+      break;
+    case "toggle":
+      current?.onToggle(action.setting);
+      break;
+    case "duplicatePolicy":
+      current?.onDuplicatePolicy(action.policy);
+      break;
+    case "placementPolicy":
+      current?.onPlacementPolicy(action.policy);
+      break;
+    case "ghostGracePeriod":
+      current?.onGhostGracePeriod(action.secs);
+      break;
+    case "action":
+      if (action.action === "groupByTags") current?.onGroupByTags?.();
+      if (action.action === "markAllSeen") current?.onMarkAllSeen?.();
+      break;
+  }
+}
+
 /** Show the native view-options menu at the cursor position */
 export async function showOptionsMenu(
-  current: LayoutMode,
-  select: (layout: LayoutMode) => void
+  layout: LayoutMode,
+  enabled: ViewToggle[],
+  duplicatePolicy: DuplicatePolicy,
+  placementPolicy: PlacementPolicy,
+  ghostGracePeriodSecs: number,
+  callbacks: OptionsMenuCallbacks
 ) {
-  onSelect = select;
+  current = callbacks;
 
   try {
-    actionListener ??= listen<{ layout: LayoutMode }>("options-menu-action", (event) =>
-      onSelect?.(event.payload.layout)
+    actionListener ??= listen<OptionsAction>("options-menu-action", (event) =>
+      handleAction(event.payload)
     );
     await actionListener;
   } catch (e) {
@@ -29,7 +85,17 @@ export async function showOptionsMenu(
 
   try {
     // Resolves when the menu is dismissed
-    await invoke("show_options_menu", { layout: current });
+    const actions: OptionsMenuAction[] = [];
+    if (callbacks.onGroupByTags) actions.push("groupByTags");
+    if (callbacks.onMarkAllSeen) actions.push("markAllSeen");
+    await invoke("show_options_menu", {
+      layout,
+      enabled,
+      duplicatePolicy,
+      placementPolicy,
+      ghostGracePeriodSecs,
+      actions,
+    });
   } catch (e) {
     console.error("Failed to show options menu:", e);
   }
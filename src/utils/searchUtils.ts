@@ -50,14 +50,106 @@ export function collectSearchableApps(items: GridItemUnion[]): GridItem[] {
   return apps;
 }
 
+type AppFilter = (app: GridItem) => boolean;
+
+/** `is:<flag>` filters: yes/no properties */
+const FLAG_FILTERS = new Map<string, AppFilter>([
+  // Intel-only, whatever this Mac is: the apps to retire before Rosetta goes
+  ["rosetta", (app) => app.architecture === "x86_64"],
+  // Placeholders for apps on an unplugged volume
+  ["offline", (app) => app.offline],
+  // No signature at all, or one without an identity behind it
+  ["unsigned", (app) => app.signature?.status === "unsigned"],
+  ["adhoc", (app) => app.signature?.status === "adHoc"],
+  ["apple", (app) => app.signature?.status === "apple"],
+  // Downloaded and not opened yet: Gatekeeper will ask on first launch
+  ["downloaded", (app) => app.quarantine?.neverOpened === true],
+]);
+
+/** `<key>:<value>` filters: valued properties, value already normalized */
+const VALUE_FILTERS = new Map<string, (app: GridItem, value: string) => boolean>([
+  [
+    "arch",
+    (app, value) => {
+      if (value === "intel" || value === "x86_64" || value === "x86") {
+        return app.architecture === "x86_64";
+      }
+      if (value === "arm" || value === "arm64" || value === "apple") {
+        return app.architecture === "arm64";
+      }
+      return app.architecture === value;
+    },
+  ],
+  [
+    "source",
+    (app, value) => {
+      if (value === "web" || value === "pwa") return app.source === "webApp";
+      if (value === "brew") return app.source === "homebrew";
+      if (value === "ios" || value === "iphone" || value === "ipad") return app.source === "iosApp";
+      if (value === "volume" || value === "external") return app.source === "externalVolume";
+      return normalize(app.source) === value;
+    },
+  ],
+  ["volume", (app, value) => app.volume !== null && normalize(app.volume) === value],
+  // Signing team ID (`team:EQHXZ8M8AV`), or the signing certificate's name
+  [
+    "team",
+    (app, value) =>
+      app.signature !== null &&
+      ((app.signature.teamId !== null && normalize(app.signature.teamId) === value) ||
+        (app.signature.signer !== null && normalize(app.signature.signer).includes(value))),
+  ],
+  // Finder tag by name; spaces may be left out (`tag:clientwork`)
+  [
+    "tag",
+    (app, value) =>
+      app.tags.some((tag) => {
+        const name = normalize(tag.name);
+        return name === value || name.replace(/\s+/g, "") === value;
+      }),
+  ],
+]);
+
+/**
+ * Split a query into its name text and its filter tokens (`is:rosetta`,
+ * `arch:universal`). A token with an unknown key stays part of the text,
+ * so names containing a colon remain searchable.
+ */
+function parseQuery(query: string): { text: string; filters: AppFilter[] } {
+  const words: string[] = [];
+  const filters: AppFilter[] = [];
+  for (const token of query.split(/\s+/).filter(Boolean)) {
+    const match = /^([a-z]+):(.+)$/i.exec(token);
+    const key = match?.[1].toLowerCase() ?? "";
+    const value = match ? normalize(match[2]) : "";
+    const flagFilter = key === "is" ? FLAG_FILTERS.get(value) : undefined;
+    const valueFilter = VALUE_FILTERS.get(key);
+    if (flagFilter) {
+      filters.push(flagFilter);
+    } else if (valueFilter) {
+      filters.push((app) => valueFilter(app, value));
+    } else {
+      words.push(token);
+    }
+  }
+  return { text: words.join(" "), filters };
+}
+
 /**
  * Filter apps by name match, best matches first (prefix, then word-prefix,
- * then substring), keeping grid order within each tier.
+ * then substring), keeping grid order within each tier. Filter tokens in
+ * the query narrow the candidates first; a query of filters alone lists
+ * every app passing them, in grid order.
  */
 export function searchApps(items: GridItemUnion[], query: string): GridItem[] {
-  const normalizedQuery = normalize(query);
-  if (!normalizedQuery) return [];
-  return collectSearchableApps(items)
+  const { text, filters } = parseQuery(query);
+  const normalizedQuery = normalize(text);
+  if (!normalizedQuery && filters.length === 0) return [];
+  const candidates = collectSearchableApps(items).filter((app) =>
+    filters.every((filter) => filter(app))
+  );
+  if (!normalizedQuery) return candidates;
+  return candidates
     .map((app) => ({ app, rank: matchRank(app.name, normalizedQuery) }))
     .filter((entry): entry is { app: GridItem; rank: number } => entry.rank !== null)
     .sort((a, b) => a.rank - b.rank)